@@ -0,0 +1,16 @@
+use std::process::Command;
+
+/// Record the git commit the binary is built from (see the run manifest)
+fn main() {
+    let commit = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map_or_else(|| "unknown".to_string(), |commit| commit.trim().to_string());
+
+    println!("cargo:rustc-env=GIT_COMMIT={commit}");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+}
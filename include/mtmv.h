@@ -0,0 +1,38 @@
+/*
+ * C interface of the min-timespan-delivery solver, built as a shared library by `cargo build --release`
+ * (target/release/libmin_timespan_delivery.so).
+ *
+ * Every function returns a JSON string owned by the library, to be released with `mtmv_free`. Failures are reported
+ * as a JSON object {"error": "..."}. Calls are serialized: the configuration is global to the process.
+ */
+#ifndef MTMV_H
+#define MTMV_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/*
+ * Solve a problem and return the run JSON, whose "solution" field holds the best solution found.
+ *
+ * `config_json` is a config JSON as written by the `run` subcommand (e.g. with `--dry-run`). The keys of the
+ * optional `problem_json` object (e.g. "customers_count", "x", "y", "demands", "dronable") replace those of the
+ * config, so that the solver parameters can be reused for new instances. Iteration logging and all other file
+ * outputs are disabled.
+ */
+char *mtmv_solve(const char *problem_json, const char *config_json);
+
+/*
+ * Re-evaluate a solution JSON (e.g. the "solution" field of the run JSON) against `config_json` and return it with
+ * its working time, violations and feasibility updated.
+ */
+char *mtmv_evaluate(const char *solution_json, const char *config_json);
+
+/* Release a string returned by `mtmv_solve` or `mtmv_evaluate`. */
+void mtmv_free(char *result);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* MTMV_H */
@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+use std::fs;
+
+use serde::Deserialize;
+
+use crate::ids::{SerializedVehicleId, VehicleId};
+
+#[derive(Deserialize)]
+struct _Assignment {
+    customer: usize,
+    #[serde(flatten)]
+    vehicle: SerializedVehicleId,
+}
+
+#[derive(Deserialize)]
+struct _AssignmentFile {
+    #[serde(default)]
+    forced: Vec<_Assignment>,
+    #[serde(default)]
+    forbidden: Vec<_Assignment>,
+    #[serde(default)]
+    separated: Vec<(usize, usize)>,
+}
+
+#[derive(Deserialize)]
+struct _ZoneAccess {
+    #[serde(flatten)]
+    vehicle: SerializedVehicleId,
+    zones: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct _ZoneFile {
+    customers: HashMap<String, Vec<usize>>,
+    #[serde(default)]
+    vehicles: Vec<_ZoneAccess>,
+}
+
+/// Business rules on which vehicle serves each customer (see `--assignments`)
+#[derive(Clone, Debug, Default)]
+pub struct Assignments {
+    /// Vehicle each customer must be served by, if any
+    forced: Vec<Option<VehicleId>>,
+    /// Vehicles each customer must not be served by
+    forbidden: Vec<Vec<VehicleId>>,
+    /// Customers each customer must not share a route with
+    separated: Vec<Vec<usize>>,
+}
+
+impl Assignments {
+    /// Load the rules of the JSON file `path` (see `--assignments`). Returns no rules when no file is given.
+    pub fn load(path: Option<&str>, customers_count: usize, trucks_count: usize, drones_count: usize) -> Self {
+        let Some(path) = path else {
+            return Self::default();
+        };
+
+        let file = serde_json::from_str::<_AssignmentFile>(&fs::read_to_string(path).unwrap()).unwrap();
+        let customer = |customer: usize| _customer(customer, customers_count);
+
+        let mut rules = Self::_empty(customers_count);
+        for assignment in file.forced {
+            let c = customer(assignment.customer);
+            assert!(
+                rules.forced[c].is_none(),
+                "Customer {c} is forced to more than one vehicle"
+            );
+            rules.forced[c] = Some(assignment.vehicle.id(trucks_count, drones_count));
+        }
+        for assignment in file.forbidden {
+            let c = customer(assignment.customer);
+            rules.forbidden[c].push(assignment.vehicle.id(trucks_count, drones_count));
+        }
+        for (i, j) in file.separated {
+            let (i, j) = (customer(i), customer(j));
+            rules.separated[i].push(j);
+            rules.separated[j].push(i);
+        }
+
+        rules
+    }
+
+    /// Add the zone restrictions of the JSON file `path` (see `--zones`) as forbidden assignments: a vehicle listed
+    /// with its allowed zones may not serve the customers outside them. Customers outside every zone and vehicles that
+    /// are not listed are unrestricted.
+    pub fn with_zones(
+        mut self,
+        path: Option<&str>,
+        customers_count: usize,
+        trucks_count: usize,
+        drones_count: usize,
+    ) -> Self {
+        let Some(path) = path else {
+            return self;
+        };
+
+        let file = serde_json::from_str::<_ZoneFile>(&fs::read_to_string(path).unwrap()).unwrap();
+        if self.forced.is_empty() {
+            self = Self::_empty(customers_count);
+        }
+
+        let mut zones_of = vec![vec![]; customers_count + 1];
+        for (zone, customers) in &file.customers {
+            for &customer in customers {
+                zones_of[_customer(customer, customers_count)].push(zone);
+            }
+        }
+
+        for access in file.vehicles {
+            let vehicle = access.vehicle.id(trucks_count, drones_count);
+            for zone in &access.zones {
+                assert!(file.customers.contains_key(zone), "Zone {zone:?} does not exist");
+            }
+
+            for (customer, zones) in zones_of.iter().enumerate() {
+                if !zones.is_empty()
+                    && !zones.iter().any(|zone| access.zones.contains(zone))
+                    && !self.forbidden[customer].contains(&vehicle)
+                {
+                    self.forbidden[customer].push(vehicle);
+                }
+            }
+        }
+
+        self
+    }
+
+    fn _empty(customers_count: usize) -> Self {
+        Self {
+            forced: vec![None; customers_count + 1],
+            forbidden: vec![vec![]; customers_count + 1],
+            separated: vec![vec![]; customers_count + 1],
+        }
+    }
+
+    /// Whether `vehicle` may serve `customer`
+    pub fn assignable(&self, customer: usize, vehicle: VehicleId) -> bool {
+        self.forced
+            .get(customer)
+            .is_none_or(|forced| forced.is_none_or(|v| v == vehicle))
+            && self
+                .forbidden
+                .get(customer)
+                .is_none_or(|forbidden| !forbidden.contains(&vehicle))
+    }
+
+    /// Customers that must not share a route with `customer`
+    pub fn separated(&self, customer: usize) -> &[usize] {
+        self.separated.get(customer).map_or(&[], Vec::as_slice)
+    }
+
+    /// Restrict the vehicle types that can serve each customer to those allowed by the rules: a customer forced to a
+    /// truck cannot be served by drones and conversely, and neither can a customer forbidden from every vehicle of a
+    /// type.
+    pub fn restrict(&self, truckable: &mut [bool], dronable: &mut [bool], trucks_count: usize, drones_count: usize) {
+        for customer in 1..self.forced.len() {
+            let trucks = (0..trucks_count).any(|t| self.assignable(customer, VehicleId::from_index(t, trucks_count)));
+            let drones = (trucks_count..trucks_count + drones_count)
+                .any(|d| self.assignable(customer, VehicleId::from_index(d, trucks_count)));
+            truckable[customer] &= trucks;
+            dronable[customer] &= drones;
+        }
+    }
+
+    /// Number of rules broken by `vehicle` performing routes visiting `routes` (each with the depot at both ends)
+    pub fn violation<'a>(&self, vehicle: VehicleId, routes: impl Iterator<Item = &'a [usize]>) -> f64 {
+        if self.forced.is_empty() {
+            return 0.0;
+        }
+
+        let mut violation = 0;
+        for customers in routes {
+            let customers = &customers[1..customers.len() - 1];
+            for (i, &customer) in customers.iter().enumerate() {
+                if !self.assignable(customer, vehicle) {
+                    violation += 1;
+                }
+                if !self.separated[customer].is_empty() {
+                    violation += customers[i + 1..]
+                        .iter()
+                        .filter(|c| self.separated[customer].contains(c))
+                        .count();
+                }
+            }
+        }
+
+        violation as f64
+    }
+}
+
+fn _customer(customer: usize, customers_count: usize) -> usize {
+    assert!(
+        (1..customers_count + 1).contains(&customer),
+        "Customer {customer} of the assignment rules does not exist"
+    );
+    customer
+}
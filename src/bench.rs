@@ -0,0 +1,131 @@
+use std::error::Error;
+use std::fmt::Write as _;
+use std::io::Write;
+use std::time::Instant;
+use std::{env, fs};
+
+use clap::Parser;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::cli::{self, SearchScope};
+use crate::config::{CONFIG, Config};
+use crate::logger::Logger;
+use crate::profile::{self, Phase};
+use crate::solutions::{self, Solution};
+
+/// Customers, trucks and drones of each synthetic instance
+const INSTANCES: [(usize, usize, usize); 3] = [(50, 3, 3), (100, 4, 4), (200, 8, 8)];
+
+/// Half the side of the square centered on the depot in which the customers are placed, in meters
+const EXTENT: f64 = 10000.0;
+
+/// A synthetic instance in the custom format with `customers` customers drawn uniformly around the depot, 80% of
+/// which are dronable, with demands between 0.1 and 3 kg
+fn _instance(customers: usize, trucks: usize, drones: usize, rng: &mut StdRng) -> String {
+    let mut data = format!(
+        "trucks_count {trucks}\ndrones_count {drones}\ncustomers {customers}\ndepot 0 0\nCoordinate X Coordinate Y \
+         Dronable Demand\n"
+    );
+    for _ in 0..customers {
+        writeln!(
+            data,
+            "{:.3} {:.3} {} {:.3}",
+            rng.random_range(-EXTENT..EXTENT),
+            rng.random_range(-EXTENT..EXTENT),
+            u8::from(rng.random_bool(0.8)),
+            rng.random_range(0.1..3.0),
+        )
+        .unwrap();
+    }
+
+    data
+}
+
+/// Mean and sample standard deviation of `values`
+fn _statistics(values: &[f64]) -> (f64, f64) {
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    let variance = if values.len() > 1 {
+        values.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / (values.len() - 1) as f64
+    } else {
+        0.0
+    };
+
+    (mean, variance.sqrt())
+}
+
+/// Run `samples` searches of `iterations` iterations with `search_scope` on each synthetic instance generated from
+/// `seed`, writing a CSV of the mean throughput to `writer`. Only the tabu search is timed (with `--profile`, to count the evaluations),
+/// not the construction of its initial solution.
+pub fn run(
+    iterations: usize,
+    samples: usize,
+    seed: u64,
+    search_scope: SearchScope,
+    search_top_k: usize,
+    writer: &mut impl Write,
+) -> Result<(), Box<dyn Error>> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let scope = match search_scope {
+        SearchScope::TopK => format!("top-{search_top_k}"),
+        scope => scope.to_string(),
+    };
+    writeln!(
+        writer,
+        "instance,customers,trucks,drones,search_scope,iterations,samples,seconds,iterations_per_second,\
+         iterations_per_second_stddev,evaluations_per_second"
+    )?;
+    for (customers, trucks, drones) in INSTANCES {
+        let name = format!("bench-{customers}.{trucks}.{drones}-{seed}");
+        let path = env::temp_dir().join(format!("{name}.txt"));
+        fs::write(&path, _instance(customers, trucks, drones, &mut rng))?;
+
+        let arguments = cli::Arguments::try_parse_from([
+            "min-timespan-delivery",
+            "run",
+            path.to_str().ok_or("Non-UTF-8 temporary directory")?,
+            "--fix-iteration",
+            &iterations.to_string(),
+            "--disable-logging",
+            "--quiet",
+            "--profile",
+            "--search-scope",
+            &search_scope.to_string(),
+            "--search-top-k",
+            &search_top_k.to_string(),
+        ])?;
+        let cli::Commands::Run(arguments) = arguments.command else {
+            unreachable!()
+        };
+        CONFIG.configure(Config::from(arguments));
+        fs::remove_file(&path)?;
+
+        let mut seconds = vec![];
+        let mut throughputs = vec![];
+        let mut evaluations = vec![];
+        for _ in 0..samples {
+            solutions::reset_penalty_coeff();
+            let root = Solution::initialize();
+            let mut logger = Logger::detached()?;
+
+            profile::reset();
+            let start = Instant::now();
+            Solution::tabu_search(root, &mut logger);
+            let elapsed = start.elapsed().as_secs_f64();
+
+            seconds.push(elapsed);
+            throughputs.push(iterations as f64 / elapsed);
+            evaluations.push(profile::calls(Phase::Evaluation) as f64 / elapsed);
+        }
+
+        let (throughput, stddev) = _statistics(&throughputs);
+        writeln!(
+            writer,
+            "{name},{customers},{trucks},{drones},{scope},{iterations},{samples},{:.3},{throughput:.1},{stddev:.1},{:.0}",
+            _statistics(&seconds).0,
+            _statistics(&evaluations).0,
+        )?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,74 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use crate::errors::ExpectedValue;
+
+/// Load the best-known-solution registry (instance name -> best known objective value, see `--objective`) from `path`.
+///
+/// A missing file is treated as an empty registry.
+pub fn load(path: &str) -> Result<BTreeMap<String, f64>, Box<dyn Error>> {
+    if !Path::new(path).is_file() {
+        return Ok(BTreeMap::new());
+    }
+
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+/// Percentage gap of `value` relative to the best known objective value `bks`
+pub fn gap(value: f64, bks: f64) -> f64 {
+    100.0 * (value - bks) / bks
+}
+
+/// Objective value of the solution of the run JSON `data` under the `--objective` of its run (the makespan for runs
+/// written before it existed)
+fn _objective(data: &serde_json::Value) -> Result<f64, Box<dyn Error>> {
+    let field = |key: &str| ExpectedValue::cast(data["solution"][key].as_f64());
+    Ok(match data["config"]["objective"].as_str() {
+        Some("total-time") => field("total_time")?,
+        Some("total-energy") => field("total_energy")?,
+        Some("weighted") => {
+            let weight = ExpectedValue::cast(data["config"]["objective_weight"].as_f64())?;
+            weight.mul_add(field("working_time")?, (1.0 - weight) * field("total_time")?)
+        }
+        _ => field("working_time")?,
+    })
+}
+
+/// Record the feasible results in the run JSON files `results` that improve on the best known solutions in
+/// `path`, then write the registry back.
+pub fn update(path: &str, results: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut registry = load(path)?;
+
+    let mut updated = 0;
+    for result in results {
+        let data = serde_json::from_str::<serde_json::Value>(&fs::read_to_string(result)?)?;
+        let problem = ExpectedValue::cast(data["problem"].as_str().map(String::from))?;
+        let value = _objective(&data)?;
+        let feasible = ExpectedValue::cast(data["solution"]["feasible"].as_bool())?;
+
+        if !feasible {
+            eprintln!("Skipping infeasible result {result}");
+            continue;
+        }
+
+        match registry.get(&problem) {
+            Some(&bks) if bks <= value => {}
+            old => {
+                match old {
+                    Some(&bks) => eprintln!("New record for {problem}: {bks} -> {value} ({:.2}%)", gap(value, bks)),
+                    None => eprintln!("New record for {problem}: {value}"),
+                }
+
+                registry.insert(problem, value);
+                updated += 1;
+            }
+        }
+    }
+
+    fs::write(path, serde_json::to_string_pretty(&registry)?)?;
+    eprintln!("Updated {updated} record(s) in {path}");
+
+    Ok(())
+}
@@ -1,6 +1,6 @@
 use std::fmt;
 
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{ArgAction, Args, Parser, Subcommand, ValueEnum};
 use serde::{Deserialize, Serialize};
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Deserialize, Serialize)]
@@ -13,6 +13,8 @@ pub enum EnergyModel {
     Endurance = 2,
     #[serde(rename = "unlimited")]
     Unlimited = 3,
+    #[serde(rename = "table")]
+    Table = 4,
 }
 
 impl fmt::Display for EnergyModel {
@@ -25,6 +27,7 @@ impl fmt::Display for EnergyModel {
                 Self::NonLinear => "non-linear",
                 Self::Endurance => "endurance",
                 Self::Unlimited => "unlimited",
+                Self::Table => "table",
             }
         )
     }
@@ -61,6 +64,8 @@ pub enum Strategy {
     Vns,
     #[serde(rename = "adaptive")]
     Adaptive,
+    #[serde(rename = "ucb")]
+    Ucb,
 }
 
 impl fmt::Display for Strategy {
@@ -73,6 +78,126 @@ impl fmt::Display for Strategy {
                 Self::Cyclic => "cyclic",
                 Self::Vns => "vns",
                 Self::Adaptive => "adaptive",
+                Self::Ucb => "ucb",
+            }
+        )
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Deserialize, Serialize)]
+pub enum Acceptance {
+    #[serde(rename = "tabu")]
+    Tabu,
+    #[serde(rename = "lahc")]
+    Lahc,
+}
+
+impl fmt::Display for Acceptance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Tabu => "tabu",
+                Self::Lahc => "lahc",
+            }
+        )
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Deserialize, Serialize)]
+pub enum PenaltyRestart {
+    #[serde(rename = "keep")]
+    Keep,
+    #[serde(rename = "reset")]
+    Reset,
+    #[serde(rename = "profile")]
+    Profile,
+}
+
+impl fmt::Display for PenaltyRestart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Keep => "keep",
+                Self::Reset => "reset",
+                Self::Profile => "profile",
+            }
+        )
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Deserialize, Serialize)]
+pub enum SearchScope {
+    #[serde(rename = "decisive")]
+    Decisive,
+    #[serde(rename = "top-k")]
+    TopK,
+    #[serde(rename = "all")]
+    All,
+}
+
+impl fmt::Display for SearchScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Decisive => "decisive",
+                Self::TopK => "top-k",
+                Self::All => "all",
+            }
+        )
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Deserialize, Serialize)]
+pub enum TieBreak {
+    #[serde(rename = "first")]
+    First,
+    #[serde(rename = "lexicographic")]
+    Lexicographic,
+    #[serde(rename = "random")]
+    Random,
+}
+
+impl fmt::Display for TieBreak {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::First => "first",
+                Self::Lexicographic => "lexicographic",
+                Self::Random => "random",
+            }
+        )
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Deserialize, Serialize)]
+pub enum InstanceFormat {
+    #[serde(rename = "custom")]
+    Custom,
+    #[serde(rename = "cvrplib")]
+    Cvrplib,
+    /// Only the coordinates and demands of a Solomon/Gehring-Homberger instance, since the model has no time windows
+    /// or service times
+    #[serde(rename = "solomon-coordinates", alias = "solomon")]
+    SolomonCoordinates,
+}
+
+impl fmt::Display for InstanceFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Custom => "custom",
+                Self::Cvrplib => "cvrplib",
+                Self::SolomonCoordinates => "solomon-coordinates",
             }
         )
     }
@@ -84,6 +209,9 @@ pub enum DistanceType {
     Manhattan,
     #[serde(rename = "euclidean")]
     Euclidean,
+    /// Great-circle distance in meters, with x and y interpreted as longitude and latitude in degrees
+    #[serde(rename = "haversine")]
+    Haversine,
 }
 
 impl fmt::Display for DistanceType {
@@ -94,13 +222,350 @@ impl fmt::Display for DistanceType {
             match self {
                 Self::Manhattan => "manhattan",
                 Self::Euclidean => "euclidean",
+                Self::Haversine => "haversine",
+            }
+        )
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Deserialize, Serialize)]
+pub enum Objective {
+    #[serde(rename = "makespan")]
+    Makespan,
+    #[serde(rename = "total-time")]
+    TotalTime,
+    #[serde(rename = "total-energy")]
+    TotalEnergy,
+    #[serde(rename = "weighted")]
+    Weighted,
+}
+
+impl fmt::Display for Objective {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Makespan => "makespan",
+                Self::TotalTime => "total-time",
+                Self::TotalEnergy => "total-energy",
+                Self::Weighted => "weighted",
+            }
+        )
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum, Deserialize, Serialize)]
+pub enum LogFormat {
+    #[serde(rename = "csv")]
+    Csv,
+    #[serde(rename = "jsonl")]
+    Jsonl,
+    /// Requires the "parquet" feature
+    #[serde(rename = "parquet")]
+    Parquet,
+}
+
+impl fmt::Display for LogFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Csv => "csv",
+                Self::Jsonl => "jsonl",
+                Self::Parquet => "parquet",
+            }
+        )
+    }
+}
+
+/// Groups of columns of the iteration log file (the iteration number is always written)
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum, Deserialize, Serialize)]
+pub enum LogColumn {
+    #[serde(rename = "cost")]
+    Cost,
+    #[serde(rename = "working-time")]
+    WorkingTime,
+    #[serde(rename = "feasible")]
+    Feasible,
+    /// Penalty coefficients and violations of all constraints
+    #[serde(rename = "violations")]
+    Violations,
+    /// Customers of every truck and drone route
+    #[serde(rename = "routes")]
+    Routes,
+    #[serde(rename = "routes-count")]
+    RoutesCount,
+    #[serde(rename = "neighborhood")]
+    Neighborhood,
+    #[serde(rename = "tabu-list")]
+    TabuList,
+}
+
+impl fmt::Display for LogColumn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Cost => "cost",
+                Self::WorkingTime => "working-time",
+                Self::Feasible => "feasible",
+                Self::Violations => "violations",
+                Self::Routes => "routes",
+                Self::RoutesCount => "routes-count",
+                Self::Neighborhood => "neighborhood",
+                Self::TabuList => "tabu-list",
+            }
+        )
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum, Deserialize, Serialize)]
+pub enum LogCompression {
+    #[serde(rename = "gzip")]
+    Gzip,
+    #[serde(rename = "zstd")]
+    Zstd,
+}
+
+impl fmt::Display for LogCompression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Gzip => "gzip",
+                Self::Zstd => "zstd",
+            }
+        )
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum, Deserialize, Serialize)]
+pub enum ProgressFormat {
+    /// Human-readable progress messages (see `--verbose`)
+    #[serde(rename = "human")]
+    Human,
+    /// Additionally, one JSON object per line with the search progress every `--progress-interval` seconds
+    #[serde(rename = "json")]
+    Json,
+}
+
+impl fmt::Display for ProgressFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Human => "human",
+                Self::Json => "json",
+            }
+        )
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum, Deserialize, Serialize)]
+pub enum LogBackend {
+    #[serde(rename = "csv")]
+    Csv,
+    #[serde(rename = "sqlite")]
+    Sqlite,
+}
+
+impl fmt::Display for LogBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Csv => "csv",
+                Self::Sqlite => "sqlite",
+            }
+        )
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    Geojson,
+    Schedule,
+}
+
+impl fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Geojson => "geojson",
+                Self::Schedule => "schedule",
+            }
+        )
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum SensitivityParameter {
+    Battery,
+    TruckSpeed,
+    WaitingTimeLimit,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Deserialize, Serialize)]
+pub enum DistanceRounding {
+    #[serde(rename = "none")]
+    None,
+    #[serde(rename = "nearest-int")]
+    NearestInt,
+    #[serde(rename = "truncate")]
+    Truncate,
+    #[serde(rename = "scale1e1")]
+    Scale1e1,
+}
+
+impl fmt::Display for DistanceRounding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::None => "none",
+                Self::NearestInt => "nearest-int",
+                Self::Truncate => "truncate",
+                Self::Scale1e1 => "scale1e1",
+            }
+        )
+    }
+}
+
+impl DistanceRounding {
+    pub fn apply(&self, distance: f64) -> f64 {
+        match self {
+            Self::None => distance,
+            Self::NearestInt => distance.round(),
+            Self::Truncate => distance.trunc(),
+            Self::Scale1e1 => (distance * 10.0).round() / 10.0,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Deserialize, Serialize)]
+pub enum Construction {
+    #[serde(rename = "cluster")]
+    Cluster,
+    #[serde(rename = "savings")]
+    Savings,
+    #[serde(rename = "regret")]
+    Regret,
+}
+
+impl fmt::Display for Construction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Cluster => "cluster",
+                Self::Savings => "savings",
+                Self::Regret => "regret",
+            }
+        )
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Deserialize, Serialize)]
+pub enum Clustering {
+    #[serde(rename = "sweep")]
+    Sweep,
+    #[serde(rename = "kmeans")]
+    Kmeans,
+    #[serde(rename = "kmedoids")]
+    Kmedoids,
+}
+
+impl fmt::Display for Clustering {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Sweep => "sweep",
+                Self::Kmeans => "kmeans",
+                Self::Kmedoids => "kmedoids",
+            }
+        )
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Deserialize, Serialize)]
+pub enum DistanceUnit {
+    #[serde(rename = "m")]
+    M,
+    #[serde(rename = "km")]
+    Km,
+}
+
+impl fmt::Display for DistanceUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::M => "m",
+                Self::Km => "km",
+            }
+        )
+    }
+}
+
+impl DistanceUnit {
+    /// Length of this unit in meters
+    pub const fn meters(&self) -> f64 {
+        match self {
+            Self::M => 1.0,
+            Self::Km => 1000.0,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Deserialize, Serialize)]
+pub enum TimeUnit {
+    #[serde(rename = "s")]
+    S,
+    #[serde(rename = "min")]
+    Min,
+}
+
+impl fmt::Display for TimeUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::S => "s",
+                Self::Min => "min",
             }
         )
     }
 }
 
+impl TimeUnit {
+    /// Duration of this unit in seconds
+    pub const fn seconds(&self) -> f64 {
+        match self {
+            Self::S => 1.0,
+            Self::Min => 60.0,
+        }
+    }
+}
+
 impl DistanceType {
-    pub fn matrix(&self, x: &[f64], y: &[f64]) -> Vec<Vec<f64>> {
+    /// Mean radius of the Earth (in meters)
+    const EARTH_RADIUS: f64 = 6_371_000.0;
+
+    pub fn matrix(&self, x: &[f64], y: &[f64], rounding: DistanceRounding) -> Vec<Vec<f64>> {
         let n = x.len();
         assert_eq!(n, y.len());
 
@@ -109,10 +574,17 @@ impl DistanceType {
             for j in 0..n {
                 let dx = x[i] - x[j];
                 let dy = y[i] - y[j];
-                matrix[i][j] = match self {
+                matrix[i][j] = rounding.apply(match self {
                     Self::Manhattan => dx.abs() + dy.abs(),
                     Self::Euclidean => (dx * dx + dy * dy).sqrt(),
-                };
+                    Self::Haversine => {
+                        let (lat_i, lat_j) = (y[i].to_radians(), y[j].to_radians());
+                        let sin_lat = (dy.to_radians() / 2.0).sin();
+                        let sin_lon = (dx.to_radians() / 2.0).sin();
+                        let h = (lat_i.cos() * lat_j.cos()).mul_add(sin_lon * sin_lon, sin_lat * sin_lat);
+                        2.0 * Self::EARTH_RADIUS * h.sqrt().min(1.0).asin()
+                    }
+                });
             }
         }
 
@@ -143,129 +615,815 @@ pub enum Commands {
         config: String,
     },
 
-    /// Run the algorithm
-    Run {
-        /// Path to the coordinate file
-        problem: String,
+    /// Simulate an existing solution under random perturbations of travel times and demands, and print the
+    /// distribution of its realized makespan and constraint violations as JSON
+    Simulate {
+        /// Path to the solution JSON file
+        solution: String,
+
+        /// Path to the config JSON file
+        config: String,
 
-        /// Path to truck config file
-        #[arg(long, default_value_t = String::from("problems/config_parameter/truck_config.json"))]
-        truck_cfg: String,
+        /// Number of simulated samples
+        #[arg(long, default_value_t = 1000)]
+        samples: usize,
 
-        /// Path to drone config file
-        #[arg(long, default_value_t = String::from("problems/config_parameter/drone_endurance_config.json"))]
-        drone_cfg: String,
+        /// Relative noise of each leg travel time: every travel time is scaled by a factor drawn uniformly from
+        /// [1 - noise, 1 + noise]
+        #[arg(long, default_value_t = 0.1)]
+        time_noise: f64,
 
-        /// The energy consumption model to use.
-        #[arg(short, long, default_value_t = EnergyModel::Endurance)]
-        config: EnergyModel,
+        /// Relative noise of each customer demand: every demand is scaled by a factor drawn uniformly from
+        /// [1 - noise, 1 + noise]
+        #[arg(long, default_value_t = 0.1)]
+        demand_noise: f64,
+    },
 
-        /// Tabu size of each neighborhood, final value = [--tabu-size-factor] * [Base]
-        #[arg(long, default_value_t = 0.75)]
-        tabu_size_factor: f64,
+    /// Re-evaluate an existing solution while sweeping one parameter over a range, and print a CSV of its makespan,
+    /// violations and feasibility for each value
+    Sensitivity {
+        /// Path to the solution JSON file
+        solution: String,
 
-        /// Number of non-improved iterations per adaptive segment = [--adaptive-iterations] * [Base]
-        #[arg(long, default_value_t = 60)]
-        adaptive_iterations: usize,
+        /// Path to the config JSON file
+        config: String,
 
-        /// Fixed number of iterations per adaptive segment = [--adaptive-iterations] * [Base]
+        /// The parameter to sweep:
+        ///
+        /// - battery: battery capacity of every drone class
+        ///
+        /// - truck-speed: truck speed
+        ///
+        /// - waiting-time-limit: waiting time limit of every customer
         #[arg(long)]
-        adaptive_fixed_iterations: bool,
+        parameter: SensitivityParameter,
 
-        /// Number of non-improved segments before resetting the current solution = [--adaptive-segments]
-        /// (note: in "adaptive" strategy, "--reset-after-factor" is ignored)
-        #[arg(long, default_value_t = 7)]
-        adaptive_segments: usize,
+        /// First value of the sweep
+        #[arg(long)]
+        from: f64,
 
-        /// Infer --adaptive-segments as a fixed number of segments per reset.
+        /// Last value of the sweep
         #[arg(long)]
-        adaptive_fixed_segments: bool,
+        to: f64,
 
-        /// The number of ejection chain iterations to run when the elite set is popped
-        #[arg(long, default_value_t = 0)]
-        ejection_chain_iterations: usize,
+        /// Number of evenly spaced values between --from and --to (inclusive)
+        #[arg(long, default_value_t = 11)]
+        steps: usize,
+    },
 
-        /// The destroy rate during destroy-and-repair procedure when the elite set is popped,
-        /// but before ejection-chain is executed (set to 0 to disable destroy-and-repair)
-        #[arg(long, default_value_t = 0.1)]
-        destroy_rate: f64,
+    /// Remove each used vehicle of an existing solution in turn, repair its customers into the remaining vehicles and
+    /// print a CSV of the resulting makespan and feasibility for each vehicle
+    VehicleRemoval {
+        /// Path to the solution JSON file
+        solution: String,
 
-        /// Speed type of drones.
-        #[arg(long, default_value_t = ConfigType::High)]
-        speed_type: ConfigType,
+        /// Path to the config JSON file
+        config: String,
+    },
 
-        /// Range type of drones.
-        #[arg(long, default_value_t = ConfigType::High)]
-        range_type: ConfigType,
+    /// Make an existing solution feasible, e.g. after it was edited by hand: customers visited twice, served by the
+    /// wrong vehicle type or causing violations are removed and reinserted along with the unserved customers. The
+    /// repaired solution is written like that of "run".
+    Repair {
+        /// Path to the solution JSON file
+        solution: String,
 
-        /// Distance type to use for trucks.
-        #[arg(long, default_value_t = DistanceType::Euclidean)]
-        truck_distance: DistanceType,
+        /// Path to the config JSON file
+        config: String,
+    },
+
+    /// Compare two solutions of the same instance and print which customers changed vehicle, route or position, along
+    /// with the Hamming distance between them
+    Diff {
+        /// Path to the solution JSON file to compare from
+        first: String,
 
-        /// Distance type to use for drones.
-        #[arg(long, default_value_t = DistanceType::Euclidean)]
-        drone_distance: DistanceType,
+        /// Path to the solution JSON file to compare to
+        second: String,
+
+        /// Path to the config JSON file, used to evaluate both solutions
+        config: String,
+    },
 
-        /// The number of trucks to override. Otherwise, use the default value.
+    /// Convert an existing solution to another format, e.g. for inspection in a GIS tool
+    Convert {
+        /// Path to the solution JSON file
+        solution: String,
+
+        /// Path to the config JSON file
+        config: String,
+
+        /// The output format:
+        ///
+        /// - geojson: a FeatureCollection with one LineString per route (with vehicle, route and sequence properties)
+        ///   and one Point per node
+        ///
+        /// - schedule: the trips of each vehicle with their start and end times, and the arrival time at each
+        ///   customer
+        #[arg(long, default_value_t = ExportFormat::Geojson)]
+        export: ExportFormat,
+
+        /// Path to the output file. Printed to stdout if omitted
         #[arg(long)]
-        trucks_count: Option<usize>,
+        output: Option<String>,
+    },
 
-        /// The number of drones to override. Otherwise, use the default value.
+    /// Plot an existing solution to an SVG file
+    Plot {
+        /// Path to the solution JSON file
+        solution: String,
+
+        /// Path to the config JSON file
+        config: String,
+
+        /// Path to the output SVG file. Defaults to the solution path with an ".svg" extension
         #[arg(long)]
-        drones_count: Option<usize>,
+        output: Option<String>,
 
-        /// The waiting time limit for each customer (in seconds).
-        #[arg(long, default_value_t = 3600.0)]
-        waiting_time_limit: f64,
+        /// Plot the schedule of the vehicles as a Gantt chart instead of the routes
+        #[arg(long)]
+        gantt: bool,
+    },
+
+    /// Re-plan the remainder of an existing solution after some time has elapsed, adding newly arrived customers.
+    /// The customers already reached (or being flown or driven to) are frozen, vehicles in the middle of a route
+    /// return to the depot from there, and the remaining customers are re-optimized with a repair followed by a tabu
+    /// search, using the parameters of the given config
+    Reoptimize {
+        /// Path to the solution JSON file
+        solution: String,
 
-        /// Tabu search neighborhood selection strategy.
-        #[arg(long, default_value_t = Strategy::Adaptive)]
-        strategy: Strategy,
+        /// Path to the config JSON file
+        config: String,
+
+        /// Path to a file containing the new customers, one per line in the custom instance format (x, y, dronable,
+        /// demand and an optional waiting time limit). Read from stdin if omitted
+        #[arg(long)]
+        customers: Option<String>,
+
+        /// Time elapsed since the vehicles left the depot
+        #[arg(long)]
+        elapsed: f64,
 
-        /// Fix the number of iterations and disable elite set extraction. Otherwise, run until the elite set is exhausted.
+        /// Override the fixed number of iterations of the config
         #[arg(long)]
         fix_iteration: Option<usize>,
+    },
+
+    /// Run the algorithm
+    Run(RunArgs),
+
+    /// Load the problem and the configs exactly like "run" and print the resolved configuration (customers served
+    /// by each vehicle type, distance matrices, fleet and the hyperparameters derived for the tabu search) without
+    /// running the search
+    ExplainConfig(RunArgs),
+
+    /// Solve an instance of at most 12 customers exactly, loading the problem and the configs exactly like "run": the
+    /// best feasible route of each subset of customers is enumerated, then the subsets are split into the routes of
+    /// each vehicle and distributed among the vehicles by dynamic programming. Meant to validate the heuristic and the
+    /// evaluation on tiny instances, only hard constraints and the makespan, total-time and total-energy objectives
+    /// are supported. The optimal solution is written like that of "run".
+    Exact(RunArgs),
+
+    /// Load the problem and the configs exactly like "run" and print a mixed integer linear formulation of the instance
+    /// in the LP file format, to cross-check small instances with an external solver (e.g. Gurobi or CBC). The model
+    /// minimizes the makespan subject to the capacity, energy and fixed time constraints and the assignment rules,
+    /// which requires the linear, endurance or unlimited energy model. The waiting time limits are not modeled.
+    ExportMilp(RunArgs),
+
+    /// Keep running and answer solve and evaluate requests over HTTP: `POST /solve` with a JSON body
+    /// `{"config": ..., "problem": ...}` returns the run JSON, `POST /evaluate` with `{"config": ..., "solution": ...}`
+    /// returns the re-evaluated solution. The config is a config JSON as written by `run`, whose keys are replaced by
+    /// those of the optional problem object. Keys naming server-side files or commands (the distance matrices,
+    /// --no-fly-zones, --pin, --assignments, --zones, --bks-file and --init-command) are rejected, and the outputs are
+    /// always disabled. Each request is solved on its own thread with its own configuration.
+    Serve {
+        /// The address to listen on. Requests can run arbitrary searches, only listen on a public interface behind a
+        /// trusted proxy.
+        #[arg(long, default_value_t = String::from("127.0.0.1"))]
+        bind: String,
+
+        /// The port to listen on
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+
+        /// Maximum size of a request body in bytes, larger requests are answered with 413
+        #[arg(long, default_value_t = 16 << 20)]
+        max_body: usize,
+
+        /// Seconds a client may take to send its request or receive its response before the connection is dropped
+        #[arg(long, default_value_t = 30)]
+        timeout: u64,
+
+        /// Maximum number of requests processed concurrently, further requests are answered with 503
+        #[arg(long, default_value_t = 4)]
+        workers: usize,
+    },
+
+    /// Run a fixed number of iterations on a few deterministic synthetic instances and print a CSV of the
+    /// iterations and candidate evaluations per second on each, to measure performance changes between builds
+    Bench {
+        /// Number of tabu search iterations per sample
+        #[arg(long, default_value_t = 1000)]
+        iterations: usize,
 
-        /// The number of non-improved iterations before resetting the current solution = [--reset-after-factor] * [Base]
-        #[arg(long, default_value_t = 125.0)]
-        reset_after_factor: f64,
+        /// Number of searches per instance, whose throughputs are averaged
+        #[arg(long, default_value_t = 3)]
+        samples: usize,
 
-        /// The maximum size of the elite set
+        /// Seed of the instance generator
         #[arg(long, default_value_t = 0)]
-        max_elite_size: usize,
+        seed: u64,
 
-        /// Exponent value E attached to the cost function:
-        ///
-        /// Cost(S) = [working time] * (1 + [weighted penalty values]).powf(E)
-        #[arg(long, default_value_t = 0.5)]
-        penalty_exponent: f64,
+        /// Vehicles searched at each iteration (see the "run" subcommand)
+        #[arg(long, default_value_t = SearchScope::Decisive)]
+        search_scope: SearchScope,
 
-        /// Allow one route per truck only (this route can still serve multiple customers)
-        #[arg(long)]
-        single_truck_route: bool,
+        /// Number of vehicles searched with "--search-scope top-k"
+        #[arg(long, default_value_t = 3)]
+        search_top_k: usize,
+    },
 
-        /// Allow one customer per drone route only (each drone can still perform multiple routes)
-        #[arg(long)]
-        single_drone_route: bool,
+    /// Manage the best-known-solution registry
+    Bks {
+        #[command(subcommand)]
+        command: BksCommands,
+    },
 
-        /// The verbose mode
-        #[arg(short, long)]
-        verbose: bool,
+    /// Upgrade run JSON files written by older versions to the current schema (see their `schema_version`) in place,
+    /// filling the fields added since. Solution JSON files have their missing fields filled the same way.
+    Migrate {
+        /// Paths to the run or solution JSON files
+        #[arg(required = true)]
+        files: Vec<String>,
+    },
+}
 
-        /// The directory to store results
-        #[arg(long, default_value_t = String::from("outputs/"))]
-        outputs: String,
+/// Arguments of the "run" and "explain-config" subcommands
+#[derive(Args, Debug)]
+pub struct RunArgs {
+    /// Path to the coordinate file, or "-" to read it from stdin
+    pub problem: String,
 
-        /// Disable CSV logging per iteration (this can significantly reduce the running time)
-        #[arg(long)]
-        disable_logging: bool,
+    /// TOML, YAML or JSON file of parameters of this subcommand, named after their flags (e.g. `tabu-size-factor`
+    /// or `tabu_size_factor`). Flags given on the command line take precedence over the file.
+    #[arg(long)]
+    pub params: Option<String>,
 
-        /// Do not run the algorithm, only generate the config file
-        #[arg(long)]
-        dry_run: bool,
+    /// Format of the problem file. CVRPLIB and Solomon instances use CAPACITY as the truck capacity and mark every
+    /// customer as dronable (subject to the drone's capacity and range). "solomon-coordinates" reads the coordinates
+    /// and demands of Solomon instances only: their time windows and service times are ignored.
+    #[arg(long, default_value_t = InstanceFormat::Custom)]
+    pub format: InstanceFormat,
+
+    /// Path to truck config file
+    #[arg(long, default_value_t = String::from("problems/config_parameter/truck_config.json"))]
+    pub truck_cfg: String,
+
+    /// Path to drone config file. For a heterogeneous fleet, repeat this option with MODEL:COUNT:PATH values
+    /// (e.g. "linear:2:problems/config_parameter/drone_linear_config.json"). In that case, --config is ignored
+    /// and the number of drones is the sum of all counts.
+    #[arg(long, default_values_t = [String::from("problems/config_parameter/drone_endurance_config.json")])]
+    pub drone_cfg: Vec<String>,
+
+    /// The energy consumption model to use.
+    #[arg(short, long, default_value_t = EnergyModel::Endurance)]
+    pub config: EnergyModel,
+
+    /// Tabu size of each neighborhood, final value = [--tabu-size-factor] * [Base]
+    #[arg(long, default_value_t = 0.75)]
+    pub tabu_size_factor: f64,
 
-        /// Extra data to store in the output JSON
-        #[arg(long, default_value_t = String::new())]
-        extra: String,
+    /// Probability of accepting an improving move that the tabu list forbids, a cheap diversification that helps
+    /// the search escape plateaus without a reset (set to 0 for the strict tabu search)
+    #[arg(long, default_value_t = 0.0)]
+    pub tabu_acceptance: f64,
+
+    /// Number of non-improved iterations per adaptive segment = [--adaptive-iterations] * [Base]
+    #[arg(long, default_value_t = 60)]
+    pub adaptive_iterations: usize,
+
+    /// Fixed number of iterations per adaptive segment = [--adaptive-iterations] * [Base]
+    #[arg(long)]
+    pub adaptive_fixed_iterations: bool,
+
+    /// Number of non-improved segments before resetting the current solution = [--adaptive-segments]
+    /// (note: in "adaptive" strategy, "--reset-after-factor" is ignored)
+    #[arg(long, default_value_t = 7)]
+    pub adaptive_segments: usize,
+
+    /// Infer --adaptive-segments as a fixed number of segments per reset.
+    #[arg(long)]
+    pub adaptive_fixed_segments: bool,
+
+    /// The number of ejection chain iterations to run when the elite set is popped
+    #[arg(long, default_value_t = 0)]
+    pub ejection_chain_iterations: usize,
+
+    /// The destroy rate during destroy-and-repair procedure when the elite set is popped,
+    /// but before ejection-chain is executed (set to 0 to disable destroy-and-repair)
+    #[arg(long, default_value_t = 0.1)]
+    pub destroy_rate: f64,
+
+    /// Weight of the long-term frequency memory during the repair phase of destroy-and-repair. Insertions that
+    /// reuse frequently visited edges and customer-vehicle assignments are penalized (set to 0 to disable)
+    #[arg(long, default_value_t = 0.0)]
+    pub frequency_penalty: f64,
+
+    /// Noise on the insertion costs during the repair phase of destroy-and-repair: each candidate position is priced
+    /// at its cost times a random factor between 1 - [--repair-noise] and 1 + [--repair-noise], so that similar
+    /// partial solutions are not always rebuilt the same way (set to 0 for the greedy repair)
+    #[arg(long, default_value_t = 0.0)]
+    pub repair_noise: f64,
+
+    /// Speed type of drones.
+    #[arg(long, default_value_t = ConfigType::High)]
+    pub speed_type: ConfigType,
+
+    /// Range type of drones.
+    #[arg(long, default_value_t = ConfigType::High)]
+    pub range_type: ConfigType,
+
+    /// Distance type to use for trucks.
+    #[arg(long, default_value_t = DistanceType::Euclidean)]
+    pub truck_distance: DistanceType,
+
+    /// Distance type to use for drones.
+    #[arg(long, default_value_t = DistanceType::Euclidean)]
+    pub drone_distance: DistanceType,
+
+    /// Path to a CSV file containing the full (possibly asymmetric) truck distance matrix, one row per node with
+    /// the depot first. Otherwise, compute distances from the coordinates with --truck-distance.
+    #[arg(long)]
+    pub truck_matrix: Option<String>,
+
+    /// Path to a CSV file containing the full (possibly asymmetric) drone distance matrix, one row per node with
+    /// the depot first. Otherwise, compute distances from the coordinates with --drone-distance.
+    #[arg(long)]
+    pub drone_matrix: Option<String>,
+
+    /// Rounding applied to distances computed from coordinates: "nearest-int" and "truncate" reproduce the
+    /// integer conventions of CVRPLIB/TSPLIB results, "scale1e1" rounds to one decimal place.
+    #[arg(long, default_value_t = DistanceRounding::None)]
+    pub distance_rounding: DistanceRounding,
+
+    /// Unit of the coordinates and distance matrices of the problem. Distances are converted to meters, the unit of the
+    /// truck and drone configs
+    #[arg(long, default_value_t = DistanceUnit::M)]
+    pub distance_unit: DistanceUnit,
+
+    /// Unit of the waiting time limits of the problem file. They are converted to seconds, the unit of the truck and
+    /// drone configs, of the other options and of the results
+    #[arg(long, default_value_t = TimeUnit::S)]
+    pub time_unit: TimeUnit,
+
+    /// Path to a JSON file containing no-fly zones as a list of polygons, each a list of [x, y] vertices. Drones
+    /// cannot fly a direct leg crossing any of these polygons: each such leg of a solution counts as a no-fly violation,
+    /// as does each leg flown against a headwind faster than the drone's cruise speed (see "windSpeed [m/s]" in the
+    /// drone config)
+    #[arg(long)]
+    pub no_fly_zones: Option<String>,
+
+    /// Customers whose location hosts a charging station, e.g. `--charging-stations 4,17`. A drone landing at a
+    /// station recharges just enough to reach the next station or the depot, trading time for energy (see
+    /// `--charging-power`), which lets drones serve customers beyond the range of a single battery.
+    #[arg(long, value_delimiter = ',')]
+    pub charging_stations: Vec<usize>,
+
+    /// Power (in W) at which a drone recharges at a charging station (see `--charging-stations`)
+    #[arg(long, default_value_t = 1000.0)]
+    pub charging_power: f64,
+
+    /// Maximum number of drones that can take off or land at the depot simultaneously (set to 0 for unlimited)
+    #[arg(long, default_value_t = 0)]
+    pub depot_slots: usize,
+
+    /// Time (in seconds) a launch or landing occupies a depot slot in addition to the takeoff/landing time
+    #[arg(long, default_value_t = 0.0)]
+    pub depot_slot_time: f64,
+
+    /// The number of trucks to override. Otherwise, use the default value.
+    #[arg(long)]
+    pub trucks_count: Option<usize>,
+
+    /// The number of drones to override. Otherwise, use the default value.
+    #[arg(long)]
+    pub drones_count: Option<usize>,
+
+    /// Loading time (in seconds) at the depot before each route of a truck except the first one. Otherwise, use
+    /// the "reloadTime (s)" value of the truck config file (0 if absent).
+    #[arg(long)]
+    pub reload_time: Option<f64>,
+
+    /// Battery swap/recharge time (in seconds) added between two consecutive routes of the same drone. Otherwise,
+    /// use the "rechargeTime [s]" value of the drone config file (0 if absent).
+    #[arg(long)]
+    pub recharge_time: Option<f64>,
+
+    /// Ambient temperature (in °C) of the operations. The battery of each drone (the fixed flight time of the
+    /// endurance model) is derated by the factor of `--derating-curve` at this temperature. No derating when absent.
+    #[arg(long, allow_negative_numbers = true)]
+    pub ambient_temperature: Option<f64>,
+
+    /// Usable fraction of the battery capacity by ambient temperature, as TEMPERATURE:FACTOR points (in °C) linearly
+    /// interpolated and extended flat beyond the first and last points (see `--ambient-temperature`)
+    #[arg(
+        long,
+        value_delimiter = ',',
+        allow_hyphen_values = true,
+        default_value = "-20:0.6,-10:0.7,0:0.8,10:0.9,20:1"
+    )]
+    pub derating_curve: Vec<String>,
+
+    /// The waiting time limit for each customer (in seconds).
+    #[arg(long, default_value_t = 3600.0)]
+    pub waiting_time_limit: f64,
+
+    /// Construction heuristic of the initial solution: "cluster" grows the routes of each cluster (see --clustering)
+    /// with the nearest customer, "savings" merges truck routes with the Clarke-Wright savings and "regret" inserts
+    /// the customers by decreasing regret over their two cheapest vehicles. With --verbose, the initial cost of every
+    /// heuristic is logged
+    #[arg(long, default_value_t = Construction::Cluster)]
+    pub construction: Construction,
+
+    /// Construct the initial solution with GRASP instead: each of --grasp-cycles cycles inserts the customers one at
+    /// a time, each drawn at random from those whose cheapest insertion costs at most min + ALPHA * (max - min) over
+    /// the pending customers (0 is greedy, 1 is uniformly random), then runs a short tabu search of
+    /// --grasp-iterations iterations. The best solution of all cycles starts the search
+    #[arg(long, value_name = "ALPHA", conflicts_with = "construction")]
+    pub grasp: Option<f64>,
+
+    /// Number of GRASP construction and short search cycles (see --grasp)
+    #[arg(long, default_value_t = 10, requires = "grasp")]
+    pub grasp_cycles: usize,
+
+    /// Number of tabu search iterations after each GRASP construction (see --grasp)
+    #[arg(long, default_value_t = 100, requires = "grasp")]
+    pub grasp_iterations: usize,
+
+    /// Partition the customers into PARTS clusters with the method of --clustering, solve each of them in parallel
+    /// with its share of the vehicles (an initial solution followed by a short tabu search of --decompose-iterations
+    /// iterations), then start the search from the merged solution. Intended for instances too large to be solved
+    /// end-to-end
+    #[arg(long, value_name = "PARTS")]
+    pub decompose: Option<usize>,
+
+    /// Number of tabu search iterations on each part of the decomposition (see --decompose)
+    #[arg(long, default_value_t = 1000, requires = "decompose")]
+    pub decompose_iterations: usize,
+
+    /// Start the search from the solution of an external program instead: the shell command CMD is run with the
+    /// config JSON of the instance (as written by "run") on its stdin, and must print a JSON object with the
+    /// "truck_routes" and "drone_routes" of a solution (such as a solution JSON) on its stdout. If it does not serve
+    /// every customer exactly once, the solution is repaired like with the "repair" subcommand
+    #[arg(long, value_name = "CMD", conflicts_with_all = ["grasp", "decompose"])]
+    pub init_command: Option<String>,
+
+    /// Number of tabu searches run in parallel from the initial solution, arranged in a ring where each island sends
+    /// its best solution to the next one every --migration-interval iterations. Only the first island logs its
+    /// iterations, and it ends with the best solution of all islands
+    #[arg(long, default_value_t = 1)]
+    pub islands: usize,
+
+    /// Number of iterations between two migrations of the best solutions between islands (see --islands)
+    #[arg(long, default_value_t = 500)]
+    pub migration_interval: usize,
+
+    /// Time the neighborhoods, the evaluation of solutions (Solution::new), destroy_and_repair and the logging
+    /// during the search, printing a breakdown at the end and embedding it in the run JSON. Nested phases are
+    /// included in their parent, e.g. the neighborhoods include the evaluation of their neighbors
+    #[arg(long)]
+    pub profile: bool,
+
+    /// Clustering of the customers among the trucks in the initial solution: "sweep" cuts the angles around the depot
+    /// into sectors, "kmeans" groups the customers by their coordinates and "kmedoids" by the truck distance matrix
+    #[arg(long, default_value_t = Clustering::Sweep)]
+    pub clustering: Clustering,
+
+    /// Tabu search neighborhood selection strategy.
+    #[arg(long, default_value_t = Strategy::Adaptive)]
+    pub strategy: Strategy,
+
+    /// Exploration constant C of the "ucb" strategy, each neighborhood is scored by
+    /// [mean reward] + C * sqrt(2 * ln([total selections]) / [selections])
+    #[arg(long, default_value_t = 1.0)]
+    pub ucb_exploration: f64,
+
+    /// Acceptance criterion of the current solution. "tabu" always moves to the best non-tabu neighbor (with
+    /// aspiration), "lahc" disables the tabu lists and only accepts a neighbor that is not worse than the current
+    /// solution or the current solution [--lahc-length] iterations ago (late acceptance hill climbing).
+    #[arg(long, default_value_t = Acceptance::Tabu)]
+    pub acceptance: Acceptance,
+
+    /// Length of the late acceptance history when "--acceptance lahc" is used
+    #[arg(long, default_value_t = 50)]
+    pub lahc_length: usize,
+
+    /// Vehicles whose routes the neighborhoods modify at each iteration: "decisive" only the busiest vehicle (the one
+    /// with the largest working time, or energy with "--objective total-energy"), "top-k" also the next busiest ones
+    /// up to --search-top-k vehicles, "all" every vehicle. Wider scopes evaluate more neighbors per iteration
+    #[arg(long, default_value_t = SearchScope::Decisive)]
+    pub search_scope: SearchScope,
+
+    /// Number of vehicles searched with "--search-scope top-k"
+    #[arg(long, default_value_t = 3)]
+    pub search_top_k: usize,
+
+    /// Choice among the best neighbors of an iteration when several have exactly the same cost: "first" keeps the
+    /// first one enumerated, "lexicographic" the one whose routes (customers of each truck, then each drone) compare
+    /// lowest, "random" the one whose routes hash lowest with --tie-break-seed. The last two do not depend on the
+    /// enumeration order, so that runs of different versions stay comparable
+    #[arg(long, default_value_t = TieBreak::First)]
+    pub tie_break: TieBreak,
+
+    /// Seed of the "--tie-break random" hash
+    #[arg(long, default_value_t = 0)]
+    pub tie_break_seed: u64,
+
+    /// Seed of the neighborhood selection, the tabu acceptance (see --tabu-acceptance) and the choice, destruction and
+    /// noisy repair of the elite solution to restart from. Otherwise, they are seeded randomly
+    #[arg(long)]
+    pub search_seed: Option<u64>,
+
+    /// Fix the number of iterations and disable elite set extraction. Otherwise, run until the elite set is exhausted.
+    #[arg(long)]
+    pub fix_iteration: Option<usize>,
+
+    /// Stop the search as soon as a feasible solution with an objective (see --objective) at most this value is found, the
+    /// time-to-target is reported in the output JSON
+    #[arg(long)]
+    pub target_cost: Option<f64>,
+
+    /// The number of non-improved iterations before resetting the current solution = [--reset-after-factor] * [Base]
+    #[arg(long, default_value_t = 125.0)]
+    pub reset_after_factor: f64,
+
+    /// The maximum size of the elite set
+    #[arg(long, default_value_t = 0)]
+    pub max_elite_size: usize,
+
+    /// Rebuild the current solution from scratch with the cluster construction (clustering the customers with k-means
+    /// or k-medoids drawn at random) every this many consecutive resets without a new best solution, instead of
+    /// destroying and repairing an elite solution. The elite set is kept, and an exhausted elite set triggers a rebuild
+    /// instead of stopping the search, which only stops once the elite set is exhausted again without a new best
+    /// solution since the last rebuild. Set to 0 to disable
+    #[arg(long, default_value_t = 0)]
+    pub recluster_after: usize,
+
+    /// Whenever a new best solution is found, redistribute its unpinned drone routes among the drones with the
+    /// longest-processing-time rule, keeping the redistribution if it is feasible and cheaper
+    #[arg(long)]
+    pub drone_rebalancing: bool,
+
+    /// Minimum Hamming distance between a new incumbent and every solution in the elite set for it to be admitted
+    /// (set to 0 to admit every new incumbent)
+    #[arg(long, default_value_t = 0)]
+    pub elite_min_distance: usize,
+
+    /// The objective to minimize:
+    ///
+    /// - makespan: the maximum working time among all vehicles
+    ///
+    /// - total-time: the sum of the working times of all vehicles
+    ///
+    /// - total-energy: the total energy consumed by the drones (not available with the endurance energy model)
+    ///
+    /// - weighted: [--objective-weight] * [makespan] + (1 - [--objective-weight]) * [total-time]
+    #[arg(long, default_value_t = Objective::Makespan)]
+    pub objective: Objective,
+
+    /// Weight of the makespan in the "weighted" objective
+    #[arg(long, default_value_t = 0.5)]
+    pub objective_weight: f64,
+
+    /// Comma-separated violations that are penalized with a fixed weight instead of the adaptive penalty
+    /// coefficients, as NAME or NAME=WEIGHT (default weight 1). Solutions violating these constraints are still
+    /// considered feasible, and the remaining violations are reported after the search. Available names: energy,
+    /// capacity, waiting-time, fixed-time, depot-slot, assignment, no-fly
+    #[arg(long, value_delimiter = ',')]
+    pub soft_constraints: Vec<String>,
+
+    /// Exponent value E attached to the cost function:
+    ///
+    /// Cost(S) = max([objective], 1) * (1 + [weighted penalty values]).powf(E)
+    #[arg(long, default_value_t = 0.5)]
+    pub penalty_exponent: f64,
+
+    /// Adaptive penalty coefficients after each reset of the current solution: "keep" carries them over from before
+    /// the reset, "reset" restores them all to 1 and "profile" re-initializes them from the violations of the new
+    /// current solution, each violated constraint starting at the geometric middle of the coefficient range [1, 1000]
+    /// and every other one at 1
+    #[arg(long, default_value_t = PenaltyRestart::Keep)]
+    pub penalty_restart: PenaltyRestart,
+
+    /// Cost added for each truck or drone serving at least one route:
+    ///
+    /// Cost(S) = [objective] * (1 + [weighted penalty values]).powf(E) + [--vehicle-cost] * [vehicles used]
+    #[arg(long, default_value_t = 0.0)]
+    pub vehicle_cost: f64,
+
+    /// Maximum number of distinct feasible routes collected from the visited solutions. After the search ends,
+    /// these routes are recombined into a new solution via greedy set-partitioning (set to 0 to disable)
+    #[arg(long, default_value_t = 0)]
+    pub route_pool: usize,
+
+    /// Maximum number of feasible solutions kept in an archive of solutions that are non-dominated with respect to
+    /// (objective, total drone energy), the objective being that of --objective. The archived front is written to the output directory after the search
+    /// ends (set to 0 to disable)
+    #[arg(long, default_value_t = 0)]
+    pub pareto_archive: usize,
+
+    /// Number of best distinct feasible solutions kept during the search and written to the output directory after it
+    /// ends, as alternatives to the best one (set to 0 to disable)
+    #[arg(long, default_value_t = 0)]
+    pub top_solutions: usize,
+
+    /// Minimum Hamming distance between any two of the --top-solutions solutions. Of two closer solutions, only the
+    /// cheaper one is kept
+    #[arg(long, default_value_t = 1)]
+    pub top_min_distance: usize,
+
+    /// After the search ends, let each truck carry a drone and greedily move customers served by depot drones to
+    /// sorties launched from and retrieved by that truck at its route nodes, with the truck waiting for its drone at
+    /// the rendezvous node. Each sortie is flown by a drone of the class of the depot drone it relieves
+    #[arg(long)]
+    pub mothership: bool,
+
+    /// Split the demand of each customer that no drone can carry whole (but can carry half of) across two visits
+    /// at the same location, which may be served by different vehicles
+    #[arg(long)]
+    pub allow_split: bool,
+
+    /// Path to a JSON file of routes and customer assignments that the search must not modify, e.g. routes already
+    /// committed when re-planning: `{"routes": [{"truck": 0, "customers": [3, 5]}], "customers": [{"customer": 8,
+    /// "drone": 1}]}`. A pinned route is kept as is by its vehicle, a pinned customer may only move between the routes
+    /// of its vehicle.
+    #[arg(long)]
+    pub pin: Option<String>,
+
+    /// Path to a JSON file of rules on the vehicle serving each customer, e.g. `{"forced": [{"customer": 42, "truck":
+    /// 1}], "forbidden": [{"customer": 5, "drone": 0}], "separated": [[10, 11]]}`: a forced customer must be served by
+    /// its vehicle, a forbidden one must not, and separated customers must not share a route. Each broken rule adds
+    /// one to the assignment violation.
+    #[arg(long)]
+    pub assignments: Option<String>,
+
+    /// Path to a JSON file of named zones of customers and the zones each vehicle may enter, e.g. `{"customers":
+    /// {"north": [1, 2, 3], "south": [4, 5]}, "vehicles": [{"truck": 0, "zones": ["north"]}]}`. A vehicle may not serve
+    /// a customer of a zone it may not enter, which breaks an assignment rule (see `--assignments`). Customers outside
+    /// every zone and vehicles that are not listed are unrestricted.
+    #[arg(long)]
+    pub zones: Option<String>,
+
+    /// Include the schedule of each vehicle (start and end of each route, arrival and departure at each customer)
+    /// in the solution JSON
+    #[arg(long)]
+    pub schedule: bool,
+
+    /// Include the legs of each drone route in the solution JSON, with the payload carried, the flight time, the
+    /// energy consumed and the battery energy left at the end of each leg
+    #[arg(long)]
+    pub detailed_routes: bool,
+
+    /// Re-sequence every route after the search ends (exact Held-Karp for routes with at most 15 customers,
+    /// 2-opt/Or-opt otherwise), keeping only the changes that reduce the solution cost
+    #[arg(long)]
+    pub tsp_polish: bool,
+
+    /// Allow one route per truck only (this route can still serve multiple customers)
+    #[arg(long)]
+    pub single_truck_route: bool,
+
+    /// Allow one customer per drone route only (each drone can still perform multiple routes)
+    #[arg(long)]
+    pub single_drone_route: bool,
+
+    /// Increase the verbosity of the progress output: -v reports resets, destroy-and-repair and ejection chains
+    /// with their timings, -vv additionally reports every iteration
+    #[arg(short, long, action = ArgAction::Count)]
+    pub verbose: u8,
+
+    /// Only report warnings and errors (the final result is not printed either)
+    #[arg(short, long, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Format of the progress reported on stderr. The JSON objects contain the iteration, the incumbent and
+    /// current costs, the elite set size and the elapsed time.
+    #[arg(long, default_value_t = ProgressFormat::Human)]
+    pub progress_format: ProgressFormat,
+
+    /// Interval in seconds between two JSON progress objects (see `--progress-format`)
+    #[arg(long, default_value_t = 1.0)]
+    pub progress_interval: f64,
+
+    /// Serve the search progress over HTTP on this port: `/status` reports the iteration, elapsed time and the
+    /// cost, working time and feasibility of the best solution, `/best-solution` returns the best solution JSON
+    #[arg(long)]
+    pub status_port: Option<u16>,
+
+    /// The address the "--status-port" server listens on. The best solution is readable by anyone who can reach it,
+    /// only listen on a public interface on a trusted network
+    #[arg(long, default_value_t = String::from("127.0.0.1"))]
+    pub status_bind: String,
+
+    /// URL to POST the run JSON to once the run is finalized, to be notified of the completion of long runs
+    #[arg(long)]
+    pub notify_url: Option<String>,
+
+    /// Show a live terminal dashboard of the search on stderr (cost curves, neighborhood weights, violations and
+    /// elite set size). Requires building with `--features tui`.
+    #[arg(long)]
+    pub tui: bool,
+
+    /// The directory to store results
+    #[arg(long, default_value_t = String::from("outputs/"))]
+    pub outputs: String,
+
+    /// Identifier of the run in the output file names (and the results database) instead of a random one. The
+    /// run fails if outputs with this identifier already exist, unless `--overwrite` or `--append` is given.
+    #[arg(long)]
+    pub run_id: Option<String>,
+
+    /// Replace the existing outputs of the run given by `--run-id`
+    #[arg(long, requires = "run_id", conflicts_with = "append")]
+    pub overwrite: bool,
+
+    /// Append to the existing iteration log of the run given by `--run-id` (the run, solution and config JSON files
+    /// are replaced). Not supported by Parquet logs and the SQLite backend.
+    #[arg(long, requires = "run_id")]
+    pub append: bool,
+
+    /// Print the solution JSON to stdout instead of the paths of the output files
+    #[arg(long)]
+    pub stdout_solution: bool,
+
+    /// Disable CSV logging per iteration (this can significantly reduce the running time)
+    #[arg(long)]
+    pub disable_logging: bool,
+
+    /// Maximum fraction of wall-clock time that CSV logging may consume. When exceeded, the logging granularity
+    /// is automatically downgraded (full -> sampled -> improvements-only). Otherwise, always log every iteration.
+    #[arg(long, value_parser = _fraction)]
+    pub log_budget: Option<f64>,
+
+    /// Where to store the results: loose CSV/JSON files per run, or the SQLite database `results.sqlite3` in the
+    /// output directory, to which runs, iterations and final solutions of multiple runs are appended
+    #[arg(long, default_value_t = LogBackend::Csv)]
+    pub log_backend: LogBackend,
+
+    /// Format of the per-iteration log file of the CSV backend: CSV rows, JSON Lines with one object per iteration
+    /// (routes and tabu list as nested arrays), or Parquet with typed columns (requires building with the
+    /// "parquet" feature)
+    #[arg(long, default_value_t = LogFormat::Csv)]
+    pub log_format: LogFormat,
+
+    /// Write only every N-th iteration to the iteration log (iterations improving the best solution are always
+    /// written)
+    #[arg(long, default_value_t = 1)]
+    pub log_interval: usize,
+
+    /// Column groups of the iteration log file, e.g. drop "routes" to avoid dumping every route at each iteration
+    #[arg(long, value_delimiter = ',', default_values_t = LogColumn::value_variants().to_vec())]
+    pub log_columns: Vec<LogColumn>,
+
+    /// Compress the iteration log file (adding the ".gz" or ".zst" extension to its name). Parquet files use the
+    /// corresponding internal compression instead.
+    #[arg(long)]
+    pub log_compress: Option<LogCompression>,
+
+    /// Path to a JSON map of instance name -> best known objective value (see --objective). When the instance is
+    /// present, the percentage gap to the best known solution is reported in the verbose progress line and the output JSON.
+    #[arg(long)]
+    pub bks_file: Option<String>,
+
+    /// Do not run the algorithm, only generate the config file
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Extra data to store in the output JSON
+    #[arg(long, default_value_t = String::new())]
+    pub extra: String,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum BksCommands {
+    /// Record the feasible results of previous runs that improve on the best known solutions
+    Update {
+        /// Path to the best-known-solution JSON file (created if it does not exist)
+        bks_file: String,
+
+        /// Paths to the output JSON files of previous runs
+        #[arg(required = true)]
+        results: Vec<String>,
     },
 }
+
+/// Parse a fraction strictly between 0 and 1
+fn _fraction(value: &str) -> Result<f64, String> {
+    let fraction = value.parse::<f64>().map_err(|e| e.to_string())?;
+    if fraction > 0.0 && fraction < 1.0 {
+        Ok(fraction)
+    } else {
+        Err(format!("{fraction} is not between 0 and 1 (exclusive)"))
+    }
+}
@@ -2,19 +2,157 @@ use std::cmp::min;
 use std::collections::HashMap;
 use std::f64::consts;
 
+use rand::distr::Distribution;
+use rand::distr::weighted::WeightedIndex;
+use rand::seq::IndexedRandom;
+
+use crate::cli::Clustering;
 use crate::config::CONFIG;
+use crate::ids::CustomerId;
+
+/// Maximum number of assignment rounds of k-means and k-medoids
+const MAX_ROUNDS: usize = 100;
+
+/// Partition `customers` into `k` clusters with the method `clustering` (see `--clustering`)
+pub fn clusterize(customers: &mut [CustomerId], k: usize, clustering: Clustering) -> Vec<Vec<CustomerId>> {
+    if customers.is_empty() || k == 0 {
+        return vec![vec![]; k];
+    }
+
+    match clustering {
+        Clustering::Sweep => _sweep(customers, k),
+        Clustering::Kmeans => _kmeans(customers, k),
+        Clustering::Kmedoids => _kmedoids(customers, k),
+    }
+}
+
+/// Pick up to `k` distinct seeds among `customers` with the k-means++ rule: each seed after the first is drawn with a
+/// probability proportional to the squared `distance` from its closest seed.
+fn _seeds(customers: &[CustomerId], k: usize, distance: impl Fn(CustomerId, CustomerId) -> f64) -> Vec<CustomerId> {
+    let mut rng = rand::rng();
+    let mut seeds = vec![*customers.choose(&mut rng).unwrap()];
+    let mut closest = customers.iter().map(|&c| distance(seeds[0], c)).collect::<Vec<_>>();
+    while seeds.len() < k {
+        // Fails when every customer coincides with a seed
+        let Ok(weights) = WeightedIndex::new(closest.iter().map(|d| d * d)) else {
+            break;
+        };
+
+        let seed = customers[weights.sample(&mut rng)];
+        seeds.push(seed);
+        for (d, &c) in closest.iter_mut().zip(customers) {
+            *d = d.min(distance(seed, c));
+        }
+    }
 
-pub fn clusterize(customers: &mut [usize], k: usize) -> Vec<Vec<usize>> {
+    seeds
+}
+
+/// Index of the closest of the `centers` to each customer
+fn _assign(customers: &[CustomerId], centers: usize, distance: impl Fn(usize, CustomerId) -> f64) -> Vec<usize> {
+    customers
+        .iter()
+        .map(|&c| {
+            (0..centers)
+                .min_by(|&i, &j| distance(i, c).total_cmp(&distance(j, c)))
+                .unwrap()
+        })
+        .collect()
+}
+
+fn _group(customers: &[CustomerId], assignment: &[usize], k: usize) -> Vec<Vec<CustomerId>> {
     let mut clusters = vec![vec![]; k];
-    if customers.is_empty() {
-        return clusters;
+    for (&customer, &cluster) in customers.iter().zip(assignment) {
+        clusters[cluster].push(customer);
     }
 
-    let x = &CONFIG.x;
-    let y = &CONFIG.y;
-    let mut angles = HashMap::<usize, f64>::new();
+    clusters
+}
+
+/// Lloyd's k-means on the coordinates of the customers
+fn _kmeans(customers: &[CustomerId], k: usize) -> Vec<Vec<CustomerId>> {
+    let (x, y) = (&CONFIG.load().x, &CONFIG.load().y);
+    let squared =
+        |(cx, cy): (f64, f64), CustomerId(c): CustomerId| (x[c] - cx).mul_add(x[c] - cx, (y[c] - cy) * (y[c] - cy));
+
+    let mut centers = _seeds(customers, k, |i, j| squared((x[i.0], y[i.0]), j).sqrt())
+        .into_iter()
+        .map(|CustomerId(c)| (x[c], y[c]))
+        .collect::<Vec<_>>();
+    let mut assignment = vec![];
+    for _ in 0..MAX_ROUNDS {
+        let next = _assign(customers, centers.len(), |i, c| squared(centers[i], c));
+        if next == assignment {
+            break;
+        }
+        assignment = next;
+
+        for (i, center) in centers.iter_mut().enumerate() {
+            let members = customers
+                .iter()
+                .zip(&assignment)
+                .filter(|&(_, &cluster)| cluster == i)
+                .map(|(&c, _)| c)
+                .collect::<Vec<_>>();
+            // An empty cluster keeps its center
+            if !members.is_empty() {
+                let count = members.len() as f64;
+                *center = (
+                    members.iter().map(|c| x[c.0]).sum::<f64>() / count,
+                    members.iter().map(|c| y[c.0]).sum::<f64>() / count,
+                );
+            }
+        }
+    }
+
+    _group(customers, &assignment, k)
+}
+
+/// Alternating k-medoids on the truck distance matrix, each customer joining the medoid with the shortest truck
+/// distance to it
+fn _kmedoids(customers: &[CustomerId], k: usize) -> Vec<Vec<CustomerId>> {
+    let distances = &CONFIG.load().truck_distances;
+
+    let mut medoids = _seeds(customers, k, |i, j| distances[i.0][j.0]);
+    let mut assignment = vec![];
+    for _ in 0..MAX_ROUNDS {
+        assignment = _assign(customers, medoids.len(), |i, c| distances[medoids[i].0][c.0]);
+
+        let next = (0..medoids.len())
+            .map(|i| {
+                let members = customers
+                    .iter()
+                    .zip(&assignment)
+                    .filter(|&(_, &cluster)| cluster == i)
+                    .map(|(&c, _)| c)
+                    .collect::<Vec<_>>();
+                members
+                    .iter()
+                    .copied()
+                    .min_by(|&m, &n| {
+                        let cost = |medoid: CustomerId| members.iter().map(|c| distances[medoid.0][c.0]).sum::<f64>();
+                        cost(m).total_cmp(&cost(n))
+                    })
+                    .unwrap_or(medoids[i])
+            })
+            .collect::<Vec<_>>();
+        if next == medoids {
+            break;
+        }
+        medoids = next;
+    }
+
+    _group(customers, &assignment, k)
+}
+
+/// Sweep around the depot, cutting the customers sorted by angle into `k` sectors of equal angular width
+fn _sweep(customers: &mut [CustomerId], k: usize) -> Vec<Vec<CustomerId>> {
+    let mut clusters = vec![vec![]; k];
+    let x = &CONFIG.load().x;
+    let y = &CONFIG.load().y;
+    let mut angles = HashMap::<CustomerId, f64>::new();
     for &customer in customers.iter() {
-        let mut angle = (y[customer] - y[0]).atan2(x[customer] - x[0]);
+        let mut angle = (y[customer.0] - y[0]).atan2(x[customer.0] - x[0]);
         if angle < 0.0 {
             angle += 2.0 * consts::PI;
         }
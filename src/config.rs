@@ -1,12 +1,23 @@
+use std::cell::RefCell;
 use std::f64::consts;
-use std::fs;
-use std::sync::LazyLock;
-
-use clap::Parser;
+use std::ops::Deref;
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, LazyLock, Mutex, PoisonError};
+use std::{fs, io, iter};
+
+use clap::ValueEnum;
 use regex::{Regex, RegexBuilder};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::{debug, info, warn};
 
-use crate::cli;
+use crate::assignments::Assignments;
+use crate::ids::{DroneId, VehicleId};
+use crate::objective::{CostFunction, PenalizedObjective};
+use crate::pins::Pins;
+use crate::{cli, params, validation};
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct TruckConfig {
@@ -15,6 +26,10 @@ pub struct TruckConfig {
 
     #[serde(rename = "M_t (kg)")]
     pub capacity: f64,
+
+    /// Loading time at the depot before each route of a truck except the first one
+    #[serde(default, rename = "reloadTime (s)")]
+    pub reload_time: f64,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -45,6 +60,16 @@ pub struct LinearJSON {
 
     #[serde(rename = "gamma(w)")]
     gamma: f64,
+
+    #[serde(default, rename = "rechargeTime [s]")]
+    recharge_time: f64,
+
+    #[serde(default, rename = "windSpeed [m/s]")]
+    wind_speed: f64,
+
+    /// Direction the wind blows from, clockwise from north
+    #[serde(default, rename = "windBearing [deg]")]
+    wind_bearing: f64,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -69,6 +94,16 @@ pub struct NonLinearJSON {
 
     speed_type: cli::ConfigType,
     range_type: cli::ConfigType,
+
+    #[serde(default, rename = "rechargeTime [s]")]
+    recharge_time: f64,
+
+    #[serde(default, rename = "windSpeed [m/s]")]
+    wind_speed: f64,
+
+    /// Direction the wind blows from, clockwise from north
+    #[serde(default, rename = "windBearing [deg]")]
+    wind_bearing: f64,
 }
 
 #[derive(Debug, Deserialize)]
@@ -105,6 +140,143 @@ pub struct EnduranceJSON {
 
     #[serde(rename = "V_max (m/s)")]
     speed: f64,
+
+    #[serde(default, rename = "rechargeTime [s]")]
+    recharge_time: f64,
+
+    #[serde(default, rename = "windSpeed [m/s]")]
+    wind_speed: f64,
+
+    /// Direction the wind blows from, clockwise from north
+    #[serde(default, rename = "windBearing [deg]")]
+    wind_bearing: f64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TableJSON {
+    #[serde(rename = "takeoffSpeed [m/s]")]
+    takeoff_speed: f64,
+
+    #[serde(rename = "cruiseSpeed [m/s]")]
+    cruise_speed: f64,
+
+    #[serde(rename = "landingSpeed [m/s]")]
+    landing_speed: f64,
+
+    #[serde(rename = "cruiseAlt [m]")]
+    altitude: f64,
+
+    #[serde(rename = "capacity [kg]")]
+    capacity: f64,
+
+    #[serde(rename = "batteryPower [Joule]")]
+    battery: f64,
+
+    speed_type: cli::ConfigType,
+    range_type: cli::ConfigType,
+
+    /// Path to the CSV power curve (relative to the config file): one row per payload with the columns
+    /// `payload [kg], takeoff [W], cruise [W], landing [W]`
+    #[serde(rename = "powerCurve")]
+    power_curve: String,
+
+    #[serde(default, rename = "rechargeTime [s]")]
+    recharge_time: f64,
+
+    #[serde(default, rename = "windSpeed [m/s]")]
+    wind_speed: f64,
+
+    /// Direction the wind blows from, clockwise from north
+    #[serde(default, rename = "windBearing [deg]")]
+    wind_bearing: f64,
+}
+
+/// Takeoff, cruise and landing power measured at increasing payloads
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PowerCurve {
+    payload: Vec<f64>,
+    takeoff: Vec<f64>,
+    cruise: Vec<f64>,
+    landing: Vec<f64>,
+}
+
+impl PowerCurve {
+    fn load(path: &Path) -> Self {
+        // Only a leading header line may be unparsable
+        let mut rows = fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .enumerate()
+            .filter_map(|(index, (number, line))| {
+                let row = line
+                    .split(',')
+                    .map(|value| value.trim().parse::<f64>().ok())
+                    .collect::<Option<Vec<f64>>>();
+                if row.is_none() && index > 0 {
+                    panic!("Invalid row {line:?} at {}:{}", path.display(), number + 1);
+                }
+
+                row
+            })
+            .collect::<Vec<Vec<f64>>>();
+        assert!(
+            !rows.is_empty() && rows.iter().all(|row| row.len() == 4),
+            "Power curve {} must have 4 numeric columns (payload, takeoff, cruise, landing)",
+            path.display()
+        );
+        rows.sort_by(|f, s| f[0].total_cmp(&s[0]));
+
+        Self {
+            payload: rows.iter().map(|row| row[0]).collect(),
+            takeoff: rows.iter().map(|row| row[1]).collect(),
+            cruise: rows.iter().map(|row| row[2]).collect(),
+            landing: rows.iter().map(|row| row[3]).collect(),
+        }
+    }
+
+    /// Linearly interpolate `power` at `weight`, clamping to the first and last measurements.
+    fn _interpolate(&self, power: &[f64], weight: f64) -> f64 {
+        let i = self.payload.partition_point(|&p| p < weight);
+        if i == 0 {
+            return power[0];
+        }
+        if i == self.payload.len() {
+            return power[i - 1];
+        }
+
+        let ratio = (weight - self.payload[i - 1]) / (self.payload[i] - self.payload[i - 1]);
+        ratio.mul_add(power[i] - power[i - 1], power[i - 1])
+    }
+}
+
+/// A direct drone leg described without the global config, so that it can be checked while the config is built
+#[derive(Clone, Copy, Debug)]
+pub struct Leg {
+    pub distance: f64,
+    /// Displacement of the leg, with y pointing north (see `DroneConfig::heading_cruise_time`)
+    pub dx: f64,
+    pub dy: f64,
+    /// Elevation gained along the leg (negative when descending)
+    pub climb: f64,
+}
+
+impl Leg {
+    /// Leg from node `from` to node `to` among the nodes at `x` and `y` and `elevations` (empty if flat), separated
+    /// by `distances`
+    pub fn new(from: usize, to: usize, x: &[f64], y: &[f64], elevations: &[f64], distances: &[Vec<f64>]) -> Self {
+        Self {
+            distance: distances[from][to],
+            dx: x[to] - x[from],
+            dy: y[to] - y[from],
+            climb: if elevations.is_empty() {
+                0.0
+            } else {
+                elevations[to] - elevations[from]
+            },
+        }
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -134,6 +306,12 @@ pub enum DroneConfig {
     Endurance {
         _data: EnduranceJSON,
     },
+    Table {
+        _data: TableJSON,
+        _curve: PowerCurve,
+        _takeoff_time: f64,
+        _landing_time: f64,
+    },
 }
 
 impl DroneConfig {
@@ -141,6 +319,7 @@ impl DroneConfig {
     const G: f64 = 9.8;
 
     fn new(path: &String, config: cli::EnergyModel, speed_type: cli::ConfigType, range_type: cli::ConfigType) -> Self {
+        validation::drone_config(path, config, speed_type, range_type);
         match config {
             cli::EnergyModel::Linear => {
                 let data = serde_json::from_str::<Vec<LinearJSON>>(&fs::read_to_string(path).unwrap()).unwrap();
@@ -218,6 +397,29 @@ impl DroneConfig {
 
                 panic!("No matching endurance config")
             }
+            cli::EnergyModel::Table => {
+                let data = serde_json::from_str::<Vec<TableJSON>>(&fs::read_to_string(path).unwrap()).unwrap();
+
+                for config in data {
+                    if config.speed_type == speed_type && config.range_type == range_type {
+                        let curve_path = Path::new(path)
+                            .parent()
+                            .unwrap_or(Path::new(""))
+                            .join(&config.power_curve);
+                        let _curve = PowerCurve::load(&curve_path);
+                        let _takeoff_time = config.altitude / config.takeoff_speed;
+                        let _landing_time = config.altitude / config.landing_speed;
+                        return Self::Table {
+                            _data: config,
+                            _curve,
+                            _takeoff_time,
+                            _landing_time,
+                        };
+                    }
+                }
+
+                panic!("No matching table config")
+            }
             cli::EnergyModel::Unlimited => Self::Endurance {
                 _data: EnduranceJSON {
                     speed_type: cli::ConfigType::High,
@@ -225,16 +427,36 @@ impl DroneConfig {
                     capacity: f64::INFINITY,
                     fixed_time: f64::INFINITY,
                     speed: 1.0,
+                    recharge_time: 0.0,
+                    wind_speed: 0.0,
+                    wind_bearing: 0.0,
                 },
             },
         }
     }
 
+    /// Whether a drone of this class can serve a customer with the given demand on a direct round trip from the
+    /// depot along the `outbound` and `inbound` legs, in the wind and including the energy to climb to the customer
+    /// and back. A leg flown against a headwind faster than the cruise speed rules the customer out.
+    pub fn can_serve(&self, demand: f64, outbound: Leg, inbound: Leg) -> bool {
+        let outbound_cruise = self.heading_cruise_time(outbound.distance, outbound.dx, outbound.dy);
+        let inbound_cruise = self.heading_cruise_time(inbound.distance, inbound.dx, inbound.dy);
+        let energy = self.leg_energy(0.0, outbound_cruise)
+            + self.leg_energy(demand, inbound_cruise)
+            + self._climb_energy(0.0, outbound.climb)
+            + self._climb_energy(demand, inbound.climb);
+
+        demand <= self.capacity()
+            && self.takeoff_time() + outbound_cruise + inbound_cruise + self.landing_time() <= self.fixed_time()
+            && energy <= self.battery()
+    }
+
     pub fn capacity(&self) -> f64 {
         match self {
             Self::Linear { _data, .. } => _data.capacity,
             Self::NonLinear { _data, .. } => _data.capacity,
             Self::Endurance { _data, .. } => _data.capacity,
+            Self::Table { _data, .. } => _data.capacity,
         }
     }
 
@@ -242,17 +464,48 @@ impl DroneConfig {
         match self {
             Self::Linear { _data, .. } => _data.battery,
             Self::NonLinear { _data, .. } => _data.battery,
+            Self::Table { _data, .. } => _data.battery,
             Self::Endurance { .. } => 1.0,
         }
     }
 
     pub fn fixed_time(&self) -> f64 {
         match self {
-            Self::Linear { .. } | Self::NonLinear { .. } => f64::INFINITY,
+            Self::Linear { .. } | Self::NonLinear { .. } | Self::Table { .. } => f64::INFINITY,
             Self::Endurance { _data, .. } => _data.fixed_time,
         }
     }
 
+    /// Battery swap/recharge time between two consecutive routes of the same drone
+    pub fn recharge_time(&self) -> f64 {
+        match self {
+            Self::Linear { _data, .. } => _data.recharge_time,
+            Self::NonLinear { _data, .. } => _data.recharge_time,
+            Self::Endurance { _data, .. } => _data.recharge_time,
+            Self::Table { _data, .. } => _data.recharge_time,
+        }
+    }
+
+    /// Scale the usable battery capacity by `factor` (see `--ambient-temperature`), or the fixed flight time of the
+    /// endurance model, which has no battery
+    fn derate(&mut self, factor: f64) {
+        match self {
+            Self::Linear { _data, .. } => _data.battery *= factor,
+            Self::NonLinear { _data, .. } => _data.battery *= factor,
+            Self::Endurance { _data, .. } => _data.fixed_time *= factor,
+            Self::Table { _data, .. } => _data.battery *= factor,
+        }
+    }
+
+    fn set_recharge_time(&mut self, recharge_time: f64) {
+        match self {
+            Self::Linear { _data, .. } => _data.recharge_time = recharge_time,
+            Self::NonLinear { _data, .. } => _data.recharge_time = recharge_time,
+            Self::Endurance { _data, .. } => _data.recharge_time = recharge_time,
+            Self::Table { _data, .. } => _data.recharge_time = recharge_time,
+        }
+    }
+
     pub fn takeoff_power(&self, weight: f64) -> f64 {
         match self {
             Self::Linear { _data, .. } => _data.beta.mul_add(weight, _data.gamma),
@@ -271,6 +524,7 @@ impl DroneConfig {
                 )
             }
             Self::Endurance { .. } => 0.0,
+            Self::Table { _curve, .. } => _curve._interpolate(&_curve.takeoff, weight),
         }
     }
 
@@ -292,6 +546,36 @@ impl DroneConfig {
                 )
             }
             Self::Endurance { .. } => 0.0,
+            Self::Table { _curve, .. } => _curve._interpolate(&_curve.landing, weight),
+        }
+    }
+
+    /// Energy to climb (or descend) from the elevation of node `from` to that of node `to` while carrying `weight`, at
+    /// the takeoff (or landing) speed and power. Only the nonlinear model accounts for elevation; the climb is assumed
+    /// to overlap the cruise, so it takes no extra time.
+    pub fn climb_energy(&self, weight: f64, from: usize, to: usize) -> f64 {
+        if !matches!(self, Self::NonLinear { .. }) {
+            return 0.0;
+        }
+
+        let config = CONFIG.load();
+        if config.elevations.is_empty() {
+            return 0.0;
+        }
+
+        self._climb_energy(weight, config.elevations[to] - config.elevations[from])
+    }
+
+    /// Energy to climb `climb` m (or descend if negative) while carrying `weight` (see `climb_energy`)
+    fn _climb_energy(&self, weight: f64, climb: f64) -> f64 {
+        let Self::NonLinear { _data, .. } = self else {
+            return 0.0;
+        };
+
+        if climb > 0.0 {
+            self.takeoff_power(weight) * climb / _data.takeoff_speed
+        } else {
+            self.landing_power(weight) * -climb / _data.landing_speed
         }
     }
 
@@ -309,33 +593,311 @@ impl DroneConfig {
                 _hori_c12 * (temp * temp + _hori_c42v4).powf(0.75) + _hori_c4v3
             }
             Self::Endurance { .. } => 0.0,
+            Self::Table { _curve, .. } => _curve._interpolate(&_curve.cruise, weight),
         }
     }
 
+    /// Energy consumed to take off, cruise for `cruise` seconds and land while carrying `weight`
+    pub fn leg_energy(&self, weight: f64, cruise: f64) -> f64 {
+        self.landing_power(weight).mul_add(
+            self.landing_time(),
+            self.takeoff_power(weight)
+                .mul_add(self.takeoff_time(), self.cruise_power(weight) * cruise),
+        )
+    }
+
     pub fn takeoff_time(&self) -> f64 {
         match self {
-            Self::Linear { _takeoff_time, .. } | Self::NonLinear { _takeoff_time, .. } => *_takeoff_time,
+            Self::Linear { _takeoff_time, .. }
+            | Self::NonLinear { _takeoff_time, .. }
+            | Self::Table { _takeoff_time, .. } => *_takeoff_time,
             Self::Endurance { .. } => 0.0,
         }
     }
 
     pub fn landing_time(&self) -> f64 {
         match self {
-            Self::Linear { _landing_time, .. } | Self::NonLinear { _landing_time, .. } => *_landing_time,
+            Self::Linear { _landing_time, .. }
+            | Self::NonLinear { _landing_time, .. }
+            | Self::Table { _landing_time, .. } => *_landing_time,
             Self::Endurance { .. } => 0.0,
         }
     }
 
     pub fn cruise_time(&self, distance: f64) -> f64 {
+        distance / self._cruise_speed()
+    }
+
+    fn _cruise_speed(&self) -> f64 {
         match self {
-            Self::Linear { _data, .. } => distance / _data.cruise_speed,
-            Self::NonLinear { _data, .. } => distance / _data.cruise_speed,
-            Self::Endurance { _data, .. } => distance / _data.speed,
+            Self::Linear { _data, .. } => _data.cruise_speed,
+            Self::NonLinear { _data, .. } => _data.cruise_speed,
+            Self::Endurance { _data, .. } => _data.speed,
+            Self::Table { _data, .. } => _data.cruise_speed,
+        }
+    }
+
+    /// Wind speed and the direction it blows from (in radians, clockwise from north)
+    fn _wind(&self) -> (f64, f64) {
+        let (speed, bearing) = match self {
+            Self::Linear { _data, .. } => (_data.wind_speed, _data.wind_bearing),
+            Self::NonLinear { _data, .. } => (_data.wind_speed, _data.wind_bearing),
+            Self::Endurance { _data, .. } => (_data.wind_speed, _data.wind_bearing),
+            Self::Table { _data, .. } => (_data.wind_speed, _data.wind_bearing),
+        };
+        (speed, bearing.to_radians())
+    }
+
+    /// Whether flying consumes energy, which the endurance model (and "--config unlimited") does not model
+    pub fn consumes_energy(&self) -> bool {
+        !matches!(self, Self::Endurance { .. })
+    }
+
+    pub fn windy(&self) -> bool {
+        self._wind().0 != 0.0
+    }
+
+    /// Cruise time of the leg from node `from` to node `to`, whose ground speed is the cruise speed plus the wind
+    /// component along the leg heading (computed from the node coordinates, with y pointing north). Legs that the
+    /// drone cannot fly against the wind take an infinite time.
+    pub fn leg_cruise_time(&self, distance: f64, from: usize, to: usize) -> f64 {
+        if !self.windy() {
+            return self.cruise_time(distance);
+        }
+
+        self.heading_cruise_time(
+            distance,
+            CONFIG.load().x[to] - CONFIG.load().x[from],
+            CONFIG.load().y[to] - CONFIG.load().y[from],
+        )
+    }
+
+    /// Cruise time of a leg of length `distance` along the displacement (`dx`, `dy`), see `leg_cruise_time`
+    pub fn heading_cruise_time(&self, distance: f64, dx: f64, dy: f64) -> f64 {
+        let (wind_speed, wind_bearing) = self._wind();
+        if wind_speed == 0.0 || (dx == 0.0 && dy == 0.0) {
+            return self.cruise_time(distance);
+        }
+
+        let heading = dx.atan2(dy);
+        let ground_speed = wind_speed.mul_add(-(heading - wind_bearing).cos(), self._cruise_speed());
+        if ground_speed <= 0.0 {
+            return f64::INFINITY;
+        }
+
+        distance / ground_speed
+    }
+}
+
+/// Load the distance matrix from the CSV file `matrix` if provided (one row per node, depot first), otherwise compute
+/// it from the coordinates with `distance_type` and `rounding`.
+/// Distance matrix in meters between the nodes at `x` and `y`, or read from the `matrix` file, both given in `unit`
+fn distances(
+    distance_type: cli::DistanceType,
+    rounding: cli::DistanceRounding,
+    unit: cli::DistanceUnit,
+    matrix: Option<&str>,
+    x: &[f64],
+    y: &[f64],
+) -> Vec<Vec<f64>> {
+    let mut result = matrix.map_or_else(
+        || distance_type.matrix(x, y, rounding),
+        |matrix| _read_matrix(matrix, x.len()),
+    );
+    if unit != cli::DistanceUnit::M {
+        for distance in result.iter_mut().flatten() {
+            *distance *= unit.meters();
+        }
+    }
+
+    result
+}
+
+fn _read_matrix(matrix: &str, n: usize) -> Vec<Vec<f64>> {
+    let result = fs::read_to_string(matrix)
+        .unwrap()
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            line.split(',')
+                .map(|value| value.trim().parse::<f64>().unwrap())
+                .collect::<Vec<f64>>()
+        })
+        .collect::<Vec<Vec<f64>>>();
+
+    assert!(
+        result.len() == n && result.iter().all(|row| row.len() == n),
+        "Distance matrix {matrix} must be {n}x{n}"
+    );
+
+    result
+}
+
+/// Expand a distance matrix between the original nodes to all nodes, each twin node inheriting the distances of its
+/// original customer
+fn split_distances(matrix: &[Vec<f64>], split_of: &[usize]) -> Vec<Vec<f64>> {
+    split_of
+        .iter()
+        .map(|&i| split_of.iter().map(|&j| matrix[i][j]).collect())
+        .collect()
+}
+
+/// Parse `--soft-constraints` entries into the fixed penalty weight of each violation, in the order of the penalty
+/// coefficients: energy, capacity, waiting time, fixed time, depot slots, assignments and no-fly zones
+fn soft_weights(soft_constraints: &[String]) -> [Option<f64>; 7] {
+    const NAMES: [&str; 7] = [
+        "energy",
+        "capacity",
+        "waiting-time",
+        "fixed-time",
+        "depot-slot",
+        "assignment",
+        "no-fly",
+    ];
+
+    let mut weights = [None; 7];
+    for spec in soft_constraints {
+        let (name, weight) = spec.split_once('=').unwrap_or((spec, "1"));
+        let index = NAMES
+            .iter()
+            .position(|&n| n == name.trim())
+            .unwrap_or_else(|| panic!("Unknown soft constraint {name:?}, expected one of {NAMES:?}"));
+        weights[index] = Some(
+            weight
+                .trim()
+                .parse::<f64>()
+                .unwrap_or_else(|_| panic!("Invalid weight in soft constraint {spec:?}")),
+        );
+    }
+
+    weights
+}
+
+fn is_asymmetric(matrix: &[Vec<f64>]) -> bool {
+    matrix
+        .iter()
+        .enumerate()
+        .any(|(i, row)| row.iter().enumerate().any(|(j, &d)| d != matrix[j][i]))
+}
+
+/// Whether segments `p1`-`p2` and `q1`-`q2` intersect (including touching and collinear overlaps)
+fn _segments_intersect(p1: (f64, f64), p2: (f64, f64), q1: (f64, f64), q2: (f64, f64)) -> bool {
+    fn _orientation(a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> f64 {
+        (b.0 - a.0).mul_add(c.1 - a.1, -(b.1 - a.1) * (c.0 - a.0))
+    }
+
+    fn _on_segment(a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> bool {
+        c.0 >= a.0.min(b.0) && c.0 <= a.0.max(b.0) && c.1 >= a.1.min(b.1) && c.1 <= a.1.max(b.1)
+    }
+
+    let d1 = _orientation(q1, q2, p1);
+    let d2 = _orientation(q1, q2, p2);
+    let d3 = _orientation(p1, p2, q1);
+    let d4 = _orientation(p1, p2, q2);
+
+    (d1 * d2 < 0.0 && d3 * d4 < 0.0)
+        || (d1 == 0.0 && _on_segment(q1, q2, p1))
+        || (d2 == 0.0 && _on_segment(q1, q2, p2))
+        || (d3 == 0.0 && _on_segment(p1, p2, q1))
+        || (d4 == 0.0 && _on_segment(p1, p2, q2))
+}
+
+/// Whether `point` lies strictly inside `polygon` (ray casting)
+fn _inside_polygon(point: (f64, f64), polygon: &[(f64, f64)]) -> bool {
+    let mut inside = false;
+    for i in 0..polygon.len() {
+        let (a, b) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+        if (a.1 > point.1) != (b.1 > point.1) && point.0 < (b.0 - a.0) * (point.1 - a.1) / (b.1 - a.1) + a.0 {
+            inside = !inside;
         }
     }
+
+    inside
+}
+
+/// Compute which direct drone legs cross a no-fly zone loaded from the JSON file `path` (a list of polygons, each a
+/// list of `[x, y]` vertices). Returns an empty matrix when no file is given.
+fn no_fly_mask(path: Option<&str>, x: &[f64], y: &[f64]) -> Vec<Vec<bool>> {
+    let Some(path) = path else {
+        return vec![];
+    };
+
+    let polygons = serde_json::from_str::<Vec<Vec<(f64, f64)>>>(&fs::read_to_string(path).unwrap()).unwrap();
+    let n = x.len();
+    let mut mask = vec![vec![false; n]; n];
+    for i in 0..n {
+        for j in i + 1..n {
+            let (p, q) = ((x[i], y[i]), (x[j], y[j]));
+            let crosses = polygons.iter().any(|polygon| {
+                _inside_polygon(p, polygon)
+                    || _inside_polygon(q, polygon)
+                    || (0..polygon.len())
+                        .any(|k| _segments_intersect(p, q, polygon[k], polygon[(k + 1) % polygon.len()]))
+            });
+            mask[i][j] = crosses;
+            mask[j][i] = crosses;
+        }
+    }
+
+    mask
+}
+
+/// Mark the nodes (among `nodes`) hosting a charging station. Returns an empty mask when there are no stations.
+fn charging_mask(stations: &[usize], nodes: usize) -> Vec<bool> {
+    if stations.is_empty() {
+        return vec![];
+    }
+
+    let mut mask = vec![false; nodes];
+    for &station in stations {
+        assert!(
+            (1..nodes).contains(&station),
+            "Charging station {station} is not a customer"
+        );
+        mask[station] = true;
+    }
+
+    mask
+}
+
+/// Usable fraction of the battery capacity at `temperature` on the derating curve `points` (TEMPERATURE:FACTOR pairs,
+/// see `--derating-curve`), linearly interpolated between the points and flat beyond them
+fn derating_factor(points: &[String], temperature: f64) -> f64 {
+    let mut curve = points
+        .iter()
+        .map(|point| {
+            point
+                .split_once(':')
+                .and_then(|(t, f)| Some((t.parse::<f64>().ok()?, f.parse::<f64>().ok()?)))
+                .unwrap_or_else(|| panic!("Invalid derating point {point:?}, expected TEMPERATURE:FACTOR"))
+        })
+        .collect::<Vec<_>>();
+    assert!(!curve.is_empty(), "The derating curve must have at least one point");
+    curve.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let (first, last) = (curve[0], curve[curve.len() - 1]);
+    if temperature <= first.0 {
+        return first.1;
+    }
+    if temperature >= last.0 {
+        return last.1;
+    }
+
+    let k = curve.partition_point(|&(t, _)| t <= temperature);
+    let ((t0, f0), (t1, f1)) = (curve[k - 1], curve[k]);
+    (f1 - f0).mul_add((temperature - t0) / (t1 - t0), f0)
+}
+
+/// Parse a drone class specification of the form MODEL:COUNT:PATH.
+pub fn parse_drone_class(spec: &str) -> Option<(cli::EnergyModel, usize, &str)> {
+    let mut parts = spec.splitn(3, ':');
+    let model = cli::EnergyModel::from_str(parts.next()?, true).ok()?;
+    let count = parts.next()?.parse::<usize>().ok()?;
+    let path = parts.next()?;
+    Some((model, count, path))
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct SerializedConfig {
     customers_count: usize,
     trucks_count: usize,
@@ -345,12 +907,22 @@ pub struct SerializedConfig {
     y: Vec<f64>,
     demands: Vec<f64>,
     dronable: Vec<bool>,
+    #[serde(default)]
+    truckable: Vec<bool>,
+    #[serde(default)]
+    waiting_time_limits: Vec<f64>,
+    #[serde(default)]
+    elevations: Vec<f64>,
 
     truck_distance: cli::DistanceType,
     drone_distance: cli::DistanceType,
 
     truck: TruckConfig,
     drone: DroneConfig,
+    #[serde(default)]
+    drone_classes: Vec<DroneConfig>,
+    #[serde(default)]
+    drone_class: Vec<usize>,
 
     problem: String,
     config: cli::EnergyModel,
@@ -371,11 +943,94 @@ pub struct SerializedConfig {
     penalty_exponent: f64,
     single_truck_route: bool,
     single_drone_route: bool,
-    verbose: bool,
+    verbose: u8,
     outputs: String,
     disable_logging: bool,
     dry_run: bool,
     extra: String,
+    log_budget: Option<f64>,
+    frequency_penalty: f64,
+    ucb_exploration: f64,
+    acceptance: cli::Acceptance,
+    lahc_length: usize,
+    elite_min_distance: usize,
+    tsp_polish: bool,
+    route_pool: usize,
+    bks_file: Option<String>,
+    target_cost: Option<f64>,
+    format: cli::InstanceFormat,
+    truck_matrix: Option<String>,
+    drone_matrix: Option<String>,
+    distance_rounding: cli::DistanceRounding,
+    vehicle_cost: f64,
+    objective: cli::Objective,
+    objective_weight: f64,
+    pareto_archive: usize,
+    no_fly_zones: Option<String>,
+    depot_slots: usize,
+    depot_slot_time: f64,
+    mothership: bool,
+    allow_split: bool,
+    #[serde(default)]
+    split_of: Vec<usize>,
+    soft_constraints: Vec<String>,
+    #[serde(default)]
+    release_times: Vec<f64>,
+    #[serde(default)]
+    original_ids: Vec<usize>,
+    schedule: bool,
+    log_backend: cli::LogBackend,
+    log_format: cli::LogFormat,
+    log_interval: usize,
+    log_columns: Vec<cli::LogColumn>,
+    log_compress: Option<cli::LogCompression>,
+    quiet: bool,
+    progress_format: cli::ProgressFormat,
+    progress_interval: f64,
+    tui: bool,
+    status_port: Option<u16>,
+    notify_url: Option<String>,
+    run_id: Option<String>,
+    overwrite: bool,
+    append: bool,
+    truck_cfg: String,
+    drone_cfg: Vec<String>,
+    stdout_solution: bool,
+    params: Option<String>,
+    distance_unit: cli::DistanceUnit,
+    time_unit: cli::TimeUnit,
+    clustering: cli::Clustering,
+    construction: cli::Construction,
+    grasp: Option<f64>,
+    grasp_cycles: usize,
+    grasp_iterations: usize,
+    decompose: Option<usize>,
+    decompose_iterations: usize,
+    islands: usize,
+    migration_interval: usize,
+    profile: bool,
+    search_scope: cli::SearchScope,
+    search_top_k: usize,
+    tie_break: cli::TieBreak,
+    tie_break_seed: u64,
+    top_solutions: usize,
+    top_min_distance: usize,
+    detailed_routes: bool,
+    pin: Option<String>,
+    assignments: Option<String>,
+    zones: Option<String>,
+    charging_stations: Vec<usize>,
+    charging_power: f64,
+    ambient_temperature: Option<f64>,
+    derating_curve: Vec<String>,
+    tabu_acceptance: f64,
+    repair_noise: f64,
+    recluster_after: usize,
+    penalty_restart: cli::PenaltyRestart,
+    drone_rebalancing: bool,
+    init_command: Option<String>,
+    search_seed: Option<u64>,
+    status_bind: String,
 }
 
 #[derive(Clone, Debug)]
@@ -388,16 +1043,40 @@ pub struct Config {
     pub y: Vec<f64>,
     pub demands: Vec<f64>,
     pub dronable: Vec<bool>,
+    /// Whether each customer can be served by trucks (customers reachable only by drone are not)
+    pub truckable: Vec<bool>,
+    /// Waiting time limit of each customer (index 0 is the depot, which holds the global `--waiting-time-limit`)
+    pub waiting_time_limits: Vec<f64>,
+    /// Elevation (in m) of each node, empty when the problem file gives none
+    pub elevations: Vec<f64>,
 
     pub truck_distance: cli::DistanceType,
     pub drone_distance: cli::DistanceType,
     pub truck_distances: Vec<Vec<f64>>,
     pub drone_distances: Vec<Vec<f64>>,
+    /// Whether the direct drone leg between two nodes crosses a no-fly zone (empty without `--no-fly-zones`)
+    pub no_fly: Vec<Vec<bool>>,
+    /// Whether each node hosts a charging station (empty without `--charging-stations`)
+    pub charging_nodes: Vec<bool>,
+    /// Routes and customer assignments the search must not modify (see `--pin`)
+    pub pins: Pins,
+    /// Rules on the vehicle serving each customer (see `--assignments`)
+    pub assignment_rules: Assignments,
+    /// Whether a drone route may fly from node `i` straight to node `j` without a hard energy or fixed time violation
+    /// (see `Config::with_drone_pairs`)
+    pub drone_pairs: Vec<Vec<bool>>,
+    /// Whether either distance matrix is asymmetric (i.e. `d[i][j] != d[j][i]` for some `i`, `j`)
+    pub asymmetric: bool,
 
     pub truck: TruckConfig,
-    pub drone: DroneConfig,
+    /// Configuration of each drone class (a homogeneous fleet has exactly one class)
+    pub drone_classes: Vec<DroneConfig>,
+    /// Class index of each drone
+    pub drone_class: Vec<usize>,
 
     pub problem: String,
+    /// SHA-256 of the problem read from stdin (i.e. `problem` is "-"), which cannot be read again to be hashed
+    pub problem_sha256: Option<String>,
     pub config: cli::EnergyModel,
     pub tabu_size_factor: f64,
     pub adaptive_iterations: usize,
@@ -416,17 +1095,222 @@ pub struct Config {
     pub penalty_exponent: f64,
     pub single_truck_route: bool,
     pub single_drone_route: bool,
-    pub verbose: bool,
+    pub verbose: u8,
     pub outputs: String,
     pub disable_logging: bool,
     pub dry_run: bool,
     pub extra: String,
+    pub log_budget: Option<f64>,
+    pub frequency_penalty: f64,
+    pub ucb_exploration: f64,
+    pub acceptance: cli::Acceptance,
+    pub lahc_length: usize,
+    pub elite_min_distance: usize,
+    pub tsp_polish: bool,
+    pub route_pool: usize,
+    pub bks_file: Option<String>,
+    pub target_cost: Option<f64>,
+    pub format: cli::InstanceFormat,
+    pub truck_matrix: Option<String>,
+    pub drone_matrix: Option<String>,
+    pub distance_rounding: cli::DistanceRounding,
+    pub vehicle_cost: f64,
+    pub objective: cli::Objective,
+    pub objective_weight: f64,
+    /// Cost minimized by the search, `PenalizedObjective` unless replaced by a library user
+    pub cost_function: Arc<dyn CostFunction>,
+    pub pareto_archive: usize,
+    pub no_fly_zones: Option<String>,
+    pub depot_slots: usize,
+    pub depot_slot_time: f64,
+    pub mothership: bool,
+    pub allow_split: bool,
+    /// The original customer of each node: split customers are served at their own node and at a twin node appended
+    /// after the original customers, each delivering half of the demand
+    pub split_of: Vec<usize>,
+    pub soft_constraints: Vec<String>,
+    /// Fixed penalty weight of each violation (see `--soft-constraints`), or `None` for the adaptive coefficient
+    pub soft_weights: [Option<f64>; 7],
+    /// Time at which each vehicle (trucks first, then drones) becomes available, empty if all start at time 0 (see
+    /// the "reoptimize" subcommand)
+    pub release_times: Vec<f64>,
+    /// Index of each node in the config of the re-optimized solution, new customers being numbered after its nodes
+    /// (empty unless re-optimizing)
+    pub original_ids: Vec<usize>,
+    pub schedule: bool,
+    pub log_backend: cli::LogBackend,
+    pub log_format: cli::LogFormat,
+    pub log_interval: usize,
+    pub log_columns: Vec<cli::LogColumn>,
+    pub log_compress: Option<cli::LogCompression>,
+    pub quiet: bool,
+    pub progress_format: cli::ProgressFormat,
+    pub progress_interval: f64,
+    pub tui: bool,
+    pub status_port: Option<u16>,
+    pub notify_url: Option<String>,
+    pub run_id: Option<String>,
+    pub overwrite: bool,
+    pub append: bool,
+    pub truck_cfg: String,
+    pub drone_cfg: Vec<String>,
+    pub stdout_solution: bool,
+    pub params: Option<String>,
+    pub distance_unit: cli::DistanceUnit,
+    pub time_unit: cli::TimeUnit,
+    pub clustering: cli::Clustering,
+    pub construction: cli::Construction,
+    pub grasp: Option<f64>,
+    pub grasp_cycles: usize,
+    pub grasp_iterations: usize,
+    pub decompose: Option<usize>,
+    pub decompose_iterations: usize,
+    pub islands: usize,
+    pub migration_interval: usize,
+    pub profile: bool,
+    pub search_scope: cli::SearchScope,
+    pub search_top_k: usize,
+    pub tie_break: cli::TieBreak,
+    pub tie_break_seed: u64,
+    pub top_solutions: usize,
+    pub top_min_distance: usize,
+    pub detailed_routes: bool,
+    pub pin: Option<String>,
+    pub assignments: Option<String>,
+    pub zones: Option<String>,
+    pub charging_stations: Vec<usize>,
+    pub charging_power: f64,
+    pub ambient_temperature: Option<f64>,
+    pub derating_curve: Vec<String>,
+    pub tabu_acceptance: f64,
+    pub repair_noise: f64,
+    pub recluster_after: usize,
+    pub penalty_restart: cli::PenaltyRestart,
+    pub drone_rebalancing: bool,
+    pub init_command: Option<String>,
+    pub search_seed: Option<u64>,
+    pub status_bind: String,
+}
+
+impl Config {
+    /// Fill `drone_pairs` from the round trip from the depot visiting `i` then `j` only, flown by each drone class.
+    /// Assuming the triangle inequality, any longer route through the leg from `i` to `j` consumes more energy and
+    /// time, so a pair that no class can fly within its battery or fixed time (unless those constraints are soft) is
+    /// hopeless in every route. With charging stations, a drone may recharge before or after the leg, so the battery
+    /// does not rule out any pair. Distance matrix files and wind do not guarantee the triangle inequality, so only
+    /// the hard no-fly zones rule out pairs then.
+    pub fn with_drone_pairs(mut self) -> Self {
+        let nodes = self.demands.len();
+        let energy_soft = self.soft_weights[0].is_some() || !self.charging_nodes.is_empty();
+        let fixed_time_soft = self.soft_weights[3].is_some();
+        let triangle = self.truck_matrix.is_none()
+            && self.drone_matrix.is_none()
+            && !self.drone_classes.iter().any(DroneConfig::windy);
+        let feasible = |route: &[usize]| {
+            !triangle
+                || self.drone_classes.iter().any(|drone| {
+                    let (takeoff, landing) = (drone.takeoff_time(), drone.landing_time());
+                    let (mut cruise_time, mut energy, mut weight) = (0.0, 0.0, 0.0);
+                    for k in 0..route.len() - 1 {
+                        let (from, to) = (route[k], route[k + 1]);
+                        let cruise = drone.cruise_time(self.drone_distances[from][to]);
+                        cruise_time += cruise;
+                        energy += drone.leg_energy(weight, cruise);
+                        // As when evaluating drone routes
+                        weight += self.demands[from];
+                    }
+                    let working_time = (takeoff + landing).mul_add(route.len() as f64 - 1.0, cruise_time);
+
+                    (energy_soft || energy <= drone.battery())
+                        && (fixed_time_soft || working_time <= drone.fixed_time())
+                })
+        };
+
+        // Legs that every class flies against a headwind faster than its cruise speed
+        let unflyable = |i: usize, j: usize| {
+            let leg = Leg::new(i, j, &self.x, &self.y, &self.elevations, &self.drone_distances);
+            self.drone_classes
+                .iter()
+                .all(|d| d.heading_cruise_time(leg.distance, leg.dx, leg.dy).is_infinite())
+        };
+        let mut pairs = vec![vec![true; nodes]; nodes];
+        for (i, row) in pairs.iter_mut().enumerate() {
+            for (j, pair) in row.iter_mut().enumerate() {
+                *pair = if i == j {
+                    true
+                } else if self.soft_weights[6].is_none()
+                    && ((!self.no_fly.is_empty() && self.no_fly[i][j]) || unflyable(i, j))
+                {
+                    false
+                } else if i == 0 {
+                    feasible(&[0, j, 0])
+                } else if j == 0 {
+                    feasible(&[0, i, 0])
+                } else {
+                    feasible(&[0, i, j, 0])
+                };
+            }
+        }
+
+        self.drone_pairs = pairs;
+        self
+    }
+
+    /// Class index of `drone`. Drone indices beyond the fleet size (used as virtual vehicles during initialization)
+    /// belong to the first class.
+    pub fn drone_class_of(&self, DroneId(drone): DroneId) -> usize {
+        self.drone_class.get(drone).copied().unwrap_or(0)
+    }
+
+    /// Time at which `vehicle` becomes available
+    pub fn release_time(&self, vehicle: VehicleId) -> f64 {
+        self.release_times
+            .get(vehicle.index(self.trucks_count))
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    /// The other node serving the same split customer as `node`, if any
+    pub fn twin(&self, node: usize) -> Option<usize> {
+        let customer = self.split_of[node];
+        (1..self.customers_count + 1).find(|&c| c != node && self.split_of[c] == customer)
+    }
 }
 
 impl From<SerializedConfig> for Config {
     fn from(config: SerializedConfig) -> Self {
-        let truck_distances = config.truck_distance.matrix(&config.x, &config.y);
-        let drone_distances = config.drone_distance.matrix(&config.x, &config.y);
+        let split_of = if config.split_of.is_empty() {
+            (0..config.customers_count + 1).collect()
+        } else {
+            config.split_of
+        };
+
+        // Distances are given for the original nodes only, twin nodes are appended after them
+        let originals = split_of.iter().enumerate().filter(|&(i, &o)| i == o).count();
+        let truck_distances = split_distances(
+            &distances(
+                config.truck_distance,
+                config.distance_rounding,
+                config.distance_unit,
+                config.truck_matrix.as_deref(),
+                &config.x[..originals],
+                &config.y[..originals],
+            ),
+            &split_of,
+        );
+        let drone_distances = split_distances(
+            &distances(
+                config.drone_distance,
+                config.distance_rounding,
+                config.distance_unit,
+                config.drone_matrix.as_deref(),
+                &config.x[..originals],
+                &config.y[..originals],
+            ),
+            &split_of,
+        );
+        let no_fly = no_fly_mask(config.no_fly_zones.as_deref(), &config.x, &config.y);
+        let charging_nodes = charging_mask(&config.charging_stations, config.x.len());
 
         Self {
             customers_count: config.customers_count,
@@ -436,13 +1320,56 @@ impl From<SerializedConfig> for Config {
             y: config.y,
             demands: config.demands,
             dronable: config.dronable,
+            truckable: if config.truckable.is_empty() {
+                vec![true; config.customers_count + 1]
+            } else {
+                config.truckable
+            },
+            waiting_time_limits: if config.waiting_time_limits.is_empty() {
+                vec![config.waiting_time_limit; config.customers_count + 1]
+            } else {
+                config.waiting_time_limits
+            },
+            elevations: config.elevations,
             truck_distance: config.truck_distance,
             drone_distance: config.drone_distance,
+            pins: Pins::load(
+                config.pin.as_deref(),
+                config.customers_count,
+                config.trucks_count,
+                config.drones_count,
+            ),
+            assignment_rules: Assignments::load(
+                config.assignments.as_deref(),
+                config.customers_count,
+                config.trucks_count,
+                config.drones_count,
+            )
+            .with_zones(
+                config.zones.as_deref(),
+                config.customers_count,
+                config.trucks_count,
+                config.drones_count,
+            ),
+            no_fly,
+            charging_nodes,
+            drone_pairs: vec![],
+            asymmetric: is_asymmetric(&truck_distances) || is_asymmetric(&drone_distances),
             truck_distances,
             drone_distances,
             truck: config.truck,
-            drone: config.drone,
+            drone_class: if config.drone_class.is_empty() {
+                vec![0; config.drones_count]
+            } else {
+                config.drone_class
+            },
+            drone_classes: if config.drone_classes.is_empty() {
+                vec![config.drone]
+            } else {
+                config.drone_classes
+            },
             problem: config.problem,
+            problem_sha256: None,
             config: config.config,
             tabu_size_factor: config.tabu_size_factor,
             adaptive_iterations: config.adaptive_iterations,
@@ -466,7 +1393,246 @@ impl From<SerializedConfig> for Config {
             disable_logging: config.disable_logging,
             dry_run: config.dry_run,
             extra: config.extra,
+            log_budget: config.log_budget,
+            frequency_penalty: config.frequency_penalty,
+            ucb_exploration: config.ucb_exploration,
+            acceptance: config.acceptance,
+            lahc_length: config.lahc_length,
+            elite_min_distance: config.elite_min_distance,
+            tsp_polish: config.tsp_polish,
+            route_pool: config.route_pool,
+            bks_file: config.bks_file,
+            target_cost: config.target_cost,
+            format: config.format,
+            truck_matrix: config.truck_matrix,
+            drone_matrix: config.drone_matrix,
+            distance_rounding: config.distance_rounding,
+            vehicle_cost: config.vehicle_cost,
+            objective: config.objective,
+            objective_weight: config.objective_weight,
+            cost_function: Arc::new(PenalizedObjective),
+            pareto_archive: config.pareto_archive,
+            no_fly_zones: config.no_fly_zones,
+            depot_slots: config.depot_slots,
+            depot_slot_time: config.depot_slot_time,
+            mothership: config.mothership,
+            allow_split: config.allow_split,
+            split_of,
+            soft_weights: soft_weights(&config.soft_constraints),
+            soft_constraints: config.soft_constraints,
+            release_times: config.release_times,
+            original_ids: config.original_ids,
+            schedule: config.schedule,
+            log_backend: config.log_backend,
+            log_format: config.log_format,
+            log_interval: config.log_interval,
+            log_columns: config.log_columns,
+            log_compress: config.log_compress,
+            quiet: config.quiet,
+            progress_format: config.progress_format,
+            progress_interval: config.progress_interval,
+            tui: config.tui,
+            status_port: config.status_port,
+            notify_url: config.notify_url,
+            run_id: config.run_id,
+            overwrite: config.overwrite,
+            append: config.append,
+            truck_cfg: config.truck_cfg,
+            drone_cfg: config.drone_cfg,
+            stdout_solution: config.stdout_solution,
+            params: config.params,
+            distance_unit: config.distance_unit,
+            time_unit: config.time_unit,
+            clustering: config.clustering,
+            construction: config.construction,
+            grasp: config.grasp,
+            grasp_cycles: config.grasp_cycles,
+            grasp_iterations: config.grasp_iterations,
+            decompose: config.decompose,
+            decompose_iterations: config.decompose_iterations,
+            islands: config.islands,
+            migration_interval: config.migration_interval,
+            profile: config.profile,
+            search_scope: config.search_scope,
+            search_top_k: config.search_top_k,
+            tie_break: config.tie_break,
+            tie_break_seed: config.tie_break_seed,
+            top_solutions: config.top_solutions,
+            top_min_distance: config.top_min_distance,
+            detailed_routes: config.detailed_routes,
+            pin: config.pin,
+            assignments: config.assignments,
+            zones: config.zones,
+            charging_stations: config.charging_stations,
+            charging_power: config.charging_power,
+            ambient_temperature: config.ambient_temperature,
+            derating_curve: config.derating_curve,
+            tabu_acceptance: config.tabu_acceptance,
+            repair_noise: config.repair_noise,
+            recluster_after: config.recluster_after,
+            penalty_restart: config.penalty_restart,
+            drone_rebalancing: config.drone_rebalancing,
+            init_command: config.init_command,
+            search_seed: config.search_seed,
+            status_bind: config.status_bind,
+        }
+        .with_drone_pairs()
+    }
+}
+
+impl SerializedConfig {
+    /// Config of the remainder of `solution` after `elapsed` seconds, with the customers of `customers` (lines of the
+    /// custom format, read from stdin if `None`) added to it.
+    ///
+    /// Each vehicle performs its routes back to back from time 0. Routes done before `elapsed` are frozen. The route
+    /// in progress at `elapsed` is split: the customers already reached, and the one the vehicle is heading to, are
+    /// frozen, after which the vehicle returns straight to the depot. The other customers of that route and the
+    /// routes not started yet are re-planned together with the new customers. Each vehicle becomes available at
+    /// `elapsed` or once back at the depot from its frozen routes (including the reload or recharge time), whichever
+    /// is later.
+    ///
+    /// Split customers whose twin is frozen are served as regular customers.
+    fn reoptimized(self, solution: &str, customers: Option<&str>, elapsed: f64) -> Self {
+        assert!(
+            self.truck_matrix.is_none() && self.drone_matrix.is_none(),
+            "Re-optimization does not support distance matrix files"
+        );
+
+        let data = serde_json::from_str::<serde_json::Value>(&fs::read_to_string(solution).unwrap()).unwrap();
+        assert!(
+            data["sorties"].as_array().is_none_or(Vec::is_empty),
+            "Solutions with drone sorties (see --mothership) cannot be re-optimized"
+        );
+        let truck_routes = serde_json::from_value::<Vec<Vec<Vec<usize>>>>(data["truck_routes"].clone()).unwrap();
+        let drone_routes = serde_json::from_value::<Vec<Vec<Vec<usize>>>>(data["drone_routes"].clone()).unwrap();
+
+        let customers = match customers {
+            Some(path) => fs::read_to_string(path).unwrap(),
+            None => io::read_to_string(io::stdin()).unwrap(),
+        };
+
+        /// Freeze the customers of a vehicle reached before `elapsed` or being reached at `elapsed`, returning the time
+        /// the vehicle becomes available
+        fn _freeze(
+            routes: &[Vec<usize>],
+            elapsed: f64,
+            gap: f64,
+            leg: impl Fn(usize, usize) -> f64,
+            visited: &mut [bool],
+        ) -> f64 {
+            let mut time = 0.0;
+            let mut release = elapsed;
+            for route in routes {
+                if time >= elapsed {
+                    break;
+                }
+
+                for i in 1..route.len() {
+                    time += leg(route[i - 1], route[i]);
+                    visited[route[i]] = true;
+                    if time >= elapsed && i < route.len() - 1 {
+                        // Complete the leg in progress, then return without serving the rest of the route
+                        time += leg(route[i], 0);
+                        break;
+                    }
+                }
+
+                time += gap;
+                release = release.max(time);
+            }
+
+            release
+        }
+
+        let base = Config::from(self.clone());
+        let mut visited = vec![false; base.customers_count + 1];
+        let mut release_times = vec![];
+        for routes in &truck_routes {
+            let leg = |i: usize, j: usize| base.truck_distances[i][j] / base.truck.speed;
+            release_times.push(_freeze(routes, elapsed, base.truck.reload_time, leg, &mut visited));
+        }
+        for (drone, routes) in drone_routes.iter().enumerate() {
+            let class = &base.drone_classes[base.drone_class_of(DroneId(drone))];
+            let leg = |i: usize, j: usize| {
+                class.takeoff_time()
+                    + class.heading_cruise_time(
+                        base.drone_distances[i][j],
+                        base.x[j] - base.x[i],
+                        base.y[j] - base.y[i],
+                    )
+                    + class.landing_time()
+            };
+            release_times.push(_freeze(routes, elapsed, class.recharge_time(), leg, &mut visited));
+        }
+
+        let original_ids = (0..base.customers_count + 1)
+            .filter(|&i| i == 0 || !visited[i])
+            .collect::<Vec<usize>>();
+        let mut config = Self {
+            customers_count: original_ids.len() - 1,
+            x: original_ids.iter().map(|&i| base.x[i]).collect(),
+            y: original_ids.iter().map(|&i| base.y[i]).collect(),
+            demands: original_ids.iter().map(|&i| base.demands[i]).collect(),
+            dronable: original_ids.iter().map(|&i| base.dronable[i]).collect(),
+            truckable: original_ids.iter().map(|&i| base.truckable[i]).collect(),
+            waiting_time_limits: original_ids.iter().map(|&i| base.waiting_time_limits[i]).collect(),
+            elevations: if base.elevations.is_empty() {
+                vec![]
+            } else {
+                original_ids.iter().map(|&i| base.elevations[i]).collect()
+            },
+            split_of: vec![],
+            release_times,
+            charging_stations: base
+                .charging_stations
+                .iter()
+                .filter_map(|&station| original_ids.iter().position(|&i| i == station))
+                .collect(),
+            original_ids,
+            // The re-optimized run gets outputs of its own
+            run_id: None,
+            overwrite: false,
+            append: false,
+            ..self
+        };
+
+        let first_new = config.x.len();
+        let customers_regex = RegexBuilder::new(CUSTOMER_ROW).multi_line(true).build().unwrap();
+        for (k, c) in customers_regex.captures_iter(&customers).enumerate() {
+            config.customers_count += 1;
+            config.x.push(c[1].parse::<f64>().unwrap());
+            config.y.push(c[2].parse::<f64>().unwrap());
+            config.dronable.push(&c[3] != "0");
+            config.truckable.push(&c[3] != "2");
+            config.demands.push(c[4].parse::<f64>().unwrap());
+            config
+                .waiting_time_limits
+                .push(c.get(5).map_or(config.waiting_time_limit, |m| {
+                    m.as_str().parse::<f64>().unwrap() * config.time_unit.seconds()
+                }));
+            if !config.elevations.is_empty() {
+                config
+                    .elevations
+                    .push(c.get(6).map_or(0.0, |m| m.as_str().parse::<f64>().unwrap()));
+            }
+            config.original_ids.push(base.customers_count + k + 1);
+        }
+
+        // New customers that no drone can serve are left to the trucks, as in the "run" subcommand
+        let reduced = Config::from(config.clone());
+        for i in first_new..config.x.len() {
+            config.dronable[i] = config.dronable[i]
+                && (reduced.no_fly.is_empty() || !reduced.no_fly[0][i])
+                && reduced.drone_classes.iter().any(|d| {
+                    d.can_serve(
+                        config.demands[i],
+                        Leg::new(0, i, &config.x, &config.y, &config.elevations, &reduced.drone_distances),
+                        Leg::new(i, 0, &config.x, &config.y, &config.elevations, &reduced.drone_distances),
+                    )
+                });
         }
+
+        config
     }
 }
 
@@ -480,10 +1646,15 @@ impl From<Config> for SerializedConfig {
             y: config.y,
             demands: config.demands,
             dronable: config.dronable,
+            truckable: config.truckable,
+            waiting_time_limits: config.waiting_time_limits,
+            elevations: config.elevations,
             truck_distance: config.truck_distance,
             drone_distance: config.drone_distance,
             truck: config.truck,
-            drone: config.drone,
+            drone: config.drone_classes[0].clone(),
+            drone_classes: config.drone_classes,
+            drone_class: config.drone_class,
             problem: config.problem,
             config: config.config,
             tabu_size_factor: config.tabu_size_factor,
@@ -508,20 +1679,366 @@ impl From<Config> for SerializedConfig {
             disable_logging: config.disable_logging,
             dry_run: config.dry_run,
             extra: config.extra,
+            log_budget: config.log_budget,
+            frequency_penalty: config.frequency_penalty,
+            ucb_exploration: config.ucb_exploration,
+            acceptance: config.acceptance,
+            lahc_length: config.lahc_length,
+            elite_min_distance: config.elite_min_distance,
+            tsp_polish: config.tsp_polish,
+            route_pool: config.route_pool,
+            bks_file: config.bks_file,
+            target_cost: config.target_cost,
+            format: config.format,
+            truck_matrix: config.truck_matrix,
+            drone_matrix: config.drone_matrix,
+            distance_rounding: config.distance_rounding,
+            vehicle_cost: config.vehicle_cost,
+            objective: config.objective,
+            objective_weight: config.objective_weight,
+            pareto_archive: config.pareto_archive,
+            no_fly_zones: config.no_fly_zones,
+            depot_slots: config.depot_slots,
+            depot_slot_time: config.depot_slot_time,
+            mothership: config.mothership,
+            allow_split: config.allow_split,
+            split_of: config.split_of,
+            soft_constraints: config.soft_constraints,
+            release_times: config.release_times,
+            original_ids: config.original_ids,
+            schedule: config.schedule,
+            log_backend: config.log_backend,
+            log_format: config.log_format,
+            log_interval: config.log_interval,
+            log_columns: config.log_columns,
+            log_compress: config.log_compress,
+            quiet: config.quiet,
+            progress_format: config.progress_format,
+            progress_interval: config.progress_interval,
+            tui: config.tui,
+            status_port: config.status_port,
+            notify_url: config.notify_url,
+            run_id: config.run_id,
+            overwrite: config.overwrite,
+            append: config.append,
+            truck_cfg: config.truck_cfg,
+            drone_cfg: config.drone_cfg,
+            stdout_solution: config.stdout_solution,
+            params: config.params,
+            distance_unit: config.distance_unit,
+            time_unit: config.time_unit,
+            clustering: config.clustering,
+            construction: config.construction,
+            grasp: config.grasp,
+            grasp_cycles: config.grasp_cycles,
+            grasp_iterations: config.grasp_iterations,
+            decompose: config.decompose,
+            decompose_iterations: config.decompose_iterations,
+            islands: config.islands,
+            migration_interval: config.migration_interval,
+            profile: config.profile,
+            search_scope: config.search_scope,
+            search_top_k: config.search_top_k,
+            tie_break: config.tie_break,
+            tie_break_seed: config.tie_break_seed,
+            top_solutions: config.top_solutions,
+            top_min_distance: config.top_min_distance,
+            detailed_routes: config.detailed_routes,
+            pin: config.pin,
+            assignments: config.assignments,
+            zones: config.zones,
+            charging_stations: config.charging_stations,
+            charging_power: config.charging_power,
+            ambient_temperature: config.ambient_temperature,
+            derating_curve: config.derating_curve,
+            tabu_acceptance: config.tabu_acceptance,
+            repair_noise: config.repair_noise,
+            recluster_after: config.recluster_after,
+            penalty_restart: config.penalty_restart,
+            drone_rebalancing: config.drone_rebalancing,
+            init_command: config.init_command,
+            search_seed: config.search_seed,
+            status_bind: config.status_bind,
         }
     }
 }
 
-pub static CONFIG: LazyLock<Config> = LazyLock::new(|| {
-    let arguments = cli::Arguments::parse();
-    eprintln!("Received {arguments:?}");
-    match arguments.command {
-        cli::Commands::Evaluate { config, .. } => {
-            let data = fs::read_to_string(config).unwrap();
-            let deserialized = serde_json::from_str::<SerializedConfig>(&data).unwrap();
-            Config::from(deserialized)
+/// Instance data parsed from a problem file. The depot is always at index 0.
+/// A customer line of the custom format: x, y, dronable (0: truck only, 1: both, 2: drone only), demand, then an
+/// optional waiting time limit and an optional elevation
+const CUSTOMER_ROW: &str =
+    r"^\s*(-?[\d\.]+)\s+(-?[\d\.]+)\s+(0|1|2)\s+([\d\.]+)(?:\s+([\d\.]+)(?:\s+(-?[\d\.]+))?)?\s*$";
+
+struct ProblemData {
+    trucks_count: usize,
+    drones_count: usize,
+    x: Vec<f64>,
+    y: Vec<f64>,
+    demands: Vec<f64>,
+    dronable: Vec<bool>,
+    truckable: Vec<bool>,
+    /// Per-customer waiting time limits (`None` for the depot and for customers using the global limit)
+    waiting_time_limits: Vec<Option<f64>>,
+    /// Elevation of each node, empty when the file gives none
+    elevations: Vec<f64>,
+    truck_capacity: Option<f64>,
+}
+
+impl ProblemData {
+    /// Parse the custom text format (`trucks_count`, `drones_count`, `depot` and one customer per line, with an
+    /// optional fifth column holding the waiting time limit of that customer and an optional sixth one holding its
+    /// elevation in m). The depot elevation follows its coordinates, nodes without one are at elevation 0.
+    ///
+    /// The dronable column is 0 (truck only), 1 (truck or drone) or 2 (drone only).
+    fn custom(data: &str, trucks_count: Option<usize>, drones_count: Option<usize>) -> Self {
+        let trucks_count_regex = Regex::new(r"trucks_count (\d+)").unwrap();
+        let drones_count_regex = Regex::new(r"drones_count (\d+)").unwrap();
+        let depot_regex = Regex::new(r"depot (-?[\d\.]+)[ \t]+(-?[\d\.]+)(?:[ \t]+(-?[\d\.]+))?").unwrap();
+        let customers_regex = RegexBuilder::new(CUSTOMER_ROW).multi_line(true).build().unwrap();
+
+        let trucks_count = trucks_count
+            .or_else(|| {
+                trucks_count_regex
+                    .captures(data)
+                    .and_then(|caps| caps.get(1))
+                    .and_then(|m| m.as_str().parse::<usize>().ok())
+            })
+            .expect("Missing trucks count");
+        let drones_count = drones_count
+            .or_else(|| {
+                drones_count_regex
+                    .captures(data)
+                    .and_then(|caps| caps.get(1))
+                    .and_then(|m| m.as_str().parse::<usize>().ok())
+            })
+            .expect("Missing drones count");
+
+        let depot = depot_regex
+            .captures(data)
+            .and_then(|caps| {
+                let x = caps.get(1)?.as_str().parse::<f64>().ok()?;
+                let y = caps.get(2)?.as_str().parse::<f64>().ok()?;
+                let elevation = caps.get(3).map(|m| m.as_str().parse::<f64>().unwrap());
+                Some((x, y, elevation))
+            })
+            .expect("Missing depot coordinates");
+
+        let mut x = vec![depot.0];
+        let mut y = vec![depot.1];
+        let mut demands = vec![0.0];
+        let mut dronable = vec![true];
+        let mut truckable = vec![true];
+        let mut waiting_time_limits = vec![None];
+        let mut elevations = vec![depot.2];
+        for c in customers_regex.captures_iter(data) {
+            x.push(c[1].parse::<f64>().unwrap());
+            y.push(c[2].parse::<f64>().unwrap());
+            dronable.push(&c[3] != "0");
+            truckable.push(&c[3] != "2");
+            demands.push(c[4].parse::<f64>().unwrap());
+            waiting_time_limits.push(c.get(5).map(|m| m.as_str().parse::<f64>().unwrap()));
+            elevations.push(c.get(6).map(|m| m.as_str().parse::<f64>().unwrap()));
+        }
+        let elevations = if elevations.iter().any(Option::is_some) {
+            elevations.into_iter().map(|e| e.unwrap_or(0.0)).collect()
+        } else {
+            vec![]
+        };
+
+        Self {
+            trucks_count,
+            drones_count,
+            x,
+            y,
+            demands,
+            dronable,
+            truckable,
+            waiting_time_limits,
+            elevations,
+            truck_capacity: None,
+        }
+    }
+
+    /// Parse a CVRPLIB `.vrp` file (NODE_COORD_SECTION, DEMAND_SECTION, DEPOT_SECTION and CAPACITY).
+    ///
+    /// All customers are marked as dronable, the truck capacity is overridden by CAPACITY and the number of trucks
+    /// defaults to VEHICLES (or the "-k" suffix of NAME) when not specified.
+    fn cvrplib(data: &str, trucks_count: Option<usize>, drones_count: Option<usize>) -> Self {
+        let mut name = String::new();
+        let mut vehicles = None;
+        let mut capacity = None;
+        let mut coordinates = vec![];
+        let mut node_demands = vec![];
+        let mut depot = None;
+
+        let mut section = "";
+        for line in data.lines() {
+            let line = line.trim();
+            if line.is_empty() || line == "EOF" {
+                continue;
+            }
+
+            if let Some((key, value)) = line.split_once(':') {
+                let value = value.trim();
+                match key.trim() {
+                    "NAME" => name = value.to_string(),
+                    "VEHICLES" => vehicles = value.parse::<usize>().ok(),
+                    "CAPACITY" => capacity = value.parse::<f64>().ok(),
+                    _ => {}
+                }
+
+                continue;
+            }
+
+            if line.ends_with("_SECTION") {
+                section = line;
+                continue;
+            }
+
+            let tokens = line.split_whitespace().collect::<Vec<&str>>();
+            match section {
+                "NODE_COORD_SECTION" => coordinates.push((
+                    tokens[0].parse::<usize>().unwrap(),
+                    tokens[1].parse::<f64>().unwrap(),
+                    tokens[2].parse::<f64>().unwrap(),
+                )),
+                "DEMAND_SECTION" => {
+                    node_demands.push((tokens[0].parse::<usize>().unwrap(), tokens[1].parse::<f64>().unwrap()));
+                }
+                "DEPOT_SECTION" => {
+                    let id = tokens[0].parse::<i64>().unwrap();
+                    if id > 0 && depot.is_none() {
+                        depot = Some(id as usize);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let trucks_count = trucks_count
+            .or(vehicles)
+            .or_else(|| {
+                Regex::new(r"-k(\d+)")
+                    .unwrap()
+                    .captures(&name)
+                    .and_then(|caps| caps.get(1))
+                    .and_then(|m| m.as_str().parse::<usize>().ok())
+            })
+            .expect("Missing trucks count");
+        let drones_count = drones_count.expect("Missing drones count (CVRPLIB instances require --drones-count)");
+        let depot = depot.unwrap_or(1);
+
+        let demand_of = |id: usize| {
+            node_demands
+                .iter()
+                .find(|&&(i, _)| i == id)
+                .map_or(0.0, |&(_, demand)| demand)
+        };
+
+        let (_, depot_x, depot_y) = *coordinates
+            .iter()
+            .find(|&&(id, _, _)| id == depot)
+            .expect("Missing depot coordinates");
+        let mut x = vec![depot_x];
+        let mut y = vec![depot_y];
+        let mut demands = vec![0.0];
+        for &(id, _x, _y) in &coordinates {
+            if id != depot {
+                x.push(_x);
+                y.push(_y);
+                demands.push(demand_of(id));
+            }
+        }
+
+        let dronable = vec![true; x.len()];
+        let truckable = vec![true; x.len()];
+        let waiting_time_limits = vec![None; x.len()];
+        Self {
+            trucks_count,
+            drones_count,
+            x,
+            y,
+            demands,
+            dronable,
+            truckable,
+            waiting_time_limits,
+            elevations: vec![],
+            truck_capacity: Some(capacity.expect("Missing CAPACITY")),
+        }
+    }
+
+    /// Parse the coordinates and demands of a Solomon/Gehring-Homberger VRPTW instance (VEHICLE NUMBER/CAPACITY and
+    /// one row per node with the depot first).
+    ///
+    /// All customers are marked as dronable and the truck capacity is overridden by CAPACITY. Since the model has
+    /// no time windows or service times, the READY TIME, DUE DATE and SERVICE TIME columns are ignored.
+    fn solomon_coordinates(data: &str, trucks_count: Option<usize>, drones_count: Option<usize>) -> Self {
+        let vehicle_regex = RegexBuilder::new(r"^\s*NUMBER\s+CAPACITY\s*$\s*^\s*(\d+)\s+([\d\.]+)\s*$")
+            .multi_line(true)
+            .build()
+            .unwrap();
+        let node_regex = RegexBuilder::new(
+            r"^\s*(\d+)\s+(-?[\d\.]+)\s+(-?[\d\.]+)\s+([\d\.]+)\s+([\d\.]+)\s+([\d\.]+)\s+([\d\.]+)\s*$",
+        )
+        .multi_line(true)
+        .build()
+        .unwrap();
+
+        let (vehicles, capacity) = vehicle_regex
+            .captures(data)
+            .and_then(|caps| {
+                let vehicles = caps.get(1)?.as_str().parse::<usize>().ok()?;
+                let capacity = caps.get(2)?.as_str().parse::<f64>().ok()?;
+                Some((vehicles, capacity))
+            })
+            .expect("Missing vehicle number and capacity");
+
+        let trucks_count = trucks_count.unwrap_or(vehicles);
+        let drones_count = drones_count.expect("Missing drones count (Solomon instances require --drones-count)");
+
+        let mut x = vec![];
+        let mut y = vec![];
+        let mut demands = vec![];
+        let mut time_windows = false;
+        for c in node_regex.captures_iter(data) {
+            let (_, [_, _x, _y, _demand, _ready, _due, _service]) = c.extract::<7>();
+            x.push(_x.parse::<f64>().unwrap());
+            y.push(_y.parse::<f64>().unwrap());
+            demands.push(if x.len() == 1 {
+                0.0
+            } else {
+                _demand.parse::<f64>().unwrap()
+            });
+            time_windows |=
+                x.len() > 1 && (_ready.parse::<f64>().unwrap() > 0.0 || _service.parse::<f64>().unwrap() > 0.0);
+        }
+
+        assert!(!x.is_empty(), "Missing depot coordinates");
+        if time_windows {
+            warn!("Time windows and service times are not supported and will be ignored");
+        }
+
+        let dronable = vec![true; x.len()];
+        let truckable = vec![true; x.len()];
+        let waiting_time_limits = vec![None; x.len()];
+        Self {
+            trucks_count,
+            drones_count,
+            x,
+            y,
+            demands,
+            dronable,
+            truckable,
+            waiting_time_limits,
+            elevations: vec![],
+            truck_capacity: Some(capacity),
         }
-        cli::Commands::Run {
+    }
+}
+
+impl From<cli::RunArgs> for Config {
+    fn from(arguments: cli::RunArgs) -> Self {
+        let cli::RunArgs {
             problem,
             truck_cfg,
             drone_cfg,
@@ -552,125 +2069,580 @@ pub static CONFIG: LazyLock<Config> = LazyLock::new(|| {
             disable_logging,
             dry_run,
             extra,
-        } => {
-            let trucks_count_regex = Regex::new(r"trucks_count (\d+)").unwrap();
-            let drones_count_regex = Regex::new(r"drones_count (\d+)").unwrap();
-            let depot_regex = Regex::new(r"depot (-?[\d\.]+)\s+(-?[\d\.]+)").unwrap();
-            let customers_regex = RegexBuilder::new(r"^\s*(-?[\d\.]+)\s+(-?[\d\.]+)\s+(0|1)\s+([\d\.]+)\s*$")
-                .multi_line(true)
-                .build()
-                .unwrap();
-
-            let data = fs::read_to_string(&problem).unwrap();
-
-            let trucks_count = trucks_count
-                .or_else(|| {
-                    trucks_count_regex
-                        .captures(&data)
-                        .and_then(|caps| caps.get(1))
-                        .and_then(|m| m.as_str().parse::<usize>().ok())
-                })
-                .expect("Missing trucks count");
-            let drones_count = drones_count
-                .or_else(|| {
-                    drones_count_regex
-                        .captures(&data)
-                        .and_then(|caps| caps.get(1))
-                        .and_then(|m| m.as_str().parse::<usize>().ok())
-                })
-                .expect("Missing drones count");
-
-            let depot = depot_regex
-                .captures(&data)
-                .and_then(|caps| {
-                    let x = caps.get(1)?.as_str().parse::<f64>().ok()?;
-                    let y = caps.get(2)?.as_str().parse::<f64>().ok()?;
-                    Some((x, y))
-                })
-                .expect("Missing depot coordinates");
-
-            let mut customers_count = 0;
-            let mut x = vec![depot.0];
-            let mut y = vec![depot.1];
-            let mut demands = vec![0.0];
-            let mut dronable = vec![true];
-            for c in customers_regex.captures_iter(&data) {
-                customers_count += 1;
-
-                let (_, [_x, _y, _dronable, _demand]) = c.extract::<4>();
-                x.push(_x.parse::<f64>().unwrap());
-                y.push(_y.parse::<f64>().unwrap());
-                dronable.push(matches!(_dronable, "1"));
-                demands.push(_demand.parse::<f64>().unwrap());
+            recharge_time,
+            reload_time,
+            log_budget,
+            frequency_penalty,
+            ucb_exploration,
+            acceptance,
+            lahc_length,
+            elite_min_distance,
+            tsp_polish,
+            route_pool,
+            bks_file,
+            target_cost,
+            format,
+            truck_matrix,
+            drone_matrix,
+            distance_rounding,
+            vehicle_cost,
+            objective,
+            objective_weight,
+            pareto_archive,
+            no_fly_zones,
+            depot_slots,
+            depot_slot_time,
+            mothership,
+            allow_split,
+            soft_constraints,
+            schedule,
+            log_backend,
+            log_format,
+            log_interval,
+            log_columns,
+            log_compress,
+            quiet,
+            progress_format,
+            progress_interval,
+            tui,
+            status_port,
+            notify_url,
+            run_id,
+            overwrite,
+            append,
+            stdout_solution,
+            params,
+            distance_unit,
+            time_unit,
+            clustering,
+            construction,
+            grasp,
+            grasp_cycles,
+            grasp_iterations,
+            decompose,
+            decompose_iterations,
+            islands,
+            migration_interval,
+            profile,
+            search_scope,
+            search_top_k,
+            tie_break,
+            tie_break_seed,
+            top_solutions,
+            top_min_distance,
+            detailed_routes,
+            pin,
+            assignments,
+            zones,
+            charging_stations,
+            charging_power,
+            ambient_temperature,
+            derating_curve,
+            tabu_acceptance,
+            repair_noise,
+            recluster_after,
+            penalty_restart,
+            drone_rebalancing,
+            init_command,
+            search_seed,
+            status_bind,
+        } = arguments;
+        assert!(
+            grasp.is_none_or(|alpha| (0.0..=1.0).contains(&alpha)),
+            "--grasp must be between 0 and 1"
+        );
+        assert!(
+            (0.0..=1.0).contains(&tabu_acceptance),
+            "--tabu-acceptance must be between 0 and 1"
+        );
+        assert!(
+            (0.0..1.0).contains(&repair_noise),
+            "--repair-noise must be at least 0 and below 1"
+        );
+        assert!(islands > 0, "--islands must be positive");
+        assert!(search_top_k > 0, "--search-top-k must be positive");
+        assert!(migration_interval > 0, "--migration-interval must be positive");
+
+        let (data, problem_sha256) = if problem == "-" {
+            let data = io::read_to_string(io::stdin()).unwrap();
+            let sha256 = format!("{:x}", Sha256::digest(&data));
+            (data, Some(sha256))
+        } else {
+            (fs::read_to_string(&problem).unwrap(), None)
+        };
+        let ProblemData {
+            trucks_count,
+            drones_count,
+            mut x,
+            mut y,
+            mut demands,
+            mut dronable,
+            mut truckable,
+            mut waiting_time_limits,
+            mut elevations,
+            truck_capacity,
+        } = match format {
+            cli::InstanceFormat::Custom => ProblemData::custom(&data, trucks_count, drones_count),
+            cli::InstanceFormat::Cvrplib => ProblemData::cvrplib(&data, trucks_count, drones_count),
+            cli::InstanceFormat::SolomonCoordinates => {
+                ProblemData::solomon_coordinates(&data, trucks_count, drones_count)
             }
+        };
+        let customers_count = x.len() - 1;
+        for limit in waiting_time_limits.iter_mut().flatten() {
+            *limit *= time_unit.seconds();
+        }
 
-            let truck_distances = truck_distance.matrix(&x, &y);
-            let drone_distances = drone_distance.matrix(&x, &y);
-
-            let truck = serde_json::from_str::<TruckConfig>(&fs::read_to_string(truck_cfg).unwrap()).unwrap();
-            let drone = DroneConfig::new(&drone_cfg, config, speed_type, range_type);
+        let truck_distances = distances(
+            truck_distance,
+            distance_rounding,
+            distance_unit,
+            truck_matrix.as_deref(),
+            &x,
+            &y,
+        );
+        let drone_distances = distances(
+            drone_distance,
+            distance_rounding,
+            distance_unit,
+            drone_matrix.as_deref(),
+            &x,
+            &y,
+        );
+        let no_fly = no_fly_mask(no_fly_zones.as_deref(), &x, &y);
+
+        validation::truck_config(&truck_cfg);
+        let mut truck = serde_json::from_str::<TruckConfig>(&fs::read_to_string(&truck_cfg).unwrap()).unwrap();
+        if let Some(capacity) = truck_capacity {
+            truck.capacity = capacity;
+        }
+        if let Some(reload_time) = reload_time {
+            truck.reload_time = reload_time;
+        }
+        let (drone_classes, drone_class) = if drone_cfg.len() == 1 && parse_drone_class(&drone_cfg[0]).is_none() {
+            (
+                vec![DroneConfig::new(&drone_cfg[0], config, speed_type, range_type)],
+                vec![0; drones_count],
+            )
+        } else {
+            let mut drone_classes = vec![];
+            let mut drone_class = vec![];
+            for spec in &drone_cfg {
+                let (model, count, path) = parse_drone_class(spec)
+                    .unwrap_or_else(|| panic!("Invalid drone class {spec:?}, expected MODEL:COUNT:PATH"));
+                drone_class.extend(iter::repeat_n(drone_classes.len(), count));
+                drone_classes.push(DroneConfig::new(&path.to_string(), model, speed_type, range_type));
+            }
 
-            let takeoff = drone.takeoff_time();
-            let takeoff_from_depot = drone.takeoff_power(0.0);
+            (drone_classes, drone_class)
+        };
+        let drones_count = drone_class.len();
 
-            let landing = drone.landing_time();
-            let landing_from_depot = drone.landing_power(0.0);
+        let mut drone_classes = drone_classes;
+        if let Some(recharge_time) = recharge_time {
+            for drone in &mut drone_classes {
+                drone.set_recharge_time(recharge_time);
+            }
+        }
+        if let Some(temperature) = ambient_temperature {
+            let factor = derating_factor(&derating_curve, temperature);
+            info!("Derating drone batteries to {:.1}% at {temperature}°C", factor * 100.0);
+            for drone in &mut drone_classes {
+                drone.derate(factor);
+            }
+        }
 
-            let cruise_from_depot = drone.cruise_power(0.0);
+        let mut split_of = (0..customers_count + 1).collect::<Vec<usize>>();
+        if allow_split {
             for i in 1..customers_count + 1 {
-                dronable[i] = dronable[i]
-                    && demands[i] <= drone.capacity()
-                    && takeoff + drone.cruise_time(drone_distances[0][i] + drone_distances[i][0]) + landing
-                        <= drone.fixed_time()
-                    && (landing_from_depot + drone.landing_power(demands[i])).mul_add(
-                        landing,
-                        drone.cruise_power(demands[i]).mul_add(
-                            drone.cruise_time(drone_distances[i][0]),
-                            (takeoff_from_depot + drone.takeoff_power(demands[i]))
-                                .mul_add(takeoff, cruise_from_depot * drone.cruise_time(drone_distances[0][i])),
-                        ),
-                    ) <= drone.battery();
+                let half = demands[i] / 2.0;
+                let can_serve = |demand| {
+                    drone_classes.iter().any(|d| {
+                        d.can_serve(
+                            demand,
+                            Leg::new(0, i, &x, &y, &elevations, &drone_distances),
+                            Leg::new(i, 0, &x, &y, &elevations, &drone_distances),
+                        )
+                    })
+                };
+                if dronable[i] && (no_fly.is_empty() || !no_fly[0][i]) && !can_serve(demands[i]) && can_serve(half) {
+                    demands[i] = half;
+
+                    x.push(x[i]);
+                    y.push(y[i]);
+                    demands.push(half);
+                    dronable.push(true);
+                    truckable.push(truckable[i]);
+                    waiting_time_limits.push(waiting_time_limits[i]);
+                    if !elevations.is_empty() {
+                        elevations.push(elevations[i]);
+                    }
+                    split_of.push(i);
+                }
+            }
+        }
+
+        let customers_count = x.len() - 1;
+        let truck_distances = split_distances(&truck_distances, &split_of);
+        let drone_distances = split_distances(&drone_distances, &split_of);
+        let no_fly = no_fly_mask(no_fly_zones.as_deref(), &x, &y);
+        let charging_nodes = charging_mask(&charging_stations, x.len());
+
+        // Whether `drone` can fly the round trip from the depot through `route`, recharging at `route[station]` just
+        // enough to fly the rest of it (see `--charging-stations`)
+        let with_charging = |drone: &DroneConfig, route: [usize; 4], station: usize| {
+            let (takeoff, landing) = (drone.takeoff_time(), drone.landing_time());
+            let (mut time, mut weight) = (0.0, 0.0);
+            let mut energies = [0.0; 3];
+            for k in 0..3 {
+                let leg = Leg::new(route[k], route[k + 1], &x, &y, &elevations, &drone_distances);
+                let cruise = drone.heading_cruise_time(leg.distance, leg.dx, leg.dy);
+                time += takeoff + cruise + landing;
+                energies[k] = drone.leg_energy(weight, cruise) + drone._climb_energy(weight, leg.climb);
+                // As when evaluating drone routes
+                weight += demands[route[k]];
             }
 
-            Config {
-                customers_count,
-                trucks_count,
-                drones_count,
-                x,
-                y,
-                demands,
-                dronable,
-                truck_distance,
-                drone_distance,
-                truck_distances,
-                drone_distances,
-                truck,
-                drone,
-                problem,
-                config,
-                tabu_size_factor,
-                adaptive_iterations,
-                adaptive_fixed_iterations,
-                adaptive_segments,
-                adaptive_fixed_segments,
-                ejection_chain_iterations,
-                destroy_rate,
-                speed_type,
-                range_type,
-                waiting_time_limit,
-                strategy,
-                fix_iteration,
-                reset_after_factor,
-                max_elite_size,
-                penalty_exponent,
-                single_truck_route,
-                single_drone_route,
-                verbose,
-                outputs,
-                disable_logging,
-                dry_run,
-                extra,
+            let before = energies[..station].iter().sum::<f64>();
+            let after = energies[station..].iter().sum::<f64>();
+            let charge = (after - (drone.battery() - before)).max(0.0);
+            weight <= drone.capacity()
+                && before <= drone.battery()
+                && after <= drone.battery()
+                && time + charge / charging_power <= drone.fixed_time()
+        };
+
+        for i in 1..customers_count + 1 {
+            let direct = drone_classes.iter().any(|d| {
+                d.can_serve(
+                    demands[i],
+                    Leg::new(0, i, &x, &y, &elevations, &drone_distances),
+                    Leg::new(i, 0, &x, &y, &elevations, &drone_distances),
+                )
+            });
+            // Customers beyond the direct range of every drone may be reached through a charging station, visited
+            // before or after them
+            let charging = || {
+                charging_stations.iter().any(|&s| {
+                    s != i
+                        && dronable[s]
+                        && drone_classes
+                            .iter()
+                            .any(|d| with_charging(d, [0, s, i, 0], 1) || with_charging(d, [0, i, s, 0], 2))
+                })
+            };
+            dronable[i] = dronable[i] && (no_fly.is_empty() || !no_fly[0][i]) && (direct || charging());
+        }
+        let assignment_rules = Assignments::load(assignments.as_deref(), customers_count, trucks_count, drones_count)
+            .with_zones(zones.as_deref(), customers_count, trucks_count, drones_count);
+        assignment_rules.restrict(&mut truckable, &mut dronable, trucks_count, drones_count);
+
+        let unservable = (1..customers_count + 1)
+            .filter(|&i| !((trucks_count > 0 && truckable[i]) || (drones_count > 0 && dronable[i])))
+            .collect::<Vec<_>>();
+        assert!(
+            unservable.is_empty(),
+            "Customers {unservable:?} can be served by neither the {trucks_count} trucks (drone-only customers, \
+             --assignments, --zones) nor the {drones_count} drones (payload, range, --no-fly-zones, --assignments, \
+             --zones)"
+        );
+
+        assert!(
+            objective != cli::Objective::TotalEnergy
+                || drones_count > 0 && drone_classes.iter().any(DroneConfig::consumes_energy),
+            "The \"total-energy\" objective requires drones whose energy model consumes energy, every solution \
+             would cost 0 otherwise"
+        );
+
+        let waiting_time_limits = waiting_time_limits
+            .into_iter()
+            .map(|limit| limit.unwrap_or(waiting_time_limit))
+            .collect();
+
+        Self {
+            customers_count,
+            trucks_count,
+            drones_count,
+            x,
+            y,
+            demands,
+            dronable,
+            truckable,
+            waiting_time_limits,
+            elevations,
+            truck_distance,
+            drone_distance,
+            pins: Pins::load(pin.as_deref(), customers_count, trucks_count, drones_count),
+            assignment_rules,
+            no_fly,
+            charging_nodes,
+            drone_pairs: vec![],
+            asymmetric: is_asymmetric(&truck_distances) || is_asymmetric(&drone_distances),
+            truck_distances,
+            drone_distances,
+            truck,
+            drone_classes,
+            drone_class,
+            problem,
+            problem_sha256,
+            config,
+            tabu_size_factor,
+            adaptive_iterations,
+            adaptive_fixed_iterations,
+            adaptive_segments,
+            adaptive_fixed_segments,
+            ejection_chain_iterations,
+            destroy_rate,
+            speed_type,
+            range_type,
+            waiting_time_limit,
+            strategy,
+            fix_iteration,
+            reset_after_factor,
+            max_elite_size,
+            penalty_exponent,
+            single_truck_route,
+            single_drone_route,
+            verbose,
+            outputs,
+            disable_logging,
+            dry_run,
+            extra,
+            log_budget,
+            frequency_penalty,
+            ucb_exploration,
+            acceptance,
+            lahc_length,
+            elite_min_distance,
+            tsp_polish,
+            route_pool,
+            bks_file,
+            target_cost,
+            format,
+            truck_matrix,
+            drone_matrix,
+            distance_rounding,
+            vehicle_cost,
+            objective,
+            objective_weight,
+            cost_function: Arc::new(PenalizedObjective),
+            pareto_archive,
+            no_fly_zones,
+            depot_slots,
+            depot_slot_time,
+            mothership,
+            allow_split,
+            split_of,
+            soft_weights: soft_weights(&soft_constraints),
+            soft_constraints,
+            release_times: vec![],
+            original_ids: vec![],
+            schedule,
+            log_backend,
+            log_format,
+            log_interval,
+            log_columns,
+            log_compress,
+            quiet,
+            progress_format,
+            progress_interval,
+            tui,
+            status_port,
+            notify_url,
+            run_id,
+            overwrite,
+            append,
+            truck_cfg,
+            drone_cfg,
+            stdout_solution,
+            params,
+            distance_unit,
+            time_unit,
+            clustering,
+            construction,
+            grasp,
+            grasp_cycles,
+            grasp_iterations,
+            decompose,
+            decompose_iterations,
+            islands,
+            migration_interval,
+            profile,
+            search_scope,
+            search_top_k,
+            tie_break,
+            tie_break_seed,
+            top_solutions,
+            top_min_distance,
+            detailed_routes,
+            pin,
+            assignments,
+            zones,
+            charging_stations,
+            charging_power,
+            ambient_temperature,
+            derating_curve,
+            tabu_acceptance,
+            repair_noise,
+            recluster_after,
+            penalty_restart,
+            drone_rebalancing,
+            init_command,
+            search_seed,
+            status_bind,
+        }
+        .with_drone_pairs()
+    }
+}
+
+/// Configuration parsed from the command line
+static ARGUMENTS_CONFIG: LazyLock<Config> = LazyLock::new(|| {
+    let arguments = params::parse();
+    debug!("Received {arguments:?}");
+    match arguments.command {
+        cli::Commands::Evaluate { config, .. }
+        | cli::Commands::Simulate { config, .. }
+        | cli::Commands::Sensitivity { config, .. }
+        | cli::Commands::VehicleRemoval { config, .. }
+        | cli::Commands::Repair { config, .. }
+        | cli::Commands::Diff { config, .. }
+        | cli::Commands::Convert { config, .. }
+        | cli::Commands::Plot { config, .. } => {
+            let data = fs::read_to_string(config).unwrap();
+            let deserialized = serde_json::from_str::<SerializedConfig>(&data).unwrap();
+            Config::from(deserialized)
+        }
+        cli::Commands::Reoptimize {
+            solution,
+            config,
+            customers,
+            elapsed,
+            fix_iteration,
+        } => {
+            let data = fs::read_to_string(config).unwrap();
+            let mut deserialized = serde_json::from_str::<SerializedConfig>(&data).unwrap();
+            if fix_iteration.is_some() {
+                deserialized.fix_iteration = fix_iteration;
             }
+
+            Config::from(deserialized.reoptimized(&solution, customers.as_deref(), elapsed))
         }
+        cli::Commands::Bks { .. } => panic!("No config is available for the \"bks\" subcommand"),
+        cli::Commands::Serve { .. } => panic!("The config of the \"serve\" subcommand is given by each request"),
+        cli::Commands::Bench { .. } => panic!("The configs of the \"bench\" subcommand are those of its instances"),
+        cli::Commands::Migrate { .. } => panic!("No config is available for the \"migrate\" subcommand"),
+        cli::Commands::Run(arguments)
+        | cli::Commands::ExplainConfig(arguments)
+        | cli::Commands::Exact(arguments)
+        | cli::Commands::ExportMilp(arguments) => Config::from(arguments),
     }
 });
+
+/// A configuration shared by the guards of a thread: cloning the `Rc` avoids the atomic reference count of the `Arc`
+#[allow(clippy::redundant_allocation)]
+type _LocalConfig = Rc<Arc<Config>>;
+
+thread_local! {
+    /// Configuration of the current thread set with `GlobalConfig::scoped`, if any
+    static _SCOPED: RefCell<Option<_LocalConfig>> = const { RefCell::new(None) };
+    /// Configuration set with `GlobalConfig::configure` last loaded by the current thread, with its generation
+    static _CONFIGURED: RefCell<Option<(usize, _LocalConfig)>> = const { RefCell::new(None) };
+}
+
+/// The global configuration: the one of the current thread set with `GlobalConfig::scoped`, the one set with
+/// `GlobalConfig::configure` (see `bench`), or else the one parsed from the command line. It is read through the guard
+/// returned by `GlobalConfig::load`, which keeps the configuration alive while it is borrowed.
+pub struct GlobalConfig {
+    _configured: Mutex<Option<Arc<Config>>>,
+    /// Number of calls to `configure`, so that each thread reloads the configuration only when it changed
+    _generation: AtomicUsize,
+    /// Number of threads running with a scoped configuration, so that the others skip the thread-local lookup
+    _scoped: AtomicUsize,
+}
+
+pub static CONFIG: GlobalConfig = GlobalConfig {
+    _configured: Mutex::new(None),
+    _generation: AtomicUsize::new(0),
+    _scoped: AtomicUsize::new(0),
+};
+
+/// A configuration loaded from `CONFIG`, which cannot be freed while the guard is alive
+pub enum ConfigGuard {
+    Arguments,
+    Shared(_LocalConfig),
+}
+
+impl Deref for ConfigGuard {
+    type Target = Config;
+
+    fn deref(&self) -> &Config {
+        match self {
+            Self::Arguments => &ARGUMENTS_CONFIG,
+            Self::Shared(config) => config,
+        }
+    }
+}
+
+/// Restores the configuration of the current thread when `GlobalConfig::scoped` returns or unwinds
+struct _ScopeGuard<'a> {
+    global: &'a GlobalConfig,
+    previous: Option<_LocalConfig>,
+}
+
+impl Drop for _ScopeGuard<'_> {
+    fn drop(&mut self) {
+        _SCOPED.set(self.previous.take());
+        self.global._scoped.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+impl GlobalConfig {
+    /// The configuration of the current thread. Each thread caches the configuration set with `configure`, so that
+    /// loading it only touches shared state when it changed.
+    pub fn load(&self) -> ConfigGuard {
+        if self._scoped.load(Ordering::Acquire) > 0
+            && let Some(config) = _SCOPED.with_borrow(Clone::clone)
+        {
+            return ConfigGuard::Shared(config);
+        }
+
+        let generation = self._generation.load(Ordering::Acquire);
+        if generation == 0 {
+            return ConfigGuard::Arguments;
+        }
+
+        _CONFIGURED.with_borrow_mut(|cached| {
+            if cached.as_ref().is_none_or(|(g, _)| *g != generation) {
+                *cached = self
+                    ._configured
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .clone()
+                    .map(|config| (generation, Rc::new(config)));
+            }
+
+            cached.as_ref().map_or(ConfigGuard::Arguments, |(_, config)| {
+                ConfigGuard::Shared(config.clone())
+            })
+        })
+    }
+
+    /// Replace the global configuration with `config`. The previous one is freed once no guard borrows it and every
+    /// thread that loaded it has loaded the new one.
+    pub fn configure(&self, config: Config) {
+        let mut configured = self._configured.lock().unwrap_or_else(PoisonError::into_inner);
+        *configured = Some(Arc::new(config));
+        self._generation.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Run `f` with `config` as the configuration of the current thread, e.g. to solve sub-instances concurrently.
+    pub fn scoped<R>(&self, config: Arc<Config>, f: impl FnOnce() -> R) -> R {
+        self._scoped.fetch_add(1, Ordering::AcqRel);
+        let _restore = _ScopeGuard {
+            global: self,
+            previous: _SCOPED.replace(Some(Rc::new(config))),
+        };
+
+        f()
+    }
+}
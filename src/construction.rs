@@ -0,0 +1,342 @@
+use std::mem;
+use std::rc::Rc;
+
+use rand::seq::IndexedRandom;
+
+use crate::config::CONFIG;
+use crate::ids::{CustomerId, DroneId, TruckId, VehicleId};
+use crate::routes::{DroneRoute, Route, TruckRoute};
+use crate::solutions::{FrequencyMemory, Solution, with_penalty_coeff};
+
+/// Penalty coefficient of every violation during construction, so that feasible placements are preferred
+const CONSTRUCTION_PENALTY: f64 = 1e3;
+
+/// Cost of the routes of `vehicle` of type `T` as if it were the only vehicle in use
+fn _vehicle_cost<T>(truck_routes: &[Vec<Rc<TruckRoute>>], drone_routes: &[Vec<Rc<DroneRoute>>], vehicle: usize) -> f64
+where
+    T: Route,
+{
+    let mut trucks = vec![vec![]; truck_routes.len()];
+    let mut drones = vec![vec![]; drone_routes.len()];
+    T::get_correct_route_mut(&mut trucks, &mut drones)[vehicle]
+        .clone_from(&T::get_correct_route(truck_routes, drone_routes)[vehicle]);
+    Solution::new(trucks, drones).cost()
+}
+
+/// Cheapest placement of a customer on a vehicle: its resulting vehicle cost, and the route and index to insert it at
+/// (`None` to append a new route)
+#[derive(Clone, Copy)]
+struct _Insertion {
+    cost: f64,
+    position: Option<(usize, usize)>,
+}
+
+fn _insertion<T>(
+    truck_routes: &mut Vec<Vec<Rc<TruckRoute>>>,
+    drone_routes: &mut Vec<Vec<Rc<DroneRoute>>>,
+    customer: usize,
+    vehicle: usize,
+) -> _Insertion
+where
+    T: Route,
+{
+    let mut best = _Insertion {
+        cost: f64::INFINITY,
+        position: None,
+    };
+
+    if !T::single_route() || T::get_correct_route(truck_routes, drone_routes)[vehicle].is_empty() {
+        T::get_correct_route_mut(truck_routes, drone_routes)[vehicle].push(T::single(CustomerId(customer)));
+        best.cost = _vehicle_cost::<T>(truck_routes, drone_routes, vehicle);
+        T::get_correct_route_mut(truck_routes, drone_routes)[vehicle].pop();
+    }
+
+    if !T::single_customer() {
+        for route in 0..T::get_correct_route(truck_routes, drone_routes)[vehicle].len() {
+            let original = T::get_correct_route(truck_routes, drone_routes)[vehicle][route].clone();
+            let customers = &original.data().customers;
+            for index in 1..customers.len() {
+                let mut buffer = customers.clone();
+                buffer.insert(index, customer);
+                T::get_correct_route_mut(truck_routes, drone_routes)[vehicle][route] = T::new(buffer);
+
+                let cost = _vehicle_cost::<T>(truck_routes, drone_routes, vehicle);
+                if cost < best.cost {
+                    best = _Insertion {
+                        cost,
+                        position: Some((route, index)),
+                    };
+                }
+            }
+
+            T::get_correct_route_mut(truck_routes, drone_routes)[vehicle][route] = original;
+        }
+    }
+
+    best
+}
+
+fn _insert<T>(routes: &mut [Vec<Rc<T>>], customer: usize, vehicle: usize, position: Option<(usize, usize)>)
+where
+    T: Route,
+{
+    match position {
+        None => routes[vehicle].push(T::single(CustomerId(customer))),
+        Some((route, index)) => {
+            let mut buffer = routes[vehicle][route].data().customers.clone();
+            buffer.insert(index, customer);
+            routes[vehicle][route] = T::new(buffer);
+        }
+    }
+}
+
+/// Cheapest placement of `customer` on `vehicle`, trucks being numbered before drones, or `None` if the vehicle
+/// cannot serve it
+fn _evaluate(
+    truck_routes: &mut Vec<Vec<Rc<TruckRoute>>>,
+    drone_routes: &mut Vec<Vec<Rc<DroneRoute>>>,
+    customer: usize,
+    vehicle: usize,
+) -> Option<_Insertion> {
+    let config = CONFIG.load();
+    let id = VehicleId::from_index(vehicle, config.trucks_count);
+    if !config.assignment_rules.assignable(customer, id) {
+        return None;
+    }
+
+    match id {
+        VehicleId::Truck(TruckId(truck)) => {
+            config.truckable[customer].then(|| _insertion::<TruckRoute>(truck_routes, drone_routes, customer, truck))
+        }
+        VehicleId::Drone(DroneId(drone)) => {
+            config.dronable[customer].then(|| _insertion::<DroneRoute>(truck_routes, drone_routes, customer, drone))
+        }
+    }
+}
+
+/// Insert every customer one at a time. Given the pending customers and the cheapest placement of each of them on
+/// each vehicle (`None` if the vehicle cannot serve it), `select` returns the index of the pending customer to insert
+/// and the vehicle to insert it on.
+fn _insert_all(mut select: impl FnMut(&[usize], &[Vec<Option<_Insertion>>]) -> (usize, usize)) -> Solution {
+    let config = CONFIG.load();
+    with_penalty_coeff(CONSTRUCTION_PENALTY, || {
+        let mut truck_routes = vec![vec![]; config.trucks_count];
+        let mut drone_routes = vec![vec![]; config.drones_count];
+        let vehicles = config.trucks_count + config.drones_count;
+
+        let mut pending = (1..config.customers_count + 1).collect::<Vec<_>>();
+        let mut insertions = pending
+            .iter()
+            .map(|&c| {
+                (0..vehicles)
+                    .map(|v| _evaluate(&mut truck_routes, &mut drone_routes, c, v))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        while !pending.is_empty() {
+            let (index, vehicle) = select(&pending, &insertions);
+            let customer = pending.swap_remove(index);
+            let placements = insertions.swap_remove(index);
+
+            let position = placements[vehicle].unwrap().position;
+            match VehicleId::from_index(vehicle, config.trucks_count) {
+                VehicleId::Truck(TruckId(truck)) => _insert(&mut truck_routes, customer, truck, position),
+                VehicleId::Drone(DroneId(drone)) => _insert(&mut drone_routes, customer, drone, position),
+            }
+
+            // Only the placements on the modified vehicle change
+            for (&c, placements) in pending.iter().zip(insertions.iter_mut()) {
+                placements[vehicle] = _evaluate(&mut truck_routes, &mut drone_routes, c, vehicle);
+            }
+        }
+
+        Solution::new(truck_routes, drone_routes)
+    })
+}
+
+/// The two lowest costs among the `placements` of `customer`, with the vehicle of the lowest one
+fn _cheapest(customer: usize, placements: &[Option<_Insertion>]) -> (f64, usize, f64) {
+    let mut best = (f64::INFINITY, usize::MAX);
+    let mut second = f64::INFINITY;
+    for (v, insertion) in placements.iter().enumerate() {
+        if let Some(insertion) = insertion {
+            if insertion.cost < best.0 {
+                second = best.0;
+                best = (insertion.cost, v);
+            } else if insertion.cost < second {
+                second = insertion.cost;
+            }
+        }
+    }
+
+    assert!(
+        best.1 != usize::MAX,
+        "Customer {customer} cannot be served by neither trucks nor drones"
+    );
+    (best.0, best.1, second)
+}
+
+/// Regret-2 insertion: repeatedly insert the customer whose cheapest placement is the most ahead of its cheapest
+/// placement on any other vehicle. Placements are compared by the resulting cost of their vehicle rather than by its
+/// increase, which balances the working times for the makespan.
+pub fn regret() -> Solution {
+    _insert_all(|pending, insertions| {
+        let (index, _, _, vehicle) = pending
+            .iter()
+            .zip(insertions)
+            .enumerate()
+            .map(|(index, (&customer, placements))| {
+                let (best, vehicle, second) = _cheapest(customer, placements);
+                (index, second - best, best, vehicle)
+            })
+            .max_by(|f, s| f.1.total_cmp(&s.1).then(s.2.total_cmp(&f.2)))
+            .unwrap();
+
+        (index, vehicle)
+    })
+}
+
+/// Greedy randomized insertion of GRASP: repeatedly insert at its cheapest placement a customer drawn uniformly from
+/// the restricted candidate list, the pending customers whose cheapest placement costs at most
+/// `min + alpha * (max - min)` (0 is greedy, 1 is uniformly random).
+pub fn grasp(alpha: f64) -> Solution {
+    let mut rng = rand::rng();
+    _insert_all(|pending, insertions| {
+        let cheapest = pending
+            .iter()
+            .zip(insertions)
+            .map(|(&customer, placements)| _cheapest(customer, placements))
+            .collect::<Vec<_>>();
+        let (min, max) = cheapest
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &(cost, _, _)| {
+                (min.min(cost), max.max(cost))
+            });
+
+        let threshold = alpha.mul_add(max - min, min);
+        let candidates = (0..pending.len())
+            .filter(|&i| cheapest[i].0 <= threshold)
+            .collect::<Vec<_>>();
+        let &index = candidates.choose(&mut rng).unwrap();
+
+        (index, cheapest[index].1)
+    })
+}
+
+/// Clarke-Wright savings: merge single-customer truck routes by decreasing savings `d(i, 0) + d(0, j) - d(i, j)` as
+/// long as the merged route has no capacity or waiting time violation, assign the routes by decreasing working time to
+/// the vehicle (a drone if it can serve every customer of the route) whose resulting cost is the lowest, then insert the
+/// remaining customers with the destroy-and-repair insertion.
+pub fn savings() -> Solution {
+    let config = CONFIG.load();
+    with_penalty_coeff(CONSTRUCTION_PENALTY, || {
+        let distances = &config.truck_distances;
+        let customers = (1..config.customers_count + 1)
+            .filter(|&c| config.trucks_count > 0 && config.truckable[c])
+            .collect::<Vec<_>>();
+
+        let mut routes = vec![vec![]; config.customers_count + 1];
+        let mut route_of = (0..config.customers_count + 1).collect::<Vec<_>>();
+        for &c in &customers {
+            routes[c].push(c);
+        }
+
+        let mut savings = vec![];
+        for &i in &customers {
+            for &j in &customers {
+                let saving = distances[i][0] + distances[0][j] - distances[i][j];
+                if i != j && saving > 0.0 {
+                    savings.push((saving, i, j));
+                }
+            }
+        }
+        savings.sort_by(|f, s| s.0.total_cmp(&f.0));
+
+        for (_, i, j) in savings {
+            let (a, b) = (route_of[i], route_of[j]);
+            if a == b || routes[a].last() != Some(&i) || routes[b].first() != Some(&j) {
+                continue;
+            }
+
+            let mut merged = vec![0];
+            merged.extend(&routes[a]);
+            merged.extend(&routes[b]);
+            merged.push(0);
+            let route = TruckRoute::new(merged);
+            if route.capacity_violation() > 0.0 || route.waiting_time_violation() > 0.0 {
+                continue;
+            }
+
+            let moved = mem::take(&mut routes[b]);
+            for &c in &moved {
+                route_of[c] = a;
+            }
+            routes[a].extend(moved);
+        }
+
+        let mut merged = routes
+            .into_iter()
+            .filter(|r| !r.is_empty())
+            .map(|r| {
+                let mut customers = vec![0];
+                customers.extend(r);
+                customers.push(0);
+                TruckRoute::new(customers)
+            })
+            .collect::<Vec<_>>();
+        merged.sort_by(|f, s| s.working_time().total_cmp(&f.working_time()));
+
+        let mut truck_routes = vec![vec![]; config.trucks_count];
+        let mut drone_routes = vec![vec![]; config.drones_count];
+        let mut leftovers = (1..config.customers_count + 1)
+            .filter(|&c| !customers.contains(&c))
+            .collect::<Vec<_>>();
+        for route in merged {
+            let route_customers = &route.data().customers;
+            let mut best = (f64::INFINITY, None);
+            for truck in 0..config.trucks_count {
+                if !TruckRoute::single_route() || truck_routes[truck].is_empty() {
+                    truck_routes[truck].push(route.clone());
+                    let cost = _vehicle_cost::<TruckRoute>(&truck_routes, &drone_routes, truck);
+                    truck_routes[truck].pop();
+                    if cost < best.0 {
+                        best = (cost, Some((truck, true)));
+                    }
+                }
+            }
+
+            if route_customers[1..route_customers.len() - 1]
+                .iter()
+                .all(|&c| config.dronable[c])
+                && (!DroneRoute::single_customer() || route_customers.len() == 3)
+            {
+                let drone_route = DroneRoute::new(route_customers.clone());
+                for drone in 0..config.drones_count {
+                    drone_routes[drone].push(drone_route.clone());
+                    let cost = _vehicle_cost::<DroneRoute>(&truck_routes, &drone_routes, drone);
+                    drone_routes[drone].pop();
+                    if cost < best.0 {
+                        best = (cost, Some((drone, false)));
+                    }
+                }
+            }
+
+            match best.1 {
+                Some((truck, true)) => truck_routes[truck].push(route),
+                Some((drone, false)) => drone_routes[drone].push(DroneRoute::new(route_customers.clone())),
+                None => leftovers.extend(&route_customers[1..route_customers.len() - 1]),
+            }
+        }
+
+        Solution::repair(
+            truck_routes,
+            drone_routes,
+            leftovers,
+            &FrequencyMemory::new(),
+            0.0,
+            None,
+            &mut rand::rng(),
+        )
+    })
+}
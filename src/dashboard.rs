@@ -0,0 +1,200 @@
+use std::io::{self, Stderr};
+use std::time::SystemTime;
+
+use ratatui::Terminal;
+use ratatui::backend::CrosstermBackend;
+use ratatui::crossterm::cursor::{Hide, Show};
+use ratatui::crossterm::execute;
+use ratatui::crossterm::terminal::{EnterAlternateScreen, LeaveAlternateScreen};
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::style::{Color, Style};
+use ratatui::symbols::Marker;
+use ratatui::text::Line;
+use ratatui::widgets::{Axis, Bar, BarChart, BarGroup, Block, Chart, Dataset, GraphType, Paragraph};
+
+use crate::config::CONFIG;
+use crate::solutions::{NEIGHBORHOODS, Solution, penalty_coeff};
+
+/// Minimum interval in seconds between two redraws of the dashboard
+const REFRESH_INTERVAL: f64 = 0.25;
+
+/// Maximum number of points kept per cost curve; older points are thinned out beyond this
+const MAX_POINTS: usize = 2000;
+
+/// State of the search shown by the dashboard
+pub struct DashboardState<'a> {
+    pub iteration: usize,
+    pub elapsed: f64,
+    pub result: &'a Solution,
+    pub current: &'a Solution,
+    pub elite_size: usize,
+    pub weights: &'a [f64],
+}
+
+/// Live terminal dashboard of the tabu search (see `--tui`), drawn on the alternate screen of stderr so that the
+/// output paths on stdout are unaffected.
+pub struct Dashboard {
+    _terminal: Terminal<CrosstermBackend<Stderr>>,
+    _last_draw: SystemTime,
+    _incumbent: Vec<(f64, f64)>,
+    _current: Vec<(f64, f64)>,
+}
+
+/// Keep every other point of `points`
+fn _thin(points: &mut Vec<(f64, f64)>) {
+    let mut index = 0;
+    points.retain(|_| {
+        index += 1;
+        index % 2 == 1
+    });
+}
+
+impl Dashboard {
+    pub fn new() -> io::Result<Self> {
+        let mut stderr = io::stderr();
+        execute!(stderr, EnterAlternateScreen, Hide)?;
+        let mut terminal = Terminal::new(CrosstermBackend::new(stderr))?;
+        terminal.clear()?;
+
+        Ok(Self {
+            _terminal: terminal,
+            _last_draw: SystemTime::UNIX_EPOCH,
+            _incumbent: vec![],
+            _current: vec![],
+        })
+    }
+
+    /// Record the search state and redraw the dashboard, at most once every `REFRESH_INTERVAL` seconds.
+    pub fn update(&mut self, state: &DashboardState) -> io::Result<()> {
+        let now = SystemTime::now();
+        if now
+            .duration_since(self._last_draw)
+            .map_or(true, |d| d.as_secs_f64() < REFRESH_INTERVAL)
+        {
+            return Ok(());
+        }
+        self._last_draw = now;
+
+        let iteration = state.iteration as f64;
+        self._incumbent.push((iteration, state.result.cost()));
+        self._current.push((iteration, state.current.cost()));
+        if self._incumbent.len() > MAX_POINTS {
+            _thin(&mut self._incumbent);
+            _thin(&mut self._current);
+        }
+
+        let (min_cost, max_cost) = self
+            ._incumbent
+            .iter()
+            .chain(&self._current)
+            .fold((f64::MAX, f64::MIN), |(min, max), &(_, c)| (min.min(c), max.max(c)));
+        let first = self._incumbent[0].0;
+
+        let bars = NEIGHBORHOODS
+            .iter()
+            .zip(state.weights)
+            .map(|(neighborhood, &weight)| {
+                Bar::default()
+                    .label(Line::from(neighborhood.to_string()))
+                    .value((100.0 * weight) as u64)
+                    .text_value(format!("{weight:.2}"))
+            })
+            .collect::<Vec<_>>();
+
+        let violations = [
+            ("Energy", state.current.energy_violation, penalty_coeff::<0>()),
+            ("Capacity", state.current.capacity_violation, penalty_coeff::<1>()),
+            (
+                "Waiting time",
+                state.current.waiting_time_violation,
+                penalty_coeff::<2>(),
+            ),
+            ("Fixed time", state.current.fixed_time_violation, penalty_coeff::<3>()),
+            ("Depot slot", state.current.depot_slot_violation, penalty_coeff::<4>()),
+            ("Assignment", state.current.assignment_violation, penalty_coeff::<5>()),
+            ("No-fly", state.current.no_fly_violation, penalty_coeff::<6>()),
+        ];
+        let mut status = vec![
+            Line::from(format!("Problem      {}", CONFIG.load().problem)),
+            Line::from(format!("Iteration    {}", state.iteration)),
+            Line::from(format!("Elapsed      {:.1}s", state.elapsed)),
+            Line::from(format!(
+                "Incumbent    {:.2}{}",
+                state.result.cost(),
+                if state.result.feasible { "" } else { " (infeasible)" }
+            )),
+            Line::from(format!("Current      {:.2}", state.current.cost())),
+            Line::from(format!(
+                "Elite set    {}/{}",
+                state.elite_size,
+                CONFIG.load().max_elite_size
+            )),
+            Line::from(""),
+        ];
+        for (name, violation, coefficient) in violations {
+            let style = if violation > 0.0 {
+                Style::default().fg(Color::Red)
+            } else {
+                Style::default()
+            };
+            status.push(Line::styled(
+                format!("{name:<13}{violation:.2} (penalty {coefficient:.2})"),
+                style,
+            ));
+        }
+
+        self._terminal.draw(|frame| {
+            let [top, bottom] =
+                Layout::vertical([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(frame.area());
+            let [left, right] =
+                Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(bottom);
+
+            let datasets = vec![
+                Dataset::default()
+                    .name("Incumbent")
+                    .marker(Marker::Braille)
+                    .graph_type(GraphType::Line)
+                    .style(Style::default().fg(Color::Green))
+                    .data(&self._incumbent),
+                Dataset::default()
+                    .name("Current")
+                    .marker(Marker::Braille)
+                    .graph_type(GraphType::Line)
+                    .style(Style::default().fg(Color::Yellow))
+                    .data(&self._current),
+            ];
+            let chart = Chart::new(datasets)
+                .block(Block::bordered().title("Cost"))
+                .x_axis(
+                    Axis::default()
+                        .bounds([first, iteration.max(first + 1.0)])
+                        .labels([format!("{first}"), format!("{iteration}")]),
+                )
+                .y_axis(
+                    Axis::default()
+                        .bounds([min_cost, max_cost.max(min_cost + 1.0)])
+                        .labels([format!("{min_cost:.0}"), format!("{max_cost:.0}")]),
+                );
+            frame.render_widget(chart, top);
+
+            let weights = BarChart::default()
+                .block(Block::bordered().title("Neighborhood weights"))
+                .direction(Direction::Horizontal)
+                .bar_width(1)
+                .bar_gap(0)
+                .data(BarGroup::default().bars(&bars));
+            frame.render_widget(weights, left);
+
+            frame.render_widget(Paragraph::new(status).block(Block::bordered().title("Search")), right);
+        })?;
+
+        Ok(())
+    }
+}
+
+impl Drop for Dashboard {
+    /// Restore the terminal when the search ends
+    fn drop(&mut self) {
+        let _ = execute!(io::stderr(), LeaveAlternateScreen, Show);
+    }
+}
@@ -0,0 +1,237 @@
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rusqlite::{Connection, params};
+
+use crate::solutions::{Solution, penalty_coeff};
+
+/// File name of the results database in the output directory
+const DATABASE_FILE: &str = "results.sqlite3";
+
+/// Number of iteration rows written per transaction
+const COMMIT_INTERVAL: usize = 1000;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    id TEXT PRIMARY KEY,
+    problem TEXT NOT NULL,
+    started REAL NOT NULL,
+    config TEXT NOT NULL,
+    iterations INTEGER,
+    elapsed REAL,
+    working_time REAL,
+    cost REAL,
+    feasible INTEGER,
+    bks_gap REAL,
+    summary TEXT
+);
+
+CREATE TABLE IF NOT EXISTS iterations (
+    run_id TEXT NOT NULL REFERENCES runs(id),
+    iteration INTEGER NOT NULL,
+    cost REAL NOT NULL,
+    working_time REAL NOT NULL,
+    feasible INTEGER NOT NULL,
+    energy_penalty REAL NOT NULL,
+    energy_violation REAL NOT NULL,
+    capacity_penalty REAL NOT NULL,
+    capacity_violation REAL NOT NULL,
+    waiting_time_penalty REAL NOT NULL,
+    waiting_time_violation REAL NOT NULL,
+    fixed_time_penalty REAL NOT NULL,
+    fixed_time_violation REAL NOT NULL,
+    depot_slot_penalty REAL NOT NULL,
+    depot_slot_violation REAL NOT NULL,
+    assignment_penalty REAL NOT NULL DEFAULT 0,
+    assignment_violation REAL NOT NULL DEFAULT 0,
+    no_fly_penalty REAL NOT NULL DEFAULT 0,
+    no_fly_violation REAL NOT NULL DEFAULT 0,
+    truck_routes TEXT NOT NULL,
+    drone_routes TEXT NOT NULL,
+    truck_routes_count INTEGER NOT NULL,
+    drone_routes_count INTEGER NOT NULL,
+    neighborhood TEXT NOT NULL,
+    tabu_list TEXT NOT NULL,
+    PRIMARY KEY (run_id, iteration)
+);
+
+CREATE TABLE IF NOT EXISTS solutions (
+    run_id TEXT PRIMARY KEY REFERENCES runs(id),
+    solution TEXT NOT NULL
+);
+";
+
+/// SQLite database shared by the runs written to the same output directory (see `--log-backend`)
+pub struct Database {
+    _connection: Connection,
+    _path: PathBuf,
+    _run: String,
+    _pending: usize,
+}
+
+impl Database {
+    /// Open (or create) the database in `outputs` and register the run `run` of `problem` with the serialized
+    /// `config`. A run already recorded with the same id is removed if `overwrite` is set, and is an error otherwise.
+    pub fn open(
+        outputs: &Path,
+        run: &str,
+        problem: &str,
+        config: &str,
+        overwrite: bool,
+    ) -> Result<Self, Box<dyn Error>> {
+        let path = outputs.join(DATABASE_FILE);
+        let connection = Connection::open(&path)?;
+        connection.execute_batch(SCHEMA)?;
+
+        // Databases created before the assignment violation (see `--assignments`) or the no-fly violation (see
+        // `--no-fly-zones`) lack their columns
+        let columns = connection
+            .prepare("SELECT * FROM iterations")?
+            .column_names()
+            .into_iter()
+            .map(String::from)
+            .collect::<Vec<_>>();
+        if !columns.iter().any(|c| c == "assignment_violation") {
+            connection.execute_batch(
+                "ALTER TABLE iterations ADD COLUMN assignment_penalty REAL NOT NULL DEFAULT 0;
+                 ALTER TABLE iterations ADD COLUMN assignment_violation REAL NOT NULL DEFAULT 0;",
+            )?;
+        }
+        if !columns.iter().any(|c| c == "no_fly_violation") {
+            connection.execute_batch(
+                "ALTER TABLE iterations ADD COLUMN no_fly_penalty REAL NOT NULL DEFAULT 0;
+                 ALTER TABLE iterations ADD COLUMN no_fly_violation REAL NOT NULL DEFAULT 0;",
+            )?;
+        }
+
+        let exists = connection.query_row("SELECT EXISTS(SELECT 1 FROM runs WHERE id = ?1)", [run], |row| {
+            row.get::<_, bool>(0)
+        })?;
+        if exists {
+            if !overwrite {
+                return Err(format!("Run {run} already exists in {}, pass --overwrite", path.display()).into());
+            }
+            for table in ["iterations", "solutions"] {
+                connection.execute(&format!("DELETE FROM {table} WHERE run_id = ?1"), [run])?;
+            }
+            connection.execute("DELETE FROM runs WHERE id = ?1", [run])?;
+        }
+
+        let started = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs_f64();
+        connection.execute(
+            "INSERT INTO runs (id, problem, started, config) VALUES (?1, ?2, ?3, ?4)",
+            params![run, problem, started, config],
+        )?;
+        connection.execute_batch("BEGIN")?;
+
+        Ok(Self {
+            _connection: connection,
+            _path: path,
+            _run: run.to_string(),
+            _pending: 0,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self._path
+    }
+
+    /// Append the row of one iteration, with the routes, neighborhood and tabu list already formatted.
+    pub fn iteration(
+        &mut self,
+        iteration: usize,
+        solution: &Solution,
+        (truck_routes, drone_routes): (&str, &str),
+        neighborhood: &str,
+        tabu_list: &str,
+    ) -> rusqlite::Result<()> {
+        self._connection
+            .prepare_cached(
+                "INSERT INTO iterations (run_id, iteration, cost, working_time, feasible, energy_penalty, \
+                 energy_violation, capacity_penalty, capacity_violation, waiting_time_penalty, waiting_time_violation, \
+                 fixed_time_penalty, fixed_time_violation, depot_slot_penalty, depot_slot_violation, \
+                 assignment_penalty, assignment_violation, no_fly_penalty, no_fly_violation, truck_routes, drone_routes, \
+                 truck_routes_count, drone_routes_count, neighborhood, tabu_list) VALUES \
+                 (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, \
+                 ?23, ?24, ?25)",
+            )?
+            .execute(params![
+                self._run,
+                iteration,
+                solution.cost(),
+                solution.working_time,
+                solution.feasible,
+                penalty_coeff::<0>(),
+                solution.energy_violation,
+                penalty_coeff::<1>(),
+                solution.capacity_violation,
+                penalty_coeff::<2>(),
+                solution.waiting_time_violation,
+                penalty_coeff::<3>(),
+                solution.fixed_time_violation,
+                penalty_coeff::<4>(),
+                solution.depot_slot_violation,
+                penalty_coeff::<5>(),
+                solution.assignment_violation,
+                penalty_coeff::<6>(),
+                solution.no_fly_violation,
+                truck_routes,
+                drone_routes,
+                solution.truck_routes.iter().map(Vec::len).sum::<usize>(),
+                solution.drone_routes.iter().map(Vec::len).sum::<usize>(),
+                neighborhood,
+                tabu_list,
+            ])?;
+
+        self._pending += 1;
+        if self._pending >= COMMIT_INTERVAL {
+            self._connection.execute_batch("COMMIT; BEGIN")?;
+            self._pending = 0;
+        }
+
+        Ok(())
+    }
+
+    /// Record the outcome of the run: its final `solution` (serialized as `solution_json`) and the run `summary`
+    /// (the content of the run JSON of the CSV backend).
+    pub fn finalize(
+        &mut self,
+        solution: &Solution,
+        solution_json: &str,
+        (iterations, elapsed, bks_gap): (usize, f64, Option<f64>),
+        summary: &str,
+    ) -> rusqlite::Result<()> {
+        self._connection.execute(
+            "UPDATE runs SET iterations = ?2, elapsed = ?3, working_time = ?4, cost = ?5, feasible = ?6, \
+             bks_gap = ?7, summary = ?8 WHERE id = ?1",
+            params![
+                self._run,
+                iterations,
+                elapsed,
+                solution.working_time,
+                solution.cost(),
+                solution.feasible,
+                bks_gap,
+                summary,
+            ],
+        )?;
+        self._connection.execute(
+            "INSERT INTO solutions (run_id, solution) VALUES (?1, ?2)",
+            params![self._run, solution_json],
+        )?;
+        self._connection.execute_batch("COMMIT")?;
+        self._pending = 0;
+
+        Ok(())
+    }
+}
+
+impl Drop for Database {
+    /// Keep the iterations logged so far if the run ends without being finalized
+    fn drop(&mut self) {
+        if !self._connection.is_autocommit() {
+            let _ = self._connection.execute_batch("COMMIT");
+        }
+    }
+}
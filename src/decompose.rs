@@ -0,0 +1,235 @@
+use std::rc::Rc;
+use std::sync::Arc;
+use std::{cmp, iter, thread};
+
+use tracing::{info, info_span};
+
+use crate::assignments::Assignments;
+use crate::clusterize;
+use crate::config::{CONFIG, Config};
+use crate::ids::{CustomerId, DroneId, TruckId, VehicleId};
+use crate::pins::Pins;
+use crate::routes::{DroneRoute, Route, TruckRoute};
+use crate::solutions::Solution;
+
+/// Customers of the routes of each truck and each drone of a solved part, numbered as in its config
+struct _Routes {
+    truck_routes: Vec<Vec<Vec<usize>>>,
+    drone_routes: Vec<Vec<Vec<usize>>>,
+}
+
+/// Split `total` vehicles among the parts proportionally to their `sizes` with the largest remainder method, after
+/// giving `minimum` vehicles to each part
+fn _shares(total: usize, sizes: &[usize], minimum: usize) -> Vec<usize> {
+    let spare = total - minimum * sizes.len();
+    let customers = sizes.iter().sum::<usize>();
+    let mut shares = sizes
+        .iter()
+        .map(|&size| minimum + spare * size / customers)
+        .collect::<Vec<_>>();
+
+    let mut order = (0..sizes.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&i| cmp::Reverse(spare * sizes[i] % customers));
+    let left = total - shares.iter().sum::<usize>();
+    for &i in order.iter().take(left) {
+        shares[i] += 1;
+    }
+
+    shares
+}
+
+/// Config of the sub-instance made of the `nodes` of the current config (the depot first), served by `trucks` and
+/// `drones`. Sub-instances are solved silently.
+fn _config(nodes: &[usize], trucks: &[usize], drones: &[usize]) -> Config {
+    let pick = |values: &[f64]| nodes.iter().map(|&i| values[i]).collect::<Vec<_>>();
+    let submatrix = |matrix: &[Vec<f64>]| {
+        nodes
+            .iter()
+            .map(|&i| nodes.iter().map(|&j| matrix[i][j]).collect())
+            .collect()
+    };
+
+    Config {
+        customers_count: nodes.len() - 1,
+        trucks_count: trucks.len(),
+        drones_count: drones.len(),
+        x: pick(&CONFIG.load().x),
+        y: pick(&CONFIG.load().y),
+        demands: pick(&CONFIG.load().demands),
+        dronable: nodes.iter().map(|&i| CONFIG.load().dronable[i]).collect(),
+        truckable: nodes.iter().map(|&i| CONFIG.load().truckable[i]).collect(),
+        waiting_time_limits: pick(&CONFIG.load().waiting_time_limits),
+        elevations: if CONFIG.load().elevations.is_empty() {
+            vec![]
+        } else {
+            pick(&CONFIG.load().elevations)
+        },
+        truck_distances: submatrix(&CONFIG.load().truck_distances),
+        drone_distances: submatrix(&CONFIG.load().drone_distances),
+        no_fly: if CONFIG.load().no_fly.is_empty() {
+            vec![]
+        } else {
+            nodes
+                .iter()
+                .map(|&i| nodes.iter().map(|&j| CONFIG.load().no_fly[i][j]).collect())
+                .collect()
+        },
+        charging_nodes: if CONFIG.load().charging_nodes.is_empty() {
+            vec![]
+        } else {
+            nodes.iter().map(|&i| CONFIG.load().charging_nodes[i]).collect()
+        },
+        drone_pairs: nodes
+            .iter()
+            .map(|&i| nodes.iter().map(|&j| CONFIG.load().drone_pairs[i][j]).collect())
+            .collect(),
+        drone_class: drones
+            .iter()
+            .map(|&d| CONFIG.load().drone_class_of(DroneId(d)))
+            .collect(),
+        split_of: nodes
+            .iter()
+            .map(|&i| nodes.iter().position(|&j| j == CONFIG.load().split_of[i]).unwrap())
+            .collect(),
+        release_times: if CONFIG.load().release_times.is_empty() {
+            vec![]
+        } else {
+            trucks
+                .iter()
+                .map(|&t| VehicleId::Truck(TruckId(t)))
+                .chain(drones.iter().map(|&d| VehicleId::Drone(DroneId(d))))
+                .map(|vehicle| CONFIG.load().release_time(vehicle))
+                .collect()
+        },
+        original_ids: vec![],
+        pins: Pins::default(),
+        assignment_rules: Assignments::default(),
+        disable_logging: true,
+        quiet: true,
+        tui: false,
+        status_port: None,
+        notify_url: None,
+        ..CONFIG.load().clone()
+    }
+}
+
+/// Solve the instance of the current config with an initial solution followed by a short tabu search
+fn _solve() -> _Routes {
+    fn _customers<T>(vehicle_routes: &[Vec<Rc<T>>]) -> Vec<Vec<Vec<usize>>>
+    where
+        T: Route,
+    {
+        vehicle_routes
+            .iter()
+            .map(|routes| routes.iter().map(|route| route.data().customers.clone()).collect())
+            .collect()
+    }
+
+    let root = Solution::initialize();
+    let solution = Solution::short_search(root, CONFIG.load().decompose_iterations);
+    info!(
+        "Solved {} customers with {} trucks and {} drones: {:.2}{}",
+        CONFIG.load().customers_count,
+        CONFIG.load().trucks_count,
+        CONFIG.load().drones_count,
+        solution.cost(),
+        if solution.feasible { "" } else { " (infeasible)" }
+    );
+
+    _Routes {
+        truck_routes: _customers(&solution.truck_routes),
+        drone_routes: _customers(&solution.drone_routes),
+    }
+}
+
+/// Initial solution of `--decompose`: the customers are partitioned into `parts` clusters (see `--clustering`), each
+/// solved in parallel with a share of the vehicles proportional to its number of customers, and the routes of all
+/// parts are merged into a solution of the whole instance.
+pub fn root(parts: usize) -> Solution {
+    assert!(
+        (1..CONFIG.load().trucks_count + 1).contains(&parts),
+        "--decompose must be between 1 and the number of trucks ({})",
+        CONFIG.load().trucks_count
+    );
+    let _span = info_span!("decompose", parts).entered();
+
+    // Twin nodes of split customers are solved together with their original customer
+    let mut originals = (1..CONFIG.load().customers_count + 1)
+        .filter(|&c| CONFIG.load().split_of[c] == c)
+        .map(CustomerId)
+        .collect::<Vec<_>>();
+    let nodes = clusterize::clusterize(&mut originals, parts, CONFIG.load().clustering)
+        .into_iter()
+        .filter(|cluster| !cluster.is_empty())
+        .map(|cluster| {
+            let twins = (1..CONFIG.load().customers_count + 1)
+                .filter(|&c| CONFIG.load().split_of[c] != c && cluster.contains(&CustomerId(CONFIG.load().split_of[c])))
+                .collect::<Vec<_>>();
+            iter::once(0)
+                .chain(cluster.into_iter().map(|CustomerId(c)| c))
+                .chain(twins)
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let sizes = nodes.iter().map(|n| n.len() - 1).collect::<Vec<_>>();
+    let truck_shares = _shares(CONFIG.load().trucks_count, &sizes, 1);
+    let drone_shares = _shares(
+        CONFIG.load().drones_count,
+        &sizes,
+        usize::from(CONFIG.load().drones_count >= sizes.len()),
+    );
+
+    let mut vehicles = vec![];
+    let (mut truck, mut drone) = (0, 0);
+    for (&trucks, &drones) in truck_shares.iter().zip(&drone_shares) {
+        vehicles.push((
+            (truck..truck + trucks).collect::<Vec<_>>(),
+            (drone..drone + drones).collect::<Vec<_>>(),
+        ));
+        truck += trucks;
+        drone += drones;
+    }
+
+    let configs = nodes
+        .iter()
+        .zip(&vehicles)
+        .map(|(nodes, (trucks, drones))| Arc::new(_config(nodes, trucks, drones)))
+        .collect::<Vec<_>>();
+    let solved = thread::scope(|scope| {
+        // Every part is spawned before any is joined
+        let mut handles = vec![];
+        for (part, config) in configs.iter().enumerate() {
+            handles.push(scope.spawn(move || {
+                let _span = info_span!("part", part).entered();
+                CONFIG.scoped(config.clone(), _solve)
+            }));
+        }
+
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+
+    let mut truck_routes = vec![vec![]; CONFIG.load().trucks_count];
+    let mut drone_routes = vec![vec![]; CONFIG.load().drones_count];
+    for ((nodes, (trucks, drones)), routes) in nodes.iter().zip(&vehicles).zip(solved) {
+        let renumber = |customers: &[usize]| customers.iter().map(|&c| nodes[c]).collect::<Vec<_>>();
+        for (&truck, routes) in trucks.iter().zip(&routes.truck_routes) {
+            truck_routes[truck] = routes.iter().map(|r| TruckRoute::new(renumber(r))).collect();
+        }
+        for (&drone, routes) in drones.iter().zip(&routes.drone_routes) {
+            drone_routes[drone] = routes.iter().map(|r| DroneRoute::new(renumber(r))).collect();
+        }
+    }
+
+    let solution = Solution::new(truck_routes, drone_routes);
+    info!(
+        "Merged solution of {} parts: {:.2}{}",
+        nodes.len(),
+        solution.cost(),
+        if solution.feasible { "" } else { " (infeasible)" }
+    );
+    solution
+}
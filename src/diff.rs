@@ -0,0 +1,76 @@
+use std::io::{self, Write};
+
+use crate::ids::{DroneId, TruckId, VehicleId};
+use crate::solutions::{Change, Placement, Solution};
+
+/// Format `placement` as its vehicle, 1-based trip and position in the trip
+fn _placement(placement: Option<Placement>) -> String {
+    match placement {
+        Some(Placement {
+            vehicle,
+            route,
+            position,
+        }) => {
+            let vehicle = match vehicle {
+                VehicleId::Truck(TruckId(truck)) => format!("truck {truck}"),
+                VehicleId::Drone(DroneId(drone)) => format!("drone {drone}"),
+            };
+            format!("{vehicle}, trip {}, position {position}", route + 1)
+        }
+        None => "-".to_string(),
+    }
+}
+
+/// Write a report of the differences from `first` to `second` to `writer`: their objectives, the number of customers
+/// changing vehicle, route or position and the old and new placement of each of them.
+pub fn report(first: &Solution, second: &Solution, writer: &mut impl Write) -> io::Result<()> {
+    let diff = first.diff(second);
+    let count = |change: Change| diff.changes.iter().filter(|c| c.change == change).count();
+
+    writeln!(
+        writer,
+        "Working time: {:.2} -> {:.2} ({:+.2})",
+        first.working_time,
+        second.working_time,
+        second.working_time - first.working_time
+    )?;
+    writeln!(
+        writer,
+        "Cost: {:.2} -> {:.2}, feasible: {} -> {}",
+        first.cost(),
+        second.cost(),
+        first.feasible,
+        second.feasible
+    )?;
+    writeln!(writer, "Hamming distance: {}", diff.hamming_distance)?;
+    writeln!(
+        writer,
+        "Changed customers: {} ({} vehicle, {} route, {} position)",
+        diff.changes.len(),
+        count(Change::Vehicle),
+        count(Change::Route),
+        count(Change::Position)
+    )?;
+
+    if !diff.changes.is_empty() {
+        writeln!(writer)?;
+        writeln!(writer, "{:>8}  {:<10}{:<32}After", "Customer", "Change", "Before")?;
+        for change in &diff.changes {
+            let kind = match change.change {
+                Change::Vehicle => "vehicle",
+                Change::Route => "route",
+                Change::Position => "position",
+            };
+            writeln!(
+                writer,
+                "{:>8}  {:<10}{:<32}{}",
+                change.customer,
+                kind,
+                _placement(change.before),
+                _placement(change.after)
+            )?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,351 @@
+use std::iter;
+use std::rc::Rc;
+
+use tracing::info;
+
+use crate::cli::Objective;
+use crate::config::CONFIG;
+use crate::ids::{DroneId, TruckId, VehicleId};
+use crate::routes::{DroneRoute, Route, TruckRoute};
+use crate::solutions::Solution;
+use crate::tsp;
+
+/// Maximum number of customers of an instance solved exactly, since every subset of them is enumerated
+pub const MAX_CUSTOMERS: usize = 12;
+
+/// Customers of the subset `mask` (bit `i` standing for customer `i + 1`), in increasing order
+fn _customers(mask: usize) -> impl Iterator<Item = usize> {
+    (0..MAX_CUSTOMERS).filter(move |i| mask & (1 << i) != 0).map(|i| i + 1)
+}
+
+fn _weight(mask: usize) -> f64 {
+    _customers(mask).map(|c| CONFIG.load().demands[c]).sum()
+}
+
+/// Contribution of a route, or of the time between two routes of a vehicle, to the objective
+fn _value(working_time: f64, energy: f64) -> f64 {
+    match CONFIG.load().objective {
+        Objective::Makespan | Objective::TotalTime => working_time,
+        Objective::TotalEnergy => energy,
+        Objective::Weighted => unreachable!(),
+    }
+}
+
+/// Feasible truck route of least working time serving each subset of the customers, if any.
+///
+/// The shortest visiting order of each subset is tried first. When it violates the waiting time limits, every order
+/// is enumerated instead, skipping the orders whose partial distance already exceeds the best feasible route (the
+/// working time of a truck route being its distance over the truck speed).
+fn _truck_routes(n: usize) -> Vec<Option<Rc<TruckRoute>>> {
+    fn _enumerate(order: &mut Vec<usize>, remaining: usize, distance: f64, best: &mut Option<Rc<TruckRoute>>) {
+        if best
+            .as_ref()
+            .is_some_and(|best| distance / CONFIG.load().truck.speed >= best.working_time())
+        {
+            return;
+        }
+
+        let last = *order.last().unwrap();
+        if remaining == 0 {
+            let route = TruckRoute::new([order.as_slice(), &[0]].concat());
+            if route.waiting_time_violation() == 0.0
+                && best
+                    .as_ref()
+                    .is_none_or(|best| route.working_time() < best.working_time())
+            {
+                *best = Some(route);
+            }
+            return;
+        }
+
+        for customer in _customers(remaining) {
+            order.push(customer);
+            _enumerate(
+                order,
+                remaining & !(1 << (customer - 1)),
+                distance + CONFIG.load().truck_distances[last][customer],
+                best,
+            );
+            order.pop();
+        }
+    }
+
+    let mut routes = vec![None; 1 << n];
+    for (mask, route) in routes.iter_mut().enumerate().skip(1) {
+        if !_customers(mask).all(|c| CONFIG.load().truckable[c]) || _weight(mask) > CONFIG.load().truck.capacity {
+            continue;
+        }
+
+        let shortest = TruckRoute::new(tsp::resequence(
+            &[vec![0], _customers(mask).collect(), vec![0]].concat(),
+            &CONFIG.load().truck_distances,
+        ));
+        *route = if shortest.waiting_time_violation() == 0.0 {
+            Some(shortest)
+        } else {
+            let mut best = None;
+            _enumerate(&mut vec![0], mask, 0.0, &mut best);
+            best
+        };
+    }
+
+    routes
+}
+
+/// Feasible drone route of least value serving each subset of the customers, if any, for each drone class.
+///
+/// Every visiting order is enumerated, extending each order only while the drone classes can carry its customers and
+/// fly its legs, so the enumeration is only tractable when the drone routes are short.
+fn _drone_routes(n: usize) -> Vec<Vec<Option<Rc<DroneRoute>>>> {
+    fn _enumerate(order: &mut Vec<usize>, mask: usize, weight: f64, best: &mut [Vec<Option<Rc<DroneRoute>>>]) {
+        let last = *order.last().unwrap();
+        if last != 0 && CONFIG.load().drone_pairs[last][0] {
+            let route = DroneRoute::new([order.as_slice(), &[0]].concat());
+            for (class, metrics) in route.class_metrics().enumerate() {
+                let value = _value(metrics.working_time, metrics.energy);
+                if metrics.energy_violation == 0.0
+                    && metrics.capacity_violation == 0.0
+                    && metrics.waiting_time_violation == 0.0
+                    && metrics.fixed_time_violation == 0.0
+                    && metrics.no_fly_violation == 0.0
+                    && best[class][mask].as_ref().is_none_or(|best| {
+                        let metrics = best.class_metrics().nth(class).unwrap();
+                        value < _value(metrics.working_time, metrics.energy)
+                    })
+                {
+                    best[class][mask] = Some(route.clone());
+                }
+            }
+        }
+
+        if last != 0 && DroneRoute::single_customer() {
+            return;
+        }
+
+        let capacity = CONFIG
+            .load()
+            .drone_classes
+            .iter()
+            .map(|c| c.capacity())
+            .fold(0.0, f64::max);
+        for customer in 1..CONFIG.load().customers_count + 1 {
+            let weight = weight + CONFIG.load().demands[customer];
+            if mask & (1 << (customer - 1)) == 0
+                && CONFIG.load().dronable[customer]
+                && CONFIG.load().drone_pairs[last][customer]
+                && weight <= capacity
+            {
+                order.push(customer);
+                _enumerate(order, mask | (1 << (customer - 1)), weight, best);
+                order.pop();
+            }
+        }
+    }
+
+    let mut routes = vec![vec![None; 1 << n]; CONFIG.load().drone_classes.len()];
+    _enumerate(&mut vec![0], 0, 0.0, &mut routes);
+    routes
+}
+
+/// Best routes of a single vehicle for each subset of the customers
+struct _VehicleTable {
+    /// Least value of the routes of the vehicle serving each subset, infinite if they cannot be served
+    value: Vec<f64>,
+    /// Customers of one of the routes achieving `value`, the other routes serving the rest of the subset
+    route: Vec<usize>,
+}
+
+impl _VehicleTable {
+    /// Split each subset of the customers into the routes of `vehicle` of least total value, given the feasible route
+    /// `routes` of each subset and the `gap` between two consecutive routes of the vehicle
+    fn new<T>(vehicle: VehicleId, routes: &[Option<Rc<T>>], route_value: impl Fn(&T) -> f64, gap: f64) -> Self
+    where
+        T: Route,
+    {
+        let full = routes.len();
+        let release = _value(CONFIG.load().release_time(vehicle), 0.0);
+        let single = routes
+            .iter()
+            .map(|route| match route {
+                Some(route)
+                    if CONFIG
+                        .load()
+                        .assignment_rules
+                        .violation(vehicle, iter::once(route.data().customers.as_slice()))
+                        == 0.0 =>
+                {
+                    route_value(route)
+                }
+                _ => f64::INFINITY,
+            })
+            .collect::<Vec<_>>();
+
+        let mut value = vec![f64::INFINITY; full];
+        let mut route = vec![0; full];
+        value[0] = 0.0;
+        for mask in 1..full {
+            if T::single_route() {
+                (value[mask], route[mask]) = (single[mask], mask);
+                continue;
+            }
+
+            // Every split is enumerated once by assigning the lowest customer to the first route
+            let lowest = mask & mask.wrapping_neg();
+            let mut first = mask;
+            while first != 0 {
+                if first & lowest != 0 {
+                    let rest = mask ^ first;
+                    let candidate = single[first] + value[rest] + if rest == 0 { 0.0 } else { gap };
+                    if candidate < value[mask] {
+                        (value[mask], route[mask]) = (candidate, first);
+                    }
+                }
+                first = (first - 1) & mask;
+            }
+        }
+
+        for v in &mut value {
+            *v += release;
+        }
+
+        Self { value, route }
+    }
+
+    /// Routes serving `mask`, among the feasible route `routes` of each subset the table was built from
+    fn routes<T>(&self, mut mask: usize, routes: &[Option<Rc<T>>]) -> Vec<Rc<T>> {
+        let mut result = vec![];
+        while mask != 0 {
+            let route = self.route[mask];
+            result.push(routes[route].clone().unwrap());
+            mask ^= route;
+        }
+
+        result
+    }
+}
+
+/// Solve the instance exactly: the feasible route of least value is found for each subset of the customers and each
+/// vehicle type, each subset is split into the routes of each vehicle, then the customers are distributed among the
+/// vehicles, all by dynamic programming over the subsets.
+///
+/// Only instances with at most `MAX_CUSTOMERS` customers, every constraint hard and an objective that sums or
+/// maximizes over the vehicles are supported. The solution is checked against its evaluation before being returned.
+pub fn solve() -> Solution {
+    let n = CONFIG.load().customers_count;
+    assert!(
+        n <= MAX_CUSTOMERS,
+        "The exact solver supports at most {MAX_CUSTOMERS} customers, this instance has {n}"
+    );
+    assert!(
+        CONFIG.load().objective != Objective::Weighted,
+        "The exact solver does not support the weighted objective"
+    );
+    assert!(
+        CONFIG.load().vehicle_cost == 0.0,
+        "The exact solver does not support --vehicle-cost"
+    );
+    assert!(
+        CONFIG.load().soft_weights.iter().all(Option::is_none),
+        "The exact solver does not support --soft-constraints"
+    );
+    assert!(
+        !CONFIG.load().mothership,
+        "The exact solver does not support --mothership"
+    );
+    assert!(CONFIG.load().pins.is_empty(), "The exact solver does not support --pin");
+    assert!(
+        CONFIG.load().depot_slots == 0 || CONFIG.load().drones_count <= CONFIG.load().depot_slots,
+        "The exact solver does not support --depot-slots"
+    );
+
+    let candidate_truck_routes = _truck_routes(n);
+    let candidate_drone_routes = _drone_routes(n);
+    info!(
+        "Enumerated {} truck route(s) and {} drone route(s)",
+        candidate_truck_routes.iter().flatten().count(),
+        candidate_drone_routes.iter().flatten().flatten().count()
+    );
+
+    let mut vehicles = vec![];
+    for truck in 0..CONFIG.load().trucks_count {
+        let vehicle = VehicleId::Truck(TruckId(truck));
+        let table = _VehicleTable::new(
+            vehicle,
+            &candidate_truck_routes,
+            |r| _value(r.working_time(), 0.0),
+            _value(CONFIG.load().truck.reload_time, 0.0),
+        );
+        vehicles.push((vehicle, table));
+    }
+    for drone in 0..CONFIG.load().drones_count {
+        let vehicle = VehicleId::Drone(DroneId(drone));
+        let class = CONFIG.load().drone_class_of(DroneId(drone));
+        let table = _VehicleTable::new(
+            vehicle,
+            &candidate_drone_routes[class],
+            |r| {
+                let metrics = r.metrics(DroneId(drone));
+                _value(metrics.working_time, metrics.energy)
+            },
+            _value(CONFIG.load().drone_classes[class].recharge_time(), 0.0),
+        );
+        vehicles.push((vehicle, table));
+    }
+
+    // Least value of the first k + 1 vehicles serving each subset, and the subset served by the last of them
+    let combine = |f: f64, s: f64| match CONFIG.load().objective {
+        Objective::Makespan => f.max(s),
+        _ => f + s,
+    };
+    let full = 1 << n;
+    let mut best = vehicles[0].1.value.clone();
+    let mut served = vec![(0..full).collect::<Vec<_>>()];
+    for (_, table) in &vehicles[1..] {
+        let mut next = vec![f64::INFINITY; full];
+        let mut last = vec![0; full];
+        for mask in 0..full {
+            let mut subset = mask;
+            loop {
+                let candidate = combine(table.value[subset], best[mask ^ subset]);
+                if candidate < next[mask] {
+                    (next[mask], last[mask]) = (candidate, subset);
+                }
+                if subset == 0 {
+                    break;
+                }
+                subset = (subset - 1) & mask;
+            }
+        }
+
+        best = next;
+        served.push(last);
+    }
+
+    let optimum = best[full - 1];
+    assert!(optimum.is_finite(), "The instance has no feasible solution");
+
+    let mut truck_routes = vec![vec![]; CONFIG.load().trucks_count];
+    let mut drone_routes = vec![vec![]; CONFIG.load().drones_count];
+    let mut mask = full - 1;
+    for (k, (vehicle, table)) in vehicles.iter().enumerate().rev() {
+        let subset = served[k][mask];
+        match *vehicle {
+            VehicleId::Truck(TruckId(truck)) => truck_routes[truck] = table.routes(subset, &candidate_truck_routes),
+            VehicleId::Drone(DroneId(drone)) => {
+                let class = CONFIG.load().drone_class_of(DroneId(drone));
+                drone_routes[drone] = table.routes(subset, &candidate_drone_routes[class]);
+            }
+        }
+        mask ^= subset;
+    }
+
+    let solution = Solution::new(truck_routes, drone_routes);
+    assert!(solution.feasible, "The exact solution is infeasible");
+    assert!(
+        (solution.objective() - optimum).abs() <= 1e-6 * optimum.max(1.0),
+        "The exact solution evaluates to {} instead of {optimum}",
+        solution.objective()
+    );
+    info!("Optimal {} = {optimum}", CONFIG.load().objective);
+    solution
+}
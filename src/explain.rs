@@ -0,0 +1,249 @@
+use std::io::{self, Write};
+
+use crate::cli::{SearchScope, TieBreak};
+use crate::config::{CONFIG, Leg};
+use crate::solutions::Solution;
+use crate::{decompose, external};
+
+/// Format the customers `ids` as a space-separated list
+fn _list(ids: &[usize]) -> String {
+    if ids.is_empty() {
+        "-".to_string()
+    } else {
+        ids.iter().map(usize::to_string).collect::<Vec<_>>().join(" ")
+    }
+}
+
+/// Minimum, mean and maximum of the distances between distinct nodes of `matrix`
+fn _summary(matrix: &[Vec<f64>]) -> (f64, f64, f64) {
+    let mut count = 0_usize;
+    let (mut min, mut sum, mut max) = (f64::MAX, 0.0, f64::MIN);
+    for (i, row) in matrix.iter().enumerate() {
+        for (j, &distance) in row.iter().enumerate() {
+            if i != j {
+                count += 1;
+                min = min.min(distance);
+                sum += distance;
+                max = max.max(distance);
+            }
+        }
+    }
+
+    if count == 0 {
+        (0.0, 0.0, 0.0)
+    } else {
+        (min, sum / count as f64, max)
+    }
+}
+
+/// Write the configuration resolved from the command line to `writer`: the customers each vehicle type can serve
+/// after the dronability filtering, a summary of the distance matrices, the fleet and the hyperparameters of the tabu
+/// search, derived from the initial solution.
+pub fn explain(writer: &mut impl Write) -> io::Result<()> {
+    let customers = 1..CONFIG.load().customers_count + 1;
+    let dronable = customers
+        .clone()
+        .filter(|&i| CONFIG.load().dronable[i])
+        .collect::<Vec<_>>();
+    let truck_only = customers
+        .clone()
+        .filter(|&i| !CONFIG.load().dronable[i])
+        .collect::<Vec<_>>();
+    let drone_only = customers
+        .clone()
+        .filter(|&i| !CONFIG.load().truckable[i])
+        .collect::<Vec<_>>();
+    let out_of_reach = customers
+        .clone()
+        .filter(|&i| {
+            let config = CONFIG.load();
+            let leg = |from, to| {
+                Leg::new(
+                    from,
+                    to,
+                    &config.x,
+                    &config.y,
+                    &config.elevations,
+                    &config.drone_distances,
+                )
+            };
+            !config
+                .drone_classes
+                .iter()
+                .any(|d| d.can_serve(config.demands[i], leg(0, i), leg(i, 0)))
+        })
+        .collect::<Vec<_>>();
+    let no_fly = if CONFIG.load().no_fly.is_empty() {
+        vec![]
+    } else {
+        customers.clone().filter(|&i| CONFIG.load().no_fly[0][i]).collect()
+    };
+    let split = customers
+        .filter(|&i| CONFIG.load().split_of[i] != i)
+        .map(|i| CONFIG.load().split_of[i])
+        .collect::<Vec<_>>();
+
+    writeln!(writer, "Problem: {}", CONFIG.load().problem)?;
+    writeln!(writer, "Customers: {}", CONFIG.load().customers_count)?;
+    writeln!(writer, "Split customers: {}", _list(&split))?;
+    writeln!(writer, "Dronable customers ({}): {}", dronable.len(), _list(&dronable))?;
+    writeln!(
+        writer,
+        "Truck-only customers ({}): {}",
+        truck_only.len(),
+        _list(&truck_only)
+    )?;
+    writeln!(
+        writer,
+        "Drone-only customers ({}): {}",
+        drone_only.len(),
+        _list(&drone_only)
+    )?;
+    writeln!(
+        writer,
+        "Beyond the capacity or range of every drone ({}): {}",
+        out_of_reach.len(),
+        _list(&out_of_reach)
+    )?;
+    writeln!(writer, "Behind a no-fly zone ({}): {}", no_fly.len(), _list(&no_fly))?;
+    let hopeless = CONFIG
+        .load()
+        .drone_pairs
+        .iter()
+        .flatten()
+        .filter(|&&pair| !pair)
+        .count();
+    writeln!(
+        writer,
+        "Hopeless drone legs: {hopeless} of {}",
+        CONFIG.load().drone_pairs.len() * CONFIG.load().drone_pairs.len()
+    )?;
+    writeln!(writer)?;
+
+    for (name, distance, matrix) in [
+        ("Truck", CONFIG.load().truck_distance, &CONFIG.load().truck_distances),
+        ("Drone", CONFIG.load().drone_distance, &CONFIG.load().drone_distances),
+    ] {
+        let (min, mean, max) = _summary(matrix);
+        writeln!(
+            writer,
+            "{name} distances: {distance} ({}x{}), min = {min:.2}, mean = {mean:.2}, max = {max:.2}",
+            matrix.len(),
+            matrix.len()
+        )?;
+    }
+    writeln!(writer, "Asymmetric: {}", CONFIG.load().asymmetric)?;
+    writeln!(writer, "Rounding: {}", CONFIG.load().distance_rounding)?;
+    writeln!(
+        writer,
+        "Problem units: {} and {}, converted to meters and seconds",
+        CONFIG.load().distance_unit,
+        CONFIG.load().time_unit
+    )?;
+    writeln!(writer)?;
+
+    writeln!(
+        writer,
+        "Trucks: {}, speed = {}, capacity = {}",
+        CONFIG.load().trucks_count,
+        CONFIG.load().truck.speed,
+        CONFIG.load().truck.capacity
+    )?;
+    writeln!(
+        writer,
+        "Drones: {} ({} energy model, {} speed, {} range)",
+        CONFIG.load().drones_count,
+        CONFIG.load().config,
+        CONFIG.load().speed_type,
+        CONFIG.load().range_type
+    )?;
+    for (class, drone) in CONFIG.load().drone_classes.iter().enumerate() {
+        let count = CONFIG.load().drone_class.iter().filter(|&&c| c == class).count();
+        writeln!(
+            writer,
+            "  Class {class}: {count} drone(s), capacity = {}, battery = {}",
+            drone.capacity(),
+            drone.battery()
+        )?;
+    }
+    writeln!(writer, "Waiting time limit: {}", CONFIG.load().waiting_time_limit)?;
+    writeln!(writer)?;
+
+    let mut construction = match CONFIG.load().grasp {
+        Some(alpha) => format!(
+            "GRASP (alpha = {alpha}, {} cycles of {} iterations)",
+            CONFIG.load().grasp_cycles,
+            CONFIG.load().grasp_iterations
+        ),
+        None => format!("{} construction", CONFIG.load().construction),
+    };
+    let root = match (&CONFIG.load().init_command, CONFIG.load().decompose) {
+        (Some(command), _) => {
+            construction = format!("{command:?}");
+            external::root(command)
+        }
+        (None, Some(parts)) => {
+            construction = format!(
+                "{construction} on {parts} parts with {} iterations each",
+                CONFIG.load().decompose_iterations
+            );
+            decompose::root(parts)
+        }
+        (None, None) => Solution::initialize(),
+    };
+    let hyperparameters = root.hyperparameters();
+    writeln!(
+        writer,
+        "Initial solution: {construction}, {} clustering, working time = {}, feasible = {}",
+        CONFIG.load().clustering,
+        root.working_time,
+        root.feasible
+    )?;
+    writeln!(writer, "Base = {:.2} customers per used vehicle", hyperparameters.base)?;
+    writeln!(
+        writer,
+        "Tabu size = {} ({} * Base)",
+        hyperparameters.tabu_size,
+        CONFIG.load().tabu_size_factor
+    )?;
+    writeln!(
+        writer,
+        "Adaptive iterations = {} ({} * Base)",
+        hyperparameters.adaptive_iterations,
+        CONFIG.load().adaptive_iterations
+    )?;
+    match CONFIG.load().fix_iteration {
+        Some(iterations) => writeln!(writer, "Reset after = never ({iterations} fixed iterations)")?,
+        None => writeln!(
+            writer,
+            "Reset after = {} ({} * Base)",
+            hyperparameters.reset_after,
+            CONFIG.load().reset_after_factor
+        )?,
+    }
+    writeln!(
+        writer,
+        "Strategy = {}, acceptance = {}, adaptive segments = {}",
+        CONFIG.load().strategy,
+        CONFIG.load().acceptance,
+        CONFIG.load().adaptive_segments
+    )?;
+    match CONFIG.load().search_scope {
+        SearchScope::TopK => writeln!(writer, "Search scope = top-{} vehicles", CONFIG.load().search_top_k)?,
+        scope => writeln!(writer, "Search scope = {scope}")?,
+    }
+    match CONFIG.load().tie_break {
+        TieBreak::Random => writeln!(writer, "Tie-break = random (seed {})", CONFIG.load().tie_break_seed)?,
+        rule => writeln!(writer, "Tie-break = {rule}")?,
+    }
+    if CONFIG.load().islands > 1 {
+        writeln!(
+            writer,
+            "Islands = {}, migration every {} iterations",
+            CONFIG.load().islands,
+            CONFIG.load().migration_interval
+        )?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,84 @@
+use std::rc::Rc;
+
+use serde_json::{Value, json};
+
+use crate::config::CONFIG;
+use crate::routes::Route;
+use crate::solutions::Solution;
+
+fn _point(node: usize) -> Value {
+    json!([CONFIG.load().x[node], CONFIG.load().y[node]])
+}
+
+fn _route_features<T>(vehicle_routes: &[Vec<Rc<T>>], kind: &str, features: &mut Vec<Value>)
+where
+    T: Route,
+{
+    for (vehicle, routes) in vehicle_routes.iter().enumerate() {
+        for (route_idx, route) in routes.iter().enumerate() {
+            let customers = &route.data().customers;
+            features.push(json!({
+                "type": "Feature",
+                "geometry": {
+                    "type": "LineString",
+                    "coordinates": customers.iter().map(|&c| _point(c)).collect::<Vec<Value>>(),
+                },
+                "properties": {
+                    "type": kind,
+                    "vehicle": vehicle,
+                    "route": route_idx,
+                    "sequence": customers,
+                    "working_time": route.vehicle_working_time(vehicle),
+                },
+            }));
+        }
+    }
+}
+
+/// Convert `solution` to a GeoJSON FeatureCollection: one LineString per truck route, drone route and drone sortie,
+/// and one Point per node. Coordinates are the node coordinates of the config, unprojected.
+pub fn geojson(solution: &Solution) -> Value {
+    let mut features = vec![];
+    _route_features(&solution.truck_routes, "truck", &mut features);
+    _route_features(&solution.drone_routes, "drone", &mut features);
+
+    for sortie in &solution.sorties {
+        let customers = &solution.truck_routes[sortie.truck][sortie.route].data().customers;
+        let (launch, rendezvous) = (customers[sortie.launch], customers[sortie.rendezvous]);
+        features.push(json!({
+            "type": "Feature",
+            "geometry": {
+                "type": "LineString",
+                "coordinates": [_point(launch), _point(sortie.customer), _point(rendezvous)],
+            },
+            "properties": {
+                "type": "sortie",
+                "vehicle": sortie.truck,
+                "route": sortie.route,
+                "sequence": [launch, sortie.customer, rendezvous],
+            },
+        }));
+    }
+
+    for node in 0..CONFIG.load().customers_count + 1 {
+        features.push(json!({
+            "type": "Feature",
+            "geometry": {
+                "type": "Point",
+                "coordinates": _point(node),
+            },
+            "properties": {
+                "type": if node == 0 { "depot" } else { "customer" },
+                "node": node,
+                "demand": CONFIG.load().demands[node],
+                "dronable": CONFIG.load().dronable[node],
+                "truckable": CONFIG.load().truckable[node],
+            },
+        }));
+    }
+
+    json!({
+        "type": "FeatureCollection",
+        "features": features,
+    })
+}
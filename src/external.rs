@@ -0,0 +1,101 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::rc::Rc;
+use std::thread;
+
+use tracing::{info, warn};
+
+use crate::config::{CONFIG, SerializedConfig};
+use crate::repair;
+use crate::routes::Route;
+use crate::solutions::Solution;
+
+/// Routes of each vehicle at `key` of the solution object `data`, checking that they are routes of this instance
+fn _routes<T>(data: &serde_json::Value, key: &str, vehicles: usize) -> Vec<Vec<Rc<T>>>
+where
+    T: Route,
+{
+    let routes = serde_json::from_value::<Vec<Vec<Vec<usize>>>>(data[key].clone())
+        .unwrap_or_else(|e| panic!("Invalid {key:?} in the output of --init-command: {e}"));
+    assert!(
+        routes.len() == vehicles,
+        "The output of --init-command has {} {key}, expected one per vehicle ({vehicles})",
+        routes.len()
+    );
+
+    routes
+        .into_iter()
+        .map(|routes| {
+            routes
+                .into_iter()
+                .filter(|route| route.len() > 2)
+                .map(|route| {
+                    assert!(
+                        route[0] == 0 && route[route.len() - 1] == 0,
+                        "Route {route:?} of --init-command does not start and end at the depot"
+                    );
+                    assert!(
+                        route.iter().all(|&c| c <= CONFIG.load().customers_count),
+                        "Route {route:?} of --init-command visits a customer that does not exist"
+                    );
+                    T::new(route)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Initial solution of `--init-command`: the shell command `command` is run with the config JSON of the instance on
+/// its stdin, and the routes of the solution it prints on its stdout are repaired if they do not serve every customer
+/// exactly once.
+pub fn root(command: &str) -> Solution {
+    let config = serde_json::to_string(&SerializedConfig::from(CONFIG.load().clone())).unwrap();
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap_or_else(|e| panic!("Failed to run {command:?}: {e}"));
+
+    // The command may start printing before reading its whole input, or not read it at all
+    let mut stdin = child.stdin.take().unwrap();
+    let writer = thread::spawn(move || {
+        let _ = stdin.write_all(config.as_bytes());
+    });
+    let output = child.wait_with_output().unwrap();
+    writer.join().unwrap();
+    assert!(output.status.success(), "{command:?} failed with {}", output.status);
+
+    let data = serde_json::from_slice::<serde_json::Value>(&output.stdout)
+        .unwrap_or_else(|e| panic!("The output of {command:?} is not a JSON object: {e}"));
+    let solution = Solution::new(
+        _routes(&data, "truck_routes", CONFIG.load().trucks_count),
+        _routes(&data, "drone_routes", CONFIG.load().drones_count),
+    );
+
+    let mut visits = vec![0; CONFIG.load().customers_count + 1];
+    for route in solution
+        .truck_routes
+        .iter()
+        .flatten()
+        .map(|r| &r.data().customers)
+        .chain(solution.drone_routes.iter().flatten().map(|r| &r.data().customers))
+    {
+        for &c in &route[1..route.len() - 1] {
+            visits[c] += 1;
+        }
+    }
+
+    let solution = if visits[1..].iter().all(|&v| v == 1) {
+        solution
+    } else {
+        warn!("The solution of {command:?} does not serve every customer exactly once, repairing it");
+        repair::repair(&solution)
+    };
+    info!(
+        "Initial solution of {command:?}: working time = {}, feasible = {}",
+        solution.working_time, solution.feasible
+    );
+    solution
+}
@@ -0,0 +1,75 @@
+use std::error::Error;
+use std::ffi::{CStr, CString, c_char};
+
+use serde_json::{Value, json};
+
+use crate::service;
+
+/// Parse the JSON C string `pointer`, which may be null if `optional`
+unsafe fn _read(pointer: *const c_char, optional: bool) -> Result<Option<Value>, Box<dyn Error>> {
+    if pointer.is_null() {
+        return if optional {
+            Ok(None)
+        } else {
+            Err("Unexpected null pointer".into())
+        };
+    }
+
+    // SAFETY: the caller passes a valid NUL-terminated string
+    let data = unsafe { CStr::from_ptr(pointer) }.to_str()?;
+    Ok(Some(serde_json::from_str::<Value>(data)?))
+}
+
+/// Convert the result of a call to a C string to be released with `mtmv_free`, reporting errors as a JSON object
+/// `{"error": ...}`.
+fn _output(result: Result<String, String>) -> *mut c_char {
+    let output = result.unwrap_or_else(|e| json!({ "error": e }).to_string());
+
+    // JSON strings escape NUL characters
+    CString::new(output).unwrap().into_raw()
+}
+
+/// Solve the problem described by `problem_json` (keys overriding those of `config_json`, may be null) with the
+/// configuration `config_json`, returning the run JSON.
+///
+/// # Safety
+///
+/// `problem_json` must be null or a NUL-terminated string, `config_json` a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mtmv_solve(problem_json: *const c_char, config_json: *const c_char) -> *mut c_char {
+    // SAFETY: forwarded from the caller
+    let inputs = unsafe { _read(problem_json, true).and_then(|p| Ok((p, _read(config_json, false)?))) };
+    _output(match inputs {
+        Ok((problem, config)) => service::solve(problem, config.unwrap_or_default()),
+        Err(e) => Err(e.to_string()),
+    })
+}
+
+/// Evaluate the solution `solution_json` (as returned in the run JSON) against the configuration `config_json`,
+/// returning the re-evaluated solution JSON.
+///
+/// # Safety
+///
+/// `solution_json` and `config_json` must be NUL-terminated strings.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mtmv_evaluate(solution_json: *const c_char, config_json: *const c_char) -> *mut c_char {
+    // SAFETY: forwarded from the caller
+    let inputs = unsafe { _read(solution_json, false).and_then(|s| Ok((s, _read(config_json, false)?))) };
+    _output(match inputs {
+        Ok((solution, config)) => service::evaluate(solution.unwrap_or_default(), config.unwrap_or_default()),
+        Err(e) => Err(e.to_string()),
+    })
+}
+
+/// Release a string returned by `mtmv_solve` or `mtmv_evaluate`.
+///
+/// # Safety
+///
+/// `result` must be null or a string returned by this library that has not been released yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mtmv_free(result: *mut c_char) {
+    if !result.is_null() {
+        // SAFETY: `result` was created by `CString::into_raw`
+        drop(unsafe { CString::from_raw(result) });
+    }
+}
@@ -0,0 +1,67 @@
+use serde::Deserialize;
+
+/// Index of a node of the instance: the depot is 0, the customers are numbered from 1. Functions taking a single
+/// customer use it (e.g. `Route::single`), while the customer sequences of routes stay `usize` slices since they index
+/// the distance matrices and the per-node arrays of the config at every step of an evaluation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CustomerId(pub usize);
+
+/// Index of a truck of the fleet
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TruckId(pub usize);
+
+/// Index of a drone of the fleet, counted separately from the trucks
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DroneId(pub usize);
+
+/// A truck or a drone, where both fleets are handled together. Arrays over the whole fleet are indexed with the trucks
+/// first, then the drones (see `index`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum VehicleId {
+    Truck(TruckId),
+    Drone(DroneId),
+}
+
+impl VehicleId {
+    /// Vehicle at `index` of an array over a fleet of `trucks_count` trucks followed by the drones
+    pub fn from_index(index: usize, trucks_count: usize) -> Self {
+        if index < trucks_count {
+            Self::Truck(TruckId(index))
+        } else {
+            Self::Drone(DroneId(index - trucks_count))
+        }
+    }
+
+    /// Index of this vehicle in an array over a fleet of `trucks_count` trucks followed by the drones
+    pub fn index(self, trucks_count: usize) -> usize {
+        match self {
+            Self::Truck(TruckId(truck)) => truck,
+            Self::Drone(DroneId(drone)) => trucks_count + drone,
+        }
+    }
+}
+
+/// A vehicle named in a JSON side file as `"truck": <index>` or `"drone": <index>`, usually flattened into the object
+/// it applies to
+#[derive(Deserialize)]
+pub struct SerializedVehicleId {
+    truck: Option<usize>,
+    drone: Option<usize>,
+}
+
+impl SerializedVehicleId {
+    /// The vehicle, which must exist in a fleet of `trucks_count` trucks and `drones_count` drones
+    pub fn id(&self, trucks_count: usize, drones_count: usize) -> VehicleId {
+        match (self.truck, self.drone) {
+            (Some(truck), None) => {
+                assert!(truck < trucks_count, "Truck {truck} does not exist");
+                VehicleId::Truck(TruckId(truck))
+            }
+            (None, Some(drone)) => {
+                assert!(drone < drones_count, "Drone {drone} does not exist");
+                VehicleId::Drone(DroneId(drone))
+            }
+            _ => panic!("Expected exactly one of \"truck\" or \"drone\""),
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+use tracing::{debug, info, info_span};
+
+use crate::cli::LogBackend;
+use crate::config::{CONFIG, Config};
+use crate::logger::Logger;
+use crate::routes::Route;
+use crate::solutions::Solution;
+
+/// Customers of the routes of each truck and each drone of a solution sent to another island, since solutions cannot
+/// be shared between threads
+struct Migrant {
+    truck_routes: Vec<Vec<Vec<usize>>>,
+    drone_routes: Vec<Vec<Vec<usize>>>,
+}
+
+impl Migrant {
+    fn new(solution: &Solution) -> Self {
+        fn _customers<T>(vehicle_routes: &[Vec<Rc<T>>]) -> Vec<Vec<Vec<usize>>>
+        where
+            T: Route,
+        {
+            vehicle_routes
+                .iter()
+                .map(|routes| routes.iter().map(|route| route.data().customers.clone()).collect())
+                .collect()
+        }
+
+        Self {
+            truck_routes: _customers(&solution.truck_routes),
+            drone_routes: _customers(&solution.drone_routes),
+        }
+    }
+
+    fn solution(self) -> Solution {
+        fn _routes<T>(vehicle_routes: Vec<Vec<Vec<usize>>>) -> Vec<Vec<Rc<T>>>
+        where
+            T: Route,
+        {
+            vehicle_routes
+                .into_iter()
+                .map(|routes| routes.into_iter().map(T::new).collect())
+                .collect()
+        }
+
+        Solution::new(_routes(self.truck_routes), _routes(self.drone_routes))
+    }
+}
+
+/// Where an island reports its final solution
+enum _Results {
+    /// The first island collects the final solutions of all others
+    Collect(Receiver<Migrant>),
+    Report(Sender<Migrant>),
+}
+
+/// One of the `--islands` searches, arranged in a ring: each island sends its best solution to the next one and
+/// receives that of the previous one
+pub struct Island {
+    index: usize,
+    sender: Sender<Migrant>,
+    receiver: Receiver<Migrant>,
+    results: _Results,
+}
+
+impl Island {
+    /// Send `best` to the next island, returning the solutions received from the previous one since the last
+    /// migration. Never blocks, so that islands may run at different paces.
+    pub fn migrate(&self, best: &Solution) -> Vec<Solution> {
+        // The next island may have finished already
+        self.sender.send(Migrant::new(best)).ok();
+
+        let migrants = self.receiver.try_iter().map(Migrant::solution).collect::<Vec<_>>();
+        for migrant in &migrants {
+            debug!(island = self.index, cost = migrant.cost(), "Received a migrant");
+        }
+
+        migrants
+    }
+
+    /// End the search of this island with `best`. The first island waits for the final solutions of all others and
+    /// returns them, the others report `best` to it.
+    pub fn finish(self, best: &Solution) -> Vec<Solution> {
+        match self.results {
+            _Results::Collect(receiver) => receiver.iter().map(Migrant::solution).collect(),
+            _Results::Report(sender) => {
+                sender.send(Migrant::new(best)).ok();
+                vec![]
+            }
+        }
+    }
+}
+
+/// Config of the islands other than the first one, which log nothing and skip the post-optimization
+fn _config() -> Config {
+    Config {
+        disable_logging: true,
+        log_backend: LogBackend::Csv,
+        quiet: true,
+        tui: false,
+        status_port: None,
+        notify_url: None,
+        overwrite: true,
+        route_pool: 0,
+        tsp_polish: false,
+        mothership: false,
+        pareto_archive: 0,
+        top_solutions: 0,
+        ..CONFIG.load().clone()
+    }
+}
+
+/// Run `--islands` tabu searches from `root` in parallel, the first one on the current thread with `logger`, the
+/// others silently on threads of their own. Every `--migration-interval` iterations, each island sends its best
+/// solution to the next one. The first island ends with the best solution of all islands.
+pub fn search(root: Solution, logger: &mut Logger) -> Solution {
+    let count = CONFIG.load().islands;
+    let (senders, receivers) = (0..count).map(|_| mpsc::channel()).unzip::<_, _, Vec<_>, Vec<_>>();
+    let (report, collect) = mpsc::channel();
+
+    let mut islands = receivers
+        .into_iter()
+        .enumerate()
+        .map(|(index, receiver)| Island {
+            index,
+            sender: senders[(index + 1) % count].clone(),
+            receiver,
+            results: _Results::Report(report.clone()),
+        })
+        .collect::<Vec<_>>();
+    islands[0].results = _Results::Collect(collect);
+    // The first island stops collecting once every other island has reported
+    drop(report);
+
+    let config = Arc::new(_config());
+    thread::scope(|scope| {
+        for island in islands.drain(1..) {
+            let root = Migrant::new(&root);
+            let config = config.clone();
+            scope.spawn(move || {
+                let _span = info_span!("island", index = island.index).entered();
+                CONFIG.scoped(config, || {
+                    let mut logger = Logger::detached().unwrap();
+                    let result = Solution::island_search(root.solution(), &mut logger, island);
+                    info!("Island finished with {:.2}", result.cost());
+                });
+            });
+        }
+
+        let island = islands.pop().unwrap();
+        let _span = info_span!("island", index = island.index).entered();
+        Solution::island_search(root, logger, island)
+    })
+}
@@ -0,0 +1,126 @@
+use serde::Serialize;
+
+use crate::config::CONFIG;
+use crate::ids::{DroneId, VehicleId};
+use crate::routes::Route;
+use crate::schedule::Trip;
+use crate::solutions::Solution;
+
+/// Fraction of a limit from which a satisfied constraint is counted as a near-violation
+const NEAR_VIOLATION_THRESHOLD: f64 = 0.9;
+
+/// Operational indicators of one vehicle of a solution
+#[derive(Serialize)]
+pub struct VehicleKpi {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub vehicle: usize,
+    pub trips: usize,
+    pub distance: f64,
+    pub working_time: f64,
+    /// Working time relative to the makespan, i.e. to the working time of the decisive vehicle
+    pub utilization: f64,
+    /// Total energy consumed (always 0 for trucks, drone sorties launched from a truck are not included)
+    pub energy: f64,
+    /// Largest total demand carried on a single trip
+    pub max_load: f64,
+    /// Number of satisfied constraints (capacity, energy and fixed time per trip, waiting time per customer) whose
+    /// value reaches `NEAR_VIOLATION_THRESHOLD` of their limit
+    pub near_violations: usize,
+}
+
+fn _near(value: f64, limit: f64) -> bool {
+    value <= limit && value >= NEAR_VIOLATION_THRESHOLD * limit
+}
+
+/// Number of customers of `trip` whose samples wait for at least `NEAR_VIOLATION_THRESHOLD` of their limit
+fn _near_waiting_time(trip: &Trip) -> usize {
+    trip.visits
+        .iter()
+        .filter(|v| _near(trip.end - v.departure, CONFIG.load().waiting_time_limits[v.customer]))
+        .count()
+}
+
+/// Compute the indicators of every vehicle of `solution`, trucks first, then drones
+pub fn report(solution: &Solution) -> Vec<VehicleKpi> {
+    let schedule = solution.schedule();
+    let utilization = |working_time: f64| {
+        if solution.working_time > 0.0 {
+            working_time / solution.working_time
+        } else {
+            0.0
+        }
+    };
+
+    let mut result = vec![];
+    for (truck, routes) in solution.truck_routes.iter().enumerate() {
+        let mut near_violations = 0;
+        for (route, trip) in routes.iter().zip(&schedule[truck].trips) {
+            near_violations += usize::from(_near(route.data().weight(), CONFIG.load().truck.capacity));
+            near_violations += _near_waiting_time(trip);
+        }
+
+        result.push(VehicleKpi {
+            kind: "truck",
+            vehicle: truck,
+            trips: routes.len(),
+            distance: routes.iter().map(|r| r.data().distance()).sum(),
+            working_time: solution.truck_working_time[truck],
+            utilization: utilization(solution.truck_working_time[truck]),
+            energy: 0.0,
+            max_load: routes.iter().map(|r| r.data().weight()).fold(0.0, f64::max),
+            near_violations,
+        });
+    }
+
+    for (drone, routes) in solution.drone_routes.iter().enumerate() {
+        let class = &CONFIG.load().drone_classes[CONFIG.load().drone_class_of(DroneId(drone))];
+        let mut near_violations = 0;
+        for (route, trip) in routes
+            .iter()
+            .zip(&schedule[VehicleId::Drone(DroneId(drone)).index(CONFIG.load().trucks_count)].trips)
+        {
+            let metrics = route.metrics(DroneId(drone));
+            near_violations += usize::from(_near(route.data().weight(), class.capacity()));
+            near_violations += usize::from(_near(metrics.energy, class.battery()));
+            near_violations += usize::from(_near(metrics.working_time, class.fixed_time()));
+            near_violations += _near_waiting_time(trip);
+        }
+
+        result.push(VehicleKpi {
+            kind: "drone",
+            vehicle: drone,
+            trips: routes.len(),
+            distance: routes.iter().map(|r| r.data().distance()).sum(),
+            working_time: solution.drone_working_time[drone],
+            utilization: utilization(solution.drone_working_time[drone]),
+            energy: routes.iter().map(|r| r.metrics(DroneId(drone)).energy).sum(),
+            max_load: routes.iter().map(|r| r.data().weight()).fold(0.0, f64::max),
+            near_violations,
+        });
+    }
+
+    result
+}
+
+/// Print `report` as a table to stderr
+pub fn print(report: &[VehicleKpi]) {
+    eprintln!(
+        "{:<8}{:>8}{:>7}{:>12}{:>14}{:>13}{:>14}{:>10}{:>17}",
+        "Type", "Vehicle", "Trips", "Distance", "Working time", "Utilization", "Energy", "Max load", "Near violations"
+    );
+    for kpi in report {
+        eprintln!(
+            "{:<8}{:>8}{:>7}{:>12.2}{:>14.2}{:>12.1}%{:>14.2}{:>10.2}{:>17}",
+            kpi.kind,
+            kpi.vehicle,
+            kpi.trips,
+            kpi.distance,
+            kpi.working_time,
+            100.0 * kpi.utilization,
+            kpi.energy,
+            kpi.max_load,
+            kpi.near_violations,
+        );
+    }
+}
@@ -0,0 +1,50 @@
+pub mod assignments;
+pub mod bench;
+pub mod bks;
+pub mod cli;
+mod clusterize;
+pub mod config;
+mod construction;
+#[cfg(feature = "tui")]
+mod dashboard;
+mod database;
+pub mod decompose;
+pub mod diff;
+mod errors;
+pub mod exact;
+pub mod explain;
+pub mod export;
+pub mod external;
+mod ffi;
+pub mod ids;
+pub mod islands;
+pub mod kpi;
+pub mod logger;
+mod manifest;
+pub mod migrate;
+pub mod milp;
+mod mothership;
+mod neighborhoods;
+pub mod objective;
+pub mod params;
+mod pareto;
+#[cfg(feature = "parquet")]
+mod parquet_log;
+pub mod pins;
+pub mod plot;
+mod pool;
+pub mod profile;
+pub mod removal;
+pub mod reoptimize;
+pub mod repair;
+pub mod routes;
+pub mod schedule;
+mod search;
+pub mod sensitivity;
+pub mod service;
+pub mod simulation;
+pub mod solutions;
+mod status;
+mod top;
+mod tsp;
+mod validation;
@@ -1,23 +1,83 @@
 use std::error::Error;
-use std::fs::{self, File};
-use std::io;
-use std::io::Write;
-use std::path::Path;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 
+use clap::ValueEnum;
+use flate2::Compression;
+use flate2::write::GzEncoder;
 use rand::Rng;
 use rand::distr::Alphanumeric;
+use tracing::{info, warn};
+use ureq::Agent;
 
+use crate::cli::{LogBackend, LogColumn, LogCompression, LogFormat, ProgressFormat, Strategy};
 use crate::config::{CONFIG, SerializedConfig};
+#[cfg(feature = "tui")]
+use crate::dashboard::{Dashboard, DashboardState};
+use crate::database::Database;
 use crate::errors::ExpectedValue;
+use crate::kpi::{self, VehicleKpi};
+use crate::manifest::{self, Manifest};
 use crate::neighborhoods::Neighborhood;
+#[cfg(feature = "parquet")]
+use crate::parquet_log::ParquetLog;
+use crate::profile::{self, PhaseTiming};
 use crate::routes::Route;
-use crate::solutions::{Solution, penalty_coeff};
+use crate::solutions::{NEIGHBORHOODS, Solution, penalty_coeffs};
+use crate::status::StatusServer;
+use crate::{bks, migrate};
+
+/// Number of iterations between two consecutive checks of the logging time budget
+const LOG_BUDGET_CHECK_INTERVAL: usize = 500;
+
+/// In sampled mode, only one in every `LOG_SAMPLE_INTERVAL` logged iterations (see `--log-interval`) is written
+const LOG_SAMPLE_INTERVAL: usize = 10;
+
+/// Timeout in seconds of the completion notification (see `--notify-url`)
+const NOTIFY_TIMEOUT: u64 = 30;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, serde::Serialize)]
+enum LogGranularity {
+    #[serde(rename = "full")]
+    Full,
+    #[serde(rename = "sampled")]
+    Sampled,
+    #[serde(rename = "improvements-only")]
+    ImprovementsOnly,
+}
+
+impl fmt::Display for LogGranularity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Full => "full",
+                Self::Sampled => "sampled",
+                Self::ImprovementsOnly => "improvements-only",
+            }
+        )
+    }
+}
+
+#[derive(serde::Serialize)]
+struct LogDowngrade {
+    iteration: usize,
+    granularity: LogGranularity,
+    logging_fraction: f64,
+}
 
 #[derive(serde::Serialize)]
 struct RunJSON<'a> {
+    /// See `migrate::SCHEMA_VERSION`
+    schema_version: u64,
+    id: &'a str,
     problem: String,
+    manifest: Manifest,
     tabu_size: usize,
     reset_after: usize,
     iterations: usize,
@@ -29,76 +89,392 @@ struct RunJSON<'a> {
     elapsed: f64,
     post_optimization: f64,
     post_optimization_elapsed: f64,
+    log_downgrades: &'a [LogDowngrade],
+    bks: Option<f64>,
+    bks_gap: Option<f64>,
+    time_to_target: Option<f64>,
+    iterations_to_target: Option<usize>,
+    kpi: Vec<VehicleKpi>,
+    neighborhood_statistics: &'a [NeighborhoodStatistics],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    adaptive_history: Option<AdaptiveHistory<'a>>,
+    /// Time spent in each phase of the search (see `--profile`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    profile: Option<Vec<PhaseTiming>>,
+}
+
+/// One iteration of the JSON Lines or Parquet iteration log (see `--log-format`), with the fields of the unselected
+/// column groups omitted (see `--log-columns`)
+#[derive(serde::Serialize)]
+pub struct IterationRecord<'a> {
+    pub iteration: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cost: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub working_time: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feasible: Option<bool>,
+    /// Penalty coefficients of the energy, capacity, waiting time, fixed time, depot slot, assignment and no-fly
+    /// violations
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub penalty_coefficients: Option<[f64; 7]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub energy_violation: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub capacity_violation: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub waiting_time_violation: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fixed_time_violation: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub depot_slot_violation: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub assignment_violation: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub no_fly_violation: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub truck_routes: Option<&'a [Vec<&'a Vec<usize>>]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub drone_routes: Option<&'a [Vec<&'a Vec<usize>>]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub truck_routes_count: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub drone_routes_count: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub neighborhood: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tabu_list: Option<&'a Vec<Vec<usize>>>,
+}
+
+/// State of the adaptive neighborhood selection at the end of a segment (see `--strategy adaptive`). Vectors are
+/// indexed like `AdaptiveHistory::neighborhoods`.
+#[derive(serde::Serialize)]
+pub struct AdaptiveSegment {
+    pub segment: usize,
+    pub iteration: usize,
+    /// Whether the search restarts from an elite solution at the end of this segment (the weights are reset first)
+    pub reset: bool,
+    /// Total score of each neighborhood over the segment
+    pub scores: Vec<f64>,
+    /// Number of times each neighborhood was selected during the segment
+    pub occurrences: Vec<u32>,
+    /// Weights of the neighborhoods for the next segment
+    pub weights: Vec<f64>,
+}
+
+/// Outcomes of the searches of one neighborhood over a run
+#[derive(serde::Serialize)]
+pub struct NeighborhoodStatistics {
+    pub neighborhood: String,
+    /// Number of iterations the neighborhood was searched
+    pub selected: usize,
+    /// Number of iterations the best move found was accepted as the current solution
+    pub accepted: usize,
+    /// Number of iterations the best move found became the new best solution
+    pub improved: usize,
+    /// Number of candidate moves that would have been the best of their search but were tabu
+    pub tabu_blocked: usize,
+}
+
+impl NeighborhoodStatistics {
+    pub fn new(neighborhood: Neighborhood) -> Self {
+        Self {
+            neighborhood: neighborhood.to_string(),
+            selected: 0,
+            accepted: 0,
+            improved: 0,
+            tabu_blocked: 0,
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+struct AdaptiveHistory<'a> {
+    neighborhoods: Vec<String>,
+    segments: &'a [AdaptiveSegment],
+}
+
+/// Search progress printed with `--progress-format json`
+#[derive(serde::Serialize)]
+struct Progress {
+    iteration: usize,
+    incumbent: f64,
+    current: f64,
+    elite_size: usize,
+    elapsed: f64,
+}
+
+/// POST the run JSON `summary` to `--notify-url`. A failed notification is only reported since the results are
+/// already saved.
+fn _notify(summary: &str) {
+    if let Some(ref url) = CONFIG.load().notify_url {
+        let agent = Agent::from(
+            Agent::config_builder()
+                .timeout_global(Some(Duration::from_secs(NOTIFY_TIMEOUT)))
+                .build(),
+        );
+        match agent.post(url).header("Content-Type", "application/json").send(summary) {
+            Ok(_) => info!("Notified {url} of the completed run"),
+            Err(e) => warn!("Failed to notify {url} of the completed run: {e}"),
+        }
+    }
+}
+
+/// Print the path of an output file to stdout, unless the solution is printed instead (see `--stdout-solution`)
+fn _print_path(path: &Path) {
+    if !CONFIG.load().stdout_solution {
+        println!("{}", path.display());
+    }
+}
+
+/// Create the file at `path`, or open it for appending with `--append`
+fn _create(path: &Path) -> io::Result<File> {
+    if CONFIG.load().append {
+        OpenOptions::new().create(true).append(true).open(path)
+    } else {
+        File::create(path)
+    }
+}
+
+/// Headers of the CSV columns of `column`
+fn _csv_headers(column: LogColumn) -> &'static [&'static str] {
+    match column {
+        LogColumn::Cost => &["Cost"],
+        LogColumn::WorkingTime => &["Working time"],
+        LogColumn::Feasible => &["Feasible"],
+        LogColumn::Violations => &[
+            "p0",
+            "Energy violation",
+            "p1",
+            "Capacity violation",
+            "p2",
+            "Waiting time violation",
+            "p3",
+            "Fixed time violation",
+            "p4",
+            "Depot slot violation",
+            "p5",
+            "Assignment violation",
+            "p6",
+            "No-fly violation",
+        ],
+        LogColumn::Routes => &["Truck routes", "Drone routes"],
+        LogColumn::RoutesCount => &["Truck routes count", "Drone routes count"],
+        LogColumn::Neighborhood => &["Neighborhood"],
+        LogColumn::TabuList => &["Tabu list"],
+    }
 }
 
-pub struct Logger<'a> {
+/// Selected column groups of the iteration log, in the order of the file
+fn _log_columns() -> impl Iterator<Item = LogColumn> {
+    LogColumn::value_variants()
+        .iter()
+        .copied()
+        .filter(|c| CONFIG.load().log_columns.contains(c))
+}
+
+pub struct Logger {
     _iteration: usize,
     _time_offset: SystemTime,
 
-    _outputs: &'a Path,
+    _granularity: LogGranularity,
+    _budget_offset: SystemTime,
+    _budget_logging_elapsed: f64,
+    _downgrades: Vec<LogDowngrade>,
+
+    _outputs: PathBuf,
     _problem: String,
     _id: String,
-    _writer: Option<File>,
+    _writer: Option<Box<dyn Write>>,
+    _database: Option<Database>,
+    _segments: Vec<AdaptiveSegment>,
+    _segments_writer: Option<File>,
+    #[cfg(feature = "parquet")]
+    _parquet: Option<ParquetLog>,
+    _bks: Option<f64>,
+    _target: Option<(f64, usize)>,
+    _detached: bool,
+    _summary: Option<String>,
+    _progress_offset: SystemTime,
+    _status: Option<StatusServer>,
+    #[cfg(feature = "tui")]
+    _dashboard: Option<Dashboard>,
 }
 
-impl Logger<'_> {
+impl Logger {
     pub fn new() -> Result<Self, Box<dyn Error>> {
-        let outputs = Path::new(&CONFIG.outputs);
-        if !outputs.is_dir() {
-            fs::create_dir_all(outputs)?;
-        }
-
-        let problem = ExpectedValue::cast(
-            Path::new(&CONFIG.problem)
-                .file_stem()
-                .and_then(|f| f.to_os_string().into_string().ok()),
-        )?;
-        let id = rand::rng()
-            .sample_iter(&Alphanumeric)
-            .take(8)
-            .map(char::from)
-            .collect::<String>();
-
-        let mut writer = if CONFIG.disable_logging {
+        Self::_new(false)
+    }
+
+    /// Logger of a run embedded in another program (see `ffi`), which writes nothing to the output directory and
+    /// keeps the run JSON in memory instead (see `summary`). Iteration logging, the status server and the dashboard
+    /// are expected to be disabled in the config.
+    pub fn detached() -> Result<Self, Box<dyn Error>> {
+        Self::_new(true)
+    }
+
+    fn _new(detached: bool) -> Result<Self, Box<dyn Error>> {
+        let outputs = PathBuf::from(&CONFIG.load().outputs);
+        if !detached && !outputs.is_dir() {
+            fs::create_dir_all(&outputs)?;
+        }
+
+        // A problem read from stdin has no file name
+        let problem = if CONFIG.load().problem == "-" {
+            "stdin".to_string()
+        } else {
+            ExpectedValue::cast(
+                Path::new(&CONFIG.load().problem)
+                    .file_stem()
+                    .and_then(|f| f.to_os_string().into_string().ok()),
+            )?
+        };
+        let id = match CONFIG.load().run_id {
+            Some(ref id) => {
+                if id.is_empty() || id.contains(['/', '\\']) {
+                    return Err(format!("Invalid run id {id:?}").into());
+                }
+                id.clone()
+            }
+            None => rand::rng()
+                .sample_iter(&Alphanumeric)
+                .take(8)
+                .map(char::from)
+                .collect::<String>(),
+        };
+
+        let bks = match CONFIG.load().bks_file {
+            Some(ref bks_file) => bks::load(bks_file)?.get(&problem).copied(),
+            None => None,
+        };
+
+        let database = match CONFIG.load().log_backend {
+            LogBackend::Csv => None,
+            LogBackend::Sqlite => {
+                let config = serde_json::to_string(&SerializedConfig::from(CONFIG.load().clone()))?;
+                assert!(!CONFIG.load().append, "The SQLite backend does not support --append");
+                let database = Database::open(&outputs, &id, &problem, &config, CONFIG.load().overwrite)?;
+                info!("Logging run {id} to {}", database.path().display());
+                Some(database)
+            }
+        };
+
+        let log_path = if CONFIG.load().disable_logging || database.is_some() {
             None
         } else {
-            Some(File::create(outputs.join(format!("{problem}-{id}.csv")))?)
+            // Parquet files are compressed internally
+            let extension = match (CONFIG.load().log_format, CONFIG.load().log_compress) {
+                (LogFormat::Parquet, _) | (_, None) => "",
+                (_, Some(LogCompression::Gzip)) => ".gz",
+                (_, Some(LogCompression::Zstd)) => ".zst",
+            };
+            let path = outputs.join(format!("{problem}-{id}.{}{extension}", CONFIG.load().log_format));
+            info!("Logging iterations to {}", path.display());
+            Some(path)
+        };
+
+        if database.is_none()
+            && !CONFIG.load().overwrite
+            && !CONFIG.load().append
+            && outputs.join(format!("{problem}-{id}.json")).exists()
+        {
+            return Err(format!("Outputs of run {id} already exist, pass --overwrite or --append").into());
+        }
+        assert!(
+            !CONFIG.load().append || log_path.is_none() || CONFIG.load().log_format != LogFormat::Parquet,
+            "Parquet iteration logs do not support --append"
+        );
+
+        #[cfg(not(feature = "parquet"))]
+        assert!(
+            log_path.is_none() || CONFIG.load().log_format != LogFormat::Parquet,
+            "Parquet logging requires building with `--features parquet`"
+        );
+        #[cfg(feature = "parquet")]
+        let parquet = log_path
+            .clone()
+            .filter(|_| CONFIG.load().log_format == LogFormat::Parquet)
+            .map(ParquetLog::new);
+
+        let status = match CONFIG.load().status_port {
+            Some(port) => {
+                let bind = &CONFIG.load().status_bind;
+                let status = StatusServer::start(bind, port)?;
+                info!("Serving the search status on {bind}:{port}");
+                Some(status)
+            }
+            None => None,
+        };
+
+        #[cfg(not(feature = "tui"))]
+        assert!(
+            !CONFIG.load().tui,
+            "The dashboard requires building with `--features tui`"
+        );
+        #[cfg(feature = "tui")]
+        let dashboard = CONFIG.load().tui.then(Dashboard::new).transpose()?;
+
+        let segments_writer = match log_path {
+            Some(_) if CONFIG.load().strategy == Strategy::Adaptive => {
+                Some(_create(&outputs.join(format!("{problem}-{id}-segments.jsonl")))?)
+            }
+            _ => None,
         };
 
-        if let Some(ref mut writer) = writer {
-            eprintln!("Logging iterations to {writer:?}");
-
-            let columns = vec![
-                "Iteration",
-                "Cost",
-                "Working time",
-                "Feasible",
-                "p0",
-                "Energy violation",
-                "p1",
-                "Capacity violation",
-                "p2",
-                "Waiting time violation",
-                "p3",
-                "Fixed time violation",
-                "Truck routes",
-                "Drone routes",
-                "Truck routes count",
-                "Drone routes count",
-                "Neighborhood",
-                "Tabu list",
-            ]
-            .join(",");
+        // Appending to a log that already has its CSV headers
+        let appended = CONFIG.load().append
+            && log_path
+                .as_ref()
+                .is_some_and(|p| p.metadata().is_ok_and(|m| m.len() > 0));
+        let mut writer: Option<Box<dyn Write>> = match log_path {
+            Some(path) if CONFIG.load().log_format != LogFormat::Parquet => {
+                // Compressed streams appended to each other are decompressed as a whole
+                let file = _create(&path)?;
+                Some(match CONFIG.load().log_compress {
+                    None => Box::new(file),
+                    Some(LogCompression::Gzip) => Box::new(GzEncoder::new(file, Compression::default())),
+                    Some(LogCompression::Zstd) => Box::new(zstd::Encoder::new(file, 0)?.auto_finish()),
+                })
+            }
+            _ => None,
+        };
+
+        if let Some(ref mut writer) = writer
+            && CONFIG.load().log_format == LogFormat::Csv
+            && !appended
+        {
+            let mut columns = vec!["Iteration"];
+            for column in _log_columns() {
+                columns.extend(_csv_headers(column));
+            }
+            let columns = columns.join(",");
             writeln!(writer, "sep=,\n{columns}")?;
         }
 
-        Ok(Logger {
+        Ok(Self {
             _iteration: 0,
             _time_offset: SystemTime::now(),
+            _granularity: LogGranularity::Full,
+            _budget_offset: SystemTime::now(),
+            _budget_logging_elapsed: 0.0,
+            _downgrades: vec![],
             _outputs: outputs,
             _id: id,
             _problem: problem,
             _writer: writer,
+            _database: database,
+            _segments: vec![],
+            _segments_writer: segments_writer,
+            #[cfg(feature = "parquet")]
+            _parquet: parquet,
+            _bks: bks,
+            _target: None,
+            _detached: detached,
+            _summary: None,
+            _progress_offset: SystemTime::now(),
+            _status: status,
+            #[cfg(feature = "tui")]
+            _dashboard: dashboard,
         })
     }
 
@@ -107,7 +483,8 @@ impl Logger<'_> {
         solution: &Solution,
         neighbor: Neighborhood,
         tabu_list: &Vec<Vec<usize>>,
-    ) -> Result<(), io::Error> {
+        improved: bool,
+    ) -> Result<(), Box<dyn Error>> {
         fn _wrap(content: &String) -> String {
             format!("\"{content}\"")
         }
@@ -123,36 +500,273 @@ impl Logger<'_> {
         }
 
         self._iteration += 1;
-        if let Some(ref mut writer) = self._writer {
-            writeln!(
-                writer,
-                "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+        #[cfg(feature = "parquet")]
+        let parquet = self._parquet.is_some();
+        #[cfg(not(feature = "parquet"))]
+        let parquet = false;
+        let database = self._database.as_mut().filter(|_| !CONFIG.load().disable_logging);
+        if self._writer.is_some() || database.is_some() || parquet {
+            let skip = match self._granularity {
+                LogGranularity::Full => !improved && !self._iteration.is_multiple_of(CONFIG.load().log_interval),
+                LogGranularity::Sampled => {
+                    !improved
+                        && !self
+                            ._iteration
+                            .is_multiple_of(LOG_SAMPLE_INTERVAL * CONFIG.load().log_interval)
+                }
+                LogGranularity::ImprovementsOnly => !improved,
+            };
+
+            let write_offset = SystemTime::now();
+            if !skip {
+                let selected = |column| CONFIG.load().log_columns.contains(&column);
+                let truck_routes = _expand_routes(&solution.truck_routes);
+                let drone_routes = _expand_routes(&solution.drone_routes);
+                let truck_routes_count = solution.truck_routes.iter().map(|r| r.len()).sum::<usize>();
+                let drone_routes_count = solution.drone_routes.iter().map(|r| r.len()).sum::<usize>();
+                let penalty_coefficients = penalty_coeffs();
+                let violations = [
+                    solution.energy_violation,
+                    solution.capacity_violation,
+                    solution.waiting_time_violation,
+                    solution.fixed_time_violation,
+                    solution.depot_slot_violation,
+                    solution.assignment_violation,
+                    solution.no_fly_violation,
+                ];
+
+                let routes = selected(LogColumn::Routes);
+                let routes_count = selected(LogColumn::RoutesCount);
+                let violations_selected = selected(LogColumn::Violations);
+                let record = IterationRecord {
+                    iteration: self._iteration,
+                    cost: selected(LogColumn::Cost).then(|| solution.cost()),
+                    working_time: selected(LogColumn::WorkingTime).then_some(solution.working_time),
+                    feasible: selected(LogColumn::Feasible).then_some(solution.feasible),
+                    penalty_coefficients: violations_selected.then_some(penalty_coefficients),
+                    energy_violation: violations_selected.then_some(solution.energy_violation),
+                    capacity_violation: violations_selected.then_some(solution.capacity_violation),
+                    waiting_time_violation: violations_selected.then_some(solution.waiting_time_violation),
+                    fixed_time_violation: violations_selected.then_some(solution.fixed_time_violation),
+                    depot_slot_violation: violations_selected.then_some(solution.depot_slot_violation),
+                    assignment_violation: violations_selected.then_some(solution.assignment_violation),
+                    no_fly_violation: violations_selected.then_some(solution.no_fly_violation),
+                    truck_routes: routes.then_some(&truck_routes),
+                    drone_routes: routes.then_some(&drone_routes),
+                    truck_routes_count: routes_count.then_some(truck_routes_count),
+                    drone_routes_count: routes_count.then_some(drone_routes_count),
+                    neighborhood: selected(LogColumn::Neighborhood).then(|| neighbor.to_string()),
+                    tabu_list: selected(LogColumn::TabuList).then_some(tabu_list),
+                };
+                if let Some(ref mut writer) = self._writer
+                    && CONFIG.load().log_format == LogFormat::Jsonl
+                {
+                    serde_json::to_writer(&mut *writer, &record)?;
+                    writeln!(writer)?;
+                }
+                #[cfg(feature = "parquet")]
+                if let Some(ref mut parquet) = self._parquet {
+                    parquet.append(&record)?;
+                }
+
+                // Formatting the routes is expensive, skip it when they are not written anywhere
+                let (truck_routes, drone_routes) = if database.is_some()
+                    || (CONFIG.load().log_format == LogFormat::Csv && selected(LogColumn::Routes))
+                {
+                    (format!("{truck_routes:?}"), format!("{drone_routes:?}"))
+                } else {
+                    (String::new(), String::new())
+                };
+                let tabu_list = format!("{tabu_list:?}");
+                if let Some(ref mut writer) = self._writer
+                    && CONFIG.load().log_format == LogFormat::Csv
+                {
+                    let mut fields = vec![self._iteration.to_string()];
+                    for column in _log_columns() {
+                        match column {
+                            LogColumn::Cost => fields.push(solution.cost().to_string()),
+                            LogColumn::WorkingTime => fields.push(solution.working_time.to_string()),
+                            LogColumn::Feasible => fields.push(i32::from(solution.feasible).to_string()),
+                            LogColumn::Violations => {
+                                for (p, v) in penalty_coefficients.iter().zip(violations) {
+                                    fields.extend([p.to_string(), v.to_string()]);
+                                }
+                            }
+                            LogColumn::Routes => fields.extend([_wrap(&truck_routes), _wrap(&drone_routes)]),
+                            LogColumn::RoutesCount => {
+                                fields.extend([truck_routes_count.to_string(), drone_routes_count.to_string()]);
+                            }
+                            LogColumn::Neighborhood => fields.push(_wrap(&neighbor.to_string())),
+                            LogColumn::TabuList => fields.push(_wrap(&tabu_list)),
+                        }
+                    }
+                    writeln!(writer, "{}", fields.join(","))?;
+                }
+                if let Some(database) = database {
+                    database.iteration(
+                        self._iteration,
+                        solution,
+                        (&truck_routes, &drone_routes),
+                        &neighbor.to_string(),
+                        &tabu_list,
+                    )?;
+                }
+            }
+
+            self._budget_logging_elapsed += SystemTime::now().duration_since(write_offset).unwrap().as_secs_f64();
+            self._check_budget();
+        }
+
+        Ok(())
+    }
+
+    /// Downgrade the logging granularity if writing the CSV consumed more than `--log-budget` of the wall-clock
+    /// time since the last check.
+    fn _check_budget(&mut self) {
+        let Some(budget) = CONFIG.load().log_budget else {
+            return;
+        };
+
+        if !self._iteration.is_multiple_of(LOG_BUDGET_CHECK_INTERVAL)
+            || self._granularity == LogGranularity::ImprovementsOnly
+        {
+            return;
+        }
+
+        let now = SystemTime::now();
+        let window = now.duration_since(self._budget_offset).unwrap().as_secs_f64();
+        let fraction = self._budget_logging_elapsed / window;
+        if fraction > budget {
+            self._granularity = match self._granularity {
+                LogGranularity::Full => LogGranularity::Sampled,
+                LogGranularity::Sampled | LogGranularity::ImprovementsOnly => LogGranularity::ImprovementsOnly,
+            };
+
+            info!(
+                "Logging consumed {:.1}% of wall-clock time (budget {:.1}%), switching to {} logging at iteration {}",
+                100.0 * fraction,
+                100.0 * budget,
+                self._granularity,
                 self._iteration,
-                solution.cost(),
-                solution.working_time,
-                i32::from(solution.feasible),
-                penalty_coeff::<0>(),
-                solution.energy_violation,
-                penalty_coeff::<1>(),
-                solution.capacity_violation,
-                penalty_coeff::<2>(),
-                solution.waiting_time_violation,
-                penalty_coeff::<3>(),
-                solution.fixed_time_violation,
-                _wrap(&format!("{:?}", _expand_routes(&solution.truck_routes))),
-                _wrap(&format!("{:?}", _expand_routes(&solution.drone_routes))),
-                solution.truck_routes.iter().map(|r| r.len()).sum::<usize>(),
-                solution.drone_routes.iter().map(|r| r.len()).sum::<usize>(),
-                _wrap(&neighbor.to_string()),
-                _wrap(&format!("{tabu_list:?}")),
-            )?;
+            );
+            self._downgrades.push(LogDowngrade {
+                iteration: self._iteration,
+                granularity: self._granularity,
+                logging_fraction: fraction,
+            });
+        }
+
+        self._budget_offset = now;
+        self._budget_logging_elapsed = 0.0;
+    }
+
+    /// Record the state of the adaptive neighborhood selection at the end of a segment, writing it to the segments
+    /// log if iterations are logged to files.
+    pub fn adaptive_segment(&mut self, segment: AdaptiveSegment) -> Result<(), Box<dyn Error>> {
+        if let Some(ref mut writer) = self._segments_writer {
+            serde_json::to_writer(&mut *writer, &segment)?;
+            writeln!(writer)?;
+        }
+
+        self._segments.push(segment);
+        Ok(())
+    }
+
+    /// The run JSON of a detached logger, once finalized
+    pub fn summary(&self) -> Option<&str> {
+        self._summary.as_deref()
+    }
+
+    /// Percentage gap of `value` to the best known objective value of this instance, if it is known.
+    pub fn bks_gap(&self, value: f64) -> Option<f64> {
+        self._bks.map(|b| bks::gap(value, b))
+    }
+
+    /// Report the search progress: publish it to the status server if `--status-port` is set, update the dashboard if
+    /// `--tui` is set, and print a JSON progress object to stderr if `--progress-format json` is set and
+    /// `--progress-interval` seconds have passed since the previous one.
+    pub fn progress(
+        &mut self,
+        iteration: usize,
+        result: &Solution,
+        current: &Solution,
+        elite_size: usize,
+        weights: &[f64],
+    ) -> Result<(), Box<dyn Error>> {
+        if let Some(ref status) = self._status {
+            status.update(iteration, result)?;
+        }
+
+        #[cfg(feature = "tui")]
+        if let Some(ref mut dashboard) = self._dashboard {
+            dashboard.update(&DashboardState {
+                iteration,
+                elapsed: SystemTime::now().duration_since(self._time_offset)?.as_secs_f64(),
+                result,
+                current,
+                elite_size,
+                weights,
+            })?;
+        }
+        #[cfg(not(feature = "tui"))]
+        let _ = weights;
+
+        if CONFIG.load().progress_format != ProgressFormat::Json {
+            return Ok(());
+        }
+
+        let now = SystemTime::now();
+        if now.duration_since(self._progress_offset)?.as_secs_f64() < CONFIG.load().progress_interval {
+            return Ok(());
         }
+        self._progress_offset = now;
+
+        let progress = Progress {
+            iteration,
+            incumbent: result.cost(),
+            current: current.cost(),
+            elite_size,
+            elapsed: now.duration_since(self._time_offset)?.as_secs_f64(),
+        };
+        eprintln!("{}", serde_json::to_string(&progress)?);
 
         Ok(())
     }
 
+    /// Record that the target cost has been reached at the current iteration.
+    pub fn reach_target(&mut self) {
+        let elapsed = SystemTime::now()
+            .duration_since(self._time_offset)
+            .unwrap()
+            .as_secs_f64();
+        info!(
+            "Target cost reached after {elapsed:.2}s at iteration {}",
+            self._iteration
+        );
+        self._target = Some((elapsed, self._iteration));
+    }
+
+    /// Write the solutions of a Pareto front to the output directory.
+    pub fn pareto_front(&self, front: &[Rc<Solution>]) -> Result<(), Box<dyn Error>> {
+        let json_path = self
+            ._outputs
+            .join(format!("{}-{}-pareto.json", self._problem, self._id));
+        let mut json = File::create(&json_path)?;
+        info!(
+            "Pareto front of {} solution(s) written to {}",
+            front.len(),
+            json_path.display()
+        );
+        json.write_all(serde_json::to_string(&front.iter().map(Rc::as_ref).collect::<Vec<&Solution>>())?.as_bytes())?;
+
+        Ok(())
+    }
+
+    /// Write the run JSON, the solution JSON and the config JSON to the output directory and print their paths, or
+    /// record the run in the results database and print its path (see `--log-backend`). The `alternatives` of
+    /// `--top-solutions` are written next to them without printing their path.
     pub fn finalize(
-        &self,
+        &mut self,
         result: &Solution,
         tabu_size: usize,
         reset_after: usize,
@@ -161,48 +775,123 @@ impl Logger<'_> {
         last_improved: usize,
         post_optimization: f64,
         post_optimization_elapsed: f64,
+        neighborhood_statistics: &[NeighborhoodStatistics],
+        alternatives: &[Rc<Solution>],
     ) -> Result<(), Box<dyn Error>> {
         let elapsed = SystemTime::now()
             .duration_since(self._time_offset)
             .unwrap()
             .as_secs_f64();
-        let serialized_config = SerializedConfig::from(CONFIG.clone());
+        let serialized_config = SerializedConfig::from(CONFIG.load().clone());
+
+        // Restore the terminal before printing the output paths
+        #[cfg(feature = "tui")]
+        {
+            self._dashboard = None;
+        }
+
+        // Dropping the writer finishes the compressed stream of the iteration log
+        if let Some(mut writer) = self._writer.take() {
+            writer.flush()?;
+        }
+        #[cfg(feature = "parquet")]
+        if let Some(parquet) = self._parquet.take() {
+            parquet.close()?;
+        }
+
+        let bks_gap = self.bks_gap(result.objective());
+
+        let summary = serde_json::to_string(&RunJSON {
+            schema_version: migrate::SCHEMA_VERSION,
+            id: &self._id,
+            problem: self._problem.clone(),
+            manifest: manifest::manifest(self._detached),
+            tabu_size,
+            reset_after,
+            iterations: self._iteration,
+            actual_adaptive_iterations,
+            total_adaptive_segments,
+            solution: result,
+            config: &serialized_config,
+            last_improved,
+            elapsed,
+            post_optimization,
+            post_optimization_elapsed,
+            log_downgrades: &self._downgrades,
+            bks: self._bks,
+            bks_gap,
+            time_to_target: self._target.map(|t| t.0),
+            iterations_to_target: self._target.map(|t| t.1),
+            kpi: kpi::report(result),
+            neighborhood_statistics,
+            adaptive_history: (CONFIG.load().strategy == Strategy::Adaptive).then(|| AdaptiveHistory {
+                neighborhoods: NEIGHBORHOODS.iter().map(Neighborhood::to_string).collect(),
+                segments: &self._segments,
+            }),
+            profile: profile::report(),
+        })?;
+
+        if self._detached {
+            self._summary = Some(summary);
+            return Ok(());
+        }
+
+        let mut solution = serde_json::to_value(result)?;
+        if CONFIG.load().schedule {
+            solution["schedule"] = serde_json::to_value(result.schedule())?;
+        }
+        if CONFIG.load().detailed_routes {
+            solution["drone_legs"] = serde_json::to_value(result.drone_legs())?;
+        }
+        let solution = serde_json::to_string(&solution)?;
+
+        if let Some(ref mut database) = self._database {
+            database.finalize(result, &solution, (self._iteration, elapsed, bks_gap), &summary)?;
+            _print_path(database.path());
+            if CONFIG.load().stdout_solution {
+                println!("{solution}");
+            }
+            _notify(&summary);
+            return Ok(());
+        }
 
         let json_path = self._outputs.join(format!("{}-{}.json", self._problem, self._id));
         let mut json = File::create(&json_path)?;
-        println!("{}", json_path.display());
-        json.write_all(
-            serde_json::to_string(&RunJSON {
-                problem: self._problem.clone(),
-                tabu_size,
-                reset_after,
-                iterations: self._iteration,
-                actual_adaptive_iterations,
-                total_adaptive_segments,
-                solution: result,
-                config: &serialized_config,
-                last_improved,
-                elapsed,
-                post_optimization,
-                post_optimization_elapsed,
-            })?
-            .as_bytes(),
-        )?;
+        _print_path(&json_path);
+        json.write_all(summary.as_bytes())?;
 
         let json_path = self
             ._outputs
             .join(format!("{}-{}-solution.json", self._problem, self._id));
         let mut json = File::create(&json_path)?;
-        println!("{}", json_path.display());
-        json.write_all(serde_json::to_string(&result)?.as_bytes())?;
+        _print_path(&json_path);
+        json.write_all(solution.as_bytes())?;
 
         let json_path = self
             ._outputs
             .join(format!("{}-{}-config.json", self._problem, self._id));
         let mut json = File::create(&json_path)?;
-        println!("{}", json_path.display());
+        _print_path(&json_path);
         json.write_all(serde_json::to_string(&serialized_config)?.as_bytes())?;
 
+        if !alternatives.is_empty() {
+            let json_path = self._outputs.join(format!("{}-{}-top.json", self._problem, self._id));
+            let mut json = File::create(&json_path)?;
+            info!(
+                "{} best distinct solution(s) written to {}",
+                alternatives.len(),
+                json_path.display()
+            );
+            json.write_all(
+                serde_json::to_string(&alternatives.iter().map(Rc::as_ref).collect::<Vec<&Solution>>())?.as_bytes(),
+            )?;
+        }
+
+        if CONFIG.load().stdout_solution {
+            println!("{solution}");
+        }
+
+        _notify(&summary);
         Ok(())
     }
 }
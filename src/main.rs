@@ -1,59 +1,244 @@
-use std::fs;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use std::{fs, io};
 
-use clap::Parser;
 use colored::Colorize;
 use mimalloc::MiMalloc;
-use routes::Route;
-
-mod cli;
-mod clusterize;
-mod config;
-mod errors;
-mod logger;
-mod neighborhoods;
-mod routes;
-mod solutions;
+use min_timespan_delivery::{
+    bench, bks, cli, config, decompose, diff, exact, explain, export, external, islands, kpi, logger, migrate, milp,
+    params, plot, profile, removal, reoptimize, repair, schedule, sensitivity, service, simulation, solutions,
+};
+use tracing::{Level, info};
+use tracing_subscriber::fmt::format::FmtSpan;
 
 #[global_allocator]
 static GLOBAL: MiMalloc = MiMalloc;
 
+/// Load a solution JSON file, re-evaluating it against the current config
+fn _load_solution(path: &str) -> solutions::Solution {
+    let data = fs::read_to_string(path).unwrap();
+    serde_json::from_str::<solutions::Solution>(&data)
+        .unwrap()
+        .reevaluated()
+}
+
 fn main() {
+    let arguments = params::parse();
+    let level = match arguments.command {
+        // Log messages would be drawn over the dashboard
+        cli::Commands::Run(cli::RunArgs { tui: true, .. }) => Level::ERROR,
+        cli::Commands::Run(cli::RunArgs { quiet: true, .. }) => Level::WARN,
+        cli::Commands::Run(cli::RunArgs { verbose: 0, .. }) => Level::INFO,
+        cli::Commands::Run(cli::RunArgs { verbose: 1, .. }) => Level::DEBUG,
+        cli::Commands::Run(_) => Level::TRACE,
+        _ => Level::INFO,
+    };
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(io::stderr)
+        .with_ansi(io::stderr().is_terminal())
+        .with_span_events(FmtSpan::CLOSE)
+        .init();
+
+    if let cli::Commands::Bks {
+        command: cli::BksCommands::Update { bks_file, results },
+    } = &arguments.command
+    {
+        bks::update(bks_file, results).unwrap();
+        return;
+    }
+
+    if let cli::Commands::Migrate { files } = &arguments.command {
+        migrate::migrate(files).unwrap();
+        return;
+    }
+
+    if let cli::Commands::Bench {
+        iterations,
+        samples,
+        seed,
+        search_scope,
+        search_top_k,
+    } = arguments.command
+    {
+        bench::run(iterations, samples, seed, search_scope, search_top_k, &mut io::stdout()).unwrap();
+        return;
+    }
+
+    if let cli::Commands::Serve {
+        ref bind,
+        port,
+        max_body,
+        timeout,
+        workers,
+    } = arguments.command
+    {
+        service::serve(bind, port, max_body, Duration::from_secs(timeout), workers).unwrap();
+        return;
+    }
+
+    if let cli::Commands::ExplainConfig(_) = arguments.command {
+        explain::explain(&mut io::stdout()).unwrap();
+        return;
+    }
+
+    if let cli::Commands::ExportMilp(_) = arguments.command {
+        milp::export(&mut io::stdout()).unwrap();
+        return;
+    }
+
+    if let cli::Commands::Simulate {
+        solution,
+        samples,
+        time_noise,
+        demand_noise,
+        ..
+    } = &arguments.command
+    {
+        let s = _load_solution(solution);
+        let report = simulation::simulate(&s, *samples, *time_noise, *demand_noise);
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+        return;
+    }
+
+    if let cli::Commands::Sensitivity {
+        solution,
+        parameter,
+        from,
+        to,
+        steps,
+        ..
+    } = &arguments.command
+    {
+        let s = _load_solution(solution);
+        sensitivity::sweep(&s, *parameter, (*from, *to), *steps, &mut io::stdout()).unwrap();
+        return;
+    }
+
+    if let cli::Commands::VehicleRemoval { solution, .. } = &arguments.command {
+        let s = _load_solution(solution);
+        removal::analyze(&s, &mut io::stdout()).unwrap();
+        return;
+    }
+
+    if let cli::Commands::Diff { first, second, .. } = &arguments.command {
+        let (first, second) = (_load_solution(first), _load_solution(second));
+        diff::report(&first, &second, &mut io::stdout()).unwrap();
+        return;
+    }
+
+    if let cli::Commands::Convert {
+        solution,
+        export,
+        output,
+        ..
+    } = &arguments.command
+    {
+        let s = _load_solution(solution);
+        let data = match export {
+            cli::ExportFormat::Geojson => export::geojson(&s),
+            cli::ExportFormat::Schedule => serde_json::to_value(s.schedule()).unwrap(),
+        };
+        let data = serde_json::to_string_pretty(&data).unwrap();
+        match output {
+            Some(path) => fs::write(path, data).unwrap(),
+            None => println!("{data}"),
+        }
+        return;
+    }
+
+    if let cli::Commands::Plot {
+        solution,
+        output,
+        gantt,
+        ..
+    } = &arguments.command
+    {
+        let s = _load_solution(solution);
+        let output = output
+            .as_ref()
+            .map_or_else(|| Path::new(solution).with_extension("svg"), PathBuf::from);
+        if *gantt {
+            schedule::gantt(&s.schedule(), &output).unwrap();
+        } else {
+            plot::plot(&s, &output).unwrap();
+        }
+        info!("Plotted solution to {}", output.display());
+        return;
+    }
+
     let mut logger = logger::Logger::new().unwrap();
 
-    let solution = match cli::Arguments::parse().command {
+    let solution = match arguments.command {
         cli::Commands::Evaluate { solution, .. } => {
-            let data = fs::read_to_string(solution).unwrap();
-
-            // Note: Solution `s` here contains attributes calculated using its old config.
-            // In order to evaluate `s` with the new config, we construct a new solution.
-            let s = serde_json::from_str::<solutions::Solution>(&data).unwrap();
-
-            let mut truck_routes = vec![vec![]; s.truck_routes.len()];
-            for (truck, routes) in s.truck_routes.into_iter().enumerate() {
-                for route in routes {
-                    let new = routes::TruckRoute::new(route.data().customers.clone());
-                    truck_routes[truck].push(new);
-                }
-            }
-
-            let mut drone_routes = vec![vec![]; s.drone_routes.len()];
-            for (drone, routes) in s.drone_routes.into_iter().enumerate() {
-                for route in routes {
-                    let new = routes::DroneRoute::new(route.data().customers.clone());
-                    drone_routes[drone].push(new);
-                }
+            let s = _load_solution(&solution);
+            logger.finalize(&s, 0, 0, 0, 0, 0, 0.0, 0.0, &[], &[]).unwrap();
+            s
+        }
+        cli::Commands::Run(_) => {
+            let root = match (&config::CONFIG.load().init_command, config::CONFIG.load().decompose) {
+                (Some(command), _) => external::root(command),
+                (None, Some(parts)) => decompose::root(parts),
+                (None, None) => solutions::Solution::initialize(),
+            };
+            if config::CONFIG.load().islands > 1 {
+                islands::search(root, &mut logger)
+            } else {
+                solutions::Solution::tabu_search(root, &mut logger)
             }
-
-            let s = solutions::Solution::new(truck_routes, drone_routes);
-            logger.finalize(&s, 0, 0, 0, 0, 0, 0.0, 0.0).unwrap();
+        }
+        cli::Commands::Repair { solution, .. } => {
+            let s = repair::repair(&_load_solution(&solution));
+            logger.finalize(&s, 0, 0, 0, 0, 0, 0.0, 0.0, &[], &[]).unwrap();
             s
         }
-        cli::Commands::Run { .. } => {
-            let root = solutions::Solution::initialize();
+        cli::Commands::Reoptimize { solution, .. } => {
+            let root = reoptimize::root(&solution);
             solutions::Solution::tabu_search(root, &mut logger)
         }
+        cli::Commands::Exact(_) => {
+            let s = exact::solve();
+            logger.finalize(&s, 0, 0, 0, 0, 0, 0.0, 0.0, &[], &[]).unwrap();
+            s
+        }
+        cli::Commands::Bks { .. }
+        | cli::Commands::Migrate { .. }
+        | cli::Commands::Bench { .. }
+        | cli::Commands::ExplainConfig(_)
+        | cli::Commands::ExportMilp(_)
+        | cli::Commands::Serve { .. }
+        | cli::Commands::Simulate { .. }
+        | cli::Commands::Sensitivity { .. }
+        | cli::Commands::VehicleRemoval { .. }
+        | cli::Commands::Diff { .. }
+        | cli::Commands::Convert { .. }
+        | cli::Commands::Plot { .. } => {
+            unreachable!()
+        }
     };
 
-    eprintln!("{}", format!("Result = {}", solution.working_time).red());
+    if !config::CONFIG.load().quiet {
+        eprintln!("{solution}");
+        eprintln!("{}", format!("Result = {}", solution.working_time).red());
+        kpi::print(&kpi::report(&solution));
+        if let Some(report) = profile::report() {
+            profile::print(&report);
+        }
+        let violations = [
+            ("energy", solution.energy_violation),
+            ("capacity", solution.capacity_violation),
+            ("waiting-time", solution.waiting_time_violation),
+            ("fixed-time", solution.fixed_time_violation),
+            ("depot-slot", solution.depot_slot_violation),
+            ("assignment", solution.assignment_violation),
+            ("no-fly", solution.no_fly_violation),
+        ];
+        for ((name, violation), soft) in violations.into_iter().zip(config::CONFIG.load().soft_weights) {
+            if soft.is_some() {
+                eprintln!("Soft constraint {name}: violation = {violation}");
+            }
+        }
+    }
     solution.verify();
 }
@@ -0,0 +1,64 @@
+use std::{env, fs};
+
+use sha2::{Digest, Sha256};
+
+use crate::config::{CONFIG, parse_drone_class};
+
+#[derive(serde::Serialize)]
+struct InputFile {
+    role: &'static str,
+    path: String,
+    /// SHA-256 of the file content, if the file is readable or the problem was read from stdin
+    sha256: Option<String>,
+}
+
+impl InputFile {
+    fn new(role: &'static str, path: &str) -> Self {
+        Self {
+            role,
+            path: path.to_string(),
+            sha256: fs::read(path).ok().map(|data| format!("{:x}", Sha256::digest(data))),
+        }
+    }
+}
+
+/// Provenance of a run: the command line, the build of the solver and the hashes of the input files
+#[derive(serde::Serialize)]
+pub struct Manifest {
+    command: Vec<String>,
+    version: &'static str,
+    git_commit: &'static str,
+    inputs: Vec<InputFile>,
+}
+
+/// Manifest of the current run. A `detached` run (see `Logger::detached`) gets neither the command line nor the input
+/// files, which belong to the server or the host program rather than to the caller the run JSON is returned to.
+pub fn manifest(detached: bool) -> Manifest {
+    if detached {
+        return Manifest {
+            command: vec![],
+            version: env!("CARGO_PKG_VERSION"),
+            git_commit: env!("GIT_COMMIT"),
+            inputs: vec![],
+        };
+    }
+
+    let config = CONFIG.load();
+    let mut problem = InputFile::new("problem", &config.problem);
+    if config.problem_sha256.is_some() {
+        problem.sha256.clone_from(&config.problem_sha256);
+    }
+
+    let mut inputs = vec![problem, InputFile::new("truck_config", &config.truck_cfg)];
+    for spec in &config.drone_cfg {
+        let path = parse_drone_class(spec).map_or(spec.as_str(), |(_, _, path)| path);
+        inputs.push(InputFile::new("drone_config", path));
+    }
+
+    Manifest {
+        command: env::args().collect(),
+        version: env!("CARGO_PKG_VERSION"),
+        git_commit: env!("GIT_COMMIT"),
+        inputs,
+    }
+}
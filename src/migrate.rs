@@ -0,0 +1,105 @@
+use std::error::Error;
+use std::fs;
+
+use serde_json::{Map, Value, json};
+
+/// Version of the schema of the run JSON written by "run". Bump it whenever a field is added, renamed or removed, and
+/// add the step upgrading the previous version to `_upgrade`.
+///
+/// - 1: run JSON files written before the schema was versioned, without a `schema_version` field
+///
+/// - 2: adds `schema_version`
+///
+/// - 3: adds `solution.assignment_violation`
+///
+/// - 4: adds `solution.no_fly_violation`
+pub const SCHEMA_VERSION: u64 = 4;
+
+/// Insert `value` at `key` of `object` if it has no such key
+fn _default(object: &mut Map<String, Value>, key: &str, value: Value) {
+    object.entry(key).or_insert(value);
+}
+
+/// Fill the fields of a solution object that older versions did not write, with the values that older solutions
+/// implicitly had
+fn _upgrade_solution(solution: &mut Map<String, Value>) {
+    _default(solution, "sorties", json!([]));
+    _default(solution, "total_time", json!(0.0));
+    _default(solution, "total_energy", json!(0.0));
+    _default(solution, "fixed_time_violation", json!(0.0));
+    _default(solution, "depot_slot_violation", json!(0.0));
+    _default(solution, "assignment_violation", json!(0.0));
+    _default(solution, "no_fly_violation", json!(0.0));
+}
+
+/// Upgrade the run JSON `run` from `version` to the next version
+fn _upgrade(run: &mut Map<String, Value>, version: u64) -> Result<(), Box<dyn Error>> {
+    match version {
+        1 => {
+            if let Some(Value::Object(solution)) = run.get_mut("solution") {
+                _upgrade_solution(solution);
+            }
+            for key in ["bks", "bks_gap", "time_to_target", "iterations_to_target"] {
+                _default(run, key, Value::Null);
+            }
+            for key in ["log_downgrades", "kpi", "neighborhood_statistics"] {
+                _default(run, key, json!([]));
+            }
+            _default(run, "post_optimization", json!(0.0));
+            _default(run, "post_optimization_elapsed", json!(0.0));
+        }
+        2 | 3 => {
+            if let Some(Value::Object(solution)) = run.get_mut("solution") {
+                _upgrade_solution(solution);
+            }
+        }
+        _ => return Err(format!("No upgrade from schema version {version}").into()),
+    }
+
+    run.insert("schema_version".to_string(), json!(version + 1));
+    Ok(())
+}
+
+/// Upgrade `data` to the current schema and return its version before the upgrade. A run JSON is upgraded step by
+/// step from its `schema_version`, a solution JSON (which carries no version) has its missing fields filled.
+pub fn upgrade(data: &mut Value) -> Result<u64, Box<dyn Error>> {
+    let object = data.as_object_mut().ok_or("Expected a JSON object")?;
+    if !object.contains_key("solution") && object.contains_key("truck_routes") {
+        _upgrade_solution(object);
+        return Ok(SCHEMA_VERSION);
+    }
+
+    let version = match object.get("schema_version") {
+        Some(version) => version.as_u64().ok_or("Invalid schema_version")?,
+        None => 1,
+    };
+    if version > SCHEMA_VERSION {
+        return Err(format!(
+            "Schema version {version} is newer than the supported version {SCHEMA_VERSION}, upgrade this program"
+        )
+        .into());
+    }
+
+    for from in version..SCHEMA_VERSION {
+        _upgrade(object, from)?;
+    }
+
+    Ok(version)
+}
+
+/// Upgrade the run or solution JSON files `paths` to the current schema in place
+pub fn migrate(paths: &[String]) -> Result<(), Box<dyn Error>> {
+    for path in paths {
+        let mut data = serde_json::from_str::<Value>(&fs::read_to_string(path)?)?;
+        let version = upgrade(&mut data).map_err(|e| format!("{path}: {e}"))?;
+        fs::write(path, serde_json::to_string(&data)?)?;
+
+        if version < SCHEMA_VERSION {
+            eprintln!("Upgraded {path} from schema version {version} to {SCHEMA_VERSION}");
+        } else {
+            eprintln!("{path} is up to date");
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,389 @@
+use std::io::{self, Write};
+use std::iter;
+
+use crate::cli::Objective;
+use crate::config::{CONFIG, DroneConfig};
+use crate::ids::{DroneId, TruckId, VehicleId};
+
+/// Maximum number of terms written on a line of the model, as some LP readers limit the length of a line
+const _TERMS_PER_LINE: usize = 8;
+
+/// A vehicle of the model, performing up to `trips` routes
+struct _Vehicle {
+    /// Prefix of the names of its variables
+    name: String,
+    /// Customers it may serve
+    customers: Vec<usize>,
+    trips: usize,
+    /// Travel time of each arc it may use, `None` for the others
+    time: Vec<Vec<Option<f64>>>,
+    capacity: f64,
+    fixed_time: f64,
+    /// Time between two consecutive routes
+    gap: f64,
+    release: f64,
+    single_customer: bool,
+    /// Drone class, whose energy is modeled when its powers are linear in the payload
+    class: Option<usize>,
+}
+
+impl _Vehicle {
+    fn new(vehicle: VehicleId) -> Self {
+        let nodes = CONFIG.load().customers_count + 1;
+        let servable = |customer: usize| {
+            CONFIG.load().assignment_rules.assignable(customer, vehicle)
+                && match vehicle {
+                    VehicleId::Truck(_) => CONFIG.load().truckable[customer],
+                    VehicleId::Drone(_) => CONFIG.load().dronable[customer],
+                }
+        };
+        let customers = (1..nodes).filter(|&c| servable(c)).collect::<Vec<_>>();
+
+        let mut time = vec![vec![None; nodes]; nodes];
+        for (i, row) in time.iter_mut().enumerate() {
+            for (j, t) in row.iter_mut().enumerate() {
+                if i == j || (i != 0 && !servable(i)) || (j != 0 && !servable(j)) {
+                    continue;
+                }
+
+                *t = match vehicle {
+                    VehicleId::Truck(_) => Some(CONFIG.load().truck_distances[i][j] / CONFIG.load().truck.speed),
+                    VehicleId::Drone(drone) => {
+                        let class = &CONFIG.load().drone_classes[CONFIG.load().drone_class_of(drone)];
+                        let cruise = class.leg_cruise_time(CONFIG.load().drone_distances[i][j], i, j);
+                        (CONFIG.load().drone_pairs[i][j] && cruise.is_finite())
+                            .then(|| class.takeoff_time() + cruise + class.landing_time())
+                    }
+                };
+            }
+        }
+
+        match vehicle {
+            VehicleId::Truck(TruckId(truck)) => Self {
+                name: format!("t{truck}"),
+                trips: if CONFIG.load().single_truck_route {
+                    1
+                } else {
+                    customers.len()
+                },
+                customers,
+                time,
+                capacity: CONFIG.load().truck.capacity,
+                fixed_time: f64::INFINITY,
+                gap: CONFIG.load().truck.reload_time,
+                release: CONFIG.load().release_time(vehicle),
+                single_customer: false,
+                class: None,
+            },
+            VehicleId::Drone(DroneId(drone)) => {
+                let class = CONFIG.load().drone_class_of(DroneId(drone));
+                let config = &CONFIG.load().drone_classes[class];
+                Self {
+                    name: format!("d{drone}"),
+                    trips: customers.len(),
+                    customers,
+                    time,
+                    capacity: config.capacity(),
+                    fixed_time: config.fixed_time(),
+                    gap: config.recharge_time(),
+                    release: CONFIG.load().release_time(vehicle),
+                    single_customer: CONFIG.load().single_drone_route,
+                    class: matches!(config, DroneConfig::Linear { .. }).then_some(class),
+                }
+            }
+        }
+    }
+
+    /// Arcs the vehicle may use, with their travel time
+    fn arcs(&self) -> impl Iterator<Item = (usize, usize, f64)> {
+        self.time
+            .iter()
+            .enumerate()
+            .flat_map(|(i, row)| row.iter().enumerate().filter_map(move |(j, t)| t.map(|t| (i, j, t))))
+    }
+
+    fn x(&self, trip: usize, i: usize, j: usize) -> String {
+        format!("x_{}_{trip}_{i}_{j}", self.name)
+    }
+
+    fn y(&self, trip: usize, customer: usize) -> String {
+        format!("y_{}_{trip}_{customer}", self.name)
+    }
+
+    fn u(&self, trip: usize) -> String {
+        format!("u_{}_{trip}", self.name)
+    }
+
+    fn p(&self, trip: usize, customer: usize) -> String {
+        format!("p_{}_{trip}_{customer}", self.name)
+    }
+
+    fn f(&self, trip: usize, i: usize, j: usize) -> String {
+        format!("f_{}_{trip}_{i}_{j}", self.name)
+    }
+}
+
+/// Write the linear constraint `name` of the `terms` (coefficient and variable), a few terms per line
+fn _constraint(writer: &mut impl Write, name: &str, terms: &[(f64, String)], sense: &str, rhs: f64) -> io::Result<()> {
+    write!(writer, " {name}:")?;
+    for (i, (coefficient, variable)) in terms.iter().enumerate() {
+        if i > 0 && i % _TERMS_PER_LINE == 0 {
+            write!(writer, "\n   ")?;
+        }
+        let sign = if *coefficient < 0.0 { '-' } else { '+' };
+        write!(writer, " {sign} {} {variable}", coefficient.abs())?;
+    }
+    writeln!(writer, " {sense} {rhs}")
+}
+
+/// Write the variables `names` of a section of the model, a few per line
+fn _names(writer: &mut impl Write, names: &[String]) -> io::Result<()> {
+    for chunk in names.chunks(_TERMS_PER_LINE) {
+        writeln!(writer, " {}", chunk.join(" "))?;
+    }
+    Ok(())
+}
+
+/// Write a mixed integer linear formulation of the loaded instance to `writer`, in the LP file format read by most
+/// solvers (e.g. Gurobi, CPLEX, CBC, HiGHS), to cross-check small instances.
+///
+/// Each vehicle performs up to one route per customer it may serve, each route being a cycle through the depot
+/// whose subtours are eliminated by the Miller-Tucker-Zemlin constraints. The makespan is minimized subject to the
+/// capacity of each route, the assignment rules, the fixed flight time of the endurance model and the battery of
+/// the linear energy model, whose energy is linear in the payload carried on each leg. The waiting time limits are
+/// not modeled.
+pub fn export(writer: &mut impl Write) -> io::Result<()> {
+    assert!(
+        CONFIG.load().objective == Objective::Makespan,
+        "Only the makespan objective can be exported"
+    );
+    assert!(
+        CONFIG
+            .load()
+            .drone_classes
+            .iter()
+            .all(|class| matches!(class, DroneConfig::Linear { .. } | DroneConfig::Endurance { .. })),
+        "Only the linear, endurance and unlimited energy models can be exported"
+    );
+    assert!(
+        CONFIG.load().vehicle_cost == 0.0,
+        "The model does not support --vehicle-cost"
+    );
+    assert!(
+        CONFIG.load().soft_weights.iter().all(Option::is_none),
+        "The model does not support --soft-constraints"
+    );
+    assert!(!CONFIG.load().mothership, "The model does not support --mothership");
+    assert!(CONFIG.load().pins.is_empty(), "The model does not support --pin");
+    assert!(
+        !CONFIG.load().charging_nodes.contains(&true),
+        "The model does not support --charging-stations"
+    );
+    assert!(
+        CONFIG.load().depot_slots == 0 || CONFIG.load().drones_count <= CONFIG.load().depot_slots,
+        "The model does not support --depot-slots"
+    );
+
+    let vehicles = (0..CONFIG.load().trucks_count)
+        .map(|truck| VehicleId::Truck(TruckId(truck)))
+        .chain((0..CONFIG.load().drones_count).map(|drone| VehicleId::Drone(DroneId(drone))))
+        .map(_Vehicle::new)
+        .collect::<Vec<_>>();
+
+    writeln!(writer, "\\ Makespan model of {}", CONFIG.load().problem)?;
+    writeln!(
+        writer,
+        "\\ Vehicles t0, t1, ... are the trucks and d0, d1, ... the drones, node 0 is the depot"
+    )?;
+    writeln!(
+        writer,
+        "\\ x_V_R_I_J: route R of vehicle V travels from node I to node J"
+    )?;
+    writeln!(writer, "\\ y_V_R_I: route R of vehicle V serves customer I")?;
+    writeln!(writer, "\\ u_V_R: vehicle V performs route R")?;
+    writeln!(writer, "\\ p_V_R_I: position of customer I in route R of vehicle V")?;
+    writeln!(
+        writer,
+        "\\ f_V_R_I_J: payload of drone V from node I to node J of route R"
+    )?;
+    writeln!(writer, "Minimize")?;
+    writeln!(writer, " obj: makespan")?;
+    writeln!(writer, "Subject To")?;
+
+    for customer in 1..CONFIG.load().customers_count + 1 {
+        let terms = vehicles
+            .iter()
+            .filter(|v| v.customers.contains(&customer))
+            .flat_map(|v| (0..v.trips).map(move |r| (1.0, v.y(r, customer))))
+            .collect::<Vec<_>>();
+        assert!(!terms.is_empty(), "Customer {customer} cannot be served by any vehicle");
+        _constraint(writer, &format!("serve_{customer}"), &terms, "=", 1.0)?;
+    }
+
+    let mut binaries = vec![];
+    let mut bounds = vec![];
+    for v in &vehicles {
+        let arcs = v.arcs().collect::<Vec<_>>();
+        let positions = if v.single_customer { 1 } else { v.customers.len() };
+        for r in 0..v.trips {
+            let name = format!("{}_{r}", v.name);
+
+            // Flow conservation: the route leaves and enters the depot once if performed, and each customer it serves
+            for node in iter::once(0).chain(v.customers.iter().copied()) {
+                let (visit, suffix) = if node == 0 {
+                    (v.u(r), "0".to_string())
+                } else {
+                    (v.y(r, node), node.to_string())
+                };
+                let out = arcs
+                    .iter()
+                    .filter(|&&(i, _, _)| i == node)
+                    .map(|&(i, j, _)| (1.0, v.x(r, i, j)))
+                    .chain([(-1.0, visit.clone())])
+                    .collect::<Vec<_>>();
+                let inbound = arcs
+                    .iter()
+                    .filter(|&&(_, j, _)| j == node)
+                    .map(|&(i, j, _)| (1.0, v.x(r, i, j)))
+                    .chain([(-1.0, visit)])
+                    .collect::<Vec<_>>();
+                _constraint(writer, &format!("out_{name}_{suffix}"), &out, "=", 0.0)?;
+                _constraint(writer, &format!("in_{name}_{suffix}"), &inbound, "=", 0.0)?;
+            }
+
+            if r > 0 {
+                _constraint(
+                    writer,
+                    &format!("order_{name}"),
+                    &[(1.0, v.u(r)), (-1.0, v.u(r - 1))],
+                    "<=",
+                    0.0,
+                )?;
+            }
+
+            if v.capacity.is_finite() {
+                let terms = v
+                    .customers
+                    .iter()
+                    .map(|&c| (CONFIG.load().demands[c], v.y(r, c)))
+                    .collect::<Vec<_>>();
+                _constraint(writer, &format!("capacity_{name}"), &terms, "<=", v.capacity)?;
+            }
+
+            if v.single_customer {
+                let terms = v.customers.iter().map(|&c| (1.0, v.y(r, c))).collect::<Vec<_>>();
+                _constraint(writer, &format!("single_{name}"), &terms, "<=", 1.0)?;
+            }
+
+            if v.fixed_time.is_finite() {
+                let terms = arcs.iter().map(|&(i, j, t)| (t, v.x(r, i, j))).collect::<Vec<_>>();
+                _constraint(writer, &format!("fixed_time_{name}"), &terms, "<=", v.fixed_time)?;
+            }
+
+            for &(i, j, _) in &arcs {
+                if i != 0 && j != 0 && !v.single_customer {
+                    _constraint(
+                        writer,
+                        &format!("mtz_{name}_{i}_{j}"),
+                        &[(1.0, v.p(r, i)), (-1.0, v.p(r, j)), (positions as f64, v.x(r, i, j))],
+                        "<=",
+                        positions as f64 - 1.0,
+                    )?;
+                }
+            }
+
+            for &c in &v.customers {
+                for &other in CONFIG.load().assignment_rules.separated(c) {
+                    if c < other && v.customers.contains(&other) {
+                        _constraint(
+                            writer,
+                            &format!("separated_{name}_{c}_{other}"),
+                            &[(1.0, v.y(r, c)), (1.0, v.y(r, other))],
+                            "<=",
+                            1.0,
+                        )?;
+                    }
+                }
+            }
+
+            if let Some(class) = v.class {
+                // The payload of a leg is the demand of the customers served before its start (the energy of the
+                // leg leaving a customer does not include its own demand), and the power of each phase of a leg is
+                // affine in the payload
+                let drone = &CONFIG.load().drone_classes[class];
+                let payload_arcs = arcs.iter().filter(|&&(i, _, _)| i != 0).collect::<Vec<_>>();
+                for &c in &v.customers {
+                    let terms = payload_arcs
+                        .iter()
+                        .filter(|&&&(i, _, _)| i == c)
+                        .map(|&&(i, j, _)| (1.0, v.f(r, i, j)))
+                        .chain(
+                            payload_arcs
+                                .iter()
+                                .filter(|&&&(_, j, _)| j == c)
+                                .map(|&&(i, j, _)| (-1.0, v.f(r, i, j))),
+                        )
+                        .chain(
+                            arcs.iter()
+                                .filter(|&&(i, j, _)| j == c && i != 0)
+                                .map(|&(i, j, _)| (-CONFIG.load().demands[i], v.x(r, i, j))),
+                        )
+                        .collect::<Vec<_>>();
+                    _constraint(writer, &format!("payload_{name}_{c}"), &terms, "=", 0.0)?;
+                }
+                for &&(i, j, _) in &payload_arcs {
+                    _constraint(
+                        writer,
+                        &format!("payload_{name}_{i}_{j}"),
+                        &[(1.0, v.f(r, i, j)), (-v.capacity, v.x(r, i, j))],
+                        "<=",
+                        0.0,
+                    )?;
+                }
+
+                let energy = |weight: f64, i: usize, j: usize| {
+                    let cruise = drone.leg_cruise_time(CONFIG.load().drone_distances[i][j], i, j);
+                    drone.landing_power(weight).mul_add(
+                        drone.landing_time(),
+                        drone
+                            .takeoff_power(weight)
+                            .mul_add(drone.takeoff_time(), drone.cruise_power(weight) * cruise),
+                    )
+                };
+                let terms = arcs
+                    .iter()
+                    .map(|&(i, j, _)| (energy(0.0, i, j), v.x(r, i, j)))
+                    .chain(
+                        payload_arcs
+                            .iter()
+                            .map(|&&(i, j, _)| (energy(1.0, i, j) - energy(0.0, i, j), v.f(r, i, j))),
+                    )
+                    .collect::<Vec<_>>();
+                _constraint(writer, &format!("energy_{name}"), &terms, "<=", drone.battery())?;
+            }
+
+            binaries.push(v.u(r));
+            binaries.extend(v.customers.iter().map(|&c| v.y(r, c)));
+            binaries.extend(arcs.iter().map(|&(i, j, _)| v.x(r, i, j)));
+            if !v.single_customer {
+                bounds.extend(v.customers.iter().map(|&c| (v.p(r, c), positions)));
+            }
+        }
+
+        // The working time of the vehicle is its release time, the time of its routes and the gaps between them
+        let terms = [(1.0, "makespan".to_string())]
+            .into_iter()
+            .chain((0..v.trips).flat_map(|r| arcs.iter().map(move |&(i, j, t)| (-t, v.x(r, i, j)))))
+            .chain((1..v.trips).map(|r| (-v.gap, v.u(r))))
+            .collect::<Vec<_>>();
+        _constraint(writer, &format!("makespan_{}", v.name), &terms, ">=", v.release)?;
+    }
+
+    writeln!(writer, "Bounds")?;
+    for (p, positions) in bounds {
+        writeln!(writer, " 1 <= {p} <= {positions}")?;
+    }
+    writeln!(writer, "Binaries")?;
+    _names(writer, &binaries)?;
+    writeln!(writer, "End")
+}
@@ -0,0 +1,197 @@
+use serde::{Deserialize, Serialize};
+
+use crate::config::CONFIG;
+use crate::ids::DroneId;
+use crate::routes::{DroneRoute, Route, TruckRoute};
+use crate::solutions::Solution;
+
+/// Maximum number of truck legs a sortie may span between its launch and rendezvous nodes
+const MAX_SORTIE_SPAN: usize = 3;
+
+/// A drone sortie launched from a truck: the drone carried by `truck` leaves the truck at position `launch` of its
+/// route `route`, serves `customer` and rejoins the truck at position `rendezvous` (positions index the route's
+/// customers, including the depot at both ends).
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Sortie {
+    pub truck: usize,
+    pub route: usize,
+    pub launch: usize,
+    pub customer: usize,
+    pub rendezvous: usize,
+    /// Class of the carried drone flying the sortie: that of the depot drone whose customer it took over
+    #[serde(default)]
+    pub class: usize,
+}
+
+/// Evaluation of a truck route together with the sorties of its carried drone
+pub struct SynchronizedMetrics {
+    /// Arrival time of the truck at each node of the route, relative to the start of the route
+    pub arrival: Vec<f64>,
+    /// Time each sortie lands at its customer, in the order of `sorties`
+    pub pickups: Vec<f64>,
+    pub working_time: f64,
+    pub capacity_violation: f64,
+    pub waiting_time_violation: f64,
+    pub energy_violation: f64,
+    pub fixed_time_violation: f64,
+}
+
+/// Evaluate `route` with the truck waiting at each rendezvous node until its drone returns from `sorties`.
+///
+/// Each sortie is flown with the parameters of its drone class. A sample picked up during a sortie waits from its
+/// pickup until the truck returns to the depot, like the samples picked up by the truck itself.
+pub fn evaluate(route: &TruckRoute, sorties: &[&Sortie]) -> SynchronizedMetrics {
+    let config = CONFIG.load();
+    let customers = &route.data().customers;
+    let speed = config.truck.speed;
+
+    let mut arrival = vec![0.0; customers.len()];
+    let mut returns = vec![0.0_f64; customers.len()];
+    let mut pickups = vec![];
+    let mut energy_violation = 0.0;
+    let mut fixed_time_violation = 0.0;
+    for i in 0..customers.len() {
+        if i > 0 {
+            arrival[i] =
+                (arrival[i - 1] + config.truck_distances[customers[i - 1]][customers[i]] / speed).max(returns[i]);
+        }
+
+        for sortie in sorties.iter().filter(|s| s.launch == i) {
+            let drone = &config.drone_classes[sortie.class];
+            let (takeoff, landing) = (drone.takeoff_time(), drone.landing_time());
+            let (from, to, customer) = (customers[sortie.launch], customers[sortie.rendezvous], sortie.customer);
+            let demand = config.demands[customer];
+            let outbound = drone.leg_cruise_time(config.drone_distances[from][customer], from, customer);
+            let inbound = drone.leg_cruise_time(config.drone_distances[customer][to], customer, to);
+
+            let pickup = arrival[i] + takeoff + outbound + landing;
+            let flight = 2.0f64.mul_add(takeoff + landing, outbound + inbound);
+            returns[sortie.rendezvous] = returns[sortie.rendezvous].max(arrival[i] + flight);
+            pickups.push((customer, pickup));
+
+            let energy = drone.leg_energy(0.0, outbound)
+                + drone.leg_energy(demand, inbound)
+                + drone.climb_energy(0.0, from, customer)
+                + drone.climb_energy(demand, customer, to);
+            energy_violation += (energy - drone.battery()).max(0.0) / drone.battery();
+            fixed_time_violation += (flight - drone.fixed_time()).max(0.0) / drone.fixed_time();
+        }
+    }
+
+    let working_time = arrival[customers.len() - 1];
+    let mut waiting_time_violation = 0.0;
+    for i in 1..customers.len() - 1 {
+        waiting_time_violation += (working_time - arrival[i] - config.waiting_time_limits[customers[i]]).max(0.0);
+    }
+    for &(customer, pickup) in &pickups {
+        waiting_time_violation += (working_time - pickup - config.waiting_time_limits[customer]).max(0.0);
+    }
+
+    let weight = route.data().weight() + sorties.iter().map(|s| config.demands[s.customer]).sum::<f64>();
+    SynchronizedMetrics {
+        pickups: sorties
+            .iter()
+            .map(|s| pickups.iter().find(|&&(c, _)| c == s.customer).map_or(0.0, |&(_, t)| t))
+            .collect(),
+        arrival,
+        working_time,
+        capacity_violation: (weight - config.truck.capacity).max(0.0),
+        waiting_time_violation,
+        energy_violation,
+        fixed_time_violation,
+    }
+}
+
+/// Greedily move customers served by depot drones to sorties of the drones carried by trucks, as long as each move
+/// yields a feasible solution with a lower cost. Truck routes are kept unchanged.
+pub fn improve(solution: &Solution) -> Solution {
+    let mut best = solution.clone();
+    loop {
+        let mut candidate: Option<Solution> = None;
+        for (drone, routes) in best.drone_routes.iter().enumerate() {
+            for (route_idx, route) in routes.iter().enumerate() {
+                let customers = &route.data().customers;
+                for &customer in &customers[1..customers.len() - 1] {
+                    let mut drone_routes = best.drone_routes.clone();
+                    let remaining = customers
+                        .iter()
+                        .copied()
+                        .filter(|&c| c != customer)
+                        .collect::<Vec<usize>>();
+                    if remaining.len() > 2 {
+                        drone_routes[drone][route_idx] = DroneRoute::new(remaining);
+                    } else {
+                        drone_routes[drone].remove(route_idx);
+                    }
+
+                    for (truck, truck_routes) in best.truck_routes.iter().enumerate() {
+                        for (truck_route_idx, truck_route) in truck_routes.iter().enumerate() {
+                            let truck_customers = &truck_route.data().customers;
+                            let length = truck_customers.len();
+                            for launch in 0..length - 1 {
+                                for rendezvous in launch + 1..length.min(launch + MAX_SORTIE_SPAN + 1) {
+                                    let at = (truck, truck_route_idx, truck_customers.as_slice());
+                                    if !_available(&best.sorties, at, launch, rendezvous, customer) {
+                                        continue;
+                                    }
+
+                                    let mut sorties = best.sorties.clone();
+                                    sorties.push(Sortie {
+                                        truck,
+                                        route: truck_route_idx,
+                                        launch,
+                                        customer,
+                                        rendezvous,
+                                        class: CONFIG.load().drone_class_of(DroneId(drone)),
+                                    });
+
+                                    let s = Solution::with_sorties(
+                                        best.truck_routes.clone(),
+                                        drone_routes.clone(),
+                                        sorties,
+                                    );
+                                    let bound = candidate.as_ref().map_or(best.cost(), Solution::cost);
+                                    if s.feasible && s.cost() < bound {
+                                        candidate = Some(s);
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        match candidate {
+            Some(s) => best = s,
+            None => return best,
+        }
+    }
+}
+
+/// Whether a sortie serving `customer` between positions `launch` and `rendezvous` of route `route` of `truck`
+/// (visiting `customers`) neither overlaps the existing sorties of that route nor flies through a no-fly zone.
+fn _available(
+    sorties: &[Sortie],
+    (truck, route, customers): (usize, usize, &[usize]),
+    launch: usize,
+    rendezvous: usize,
+    customer: usize,
+) -> bool {
+    let config = CONFIG.load();
+    let overlapping = sorties
+        .iter()
+        .any(|s| s.truck == truck && s.route == route && launch < s.rendezvous && s.launch < rendezvous);
+    let no_fly = !config.no_fly.is_empty()
+        && (config.no_fly[customers[launch]][customer] || config.no_fly[customer][customers[rendezvous]]);
+
+    !overlapping && !no_fly && config.dronable[customer]
+}
+
+/// Sorties attached to route `route` of `truck`
+pub fn route_sorties(sorties: &[Sortie], truck: usize, route: usize) -> Vec<&Sortie> {
+    sorties
+        .iter()
+        .filter(|s| s.truck == truck && s.route == route)
+        .collect()
+}
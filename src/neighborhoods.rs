@@ -1,10 +1,30 @@
+use std::cell::Cell;
 use std::fmt::{self, Display};
-use std::ptr;
+use std::ops::ControlFlow;
 use std::rc::Rc;
+use std::{iter, mem, ptr};
 
+use rand::Rng;
+use rand::rngs::StdRng;
+
+use crate::cli::{Objective, SearchScope, TieBreak};
+use crate::config::CONFIG;
+use crate::ids::{CustomerId, DroneId, TruckId, VehicleId};
 use crate::routes::{AnyRoute, DroneRoute, Route, TruckRoute};
 use crate::solutions::Solution;
 
+thread_local! {
+    /// Number of candidate moves rejected by the tabu lists since the last call to `take_tabu_blocked`, per thread
+    /// like the penalty coefficients
+    static TABU_BLOCKED: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Number of candidate moves that would have been the best of their neighborhood search but were rejected by the tabu
+/// list, since the previous call
+pub fn take_tabu_blocked() -> usize {
+    TABU_BLOCKED.replace(0)
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Neighborhood {
     Move10,
@@ -51,44 +71,173 @@ struct _IterationState<'a> {
     pub min_cost: &'a mut f64,
     pub require_feasible: &'a mut bool,
     pub result: &'a mut (Solution, Vec<usize>),
+    pub rng: &'a mut StdRng,
 }
 
 impl Neighborhood {
-    fn _find_decisive_vehicle(solution: &Solution) -> (usize, bool) {
+    /// Whether this neighborhood prices its neighbors assuming symmetric distances, which excludes it from the
+    /// neighborhoods selected on asymmetric instances (see `Config::asymmetric` and `selectable_neighborhoods`). Every
+    /// neighborhood currently prices its neighbors from scratch, the segments reversed by 2-opt included.
+    pub fn assumes_symmetric(self) -> bool {
+        match self {
+            Self::Move10
+            | Self::Move11
+            | Self::Move20
+            | Self::Move21
+            | Self::Move22
+            | Self::TwoOpt
+            | Self::EjectionChain => false,
+        }
+    }
+
+    /// Find the vehicle contributing the most to the objective: the drone consuming the most energy under the
+    /// "total-energy" objective, the vehicle with the longest working time otherwise.
+    fn _find_decisive_vehicle(solution: &Solution) -> VehicleId {
+        if CONFIG.load().objective == Objective::TotalEnergy {
+            let mut max_energy = 0.0;
+            let mut result = None;
+            for (drone, routes) in solution.drone_routes.iter().enumerate() {
+                let energy = routes.iter().map(|r| r.metrics(DroneId(drone)).energy).sum::<f64>();
+                if energy > max_energy {
+                    max_energy = energy;
+                    result = Some(VehicleId::Drone(DroneId(drone)));
+                }
+            }
+
+            if let Some(result) = result {
+                return result;
+            }
+        }
+
         let mut max_time = f64::MIN;
-        let mut vehicle = 0;
-        let mut is_truck = true;
+        let mut vehicle = VehicleId::Truck(TruckId(0));
 
         for (truck, &time) in solution.truck_working_time.iter().enumerate() {
             if time > max_time {
                 max_time = time;
-                vehicle = truck;
-                is_truck = true;
+                vehicle = VehicleId::Truck(TruckId(truck));
             }
         }
 
         for (drone, &time) in solution.drone_working_time.iter().enumerate() {
             if time > max_time {
                 max_time = time;
-                vehicle = drone;
-                is_truck = false;
+                vehicle = VehicleId::Drone(DroneId(drone));
+            }
+        }
+
+        vehicle
+    }
+
+    /// Vehicles whose routes are searched according to `--search-scope`: the decisive vehicle first, followed by the
+    /// other vehicles by decreasing working time.
+    fn _search_vehicles(solution: &Solution) -> Vec<VehicleId> {
+        let config = CONFIG.load();
+        let decisive = Self::_find_decisive_vehicle(solution);
+        let count = match config.search_scope {
+            SearchScope::Decisive => return vec![decisive],
+            SearchScope::TopK => config.search_top_k,
+            SearchScope::All => usize::MAX,
+        };
+
+        let mut others = (0..solution.truck_routes.len())
+            .map(|truck| (VehicleId::Truck(TruckId(truck)), solution.truck_working_time[truck]))
+            .chain(
+                (0..solution.drone_routes.len())
+                    .map(|drone| (VehicleId::Drone(DroneId(drone)), solution.drone_working_time[drone])),
+            )
+            .filter(|&(vehicle, _)| vehicle != decisive)
+            .collect::<Vec<_>>();
+        others.sort_by(|(_, f), (_, s)| s.total_cmp(f));
+
+        iter::once(decisive)
+            .chain(others.into_iter().map(|(vehicle, _)| vehicle))
+            .take(count)
+            .collect()
+    }
+
+    /// Find the non-empty vehicle with the least working time, if any.
+    fn _find_least_loaded_vehicle(solution: &Solution) -> Option<VehicleId> {
+        let mut min_time = f64::MAX;
+        let mut result = None;
+
+        for (truck, &time) in solution.truck_working_time.iter().enumerate() {
+            if !solution.truck_routes[truck].is_empty() && time < min_time {
+                min_time = time;
+                result = Some(VehicleId::Truck(TruckId(truck)));
             }
         }
 
-        (vehicle, is_truck)
+        for (drone, &time) in solution.drone_working_time.iter().enumerate() {
+            if !solution.drone_routes[drone].is_empty() && time < min_time {
+                min_time = time;
+                result = Some(VehicleId::Drone(DroneId(drone)));
+            }
+        }
+
+        result
     }
 
-    fn _internal_update(state: &mut _IterationState, solution: &Solution, tabu: &Vec<usize>) -> bool {
+    /// Whether `candidate` is preferred to `incumbent`, a neighbor of exactly the same cost, under `--tie-break`
+    fn _wins_tie(candidate: &Solution, incumbent: &Solution) -> bool {
+        fn _routes(solution: &Solution) -> Vec<Vec<&[usize]>> {
+            let trucks = solution
+                .truck_routes
+                .iter()
+                .map(|routes| routes.iter().map(|r| r.data().customers.as_slice()).collect());
+            let drones = solution
+                .drone_routes
+                .iter()
+                .map(|routes| routes.iter().map(|r| r.data().customers.as_slice()).collect());
+            trucks.chain(drones).collect()
+        }
+
+        fn _hash(solution: &Solution) -> u64 {
+            let mut hash = CONFIG.load().tie_break_seed;
+            let mut mix = |value: u64| {
+                hash = (hash ^ value).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+                hash ^= hash >> 31;
+            };
+            for routes in _routes(solution) {
+                // Separate the vehicles, the depot already separates the routes
+                mix(u64::MAX);
+                for &customer in routes.into_iter().flatten() {
+                    mix(customer as u64);
+                }
+            }
+
+            hash
+        }
+
+        match CONFIG.load().tie_break {
+            TieBreak::First => false,
+            TieBreak::Lexicographic => _routes(candidate) < _routes(incumbent),
+            TieBreak::Random => _hash(candidate) < _hash(incumbent),
+        }
+    }
+
+    fn _internal_update(state: &mut _IterationState, solution: &Solution, tabu: &[usize]) -> bool {
+        let config = CONFIG.load();
         let feasible = solution.feasible;
         if *state.require_feasible && !feasible {
             return false;
         }
+        if !config.pins.respected(solution) {
+            return false;
+        }
 
         let cost = solution.cost();
         let new_best_global_solution = cost < *state.aspiration_cost && feasible;
-        if new_best_global_solution || (!state.tabu_list.contains(tabu) && cost < *state.min_cost) {
+        let improving =
+            cost < *state.min_cost || (cost == *state.min_cost && Self::_wins_tie(solution, &state.result.0));
+        // With --tabu-acceptance, an improving tabu move is occasionally admitted anyway
+        let mut admissible = || {
+            !state.tabu_list.iter().any(|t| t == tabu)
+                || (config.tabu_acceptance > 0.0 && state.rng.random_bool(config.tabu_acceptance))
+        };
+        if new_best_global_solution || (improving && admissible()) {
             *state.min_cost = cost;
-            *state.result = (solution.clone(), tabu.clone());
+            *state.result = (solution.clone(), tabu.to_vec());
             if new_best_global_solution {
                 *state.aspiration_cost = cost;
                 *state.require_feasible = true;
@@ -97,6 +246,10 @@ impl Neighborhood {
             return true;
         }
 
+        if cost < *state.min_cost {
+            TABU_BLOCKED.set(TABU_BLOCKED.get() + 1);
+        }
+
         false
     }
 
@@ -130,35 +283,24 @@ impl Neighborhood {
             for (vehicle_j, routes_j) in original_routes_j.iter().enumerate() {
                 for (route_idx_j, route_j) in routes_j.iter().enumerate() {
                     // Dirty trick to compare 2 routes (because each customer can only be served exactly once)
-                    if route_i.data().customers[1] == route_j.data().customers[1] {
+                    if route_i.data().customers[1] == route_j.data().customers[1]
+                        || CONFIG.load().pins.route(&route_j.data().customers)
+                    {
                         continue;
                     }
 
-                    let mut neighbors = route_i.inter_route(route_j.clone(), neighborhood);
-                    let asymmetric = neighborhood == Neighborhood::Move10
-                        || neighborhood == Neighborhood::Move20
-                        || neighborhood == Neighborhood::Move21;
-                    if asymmetric {
-                        neighbors.extend(
-                            route_j
-                                .inter_route(route_i.clone(), neighborhood)
-                                .into_iter()
-                                .map(|t| (t.1, t.0, t.2)),
-                        );
-                    }
-
-                    for (new_route_i, new_route_j, tabu) in neighbors {
+                    let mut visit = |new_route_i: Option<Rc<RI>>, new_route_j: Option<Rc<RJ>>, tabu: &[usize]| {
                         if let Some(ref new_route_i) = new_route_i
                             && RI::single_customer()
                             && new_route_i.data().customers.len() != 3
                         {
-                            continue;
+                            return ControlFlow::Continue(());
                         }
                         if let Some(ref new_route_j) = new_route_j
                             && RJ::single_customer()
                             && new_route_j.data().customers.len() != 3
                         {
-                            continue;
+                            return ControlFlow::Continue(());
                         }
 
                         // Temporary assign new routes.
@@ -197,9 +339,9 @@ impl Neighborhood {
 
                         // Construct the new solution: move `truck_cloned` and `drone_cloned` to the temp solution
                         // and get them back later during restoration
-                        let s = Solution::new(truck_cloned, drone_cloned);
+                        let s = Solution::new(mem::take(&mut truck_cloned), mem::take(&mut drone_cloned));
 
-                        Neighborhood::_internal_update(state, &s, &tabu);
+                        Neighborhood::_internal_update(state, &s, tabu);
 
                         // Restore old routes
                         truck_cloned = s.truck_routes;
@@ -232,6 +374,19 @@ impl Neighborhood {
                                 }
                             }
                         }
+
+                        ControlFlow::Continue(())
+                    };
+
+                    // Every neighbor is evaluated, `visit` never stops the enumeration
+                    let _ = route_i.inter_route(route_j.clone(), neighborhood, &mut visit);
+                    let asymmetric = neighborhood == Neighborhood::Move10
+                        || neighborhood == Neighborhood::Move20
+                        || neighborhood == Neighborhood::Move21;
+                    if asymmetric {
+                        let _ = route_j.inter_route(route_i.clone(), neighborhood, |new_route_j, new_route_i, tabu| {
+                            visit(new_route_i, new_route_j, tabu)
+                        });
                     }
                 }
             }
@@ -241,6 +396,10 @@ impl Neighborhood {
 
         let original_routes_i = RI::get_correct_route(&state.original.truck_routes, &state.original.drone_routes);
         for (route_idx_i, route_i) in original_routes_i[vehicle_i].iter().enumerate() {
+            if CONFIG.load().pins.route(&route_i.data().customers) {
+                continue;
+            }
+
             (truck_cloned, drone_cloned) = iterate_route_j::<RI, TruckRoute>(
                 self,
                 state,
@@ -330,6 +489,10 @@ impl Neighborhood {
 
         let original_routes_i = RI::get_correct_route(&state.original.truck_routes, &state.original.drone_routes);
         for (route_idx_i, route_i) in original_routes_i[vehicle_i].iter().enumerate() {
+            if CONFIG.load().pins.route(&route_i.data().customers) {
+                continue;
+            }
+
             (truck_cloned, drone_cloned) = iterate_route_j_append::<RI, TruckRoute>(
                 self,
                 state,
@@ -353,6 +516,246 @@ impl Neighborhood {
         (truck_cloned, drone_cloned)
     }
 
+    /// Relocate each whole route of drone `drone_i` to every drone of a different class. This is only useful for
+    /// heterogeneous fleets, since a route is evaluated against the parameters of the drone performing it.
+    fn _drone_class_internal(
+        state: &mut _IterationState,
+        mut truck_cloned: Vec<Vec<Rc<TruckRoute>>>,
+        mut drone_cloned: Vec<Vec<Rc<DroneRoute>>>,
+        drone_i: usize,
+    ) -> (Vec<Vec<Rc<TruckRoute>>>, Vec<Vec<Rc<DroneRoute>>>) {
+        let config = CONFIG.load();
+        for (route_idx_i, route_i) in state.original.drone_routes[drone_i].iter().enumerate() {
+            let customers = &route_i.data().customers;
+            let tabu = customers[1..customers.len() - 1].to_vec();
+
+            for drone_j in 0..drone_cloned.len() {
+                if config.drone_class_of(DroneId(drone_j)) == config.drone_class_of(DroneId(drone_i)) {
+                    continue;
+                }
+
+                let route = drone_cloned[drone_i].swap_remove(route_idx_i);
+                drone_cloned[drone_j].push(route);
+
+                let s = Solution::new(truck_cloned, drone_cloned);
+
+                Self::_internal_update(state, &s, &tabu);
+
+                // Restore old routes
+                truck_cloned = s.truck_routes;
+                drone_cloned = s.drone_routes;
+
+                let route = drone_cloned[drone_j].pop().unwrap();
+                _swap_push(&mut drone_cloned[drone_i], route_idx_i, route);
+            }
+        }
+
+        (truck_cloned, drone_cloned)
+    }
+
+    /// Split and merge the visits of split customers served by `vehicle_i`: a visit next to its twin is moved to a new
+    /// route of each drone, and a visit apart from its twin is moved right after it.
+    fn _split_merge_internal<RI>(state: &mut _IterationState, vehicle_i: usize)
+    where
+        RI: Route,
+    {
+        fn _set_route<T>(vehicle_routes: &mut [Vec<Rc<T>>], vehicle: usize, route_idx: usize, customers: Vec<usize>)
+        where
+            T: Route,
+        {
+            if customers.len() > 2 {
+                vehicle_routes[vehicle][route_idx] = T::new(customers);
+            } else {
+                vehicle_routes[vehicle].remove(route_idx);
+            }
+        }
+
+        fn _merge<T>(vehicle_routes: &mut [Vec<Rc<T>>], customer: usize, twin: usize) -> bool
+        where
+            T: Route,
+        {
+            for route in vehicle_routes.iter_mut().flatten() {
+                let customers = &route.data().customers;
+                if let Some(position) = customers.iter().position(|&c| c == twin) {
+                    if T::single_customer() {
+                        return false;
+                    }
+
+                    let mut customers = customers.clone();
+                    customers.insert(position + 1, customer);
+                    *route = T::new(customers);
+                    return true;
+                }
+            }
+
+            false
+        }
+
+        let original_routes_i = RI::get_correct_route(&state.original.truck_routes, &state.original.drone_routes);
+        for (route_idx_i, route_i) in original_routes_i[vehicle_i].iter().enumerate() {
+            let customers = &route_i.data().customers;
+            for position in 1..customers.len() - 1 {
+                let customer = customers[position];
+                let Some(twin) = CONFIG.load().twin(customer) else {
+                    continue;
+                };
+
+                let mut truck_cloned = state.original.truck_routes.clone();
+                let mut drone_cloned = state.original.drone_routes.clone();
+
+                let mut remaining = customers.clone();
+                remaining.remove(position);
+                _set_route(
+                    RI::get_correct_route_mut(&mut truck_cloned, &mut drone_cloned),
+                    vehicle_i,
+                    route_idx_i,
+                    remaining,
+                );
+
+                if customers[position - 1] == twin || customers[position + 1] == twin {
+                    if !CONFIG.load().dronable[customer] {
+                        continue;
+                    }
+
+                    for drone in 0..drone_cloned.len() {
+                        if DroneRoute::single_route() && !drone_cloned[drone].is_empty() {
+                            continue;
+                        }
+
+                        let mut drone_split = drone_cloned.clone();
+                        drone_split[drone].push(DroneRoute::single(CustomerId(customer)));
+
+                        let s = Solution::new(truck_cloned.clone(), drone_split);
+                        Self::_internal_update(state, &s, &[customer]);
+                    }
+                } else if _merge(&mut truck_cloned, customer, twin) || _merge(&mut drone_cloned, customer, twin) {
+                    let s = Solution::new(truck_cloned, drone_cloned);
+                    Self::_internal_update(state, &s, &[customer]);
+                }
+            }
+        }
+    }
+
+    /// Move the longest unpinned route of `vehicle` to each empty vehicle of the same type, which no other operator
+    /// does since they only feed vehicles with routes or extract parts of routes.
+    fn _activate_vehicle_internal(
+        state: &mut _IterationState,
+        truck_cloned: Vec<Vec<Rc<TruckRoute>>>,
+        drone_cloned: Vec<Vec<Rc<DroneRoute>>>,
+        vehicle: VehicleId,
+    ) -> (Vec<Vec<Rc<TruckRoute>>>, Vec<Vec<Rc<DroneRoute>>>) {
+        fn _activate<T>(
+            state: &mut _IterationState,
+            mut truck_cloned: Vec<Vec<Rc<TruckRoute>>>,
+            mut drone_cloned: Vec<Vec<Rc<DroneRoute>>>,
+            vehicle: usize,
+        ) -> (Vec<Vec<Rc<TruckRoute>>>, Vec<Vec<Rc<DroneRoute>>>)
+        where
+            T: Route,
+        {
+            let original_routes = T::get_correct_route(&state.original.truck_routes, &state.original.drone_routes);
+            let longest = original_routes[vehicle]
+                .iter()
+                .enumerate()
+                .filter(|(_, route)| !route.data().customers.iter().any(|&c| CONFIG.load().pins.customer(c)))
+                .max_by(|(_, f), (_, s)| {
+                    f.vehicle_working_time(vehicle)
+                        .total_cmp(&s.vehicle_working_time(vehicle))
+                });
+            let Some((route_idx, route)) = longest else {
+                return (truck_cloned, drone_cloned);
+            };
+
+            let customers = &route.data().customers;
+            let tabu = customers[1..customers.len() - 1].to_vec();
+            for target in 0..original_routes.len() {
+                if !original_routes[target].is_empty() {
+                    continue;
+                }
+
+                {
+                    let cloned_routes = T::get_correct_route_mut(&mut truck_cloned, &mut drone_cloned);
+                    let route = cloned_routes[vehicle].swap_remove(route_idx);
+                    cloned_routes[target].push(route);
+                }
+
+                let s = Solution::new(truck_cloned, drone_cloned);
+
+                Neighborhood::_internal_update(state, &s, &tabu);
+
+                // Restore old routes
+                truck_cloned = s.truck_routes;
+                drone_cloned = s.drone_routes;
+
+                let cloned_routes = T::get_correct_route_mut(&mut truck_cloned, &mut drone_cloned);
+                let route = cloned_routes[target].pop().unwrap();
+                _swap_push(&mut cloned_routes[vehicle], route_idx, route);
+            }
+
+            (truck_cloned, drone_cloned)
+        }
+
+        match vehicle {
+            VehicleId::Truck(TruckId(truck)) => _activate::<TruckRoute>(state, truck_cloned, drone_cloned, truck),
+            VehicleId::Drone(DroneId(drone)) => _activate::<DroneRoute>(state, truck_cloned, drone_cloned, drone),
+        }
+    }
+
+    /// Move every route of `vehicle` to the other vehicles of the same type (longest route first, each to the
+    /// vehicle that would finish it the earliest), leaving `vehicle` empty.
+    fn _empty_vehicle_internal(
+        state: &mut _IterationState,
+        mut truck_cloned: Vec<Vec<Rc<TruckRoute>>>,
+        mut drone_cloned: Vec<Vec<Rc<DroneRoute>>>,
+        vehicle: VehicleId,
+    ) {
+        fn _redistribute<T>(vehicle_routes: &mut [Vec<Rc<T>>], working_time: &[f64], vehicle: usize) -> Vec<usize>
+        where
+            T: Route,
+        {
+            let mut routes = vehicle_routes[vehicle].drain(..).collect::<Vec<Rc<T>>>();
+            routes.sort_by(|f, s| {
+                f.vehicle_working_time(vehicle)
+                    .total_cmp(&s.vehicle_working_time(vehicle))
+                    .reverse()
+            });
+
+            let mut working_time = working_time.to_vec();
+            let mut tabu = vec![];
+            for route in routes {
+                let customers = &route.data().customers;
+                tabu.extend_from_slice(&customers[1..customers.len() - 1]);
+
+                let finish = |v: usize| working_time[v] + route.vehicle_working_time(v);
+                let target = (0..vehicle_routes.len())
+                    .filter(|&v| v != vehicle && (!T::single_route() || vehicle_routes[v].is_empty()))
+                    .min_by(|&a, &b| finish(a).total_cmp(&finish(b)));
+                let Some(target) = target else {
+                    return vec![];
+                };
+
+                working_time[target] += route.vehicle_working_time(target);
+                vehicle_routes[target].push(route);
+            }
+
+            tabu
+        }
+
+        let tabu = match vehicle {
+            VehicleId::Truck(TruckId(truck)) => {
+                _redistribute(&mut truck_cloned, &state.original.truck_working_time, truck)
+            }
+            VehicleId::Drone(DroneId(drone)) => {
+                _redistribute(&mut drone_cloned, &state.original.drone_working_time, drone)
+            }
+        };
+
+        if !tabu.is_empty() {
+            let s = Solution::new(truck_cloned, drone_cloned);
+            Self::_internal_update(state, &s, &tabu);
+        }
+    }
+
     fn _ejection_chain_internal(self, state: &mut _IterationState) {
         #[derive(Clone)]
         struct _IndexingHelper {
@@ -369,50 +772,59 @@ impl Neighborhood {
                 }
             }
 
-            fn vehicle_index(&self, vehicle: usize) -> &Vec<AnyRoute> {
-                if vehicle < self.truck_routes.len() {
-                    &self.truck_routes[vehicle]
-                } else {
-                    &self.drone_routes[vehicle - self.truck_routes.len()]
+            fn vehicles(&self) -> Vec<VehicleId> {
+                (0..self.truck_routes.len())
+                    .map(|truck| VehicleId::Truck(TruckId(truck)))
+                    .chain((0..self.drone_routes.len()).map(|drone| VehicleId::Drone(DroneId(drone))))
+                    .collect()
+            }
+
+            fn vehicle_index(&self, vehicle: VehicleId) -> &Vec<AnyRoute> {
+                match vehicle {
+                    VehicleId::Truck(TruckId(truck)) => &self.truck_routes[truck],
+                    VehicleId::Drone(DroneId(drone)) => &self.drone_routes[drone],
                 }
             }
 
-            fn route_index(&self, vehicle: usize, route_idx: usize) -> &AnyRoute {
+            fn vehicle_index_mut(&mut self, vehicle: VehicleId) -> &mut Vec<AnyRoute> {
+                match vehicle {
+                    VehicleId::Truck(TruckId(truck)) => &mut self.truck_routes[truck],
+                    VehicleId::Drone(DroneId(drone)) => &mut self.drone_routes[drone],
+                }
+            }
+
+            fn route_index(&self, vehicle: VehicleId, route_idx: usize) -> &AnyRoute {
                 &self.vehicle_index(vehicle)[route_idx]
             }
 
             fn same_route(
                 &self,
-                first_vehicle: usize,
+                first_vehicle: VehicleId,
                 first_route: usize,
-                second_vehicle: usize,
+                second_vehicle: VehicleId,
                 second_route: usize,
             ) -> bool {
                 self.route_index(first_vehicle, first_route).customers()[1]
                     == self.route_index(second_vehicle, second_route).customers()[1]
             }
 
-            fn update(&mut self, vehicle: usize, route_idx: usize, new_route: AnyRoute) {
-                if vehicle < self.truck_routes.len() {
-                    self.truck_routes[vehicle][route_idx] = new_route;
-                } else {
-                    self.drone_routes[vehicle - self.truck_routes.len()][route_idx] = new_route;
-                }
+            fn update(&mut self, vehicle: VehicleId, route_idx: usize, new_route: AnyRoute) {
+                self.vehicle_index_mut(vehicle)[route_idx] = new_route;
             }
         }
 
         let mut indexer = _IndexingHelper::from_solution(state.original);
-        let total_vehicles = indexer.truck_routes.len() + indexer.drone_routes.len();
+        let vehicles = indexer.vehicles();
 
-        for vehicle_i in 0..total_vehicles {
+        for &vehicle_i in &vehicles {
             for route_idx_i in 0..indexer.vehicle_index(vehicle_i).len() {
-                for vehicle_j in 0..total_vehicles {
+                for &vehicle_j in &vehicles {
                     for route_idx_j in 0..indexer.vehicle_index(vehicle_j).len() {
                         if indexer.same_route(vehicle_i, route_idx_i, vehicle_j, route_idx_j) {
                             continue;
                         }
 
-                        for vehicle_k in 0..total_vehicles {
+                        for &vehicle_k in &vehicles {
                             for route_idx_k in 0..indexer.vehicle_index(vehicle_k).len() {
                                 if indexer.same_route(vehicle_j, route_idx_j, vehicle_k, route_idx_k) {
                                     continue;
@@ -441,12 +853,7 @@ impl Neighborhood {
                                             new_indexer.update(vehicle_i, route_idx_i, new_route_i.clone());
                                         }
                                         None => {
-                                            if vehicle_i < new_indexer.truck_routes.len() {
-                                                new_indexer.truck_routes[vehicle_i].swap_remove(route_idx_i);
-                                            } else {
-                                                new_indexer.drone_routes[vehicle_i - new_indexer.truck_routes.len()]
-                                                    .swap_remove(route_idx_i);
-                                            }
+                                            new_indexer.vehicle_index_mut(vehicle_i).swap_remove(route_idx_i);
                                         }
                                     }
 
@@ -480,8 +887,10 @@ impl Neighborhood {
         solution: &Solution,
         tabu_list: &[Vec<usize>],
         mut aspiration_cost: f64,
+        rng: &mut StdRng,
     ) -> (Solution, Vec<usize>) {
-        let (vehicle_i, is_truck) = Self::_find_decisive_vehicle(solution);
+        let config = CONFIG.load();
+        let vehicles = Self::_search_vehicles(solution);
 
         let mut truck_cloned = solution.truck_routes.clone();
         let mut drone_cloned = solution.drone_routes.clone();
@@ -497,6 +906,7 @@ impl Neighborhood {
             min_cost: &mut min_cost,
             require_feasible: &mut require_feasible,
             result: &mut result,
+            rng,
         };
 
         match self {
@@ -508,16 +918,49 @@ impl Neighborhood {
             | Self::TwoOpt
             // | Self::CrossExchange
             => {
-                (truck_cloned, drone_cloned) = if is_truck {
-                    self._inter_route_internal::<TruckRoute>(&mut state, truck_cloned, drone_cloned, vehicle_i)
-                } else {
-                    self._inter_route_internal::<DroneRoute>(&mut state, truck_cloned, drone_cloned, vehicle_i)
-                };
+                for &vehicle in &vehicles {
+                    match vehicle {
+                        VehicleId::Truck(TruckId(truck)) => {
+                            (truck_cloned, drone_cloned) =
+                                self._inter_route_internal::<TruckRoute>(&mut state, truck_cloned, drone_cloned, truck);
+                            (truck_cloned, drone_cloned) =
+                                self._inter_route_extract_internal::<TruckRoute>(&mut state, truck_cloned, drone_cloned, truck);
+
+                            if config.allow_split && self == Self::Move10 {
+                                Self::_split_merge_internal::<TruckRoute>(&mut state, truck);
+                            }
+                        }
+                        VehicleId::Drone(DroneId(drone)) => {
+                            (truck_cloned, drone_cloned) =
+                                self._inter_route_internal::<DroneRoute>(&mut state, truck_cloned, drone_cloned, drone);
+                            (truck_cloned, drone_cloned) =
+                                self._inter_route_extract_internal::<DroneRoute>(&mut state, truck_cloned, drone_cloned, drone);
+
+                            // Reassigning routes to a different drone class is part of the relocation neighborhood
+                            if self == Self::Move10 && config.drone_classes.len() > 1 {
+                                (truck_cloned, drone_cloned) =
+                                    Self::_drone_class_internal(&mut state, truck_cloned, drone_cloned, drone);
+                            }
+
+                            if config.allow_split && self == Self::Move10 {
+                                Self::_split_merge_internal::<DroneRoute>(&mut state, drone);
+                            }
+                        }
+                    }
+                }
+
+                // Hand the longest route of the decisive vehicle over to an idle vehicle
+                if self == Self::Move10 {
+                    (truck_cloned, drone_cloned) =
+                        Self::_activate_vehicle_internal(&mut state, truck_cloned, drone_cloned, vehicles[0]);
+                }
 
-                if is_truck {
-                    self._inter_route_extract_internal::<TruckRoute>(&mut state, truck_cloned, drone_cloned, vehicle_i);
-                } else {
-                    self._inter_route_extract_internal::<DroneRoute>(&mut state, truck_cloned, drone_cloned, vehicle_i);
+                // Bias toward emptying the least-loaded vehicle when each used vehicle is penalized
+                if config.vehicle_cost > 0.0
+                    && self == Self::Move10
+                    && let Some(least_loaded) = Self::_find_least_loaded_vehicle(solution)
+                {
+                    Self::_empty_vehicle_internal(&mut state, truck_cloned, drone_cloned, least_loaded);
                 }
             }
 
@@ -534,13 +977,14 @@ impl Neighborhood {
         solution: &Solution,
         tabu_list: &[Vec<usize>],
         mut aspiration_cost: f64,
+        rng: &mut StdRng,
     ) -> (Solution, Vec<usize>) {
         let mut result = (solution.clone(), vec![]);
         if let Self::EjectionChain = self {
             return result;
         }
 
-        let (vehicle, is_truck) = Self::_find_decisive_vehicle(solution);
+        let vehicles = Self::_search_vehicles(solution);
 
         let mut truck_cloned = solution.truck_routes.clone();
         let mut drone_cloned = solution.drone_routes.clone();
@@ -555,34 +999,45 @@ impl Neighborhood {
             min_cost: &mut min_cost,
             require_feasible: &mut require_feasible,
             result: &mut result,
+            rng,
         };
 
         macro_rules! search_route {
-            ($original_routes:expr, $cloned_routes:expr) => {
-                for (i, route) in $original_routes[vehicle].iter().enumerate() {
-                    for (new_route, tabu) in route.intra_route(self).iter() {
+            ($original_routes:expr, $cloned_routes:expr, $vehicle:expr, $id:expr) => {
+                for (i, route) in $original_routes[$vehicle].iter().enumerate() {
+                    if CONFIG.load().pins.route(&route.data().customers) {
+                        continue;
+                    }
+
+                    let _ = route.intra_route(self, |new_route, tabu| {
                         // Temporary assign new route
-                        $cloned_routes[vehicle][i] = new_route.clone();
+                        $cloned_routes[$vehicle][i] = new_route;
 
                         // Construct the new solution: move `truck_cloned` and `drone_cloned` to the temp solution
                         // and get them back later during restoration
-                        let s = Solution::new(truck_cloned, drone_cloned);
+                        let s = solution.with_changed_vehicle(
+                            mem::take(&mut truck_cloned),
+                            mem::take(&mut drone_cloned),
+                            $id,
+                        );
 
-                        Self::_internal_update(&mut state, &s, &tabu);
+                        Self::_internal_update(&mut state, &s, tabu);
 
                         // Restore old route
                         truck_cloned = s.truck_routes;
                         drone_cloned = s.drone_routes;
-                        $cloned_routes[vehicle][i] = route.clone();
-                    }
+                        $cloned_routes[$vehicle][i] = route.clone();
+                        ControlFlow::Continue(())
+                    });
                 }
             };
         }
 
-        if is_truck {
-            search_route!(solution.truck_routes, truck_cloned);
-        } else {
-            search_route!(solution.drone_routes, drone_cloned);
+        for vehicle in vehicles {
+            match vehicle {
+                VehicleId::Truck(TruckId(truck)) => search_route!(solution.truck_routes, truck_cloned, truck, vehicle),
+                VehicleId::Drone(DroneId(drone)) => search_route!(solution.drone_routes, drone_cloned, drone, vehicle),
+            }
         }
 
         result
@@ -594,16 +1049,19 @@ impl Neighborhood {
         tabu_list: &mut Vec<Vec<usize>>,
         tabu_size: usize,
         aspiration_cost: f64,
+        rng: &mut StdRng,
     ) -> Option<Solution> {
-        let intra = self.intra_route(solution, tabu_list, aspiration_cost);
-        let inter = self.inter_route(solution, tabu_list, aspiration_cost);
+        let intra = self.intra_route(solution, tabu_list, aspiration_cost, rng);
+        let inter = self.inter_route(solution, tabu_list, aspiration_cost, rng);
 
         #[allow(clippy::if_same_then_else)]
         let (result, mut tabu) = if intra.1.is_empty() {
             inter // Intra-route neighborhood is empty
         } else if inter.1.is_empty() {
             intra // Inter-route neighborhood is empty
-        } else if intra.0.cost() < inter.0.cost() {
+        } else if intra.0.cost() < inter.0.cost()
+            || (intra.0.cost() == inter.0.cost() && Self::_wins_tie(&intra.0, &inter.0))
+        {
             intra
         } else {
             inter
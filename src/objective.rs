@@ -0,0 +1,54 @@
+use std::fmt;
+
+use crate::config::CONFIG;
+use crate::solutions::{Solution, penalty_coeffs};
+
+/// Cost minimized by the tabu search, selected by `Config::cost_function`. `Solution::cost` memoizes it until the
+/// penalty coefficients change, so it may depend only on the solution, the config and the penalty coefficients
+/// (see `penalty_coeffs`).
+pub trait CostFunction: fmt::Debug + Send + Sync {
+    fn evaluate(&self, s: &Solution) -> f64;
+}
+
+/// The objective selected by `--objective`, multiplied by a penalty that grows with the weighted violations, plus the
+/// cost of the vehicles used:
+///
+/// Cost(S) = max([objective], 1) * (1 + [weighted penalty values]).powf(E) + [--vehicle-cost] * [vehicles used]
+///
+/// The objective is bounded below so that the penalty still ranks the solutions whose objective is 0 (e.g. the total
+/// energy of a solution without drone routes).
+#[derive(Debug)]
+pub struct PenalizedObjective;
+
+impl CostFunction for PenalizedObjective {
+    fn evaluate(&self, s: &Solution) -> f64 {
+        let config = CONFIG.load();
+        let coefficients = penalty_coeffs();
+        let penalty = coefficients[6]
+            .mul_add(
+                s.no_fly_violation,
+                coefficients[5].mul_add(
+                    s.assignment_violation,
+                    coefficients[4].mul_add(
+                        s.depot_slot_violation,
+                        coefficients[3].mul_add(
+                            s.fixed_time_violation,
+                            coefficients[2].mul_add(
+                                s.waiting_time_violation,
+                                coefficients[1]
+                                    .mul_add(s.capacity_violation, coefficients[0].mul_add(s.energy_violation, 1.0)),
+                            ),
+                        ),
+                    ),
+                ),
+            )
+            .powf(config.penalty_exponent);
+
+        let objective = s.objective().max(1.0);
+        if config.vehicle_cost == 0.0 {
+            objective * penalty
+        } else {
+            objective.mul_add(penalty, config.vehicle_cost * s.vehicles_used() as f64)
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use std::path::Path;
+use std::{env, fs};
+
+use clap::builder::BoolishValueParser;
+use clap::parser::ValueSource;
+use clap::{ArgAction, Command, CommandFactory, FromArgMatches};
+use serde_json::{Map, Value};
+
+use crate::cli::Arguments;
+
+/// Prefix of the environment variables setting the options of the `run` subcommand
+const ENV_PREFIX: &str = "MTMV_";
+
+/// Load the parameter file at `path` (TOML, YAML or JSON, according to its extension) as a map of parameter names to
+/// values.
+fn _load(path: &str) -> Map<String, Value> {
+    let data = fs::read_to_string(path).unwrap();
+    let value = match Path::new(path).extension().and_then(|e| e.to_str()) {
+        Some("toml") => toml::from_str::<Value>(&data).unwrap(),
+        Some("yaml" | "yml") => serde_yaml::from_str::<Value>(&data).unwrap(),
+        Some("json") => serde_json::from_str::<Value>(&data).unwrap(),
+        _ => panic!("Unsupported parameter file {path:?}, expected a .toml, .yaml or .json file"),
+    };
+
+    match value {
+        Value::Object(parameters) => parameters,
+        _ => panic!("The parameter file {path:?} must contain a table of parameters"),
+    }
+}
+
+fn _scalar(key: &str, value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Number(n) => n.to_string(),
+        Value::Bool(b) => b.to_string(),
+        _ => panic!("Invalid value {value} of parameter {key:?}"),
+    }
+}
+
+/// Subcommands taking the arguments of `run`
+const RUN_SUBCOMMANDS: [&str; 2] = ["run", "explain-config"];
+
+/// The command line interface, where every option of the `run` subcommand can also be set with an environment
+/// variable named after it (e.g. `MTMV_TABU_SIZE_FACTOR` for `--tabu-size-factor`).
+fn _command() -> Command {
+    let mut command = Arguments::command();
+    for subcommand in RUN_SUBCOMMANDS {
+        command = command.mut_subcommand(subcommand, |run| {
+            run.mut_args(|arg| {
+                let name = format!("{ENV_PREFIX}{}", arg.get_id().as_str().to_uppercase());
+                match arg.get_action() {
+                    // Accept 1/0, yes/no and on/off as well in the environment
+                    ArgAction::SetTrue => arg.env(name).value_parser(BoolishValueParser::new()),
+                    _ => arg.env(name),
+                }
+            })
+        });
+    }
+
+    command
+}
+
+fn _parse_from(args: Vec<String>) -> Arguments {
+    Arguments::from_arg_matches(&_command().get_matches_from(args)).unwrap_or_else(|e| e.exit())
+}
+
+/// Parse the command line arguments and the `MTMV_*` environment variables. The parameters of the `--params` file of
+/// the `run` subcommand are inserted before the other arguments, except those also given on the command line or in
+/// the environment which take precedence.
+pub fn parse() -> Arguments {
+    let args = env::args().collect::<Vec<String>>();
+    let command = _command();
+    let matches = command.clone().get_matches_from(&args);
+
+    let Some((subcommand, run)) = matches.subcommand().filter(|(name, _)| RUN_SUBCOMMANDS.contains(name)) else {
+        return _parse_from(args);
+    };
+    let Some(path) = run.get_one::<String>("params") else {
+        return _parse_from(args);
+    };
+
+    let run_command = command.find_subcommand(subcommand).unwrap();
+    let mut inserted = vec![];
+    for (key, value) in _load(path) {
+        let id = key.replace('-', "_");
+        let arg = run_command
+            .get_arguments()
+            .find(|a| a.get_id() == id.as_str() && a.get_long().is_some() && id != "params")
+            .unwrap_or_else(|| panic!("Unknown parameter {key:?} in {path:?}"));
+        if matches!(
+            run.value_source(&id),
+            Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+        ) {
+            continue;
+        }
+
+        let long = format!("--{}", arg.get_long().unwrap());
+        match (arg.get_action(), &value) {
+            (_, Value::Null) | (ArgAction::SetTrue, Value::Bool(false)) => {}
+            (ArgAction::SetTrue, Value::Bool(true)) => inserted.push(long),
+            (ArgAction::Count, Value::Number(n)) => {
+                let count = n
+                    .as_u64()
+                    .unwrap_or_else(|| panic!("Invalid value {value} of parameter {key:?}"));
+                for _ in 0..count {
+                    inserted.push(long.clone());
+                }
+            }
+            (ArgAction::Append, Value::Array(values)) => {
+                for value in values {
+                    inserted.push(format!("{long}={}", _scalar(&key, value)));
+                }
+            }
+            (ArgAction::Set | ArgAction::Append, _) => inserted.push(format!("{long}={}", _scalar(&key, &value))),
+            _ => panic!("Invalid value {value} of parameter {key:?}"),
+        }
+    }
+
+    // The subcommand name is the first argument
+    let mut merged = args[..2].to_vec();
+    merged.extend(inserted);
+    merged.extend_from_slice(&args[2..]);
+    _parse_from(merged)
+}
@@ -0,0 +1,84 @@
+use std::rc::Rc;
+
+use crate::config::CONFIG;
+use crate::solutions::Solution;
+
+/// Bounded archive of feasible solutions that are non-dominated with respect to (objective, total energy), where the
+/// objective is that of `--objective`
+pub struct ParetoArchive {
+    solutions: Vec<Rc<Solution>>,
+}
+
+impl ParetoArchive {
+    pub fn new() -> Self {
+        Self { solutions: vec![] }
+    }
+
+    fn _objectives(solution: &Solution) -> (f64, f64) {
+        (solution.objective(), solution.total_energy)
+    }
+
+    /// Whether `f` dominates `s`, i.e. `f` is no worse than `s` in both objectives and better in at least one.
+    fn _dominates(f: (f64, f64), s: (f64, f64)) -> bool {
+        f.0 <= s.0 && f.1 <= s.1 && (f.0 < s.0 || f.1 < s.1)
+    }
+
+    /// Add `solution` to the archive if it is feasible and not dominated by (or equal to) an archived solution,
+    /// evicting the archived solutions it dominates. When the archive exceeds `--pareto-archive` solutions, the
+    /// most crowded non-extreme solution is removed.
+    pub fn insert(&mut self, solution: &Rc<Solution>) -> bool {
+        if !solution.feasible {
+            return false;
+        }
+
+        let objectives = Self::_objectives(solution);
+        if self.solutions.iter().any(|s| {
+            let other = Self::_objectives(s);
+            other == objectives || Self::_dominates(other, objectives)
+        }) {
+            return false;
+        }
+
+        self.solutions
+            .retain(|s| !Self::_dominates(objectives, Self::_objectives(s)));
+        self.solutions.push(solution.clone());
+        self.solutions.sort_by(|f, s| f.objective().total_cmp(&s.objective()));
+
+        if self.solutions.len() > CONFIG.load().pareto_archive {
+            self._prune();
+        }
+
+        true
+    }
+
+    /// Remove the interior solution with the smallest crowding distance. Solutions are sorted by increasing objective,
+    /// hence by decreasing total energy.
+    fn _prune(&mut self) {
+        let n = self.solutions.len();
+        if n <= 2 {
+            self.solutions.truncate(CONFIG.load().pareto_archive);
+            return;
+        }
+
+        let (first, last) = (
+            Self::_objectives(&self.solutions[0]),
+            Self::_objectives(&self.solutions[n - 1]),
+        );
+        let objective_range = (last.0 - first.0).max(f64::EPSILON);
+        let energy_range = (first.1 - last.1).max(f64::EPSILON);
+
+        let crowding = |i: usize| {
+            let prev = Self::_objectives(&self.solutions[i - 1]);
+            let next = Self::_objectives(&self.solutions[i + 1]);
+            (next.0 - prev.0) / objective_range + (prev.1 - next.1) / energy_range
+        };
+
+        let idx = (1..n - 1).min_by(|&i, &j| crowding(i).total_cmp(&crowding(j))).unwrap();
+        self.solutions.remove(idx);
+    }
+
+    /// The archived solutions in increasing order of objective
+    pub fn front(&self) -> &[Rc<Solution>] {
+        &self.solutions
+    }
+}
@@ -0,0 +1,252 @@
+use std::error::Error;
+use std::fs::File;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use arrow_array::builder::{BooleanBuilder, Float64Builder, ListBuilder, StringBuilder, UInt64Builder};
+use arrow_array::{ArrayRef, RecordBatch};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::{Compression, GzipLevel, ZstdLevel};
+use parquet::file::properties::WriterProperties;
+
+use crate::cli::LogCompression;
+use crate::config::CONFIG;
+use crate::logger::IterationRecord;
+
+/// Number of iterations per row group of the Parquet file
+const ROW_GROUP_SIZE: usize = 10_000;
+
+type RoutesBuilder = ListBuilder<ListBuilder<ListBuilder<UInt64Builder>>>;
+
+/// Iteration log written as a Parquet file (see `--log-format`). Rows are buffered into column builders and written
+/// one row group at a time; only the columns present in the records are written.
+pub struct ParquetLog {
+    _path: PathBuf,
+    _writer: Option<ArrowWriter<File>>,
+    _rows: usize,
+
+    _iteration: UInt64Builder,
+    _cost: Option<Float64Builder>,
+    _working_time: Option<Float64Builder>,
+    _feasible: Option<BooleanBuilder>,
+    _penalty_coefficients: Option<ListBuilder<Float64Builder>>,
+    _energy_violation: Option<Float64Builder>,
+    _capacity_violation: Option<Float64Builder>,
+    _waiting_time_violation: Option<Float64Builder>,
+    _fixed_time_violation: Option<Float64Builder>,
+    _depot_slot_violation: Option<Float64Builder>,
+    _assignment_violation: Option<Float64Builder>,
+    _no_fly_violation: Option<Float64Builder>,
+    _truck_routes: Option<RoutesBuilder>,
+    _drone_routes: Option<RoutesBuilder>,
+    _truck_routes_count: Option<UInt64Builder>,
+    _drone_routes_count: Option<UInt64Builder>,
+    _neighborhood: Option<StringBuilder>,
+    _tabu_list: Option<ListBuilder<ListBuilder<UInt64Builder>>>,
+}
+
+fn _append_f64(builder: &mut Option<Float64Builder>, value: Option<f64>) {
+    if let Some(value) = value {
+        builder.get_or_insert_with(Float64Builder::new).append_value(value);
+    }
+}
+
+fn _append_u64(builder: &mut Option<UInt64Builder>, value: Option<usize>) {
+    if let Some(value) = value {
+        builder
+            .get_or_insert_with(UInt64Builder::new)
+            .append_value(value as u64);
+    }
+}
+
+fn _append_routes(builder: &mut Option<RoutesBuilder>, routes: Option<&[Vec<&Vec<usize>>]>) {
+    if let Some(routes) = routes {
+        let builder =
+            builder.get_or_insert_with(|| ListBuilder::new(ListBuilder::new(ListBuilder::new(UInt64Builder::new()))));
+        for vehicle in routes {
+            for route in vehicle {
+                let customers = builder.values().values();
+                for &customer in *route {
+                    customers.values().append_value(customer as u64);
+                }
+                customers.append(true);
+            }
+            builder.values().append(true);
+        }
+        builder.append(true);
+    }
+}
+
+impl ParquetLog {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            _path: path,
+            _writer: None,
+            _rows: 0,
+            _iteration: UInt64Builder::new(),
+            _cost: None,
+            _working_time: None,
+            _feasible: None,
+            _penalty_coefficients: None,
+            _energy_violation: None,
+            _capacity_violation: None,
+            _waiting_time_violation: None,
+            _fixed_time_violation: None,
+            _depot_slot_violation: None,
+            _assignment_violation: None,
+            _no_fly_violation: None,
+            _truck_routes: None,
+            _drone_routes: None,
+            _truck_routes_count: None,
+            _drone_routes_count: None,
+            _neighborhood: None,
+            _tabu_list: None,
+        }
+    }
+
+    pub fn append(&mut self, record: &IterationRecord) -> Result<(), Box<dyn Error>> {
+        self._iteration.append_value(record.iteration as u64);
+        _append_f64(&mut self._cost, record.cost);
+        _append_f64(&mut self._working_time, record.working_time);
+        if let Some(feasible) = record.feasible {
+            self._feasible
+                .get_or_insert_with(BooleanBuilder::new)
+                .append_value(feasible);
+        }
+        if let Some(penalty_coefficients) = record.penalty_coefficients {
+            let builder = self
+                ._penalty_coefficients
+                .get_or_insert_with(|| ListBuilder::new(Float64Builder::new()));
+            builder.values().append_slice(&penalty_coefficients);
+            builder.append(true);
+        }
+        _append_f64(&mut self._energy_violation, record.energy_violation);
+        _append_f64(&mut self._capacity_violation, record.capacity_violation);
+        _append_f64(&mut self._waiting_time_violation, record.waiting_time_violation);
+        _append_f64(&mut self._fixed_time_violation, record.fixed_time_violation);
+        _append_f64(&mut self._depot_slot_violation, record.depot_slot_violation);
+        _append_f64(&mut self._assignment_violation, record.assignment_violation);
+        _append_f64(&mut self._no_fly_violation, record.no_fly_violation);
+        _append_routes(&mut self._truck_routes, record.truck_routes);
+        _append_routes(&mut self._drone_routes, record.drone_routes);
+        _append_u64(&mut self._truck_routes_count, record.truck_routes_count);
+        _append_u64(&mut self._drone_routes_count, record.drone_routes_count);
+        if let Some(ref neighborhood) = record.neighborhood {
+            self._neighborhood
+                .get_or_insert_with(StringBuilder::new)
+                .append_value(neighborhood);
+        }
+        if let Some(tabu_list) = record.tabu_list {
+            let builder = self
+                ._tabu_list
+                .get_or_insert_with(|| ListBuilder::new(ListBuilder::new(UInt64Builder::new())));
+            for attribute in tabu_list {
+                for &customer in attribute {
+                    builder.values().values().append_value(customer as u64);
+                }
+                builder.values().append(true);
+            }
+            builder.append(true);
+        }
+
+        self._rows += 1;
+        if self._rows >= ROW_GROUP_SIZE {
+            self._flush()?;
+        }
+
+        Ok(())
+    }
+
+    fn _flush(&mut self) -> Result<(), Box<dyn Error>> {
+        if self._rows == 0 {
+            return Ok(());
+        }
+
+        let mut columns: Vec<(&str, ArrayRef)> = vec![("iteration", Arc::new(self._iteration.finish()))];
+        let floats = [("cost", &mut self._cost), ("working_time", &mut self._working_time)];
+        for (name, builder) in floats {
+            if let Some(builder) = builder {
+                columns.push((name, Arc::new(builder.finish())));
+            }
+        }
+        if let Some(ref mut builder) = self._feasible {
+            columns.push(("feasible", Arc::new(builder.finish())));
+        }
+        if let Some(ref mut builder) = self._penalty_coefficients {
+            columns.push(("penalty_coefficients", Arc::new(builder.finish())));
+        }
+        let violations = [
+            ("energy_violation", &mut self._energy_violation),
+            ("capacity_violation", &mut self._capacity_violation),
+            ("waiting_time_violation", &mut self._waiting_time_violation),
+            ("fixed_time_violation", &mut self._fixed_time_violation),
+            ("depot_slot_violation", &mut self._depot_slot_violation),
+            ("assignment_violation", &mut self._assignment_violation),
+            ("no_fly_violation", &mut self._no_fly_violation),
+        ];
+        for (name, builder) in violations {
+            if let Some(builder) = builder {
+                columns.push((name, Arc::new(builder.finish())));
+            }
+        }
+        let routes = [
+            ("truck_routes", &mut self._truck_routes),
+            ("drone_routes", &mut self._drone_routes),
+        ];
+        for (name, builder) in routes {
+            if let Some(builder) = builder {
+                columns.push((name, Arc::new(builder.finish())));
+            }
+        }
+        let counts = [
+            ("truck_routes_count", &mut self._truck_routes_count),
+            ("drone_routes_count", &mut self._drone_routes_count),
+        ];
+        for (name, builder) in counts {
+            if let Some(builder) = builder {
+                columns.push((name, Arc::new(builder.finish())));
+            }
+        }
+        if let Some(ref mut builder) = self._neighborhood {
+            columns.push(("neighborhood", Arc::new(builder.finish())));
+        }
+        if let Some(ref mut builder) = self._tabu_list {
+            columns.push(("tabu_list", Arc::new(builder.finish())));
+        }
+
+        let batch = RecordBatch::try_from_iter(columns)?;
+        if self._writer.is_none() {
+            let compression = match CONFIG.load().log_compress {
+                None => Compression::UNCOMPRESSED,
+                Some(LogCompression::Gzip) => Compression::GZIP(GzipLevel::default()),
+                Some(LogCompression::Zstd) => Compression::ZSTD(ZstdLevel::default()),
+            };
+            let properties = WriterProperties::builder()
+                .set_compression(compression)
+                .set_max_row_group_size(ROW_GROUP_SIZE)
+                .build();
+            self._writer = Some(ArrowWriter::try_new(
+                File::create(&self._path)?,
+                batch.schema(),
+                Some(properties),
+            )?);
+        }
+
+        if let Some(ref mut writer) = self._writer {
+            writer.write(&batch)?;
+        }
+        self._rows = 0;
+
+        Ok(())
+    }
+
+    /// Write the remaining rows and the file footer
+    pub fn close(mut self) -> Result<(), Box<dyn Error>> {
+        self._flush()?;
+        if let Some(writer) = self._writer {
+            writer.close()?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,257 @@
+use std::fs;
+use std::rc::Rc;
+
+use serde::Deserialize;
+
+use crate::ids::{CustomerId, DroneId, SerializedVehicleId, TruckId, VehicleId};
+use crate::routes::{DroneRoute, Route, TruckRoute};
+use crate::solutions::Solution;
+
+#[derive(Deserialize)]
+struct _PinnedRoute {
+    #[serde(flatten)]
+    vehicle: SerializedVehicleId,
+    customers: Vec<usize>,
+}
+
+#[derive(Deserialize)]
+struct _PinnedCustomer {
+    #[serde(flatten)]
+    vehicle: SerializedVehicleId,
+    customer: usize,
+}
+
+#[derive(Deserialize)]
+struct _PinFile {
+    #[serde(default)]
+    routes: Vec<_PinnedRoute>,
+    #[serde(default)]
+    customers: Vec<_PinnedCustomer>,
+}
+
+/// Routes and customer assignments that the search must not modify (see `--pin`)
+#[derive(Clone, Debug, Default)]
+pub struct Pins {
+    /// Vehicle each customer is pinned to, including the customers of the pinned routes
+    vehicle_of: Vec<Option<VehicleId>>,
+    /// Index in `routes` of the pinned route of each customer
+    route_of: Vec<Option<usize>>,
+    /// Customers of each pinned route, with the depot at both ends
+    routes: Vec<Vec<usize>>,
+    /// Number of pinned customers
+    count: usize,
+}
+
+impl Pins {
+    /// Load the pins of the JSON file `path` (see `--pin`). Returns no pins when no file is given.
+    pub fn load(path: Option<&str>, customers_count: usize, trucks_count: usize, drones_count: usize) -> Self {
+        let Some(path) = path else {
+            return Self::default();
+        };
+
+        let file = serde_json::from_str::<_PinFile>(&fs::read_to_string(path).unwrap()).unwrap();
+        let mut pins = Self {
+            vehicle_of: vec![None; customers_count + 1],
+            route_of: vec![None; customers_count + 1],
+            routes: vec![],
+            count: 0,
+        };
+        let mut pin = |customer: usize, vehicle: VehicleId| {
+            assert!(
+                (1..customers_count + 1).contains(&customer),
+                "Pinned customer {customer} does not exist"
+            );
+            assert!(
+                pins.vehicle_of[customer].is_none(),
+                "Customer {customer} is pinned more than once"
+            );
+            pins.vehicle_of[customer] = Some(vehicle);
+            pins.count += 1;
+        };
+
+        let mut routes = vec![];
+        for route in file.routes {
+            assert!(
+                !route.customers.is_empty(),
+                "Pinned routes must have at least one customer"
+            );
+            let vehicle = route.vehicle.id(trucks_count, drones_count);
+            for &customer in &route.customers {
+                pin(customer, vehicle);
+            }
+            routes.push(route.customers);
+        }
+        for customer in file.customers {
+            pin(customer.customer, customer.vehicle.id(trucks_count, drones_count));
+        }
+
+        for (k, customers) in routes.into_iter().enumerate() {
+            for &customer in &customers {
+                pins.route_of[customer] = Some(k);
+            }
+            pins.routes.push([vec![0], customers, vec![0]].concat());
+        }
+
+        pins
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Whether `customer` is pinned to a vehicle
+    pub fn customer(&self, customer: usize) -> bool {
+        self.vehicle_of.get(customer).is_some_and(Option::is_some)
+    }
+
+    /// Whether the route visiting `customers` is pinned. Since each customer is served exactly once, a route is
+    /// identified by its first customer.
+    pub fn route(&self, customers: &[usize]) -> bool {
+        self.route_of.get(customers[1]).is_some_and(Option::is_some)
+    }
+
+    /// Whether every pinned customer of `solution` is served by its vehicle, and every pinned route is unchanged
+    pub fn respected(&self, solution: &Solution) -> bool {
+        fn _found<T>(pins: &Pins, vehicle_routes: &[Vec<Rc<T>>], vehicle: impl Fn(usize) -> VehicleId) -> Option<usize>
+        where
+            T: Route,
+        {
+            let mut found = 0;
+            for (v, routes) in vehicle_routes.iter().enumerate() {
+                for route in routes {
+                    let customers = &route.data().customers;
+                    for &customer in &customers[1..customers.len() - 1] {
+                        if let Some(pinned) = pins.vehicle_of[customer] {
+                            if pinned != vehicle(v) {
+                                return None;
+                            }
+                            if let Some(k) = pins.route_of[customer]
+                                && pins.routes[k] != *customers
+                            {
+                                return None;
+                            }
+                            found += 1;
+                        }
+                    }
+                }
+            }
+
+            Some(found)
+        }
+
+        if self.is_empty() {
+            return true;
+        }
+
+        let trucks = _found(self, &solution.truck_routes, |t| VehicleId::Truck(TruckId(t)));
+        let drones = _found(self, &solution.drone_routes, |d| VehicleId::Drone(DroneId(d)));
+        trucks.zip(drones).is_some_and(|(t, d)| t + d == self.count)
+    }
+
+    /// Rearrange `solution` to respect the pins: the pinned customers are removed from their routes, the pinned routes
+    /// are given to their vehicles, then each other pinned customer is inserted at the cheapest position among the
+    /// unpinned routes of its vehicle (or in a new route).
+    pub fn impose(&self, solution: Solution) -> Solution {
+        fn _unpin<T>(pins: &Pins, vehicle_routes: &mut [Vec<Rc<T>>])
+        where
+            T: Route,
+        {
+            for routes in vehicle_routes {
+                *routes = routes
+                    .iter()
+                    .filter_map(|route| {
+                        let customers = route
+                            .data()
+                            .customers
+                            .iter()
+                            .copied()
+                            .filter(|&c| !pins.customer(c))
+                            .collect::<Vec<_>>();
+                        (customers.len() > 2).then(|| T::new(customers))
+                    })
+                    .collect();
+            }
+        }
+
+        /// Solutions obtained by inserting `customer` at each position of the unpinned routes of `vehicle`, or in a
+        /// new route of it
+        fn _insertions<T>(
+            pins: &Pins,
+            truck_routes: &[Vec<Rc<TruckRoute>>],
+            drone_routes: &[Vec<Rc<DroneRoute>>],
+            vehicle: usize,
+            customer: usize,
+        ) -> Vec<Solution>
+        where
+            T: Route,
+        {
+            let mut candidates = vec![];
+            let mut candidate = |routes: Vec<Rc<T>>| {
+                let mut truck_routes = truck_routes.to_vec();
+                let mut drone_routes = drone_routes.to_vec();
+                T::get_correct_route_mut(&mut truck_routes, &mut drone_routes)[vehicle] = routes;
+                candidates.push(Solution::new(truck_routes, drone_routes));
+            };
+
+            let routes = &T::get_correct_route(truck_routes, drone_routes)[vehicle];
+            for (r, route) in routes.iter().enumerate() {
+                if pins.route(&route.data().customers) {
+                    continue;
+                }
+                for i in 1..route.data().customers.len() {
+                    let mut customers = route.data().customers.clone();
+                    customers.insert(i, customer);
+                    let mut routes = routes.clone();
+                    routes[r] = T::new(customers);
+                    candidate(routes);
+                }
+            }
+
+            let mut routes = routes.clone();
+            routes.push(T::single(CustomerId(customer)));
+            candidate(routes);
+            candidates
+        }
+
+        if self.is_empty() {
+            return solution;
+        }
+
+        let mut truck_routes = solution.truck_routes;
+        let mut drone_routes = solution.drone_routes;
+        _unpin(self, &mut truck_routes);
+        _unpin(self, &mut drone_routes);
+
+        for customers in &self.routes {
+            match self.vehicle_of[customers[1]].unwrap() {
+                VehicleId::Truck(TruckId(truck)) => truck_routes[truck].push(TruckRoute::new(customers.clone())),
+                VehicleId::Drone(DroneId(drone)) => drone_routes[drone].push(DroneRoute::new(customers.clone())),
+            }
+        }
+
+        let mut result = Solution::new(truck_routes, drone_routes);
+        for customer in 1..self.vehicle_of.len() {
+            let Some(vehicle) = self.vehicle_of[customer] else {
+                continue;
+            };
+            if self.route_of[customer].is_some() {
+                continue;
+            }
+
+            let candidates = match vehicle {
+                VehicleId::Truck(TruckId(truck)) => {
+                    _insertions::<TruckRoute>(self, &result.truck_routes, &result.drone_routes, truck, customer)
+                }
+                VehicleId::Drone(DroneId(drone)) => {
+                    _insertions::<DroneRoute>(self, &result.truck_routes, &result.drone_routes, drone, customer)
+                }
+            };
+            result = candidates
+                .into_iter()
+                .min_by(|a, b| a.cost().total_cmp(&b.cost()))
+                .unwrap();
+        }
+
+        result
+    }
+}
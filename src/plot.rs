@@ -0,0 +1,95 @@
+use std::error::Error;
+use std::iter;
+use std::path::Path;
+use std::rc::Rc;
+
+use plotters::coord::types::RangedCoordf64;
+use plotters::prelude::*;
+
+use crate::config::CONFIG;
+use crate::routes::Route;
+use crate::solutions::Solution;
+
+const SIZE: (u32, u32) = (1000, 1000);
+
+fn _point(node: usize) -> (f64, f64) {
+    (CONFIG.load().x[node], CONFIG.load().y[node])
+}
+
+fn _draw_routes<T, DB>(
+    chart: &mut ChartContext<DB, Cartesian2d<RangedCoordf64, RangedCoordf64>>,
+    vehicle_routes: &[Vec<Rc<T>>],
+    offset: usize,
+    width: u32,
+) -> Result<(), Box<dyn Error>>
+where
+    T: Route,
+    DB: DrawingBackend,
+    DB::ErrorType: 'static,
+{
+    for (vehicle, routes) in vehicle_routes.iter().enumerate() {
+        let style = Palette99::pick(offset + vehicle).stroke_width(width);
+        for route in routes {
+            chart.draw_series(LineSeries::new(
+                route.data().customers.iter().map(|&c| _point(c)),
+                style,
+            ))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Render `solution` to the SVG file `path`: truck routes as thick lines and drone routes as thin lines (one color per
+/// vehicle), drone sorties as dashed lines, customers colored by the vehicles that can serve them (red: trucks only,
+/// green: both, blue: drones only) and the depot as a black square.
+pub fn plot(solution: &Solution, path: &Path) -> Result<(), Box<dyn Error>> {
+    let bounds = |values: &[f64]| {
+        let (min, max) = values.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &v| {
+            (lo.min(v), hi.max(v))
+        });
+        let margin = ((max - min) * 0.05).max(1.0);
+        (min - margin)..(max + margin)
+    };
+
+    let root = SVGBackend::new(path, SIZE).into_drawing_area();
+    root.fill(&WHITE)?;
+
+    let mut chart = ChartBuilder::on(&root)
+        .margin(10)
+        .build_cartesian_2d(bounds(&CONFIG.load().x), bounds(&CONFIG.load().y))?;
+
+    _draw_routes(&mut chart, &solution.truck_routes, 0, 3)?;
+    _draw_routes(&mut chart, &solution.drone_routes, CONFIG.load().trucks_count, 1)?;
+
+    for sortie in &solution.sorties {
+        let customers = &solution.truck_routes[sortie.truck][sortie.route].data().customers;
+        let points = [
+            _point(customers[sortie.launch]),
+            _point(sortie.customer),
+            _point(customers[sortie.rendezvous]),
+        ];
+        chart.draw_series(DashedLineSeries::new(
+            points,
+            6,
+            4,
+            Palette99::pick(sortie.truck).stroke_width(1),
+        ))?;
+    }
+
+    chart.draw_series((1..CONFIG.load().customers_count + 1).map(|c| {
+        let color = match (CONFIG.load().truckable[c], CONFIG.load().dronable[c]) {
+            (true, false) => RED,
+            (false, true) => BLUE,
+            _ => GREEN,
+        };
+        Circle::new(_point(c), 5, color.filled())
+    }))?;
+
+    chart.draw_series(iter::once(
+        EmptyElement::at(_point(0)) + Rectangle::new([(-7, -7), (7, 7)], BLACK.filled()),
+    ))?;
+
+    root.present()?;
+    Ok(())
+}
@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use rand::{Rng, rng};
+
+use crate::config::CONFIG;
+use crate::ids::CustomerId;
+use crate::routes::{AnyRoute, DroneRoute, Route, TruckRoute};
+use crate::solutions::Solution;
+
+/// Number of randomized greedy passes performed during recombination (the first pass is deterministic)
+const RECOMBINATION_PASSES: usize = 100;
+
+/// Pool of distinct feasible routes collected from the solutions visited during the search
+pub struct RoutePool {
+    truck_routes: HashMap<Vec<usize>, Rc<TruckRoute>>,
+    drone_routes: HashMap<Vec<usize>, Rc<DroneRoute>>,
+}
+
+impl RoutePool {
+    pub fn new() -> Self {
+        Self {
+            truck_routes: HashMap::new(),
+            drone_routes: HashMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.truck_routes.len() + self.drone_routes.len()
+    }
+
+    /// Add every feasible route of `solution` to the pool, up to `--route-pool` routes in total.
+    pub fn insert(&mut self, solution: &Solution) {
+        let config = CONFIG.load();
+        for routes in &solution.truck_routes {
+            for route in routes {
+                if self.len() >= config.route_pool {
+                    return;
+                }
+
+                let customers = &route.data().customers;
+                if route.capacity_violation() == 0.0
+                    && route.waiting_time_violation() == 0.0
+                    && !self.truck_routes.contains_key(customers)
+                {
+                    self.truck_routes.insert(customers.clone(), route.clone());
+                }
+            }
+        }
+
+        for routes in &solution.drone_routes {
+            for route in routes {
+                if self.len() >= config.route_pool {
+                    return;
+                }
+
+                let customers = &route.data().customers;
+                let feasible = route.class_metrics().any(|m| {
+                    m.capacity_violation == 0.0
+                        && m.waiting_time_violation == 0.0
+                        && m.energy_violation == 0.0
+                        && m.fixed_time_violation == 0.0
+                        && m.no_fly_violation == 0.0
+                });
+                if feasible && !self.drone_routes.contains_key(customers) {
+                    self.drone_routes.insert(customers.clone(), route.clone());
+                }
+            }
+        }
+    }
+
+    /// Recombine the pooled routes into a solution that is better than `incumbent`, if possible.
+    ///
+    /// Each pass greedily selects non-overlapping routes in increasing order of working time per customer (with
+    /// random noise after the first pass), covers the remaining customers with single-customer routes and then
+    /// distributes the selected routes among the vehicles with the longest-processing-time rule.
+    pub fn recombine(&self, incumbent: &Solution) -> Solution {
+        fn _assign<T>(routes: Vec<Rc<T>>, vehicles: usize) -> Vec<Vec<Rc<T>>>
+        where
+            T: Route,
+        {
+            // Longest first, by the working time on the fastest vehicle
+            let mut routes = routes
+                .into_iter()
+                .map(|route| {
+                    let fastest = (0..vehicles)
+                        .map(|v| route.vehicle_working_time(v))
+                        .fold(f64::INFINITY, f64::min);
+                    (fastest, route)
+                })
+                .collect::<Vec<_>>();
+            routes.sort_by(|(f, _), (s, _)| f.total_cmp(s).reverse());
+
+            let mut result = vec![vec![]; vehicles];
+            let mut working_time = vec![0.0; vehicles];
+            for (_, route) in routes {
+                let mut min_idx = 0;
+                let mut min_time = f64::INFINITY;
+                for (i, &time) in working_time.iter().enumerate() {
+                    let finish = time + route.vehicle_working_time(i);
+                    if finish < min_time {
+                        min_time = finish;
+                        min_idx = i;
+                    }
+                }
+
+                working_time[min_idx] += route.vehicle_working_time(min_idx);
+                result[min_idx].push(route);
+            }
+
+            result
+        }
+
+        let mut candidates = vec![];
+        for route in self.truck_routes.values() {
+            let efficiency = route.working_time() / (route.data().customers.len() - 2) as f64;
+            candidates.push((efficiency, AnyRoute::Truck(route.clone())));
+        }
+        for route in self.drone_routes.values() {
+            let fastest = route
+                .class_metrics()
+                .map(|m| m.working_time)
+                .fold(f64::INFINITY, f64::min);
+            let efficiency = fastest / (route.data().customers.len() - 2) as f64;
+            candidates.push((efficiency, AnyRoute::Drone(route.clone())));
+        }
+
+        let mut best = incumbent.clone();
+        let mut rng = rng();
+        for pass in 0..RECOMBINATION_PASSES {
+            let mut order = candidates
+                .iter()
+                .enumerate()
+                .map(|(i, (efficiency, _))| {
+                    let noise = if pass == 0 { 1.0 } else { rng.random_range(0.8..1.2) };
+                    (efficiency * noise, i)
+                })
+                .collect::<Vec<(f64, usize)>>();
+            order.sort_by(|f, s| f.0.total_cmp(&s.0));
+
+            let mut covered = vec![false; CONFIG.load().customers_count + 1];
+            let mut truck_routes = vec![];
+            let mut drone_routes = vec![];
+            for (_, i) in order {
+                let route = &candidates[i].1;
+                let customers = route.customers();
+                let inner = &customers[1..customers.len() - 1];
+                if inner.iter().any(|&c| covered[c]) {
+                    continue;
+                }
+
+                match route {
+                    AnyRoute::Truck(route) => {
+                        if CONFIG.load().single_truck_route && truck_routes.len() == CONFIG.load().trucks_count {
+                            continue;
+                        }
+
+                        truck_routes.push(route.clone());
+                    }
+                    AnyRoute::Drone(route) => drone_routes.push(route.clone()),
+                }
+
+                for &c in inner {
+                    covered[c] = true;
+                }
+            }
+
+            let mut complete = true;
+            for (customer, &covered) in covered.iter().enumerate().skip(1) {
+                if covered {
+                    continue;
+                }
+
+                if CONFIG.load().drones_count > 0 && CONFIG.load().dronable[customer] {
+                    drone_routes.push(DroneRoute::single(CustomerId(customer)));
+                } else if CONFIG.load().trucks_count > 0
+                    && CONFIG.load().truckable[customer]
+                    && (!CONFIG.load().single_truck_route || truck_routes.len() < CONFIG.load().trucks_count)
+                {
+                    truck_routes.push(TruckRoute::single(CustomerId(customer)));
+                } else {
+                    complete = false;
+                    break;
+                }
+            }
+
+            if !complete {
+                continue;
+            }
+
+            let s = Solution::new(
+                _assign(truck_routes, CONFIG.load().trucks_count),
+                _assign(drone_routes, CONFIG.load().drones_count),
+            );
+            if s.feasible && s.cost() < best.cost() {
+                best = s;
+            }
+        }
+
+        best
+    }
+}
@@ -0,0 +1,109 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::time::Instant;
+
+use serde::Serialize;
+
+use crate::config::CONFIG;
+use crate::neighborhoods::Neighborhood;
+
+/// A phase of the search timed with `--profile`
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Neighborhood(Neighborhood),
+    /// Construction and evaluation of a solution from its routes
+    Evaluation,
+    DestroyAndRepair,
+    Logging,
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Neighborhood(neighborhood) => write!(f, "{neighborhood}"),
+            Self::Evaluation => write!(f, "Solution::new"),
+            Self::DestroyAndRepair => write!(f, "destroy_and_repair"),
+            Self::Logging => write!(f, "logging"),
+        }
+    }
+}
+
+/// Time spent in a phase of the search
+#[derive(Serialize)]
+pub struct PhaseTiming {
+    pub phase: String,
+    pub calls: usize,
+    pub seconds: f64,
+}
+
+thread_local! {
+    /// Number of calls and seconds spent in each phase by the current thread
+    static _TIMINGS: RefCell<Vec<(Phase, usize, f64)>> = const { RefCell::new(vec![]) };
+}
+
+/// Run `f`, accounting its duration to `phase` with `--profile`. Phases may be nested, e.g. the neighborhoods include
+/// the evaluation of the solutions they construct.
+pub fn time<R>(phase: Phase, f: impl FnOnce() -> R) -> R {
+    if !CONFIG.load().profile {
+        return f();
+    }
+
+    let start = Instant::now();
+    let result = f();
+    let elapsed = start.elapsed().as_secs_f64();
+    _TIMINGS.with_borrow_mut(|timings| match timings.iter_mut().find(|(p, _, _)| *p == phase) {
+        Some((_, calls, seconds)) => {
+            *calls += 1;
+            *seconds += elapsed;
+        }
+        None => timings.push((phase, 1, elapsed)),
+    });
+
+    result
+}
+
+/// Discard the time accumulated by the current thread
+pub fn reset() {
+    _TIMINGS.with_borrow_mut(Vec::clear);
+}
+
+/// Number of calls to `phase` by the current thread
+pub fn calls(phase: Phase) -> usize {
+    _TIMINGS.with_borrow(|timings| {
+        timings
+            .iter()
+            .find(|(p, _, _)| *p == phase)
+            .map_or(0, |&(_, calls, _)| calls)
+    })
+}
+
+/// Time spent in each phase by the current thread in decreasing order, or `None` without `--profile`
+pub fn report() -> Option<Vec<PhaseTiming>> {
+    CONFIG.load().profile.then(|| {
+        let mut report = _TIMINGS.with_borrow(|timings| {
+            timings
+                .iter()
+                .map(|&(phase, calls, seconds)| PhaseTiming {
+                    phase: phase.to_string(),
+                    calls,
+                    seconds,
+                })
+                .collect::<Vec<_>>()
+        });
+        report.sort_by(|f, s| s.seconds.total_cmp(&f.seconds));
+        report
+    })
+}
+
+pub fn print(report: &[PhaseTiming]) {
+    eprintln!("{:<20}{:>12}{:>12}{:>16}", "Phase", "Calls", "Seconds", "Per call (us)");
+    for timing in report {
+        eprintln!(
+            "{:<20}{:>12}{:>12.3}{:>16.2}",
+            timing.phase,
+            timing.calls,
+            timing.seconds,
+            1e6 * timing.seconds / timing.calls as f64
+        );
+    }
+}
@@ -0,0 +1,110 @@
+use std::io::{self, Write};
+use std::rc::Rc;
+
+use crate::config::CONFIG;
+use crate::ids::{DroneId, TruckId, VehicleId};
+use crate::routes::Route;
+use crate::solutions::{FrequencyMemory, Solution};
+
+/// Remove the routes of `vehicle` from `solution` and repair its customers into the other vehicles. Returns `None`
+/// if some customer can only be served by the removed vehicle.
+fn _remove_vehicle(solution: &Solution, vehicle: VehicleId) -> Option<Solution> {
+    fn _take<T>(vehicle_routes: &mut [Vec<Rc<T>>], vehicle: usize) -> Vec<usize>
+    where
+        T: Route,
+    {
+        vehicle_routes[vehicle]
+            .drain(..)
+            .flat_map(|route| {
+                let customers = &route.data().customers;
+                customers[1..customers.len() - 1].to_vec()
+            })
+            .collect()
+    }
+
+    let mut truck_routes = solution.truck_routes.clone();
+    let mut drone_routes = solution.drone_routes.clone();
+    let customers = match vehicle {
+        VehicleId::Truck(TruckId(truck)) => _take(&mut truck_routes, truck),
+        VehicleId::Drone(DroneId(drone)) => _take(&mut drone_routes, drone),
+    };
+
+    let config = CONFIG.load();
+    let servable = |customer: usize| {
+        let trucks = (0..config.trucks_count)
+            .filter(|_| config.truckable[customer])
+            .map(|truck| VehicleId::Truck(TruckId(truck)));
+        let drones = (0..config.drones_count)
+            .filter(|_| config.dronable[customer])
+            .map(|drone| VehicleId::Drone(DroneId(drone)));
+        trucks
+            .chain(drones)
+            .any(|v| v != vehicle && config.assignment_rules.assignable(customer, v))
+    };
+    if !customers.iter().all(|&c| servable(c)) {
+        return None;
+    }
+
+    Some(Solution::repair(
+        truck_routes,
+        drone_routes,
+        customers,
+        &FrequencyMemory::new(),
+        0.0,
+        Some(vehicle),
+        &mut rand::rng(),
+    ))
+}
+
+/// Remove each used vehicle of `solution` in turn, repair its customers with the destroy-and-repair insertion and
+/// write a CSV row to `writer` with the resulting makespan and feasibility.
+pub fn analyze(solution: &Solution, writer: &mut impl Write) -> io::Result<()> {
+    assert!(
+        solution.sorties.is_empty(),
+        "Solutions with drone sorties (see --mothership) cannot be analyzed"
+    );
+
+    writeln!(
+        writer,
+        "Vehicle,Type,Routes,Working time,Makespan,Makespan increase,Feasible"
+    )?;
+
+    let vehicles = (0..solution.truck_routes.len())
+        .map(|truck| VehicleId::Truck(TruckId(truck)))
+        .chain((0..solution.drone_routes.len()).map(|drone| VehicleId::Drone(DroneId(drone))));
+    for vehicle in vehicles {
+        let (index, kind, routes, working_time) = match vehicle {
+            VehicleId::Truck(TruckId(truck)) => (
+                truck,
+                "truck",
+                solution.truck_routes[truck].len(),
+                solution.truck_working_time[truck],
+            ),
+            VehicleId::Drone(DroneId(drone)) => (
+                drone,
+                "drone",
+                solution.drone_routes[drone].len(),
+                solution.drone_working_time[drone],
+            ),
+        };
+        if routes == 0 {
+            continue;
+        }
+
+        let (makespan, feasible) =
+            _remove_vehicle(solution, vehicle).map_or((f64::INFINITY, false), |s| (s.working_time, s.feasible));
+        writeln!(
+            writer,
+            "{},{},{},{},{},{},{}",
+            index,
+            kind,
+            routes,
+            working_time,
+            makespan,
+            makespan - solution.working_time,
+            i32::from(feasible),
+        )?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,75 @@
+use std::fs;
+use std::rc::Rc;
+
+use tracing::info;
+
+use crate::config::CONFIG;
+use crate::routes::{DroneRoute, Route, TruckRoute};
+use crate::solutions::{FrequencyMemory, Solution};
+
+/// Initial solution of the "reoptimize" subcommand: the routes of `solution` that were not started are kept as they
+/// are, and the new customers are inserted by a repair.
+pub fn root(solution: &str) -> Solution {
+    let data = serde_json::from_str::<serde_json::Value>(&fs::read_to_string(solution).unwrap()).unwrap();
+    let truck_routes = serde_json::from_value::<Vec<Vec<Vec<usize>>>>(data["truck_routes"].clone()).unwrap();
+    let drone_routes = serde_json::from_value::<Vec<Vec<Vec<usize>>>>(data["drone_routes"].clone()).unwrap();
+
+    let mut node_of = vec![None; CONFIG.load().original_ids.iter().max().map_or(1, |&i| i + 1)];
+    for (node, &original) in CONFIG.load().original_ids.iter().enumerate() {
+        node_of[original] = Some(node);
+    }
+
+    let mut placed = vec![false; CONFIG.load().customers_count + 1];
+    placed[0] = true;
+
+    /// Keep the routes whose customers are all still to be served, renumbered to the nodes of the current config
+    fn _pending<T>(
+        vehicle_routes: &[Vec<Vec<usize>>],
+        node_of: &[Option<usize>],
+        placed: &mut [bool],
+    ) -> Vec<Vec<Rc<T>>>
+    where
+        T: Route,
+    {
+        vehicle_routes
+            .iter()
+            .map(|routes| {
+                routes
+                    .iter()
+                    .filter_map(|route| {
+                        let customers = route
+                            .iter()
+                            .map(|&c| node_of.get(c).copied().flatten())
+                            .collect::<Option<Vec<usize>>>()?;
+                        for &c in &customers {
+                            placed[c] = true;
+                        }
+
+                        Some(T::new(customers))
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    let truck_routes = _pending::<TruckRoute>(&truck_routes, &node_of, &mut placed);
+    let drone_routes = _pending::<DroneRoute>(&drone_routes, &node_of, &mut placed);
+    let customers = (1..CONFIG.load().customers_count + 1)
+        .filter(|&c| !placed[c])
+        .collect::<Vec<usize>>();
+    info!(
+        "Re-optimizing {} customers, {} of which are inserted into the remaining routes",
+        CONFIG.load().customers_count,
+        customers.len()
+    );
+
+    Solution::repair(
+        truck_routes,
+        drone_routes,
+        customers,
+        &FrequencyMemory::new(),
+        0.0,
+        None,
+        &mut rand::rng(),
+    )
+}
@@ -0,0 +1,180 @@
+use std::collections::HashSet;
+use std::iter;
+use std::rc::Rc;
+
+use tracing::info;
+
+use crate::config::CONFIG;
+use crate::ids::{DroneId, TruckId, VehicleId};
+use crate::routes::{DroneRoute, Route, TruckRoute};
+use crate::solutions::{FrequencyMemory, Solution};
+
+/// Sum of the energy, capacity, waiting time, fixed time, depot slot, assignment and no-fly `violations` of a route
+/// that are hard constraints (see `--soft-constraints`)
+fn _hard_violation(violations: [f64; 7]) -> f64 {
+    violations
+        .iter()
+        .zip(CONFIG.load().soft_weights)
+        .filter(|(_, soft)| soft.is_none())
+        .map(|(&violation, _)| violation)
+        .sum()
+}
+
+/// Remove customers from `customers` (a route without its depots) until `violation` of the remaining route is zero,
+/// each time the customer whose removal leaves the lowest violation. The removed customers are appended to `removed`.
+fn _trim<T>(mut customers: Vec<usize>, violation: impl Fn(&T) -> f64, removed: &mut Vec<usize>) -> Option<Rc<T>>
+where
+    T: Route,
+{
+    let route = |customers: &[usize]| {
+        let mut nodes = vec![0];
+        nodes.extend_from_slice(customers);
+        nodes.push(0);
+        T::new(nodes)
+    };
+
+    while !customers.is_empty() {
+        let current = route(&customers);
+        if violation(&current) == 0.0 {
+            return Some(current);
+        }
+
+        let best = (0..customers.len())
+            .map(|i| {
+                let mut rest = customers.clone();
+                rest.remove(i);
+                (i, if rest.is_empty() { 0.0 } else { violation(&route(&rest)) })
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap()
+            .0;
+        removed.push(customers.remove(best));
+    }
+
+    None
+}
+
+/// Make `solution` feasible with as few changes as possible, e.g. after it was edited by hand: customers served more
+/// than once keep their first visit, customers served by a vehicle that cannot serve them and customers whose
+/// removal eliminates the violations of their route are removed, then every removed or unserved customer is
+/// reinserted with the destroy-and-repair insertion.
+pub fn repair(solution: &Solution) -> Solution {
+    assert!(
+        solution.sorties.is_empty(),
+        "Solutions with drone sorties (see --mothership) cannot be repaired"
+    );
+
+    let mut served = HashSet::new();
+    let mut removed = vec![];
+    let mut keep = |customer: usize, servable: bool, removed: &mut Vec<usize>| {
+        if !served.insert(customer) {
+            false
+        } else if servable {
+            true
+        } else {
+            removed.push(customer);
+            false
+        }
+    };
+
+    let mut truck_routes = vec![];
+    for (truck, routes) in solution.truck_routes.iter().enumerate() {
+        let vehicle = VehicleId::Truck(TruckId(truck));
+        let mut trimmed = vec![];
+        for route in routes {
+            let customers = &route.data().customers;
+            let customers = customers[1..customers.len() - 1]
+                .iter()
+                .copied()
+                .filter(|&c| {
+                    keep(
+                        c,
+                        CONFIG.load().truckable[c] && CONFIG.load().assignment_rules.assignable(c, vehicle),
+                        &mut removed,
+                    )
+                })
+                .collect::<Vec<_>>();
+            trimmed.extend(_trim(
+                customers,
+                |r: &TruckRoute| {
+                    _hard_violation([
+                        0.0,
+                        r.capacity_violation(),
+                        r.waiting_time_violation(),
+                        0.0,
+                        0.0,
+                        CONFIG
+                            .load()
+                            .assignment_rules
+                            .violation(vehicle, iter::once(r.data().customers.as_slice())),
+                        0.0,
+                    ])
+                },
+                &mut removed,
+            ));
+        }
+        truck_routes.push(trimmed);
+    }
+
+    let mut drone_routes = vec![];
+    for (drone, routes) in solution.drone_routes.iter().enumerate() {
+        let vehicle = VehicleId::Drone(DroneId(drone));
+        let mut trimmed = vec![];
+        for route in routes {
+            let customers = &route.data().customers;
+            let customers = customers[1..customers.len() - 1]
+                .iter()
+                .copied()
+                .filter(|&c| {
+                    keep(
+                        c,
+                        CONFIG.load().dronable[c] && CONFIG.load().assignment_rules.assignable(c, vehicle),
+                        &mut removed,
+                    )
+                })
+                .collect::<Vec<_>>();
+            trimmed.extend(_trim(
+                customers,
+                |r: &DroneRoute| {
+                    let metrics = r.metrics(DroneId(drone));
+                    _hard_violation([
+                        metrics.energy_violation,
+                        metrics.capacity_violation,
+                        metrics.waiting_time_violation,
+                        metrics.fixed_time_violation,
+                        0.0,
+                        CONFIG
+                            .load()
+                            .assignment_rules
+                            .violation(vehicle, iter::once(r.data().customers.as_slice())),
+                        metrics.no_fly_violation,
+                    ])
+                },
+                &mut removed,
+            ));
+        }
+        drone_routes.push(trimmed);
+    }
+
+    let unserved = (1..CONFIG.load().customers_count + 1)
+        .filter(|c| !served.contains(c))
+        .collect::<Vec<_>>();
+    info!(
+        "Removed {} violating customer(s) {:?}, inserting them along with {} unserved customer(s) {:?}",
+        removed.len(),
+        removed,
+        unserved.len(),
+        unserved
+    );
+    removed.extend(unserved);
+
+    Solution::repair(
+        truck_routes,
+        drone_routes,
+        removed,
+        &FrequencyMemory::new(),
+        0.0,
+        None,
+        &mut rand::rng(),
+    )
+}
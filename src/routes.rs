@@ -1,9 +1,14 @@
+use std::cell::OnceCell;
 use std::collections::VecDeque;
-use std::fmt;
 use std::mem::swap;
+use std::ops::ControlFlow;
 use std::rc::Rc;
+use std::{fmt, iter};
 
-use crate::config::CONFIG;
+use serde::Serialize;
+
+use crate::config::{CONFIG, DroneConfig};
+use crate::ids::{CustomerId, DroneId};
 use crate::neighborhoods::Neighborhood;
 use crate::solutions::Solution;
 
@@ -21,6 +26,7 @@ pub struct _RouteData {
 
 impl _RouteData {
     fn _construct(customers: Vec<usize>, distances: &[Vec<f64>]) -> Self {
+        let config = CONFIG.load();
         assert_eq!(customers.first(), Some(&0));
         assert_eq!(customers.last(), Some(&0));
         assert!(customers.len() >= 3);
@@ -29,7 +35,7 @@ impl _RouteData {
         let mut weight = 0.0;
         for i in 0..customers.len() - 1 {
             distance += distances[customers[i]][customers[i + 1]];
-            weight += CONFIG.demands[customers[i]];
+            weight += config.demands[customers[i]];
         }
 
         Self {
@@ -37,11 +43,25 @@ impl _RouteData {
             value: _RouteDataValues { distance, weight },
         }
     }
+
+    /// Total demand of the customers of the route
+    pub fn weight(&self) -> f64 {
+        self.value.weight
+    }
+
+    /// Total travel distance of the route
+    pub fn distance(&self) -> f64 {
+        self.value.distance
+    }
 }
 
+/// Intra-route neighbors of a route with their sorted tabu customers, indexed by `Neighborhood as usize` and
+/// enumerated on first use
+type _IntraNeighbors<T> = [OnceCell<Vec<(Rc<T>, Vec<usize>)>>; 7];
+
 pub trait Route: Sized {
     fn new(customers: Vec<usize>) -> Rc<Self>;
-    fn single(customer: usize) -> Rc<Self> {
+    fn single(CustomerId(customer): CustomerId) -> Rc<Self> {
         Self::new(vec![0, customer, 0])
     }
     fn get_correct_route<'a>(
@@ -57,7 +77,11 @@ pub trait Route: Sized {
     fn single_route() -> bool;
 
     fn data(&self) -> &_RouteData;
+    fn _intra_neighbors(&self) -> &_IntraNeighbors<Self>;
+    /// Working time of this route, for drone routes when performed by a drone of the first class
     fn working_time(&self) -> f64;
+    /// Working time of this route when performed by vehicle `vehicle` of its type, whose drone class matters
+    fn vehicle_working_time(&self, vehicle: usize) -> f64;
     fn capacity_violation(&self) -> f64;
     fn waiting_time_violation(&self) -> f64;
 
@@ -75,7 +99,11 @@ pub trait Route: Sized {
         Self::new(new_customers)
     }
 
-    fn _servable(customer: usize) -> bool;
+    fn _servable(customer: CustomerId) -> bool;
+
+    /// Whether a route visiting `customers` flies a leg that no feasible route can contain (see
+    /// `Config::with_drone_pairs`)
+    fn _hopeless(customers: &[usize]) -> bool;
 
     /// Extract customer subsegments from this route to form a new route during an inter-route operation.
     ///
@@ -99,7 +127,7 @@ pub trait Route: Sized {
         }
 
         for i in 1..customers.len() - 1 {
-            if T::_servable(customers[i]) {
+            if T::_servable(CustomerId(customers[i])) {
                 queue.push_back(customers[i]);
                 if queue.len() > size {
                     queue.pop_front();
@@ -130,11 +158,15 @@ pub trait Route: Sized {
     /// `r1.inter_route(r2, Neighborhood::Move10)` will move 1 customer from `r1` to `r2`, but not from `r2` to `r1`.
     ///
     /// For symmetric neighborhoods (e.g. `Neighborhood::Move11`), this function will be commutative though.
+    ///
+    /// Each neighbor (the new routes, `None` for an emptied route, and the tabu customers) is passed to `visit` as
+    /// soon as it is constructed, and the enumeration stops early once `visit` breaks.
     fn inter_route<T>(
         &self,
         other: Rc<T>,
         neighborhood: Neighborhood,
-    ) -> Vec<(Option<Rc<Self>>, Option<Rc<T>>, Vec<usize>)>
+        mut visit: impl FnMut(Option<Rc<Self>>, Option<Rc<T>>, &[usize]) -> ControlFlow<()>,
+    ) -> ControlFlow<()>
     where
         T: Route,
     {
@@ -147,12 +179,16 @@ pub trait Route: Sized {
         let mut buffer_i = customers_i.clone();
         let mut buffer_j = customers_j.clone();
 
-        let mut results = vec![];
+        // Neighbors flying a hopeless drone leg are skipped, unless the original route already flies one
+        let (screen_i, screen_j) = (!Self::_hopeless(customers_i), !T::_hopeless(customers_j));
+        let hopeless = |buffer_i: &[usize], buffer_j: &[usize]| {
+            (screen_i && Self::_hopeless(buffer_i)) || (screen_j && T::_hopeless(buffer_j))
+        };
 
         match neighborhood {
             Neighborhood::Move10 => {
                 for (idx_i, &customer_i) in customers_i.iter().enumerate().take(length_i - 1).skip(1) {
-                    if !T::_servable(customer_i) {
+                    if !T::_servable(CustomerId(customer_i)) {
                         continue;
                     }
 
@@ -167,8 +203,10 @@ pub trait Route: Sized {
                     buffer_j.insert(1, removed);
 
                     for idx_j in 1..length_j {
-                        let ptr = T::new(buffer_j.clone());
-                        results.push((route_i.clone(), Some(ptr), tabu.clone()));
+                        if !hopeless(&buffer_i, &buffer_j) {
+                            let ptr = T::new(buffer_j.clone());
+                            visit(route_i.clone(), Some(ptr), &tabu)?;
+                        }
 
                         buffer_j.swap(idx_j, idx_j + 1);
                     }
@@ -179,21 +217,23 @@ pub trait Route: Sized {
             }
             Neighborhood::Move11 => {
                 for idx_i in 1..length_i - 1 {
-                    if !T::_servable(buffer_i[idx_i]) {
+                    if !T::_servable(CustomerId(buffer_i[idx_i])) {
                         continue;
                     }
 
                     for idx_j in 1..length_j - 1 {
-                        if !Self::_servable(buffer_j[idx_j]) {
+                        if !Self::_servable(CustomerId(buffer_j[idx_j])) {
                             continue;
                         }
 
                         swap(&mut buffer_i[idx_i], &mut buffer_j[idx_j]);
 
-                        let ptr_i = Self::new(buffer_i.clone());
-                        let ptr_j = T::new(buffer_j.clone());
-                        let tabu = vec![customers_i[idx_i], customers_j[idx_j]];
-                        results.push((Some(ptr_i), Some(ptr_j), tabu));
+                        if !hopeless(&buffer_i, &buffer_j) {
+                            let ptr_i = Self::new(buffer_i.clone());
+                            let ptr_j = T::new(buffer_j.clone());
+                            let tabu = vec![customers_i[idx_i], customers_j[idx_j]];
+                            visit(Some(ptr_i), Some(ptr_j), &tabu)?;
+                        }
 
                         swap(&mut buffer_i[idx_i], &mut buffer_j[idx_j]);
                     }
@@ -201,7 +241,7 @@ pub trait Route: Sized {
             }
             Neighborhood::Move20 => {
                 for idx_i in 1..length_i - 2 {
-                    if !T::_servable(buffer_i[idx_i]) || !T::_servable(buffer_i[idx_i + 1]) {
+                    if !T::_servable(CustomerId(buffer_i[idx_i])) || !T::_servable(CustomerId(buffer_i[idx_i + 1])) {
                         continue;
                     }
 
@@ -219,8 +259,10 @@ pub trait Route: Sized {
                     buffer_j.insert(2, removed_y);
 
                     for idx_j in 1..length_j {
-                        let ptr = T::new(buffer_j.clone());
-                        results.push((route_i.clone(), Some(ptr), tabu.clone()));
+                        if !hopeless(&buffer_i, &buffer_j) {
+                            let ptr = T::new(buffer_j.clone());
+                            visit(route_i.clone(), Some(ptr), &tabu)?;
+                        }
 
                         buffer_j.swap(idx_j + 1, idx_j + 2);
                         buffer_j.swap(idx_j, idx_j + 1);
@@ -234,7 +276,7 @@ pub trait Route: Sized {
             }
             Neighborhood::Move21 => {
                 for idx_i in 1..length_i - 2 {
-                    if !T::_servable(buffer_i[idx_i]) || !T::_servable(buffer_i[idx_i + 1]) {
+                    if !T::_servable(CustomerId(buffer_i[idx_i])) || !T::_servable(CustomerId(buffer_i[idx_i + 1])) {
                         continue;
                     }
 
@@ -242,11 +284,11 @@ pub trait Route: Sized {
                     buffer_j.insert(2, buffer_i.remove(idx_i + 1));
 
                     for idx_j in 1..length_j - 1 {
-                        if Self::_servable(buffer_j[idx_j]) {
+                        if Self::_servable(CustomerId(buffer_j[idx_j])) && !hopeless(&buffer_i, &buffer_j) {
                             let ptr_i = Self::new(buffer_i.clone());
                             let ptr_j = T::new(buffer_j.clone());
                             let tabu = vec![buffer_j[idx_j], buffer_j[idx_j + 1], buffer_i[idx_i]];
-                            results.push((Some(ptr_i), Some(ptr_j), tabu));
+                            visit(Some(ptr_i), Some(ptr_j), &tabu)?;
                         }
 
                         swap(&mut buffer_i[idx_i], &mut buffer_j[idx_j + 2]);
@@ -260,27 +302,31 @@ pub trait Route: Sized {
             }
             Neighborhood::Move22 => {
                 for idx_i in 1..length_i - 2 {
-                    if !T::_servable(buffer_i[idx_i]) || !T::_servable(buffer_i[idx_i + 1]) {
+                    if !T::_servable(CustomerId(buffer_i[idx_i])) || !T::_servable(CustomerId(buffer_i[idx_i + 1])) {
                         continue;
                     }
 
                     for idx_j in 1..length_j - 2 {
-                        if !Self::_servable(buffer_j[idx_j]) || !Self::_servable(buffer_j[idx_j + 1]) {
+                        if !Self::_servable(CustomerId(buffer_j[idx_j]))
+                            || !Self::_servable(CustomerId(buffer_j[idx_j + 1]))
+                        {
                             continue;
                         }
 
                         swap(&mut buffer_i[idx_i], &mut buffer_j[idx_j]);
                         swap(&mut buffer_i[idx_i + 1], &mut buffer_j[idx_j + 1]);
 
-                        let ptr_i = Self::new(buffer_i.clone());
-                        let ptr_j = T::new(buffer_j.clone());
-                        let tabu = vec![
-                            buffer_i[idx_i],
-                            buffer_i[idx_i + 1],
-                            buffer_j[idx_j],
-                            buffer_j[idx_j + 1],
-                        ];
-                        results.push((Some(ptr_i), Some(ptr_j), tabu));
+                        if !hopeless(&buffer_i, &buffer_j) {
+                            let ptr_i = Self::new(buffer_i.clone());
+                            let ptr_j = T::new(buffer_j.clone());
+                            let tabu = vec![
+                                buffer_i[idx_i],
+                                buffer_i[idx_i + 1],
+                                buffer_j[idx_j],
+                                buffer_j[idx_j + 1],
+                            ];
+                            visit(Some(ptr_i), Some(ptr_j), &tabu)?;
+                        }
 
                         swap(&mut buffer_i[idx_i], &mut buffer_j[idx_j]);
                         swap(&mut buffer_i[idx_i + 1], &mut buffer_j[idx_j + 1]);
@@ -289,12 +335,12 @@ pub trait Route: Sized {
             }
             Neighborhood::TwoOpt => {
                 let mut offset_i = length_i - 1;
-                while offset_i > 1 && T::_servable(buffer_i[offset_i - 1]) {
+                while offset_i > 1 && T::_servable(CustomerId(buffer_i[offset_i - 1])) {
                     offset_i -= 1;
                 }
 
                 let mut offset_j = length_j - 1;
-                while offset_j > 1 && Self::_servable(buffer_j[offset_j - 1]) {
+                while offset_j > 1 && Self::_servable(CustomerId(buffer_j[offset_j - 1])) {
                     offset_j -= 1;
                 }
 
@@ -306,13 +352,16 @@ pub trait Route: Sized {
 
                         buffer_i.extend_from_slice(&customers_j[idx_j..]);
                         buffer_j.extend_from_slice(&customers_i[idx_i..]);
+                        if hopeless(&buffer_i, &buffer_j) {
+                            continue;
+                        }
 
                         let tabu = vec![buffer_i[idx_i], buffer_j[idx_j]];
 
                         // Move the buffers to the new routes
                         let ptr_i = Self::new(buffer_i);
                         let ptr_j = T::new(buffer_j);
-                        results.push((Some(ptr_i), Some(ptr_j), tabu));
+                        visit(Some(ptr_i), Some(ptr_j), &tabu)?;
                     }
                 }
             }
@@ -352,7 +401,7 @@ pub trait Route: Sized {
             //                             .chain(customers_j[l_j..r_j].iter())
             //                             .copied()
             //                             .collect();
-            //                         results.push((Some(ptr_i), Some(ptr_j), tabu));
+            //                         visit(Some(ptr_i), Some(ptr_j), &tabu)?;
             //                     }
 
             //                     buffer_i.clone_from(customers_i);
@@ -365,7 +414,7 @@ pub trait Route: Sized {
             _ => panic!("inter_route called with invalid neighborhood {neighborhood}"),
         }
 
-        results
+        ControlFlow::Continue(())
     }
 
     fn inter_route_3<T1, T2>(
@@ -395,13 +444,13 @@ pub trait Route: Sized {
         match neighborhood {
             Neighborhood::EjectionChain => {
                 for idx_i in 1..length_i - 1 {
-                    if !T1::_servable(buffer_i[idx_i]) {
+                    if !T1::_servable(CustomerId(buffer_i[idx_i])) {
                         continue;
                     }
 
                     let remove_x = buffer_i.remove(idx_i);
                     for idx_j in 1..length_j - 1 {
-                        if !T2::_servable(buffer_j[idx_j]) {
+                        if !T2::_servable(CustomerId(buffer_j[idx_j])) {
                             continue;
                         }
 
@@ -435,12 +484,33 @@ pub trait Route: Sized {
         results
     }
 
-    /// Returns a pointer to the underlying cached intra-route neighbors.
-    fn intra_route(&self, neighborhood: Neighborhood) -> Vec<(Rc<Self>, Vec<usize>)> {
+    /// Perform intra-route neighborhood search, passing each neighbor and its sorted tabu customers to `visit`. The
+    /// enumeration stops early once `visit` breaks. The neighbors are enumerated once per route and neighborhood, then
+    /// cached for the later searches from the same route (e.g. while its vehicle remains the decisive one).
+    fn intra_route(
+        &self,
+        neighborhood: Neighborhood,
+        mut visit: impl FnMut(Rc<Self>, &[usize]) -> ControlFlow<()>,
+    ) -> ControlFlow<()> {
+        let neighbors =
+            self._intra_neighbors()[neighborhood as usize].get_or_init(|| self._enumerate_intra_route(neighborhood));
+        for (route, tabu) in neighbors {
+            visit(route.clone(), tabu)?;
+        }
+
+        ControlFlow::Continue(())
+    }
+
+    /// Every intra-route neighbor of this route in `neighborhood`, with its sorted tabu customers
+    fn _enumerate_intra_route(&self, neighborhood: Neighborhood) -> Vec<(Rc<Self>, Vec<usize>)> {
         let data = self.data();
 
         let length = data.customers.len();
-        let mut results = vec![];
+        let mut neighbors = vec![];
+        let mut emit = |route: Rc<Self>, mut tabu: Vec<usize>| {
+            tabu.sort();
+            neighbors.push((route, tabu));
+        };
         let mut buffer = data.customers.clone();
         match neighborhood {
             Neighborhood::Move10 => {
@@ -451,7 +521,7 @@ pub trait Route: Sized {
                         let ptr = Self::new(buffer.clone());
                         let tabu = vec![data.customers[i]];
                         // println!("buffer = {:?}, tabu = {:?}", buffer, tabu);
-                        results.push((ptr, tabu));
+                        emit(ptr, tabu);
                     }
 
                     buffer[i..length - 1].rotate_right(1);
@@ -464,7 +534,7 @@ pub trait Route: Sized {
                         let ptr = Self::new(buffer.clone());
                         let tabu = vec![data.customers[i]];
                         // println!("buffer = {:?}, tabu = {:?}", buffer, tabu);
-                        results.push((ptr, tabu));
+                        emit(ptr, tabu);
                     }
 
                     buffer[1..i + 1].rotate_left(1);
@@ -479,7 +549,7 @@ pub trait Route: Sized {
                         let ptr = Self::new(buffer.clone());
                         let tabu = vec![data.customers[i], data.customers[j + 1]];
                         // println!("buffer = {:?}, tabu = {:?}", buffer, tabu);
-                        results.push((ptr, tabu));
+                        emit(ptr, tabu);
                     }
 
                     buffer.swap(i, length - 2);
@@ -494,7 +564,7 @@ pub trait Route: Sized {
                         let ptr = Self::new(buffer.clone());
                         let tabu = vec![data.customers[i], data.customers[i + 1]];
                         // println!("buffer = {:?}, tabu = {:?}", buffer, tabu);
-                        results.push((ptr, tabu));
+                        emit(ptr, tabu);
                     }
 
                     buffer[i..length - 1].rotate_right(2);
@@ -508,7 +578,7 @@ pub trait Route: Sized {
                         let ptr = Self::new(buffer.clone());
                         let tabu = vec![data.customers[i], data.customers[i + 1]];
                         // println!("buffer = {:?}, tabu = {:?}", buffer, tabu);
-                        results.push((ptr, tabu));
+                        emit(ptr, tabu);
                     }
 
                     buffer[1..i + 2].rotate_left(2);
@@ -524,7 +594,7 @@ pub trait Route: Sized {
                         let ptr = Self::new(buffer.clone());
                         let tabu = vec![data.customers[i], data.customers[i + 1], data.customers[j + 2]];
                         // println!("buffer = {:?}, tabu = {:?}", buffer, tabu);
-                        results.push((ptr, tabu));
+                        emit(ptr, tabu);
                     }
 
                     buffer.swap(i, length - 3);
@@ -540,7 +610,7 @@ pub trait Route: Sized {
                         let ptr = Self::new(buffer.clone());
                         let tabu = vec![data.customers[i], data.customers[i + 1], data.customers[j]];
                         // println!("buffer = {:?}, tabu = {:?}", buffer, tabu);
-                        results.push((ptr, tabu));
+                        emit(ptr, tabu);
                     }
 
                     buffer.swap(1, i + 1);
@@ -561,7 +631,7 @@ pub trait Route: Sized {
                             data.customers[i + 3],
                         ];
                         // println!("buffer = {:?}, tabu = {:?}", buffer, tabu);
-                        results.push((ptr, tabu));
+                        emit(ptr, tabu);
                     }
 
                     for j in i + 3..length - 2 {
@@ -578,7 +648,7 @@ pub trait Route: Sized {
                             data.customers[j + 1],
                         ];
                         // println!("buffer = {:?}, tabu = {:?}", buffer, tabu);
-                        results.push((ptr, tabu));
+                        emit(ptr, tabu);
                     }
 
                     buffer.swap(i, length - 3);
@@ -586,6 +656,8 @@ pub trait Route: Sized {
                 }
             }
             Neighborhood::TwoOpt => {
+                // Every neighbor is re-priced from scratch in `Self::new`, so reversed segments are also correct for
+                // asymmetric distances
                 for i in 1..length - 2 {
                     {
                         buffer.swap(i, i + 1);
@@ -593,7 +665,7 @@ pub trait Route: Sized {
                         let ptr = Self::new(buffer.clone());
                         let tabu = vec![data.customers[i], data.customers[i + 1]];
                         // println!("buffer = {:?}, tabu = {:?}", buffer, tabu);
-                        results.push((ptr, tabu));
+                        emit(ptr, tabu);
                     }
 
                     for j in i + 2..length - 1 {
@@ -602,7 +674,7 @@ pub trait Route: Sized {
                         let ptr = Self::new(buffer.clone());
                         let tabu = vec![data.customers[i], data.customers[j]];
                         // println!("buffer = {:?}, tabu = {:?}", buffer, tabu);
-                        results.push((ptr, tabu));
+                        emit(ptr, tabu);
                     }
 
                     buffer[i..length - 1].reverse();
@@ -611,16 +683,13 @@ pub trait Route: Sized {
             _ => panic!("intra_route called with invalid neighborhood {neighborhood}"),
         }
 
-        for (_, tabu) in results.iter_mut() {
-            tabu.sort();
-        }
-
-        results
+        neighbors
     }
 }
 
 pub struct TruckRoute {
     _data: _RouteData,
+    _intra_neighbors: _IntraNeighbors<Self>,
     _working_time: f64,
     _capacity_violation: f64,
     _waiting_time_violation: f64,
@@ -636,7 +705,7 @@ impl Route for TruckRoute {
     fn new(customers: Vec<usize>) -> Rc<Self> {
         Rc::new(Self::_construct(_RouteData::_construct(
             customers.clone(),
-            &CONFIG.truck_distances,
+            &CONFIG.load().truck_distances,
         )))
     }
 
@@ -659,17 +728,25 @@ impl Route for TruckRoute {
     }
 
     fn single_route() -> bool {
-        CONFIG.single_truck_route
+        CONFIG.load().single_truck_route
     }
 
     fn data(&self) -> &_RouteData {
         &self._data
     }
 
+    fn _intra_neighbors(&self) -> &_IntraNeighbors<Self> {
+        &self._intra_neighbors
+    }
+
     fn working_time(&self) -> f64 {
         self._working_time
     }
 
+    fn vehicle_working_time(&self, _: usize) -> f64 {
+        self._working_time
+    }
+
     fn capacity_violation(&self) -> f64 {
         self._capacity_violation
     }
@@ -678,32 +755,55 @@ impl Route for TruckRoute {
         self._waiting_time_violation
     }
 
-    fn _servable(_customer: usize) -> bool {
-        true
+    fn _servable(CustomerId(customer): CustomerId) -> bool {
+        CONFIG.load().truckable[customer]
+    }
+
+    fn _hopeless(_: &[usize]) -> bool {
+        false
     }
 }
 
 impl TruckRoute {
+    /// Each sample waits from its pickup until the truck returns to the depot. Reloading before a later route
+    /// delays both equally, so the reload time does not contribute to the waiting time.
     fn _calculate_waiting_time_violation(customers: &[usize], working_time: f64) -> f64 {
-        let speed = CONFIG.truck.speed;
+        let config = CONFIG.load();
+        let arrivals = Self::_arrival_times(customers);
         let mut waiting_time_violation = 0.0;
-        let mut accumulate_time = 0.0;
         for i in 1..customers.len() - 1 {
-            accumulate_time += CONFIG.truck_distances[customers[i - 1]][customers[i]] / speed;
-            waiting_time_violation += (working_time - accumulate_time - CONFIG.waiting_time_limit).max(0.0);
+            waiting_time_violation += (working_time - arrivals[i] - config.waiting_time_limits[customers[i]]).max(0.0);
         }
 
         waiting_time_violation
     }
 
+    fn _arrival_times(customers: &[usize]) -> Vec<f64> {
+        let config = CONFIG.load();
+        let speed = config.truck.speed;
+        let mut arrivals = vec![0.0; customers.len()];
+        for i in 1..customers.len() {
+            arrivals[i] = arrivals[i - 1] + config.truck_distances[customers[i - 1]][customers[i]] / speed;
+        }
+
+        arrivals
+    }
+
+    /// Arrival time at each node of the route (including the depot at both ends), relative to its start
+    pub fn arrival_times(&self) -> Vec<f64> {
+        Self::_arrival_times(&self._data.customers)
+    }
+
     fn _construct(data: _RouteData) -> Self {
-        let speed = CONFIG.truck.speed;
+        let config = CONFIG.load();
+        let speed = config.truck.speed;
         let _working_time = data.value.distance / speed;
-        let _capacity_violation = (data.value.weight - CONFIG.truck.capacity).max(0.0);
+        let _capacity_violation = (data.value.weight - config.truck.capacity).max(0.0);
         let _waiting_time_violation = Self::_calculate_waiting_time_violation(&data.customers, _working_time);
 
         Self {
             _data: data,
+            _intra_neighbors: Default::default(),
             _working_time,
             _capacity_violation,
             _waiting_time_violation,
@@ -711,14 +811,45 @@ impl TruckRoute {
     }
 }
 
-pub struct DroneRoute {
-    _data: _RouteData,
-    _working_time: f64,
-    _capacity_violation: f64,
-    _waiting_time_violation: f64,
-
+/// Evaluation of a drone route against the parameters of a drone class
+#[derive(Clone, Copy, Debug)]
+pub struct DroneMetrics {
+    pub working_time: f64,
+    pub capacity_violation: f64,
+    pub waiting_time_violation: f64,
     pub energy_violation: f64,
     pub fixed_time_violation: f64,
+    /// Number of legs crossing a no-fly zone (see `--no-fly-zones`) or flown against a headwind faster than the
+    /// cruise speed
+    pub no_fly_violation: f64,
+    /// Total energy consumed along the route
+    pub energy: f64,
+}
+
+/// One leg of a drone route, from a node to the next (see `--detailed-routes`)
+#[derive(Clone, Debug, Serialize)]
+pub struct DroneLeg {
+    pub from: usize,
+    pub to: usize,
+    /// Weight carried along the leg
+    pub payload: f64,
+    /// Takeoff, cruise and landing time of the leg
+    pub time: f64,
+    /// Energy consumed by the takeoff, cruise and landing of the leg
+    pub energy: f64,
+    /// Battery energy left at the end of the leg, negative when the route exceeds the battery
+    pub remaining_energy: f64,
+    /// Energy recharged at a charging station at the end of the leg (see `--charging-stations`)
+    pub charge: f64,
+}
+
+pub struct DroneRoute {
+    _data: _RouteData,
+    _intra_neighbors: _IntraNeighbors<Self>,
+    /// Metrics of this route for the first drone class, also reported by `Route` methods
+    _metrics: DroneMetrics,
+    /// Metrics of this route for the remaining drone classes (empty for a homogeneous fleet)
+    _other_metrics: Vec<DroneMetrics>,
 }
 
 impl fmt::Debug for DroneRoute {
@@ -731,7 +862,7 @@ impl Route for DroneRoute {
     fn new(customers: Vec<usize>) -> Rc<Self> {
         Rc::new(Self::_construct(_RouteData::_construct(
             customers.clone(),
-            &CONFIG.drone_distances,
+            &CONFIG.load().drone_distances,
         )))
     }
 
@@ -750,7 +881,7 @@ impl Route for DroneRoute {
     }
 
     fn single_customer() -> bool {
-        CONFIG.single_drone_route
+        CONFIG.load().single_drone_route
     }
 
     fn single_route() -> bool {
@@ -761,66 +892,259 @@ impl Route for DroneRoute {
         &self._data
     }
 
+    fn _intra_neighbors(&self) -> &_IntraNeighbors<Self> {
+        &self._intra_neighbors
+    }
+
     fn working_time(&self) -> f64 {
-        self._working_time
+        self._metrics.working_time
+    }
+
+    fn vehicle_working_time(&self, vehicle: usize) -> f64 {
+        self.metrics(DroneId(vehicle)).working_time
     }
 
     fn capacity_violation(&self) -> f64 {
-        self._capacity_violation
+        self._metrics.capacity_violation
     }
 
     fn waiting_time_violation(&self) -> f64 {
-        self._waiting_time_violation
+        self._metrics.waiting_time_violation
+    }
+
+    fn _servable(CustomerId(customer): CustomerId) -> bool {
+        CONFIG.load().dronable[customer]
     }
 
-    fn _servable(customer: usize) -> bool {
-        CONFIG.dronable[customer]
+    fn _hopeless(customers: &[usize]) -> bool {
+        customers
+            .windows(2)
+            .any(|leg| !CONFIG.load().drone_pairs[leg[0]][leg[1]])
     }
 }
 
 impl DroneRoute {
-    fn _construct(data: _RouteData) -> Self {
+    /// Metrics of this route when performed by `drone`
+    pub fn metrics(&self, drone: DroneId) -> &DroneMetrics {
+        self.class_metrics_of(CONFIG.load().drone_class_of(drone))
+    }
+
+    /// Metrics of this route when performed by a drone of class `class`
+    pub fn class_metrics_of(&self, class: usize) -> &DroneMetrics {
+        match class {
+            0 => &self._metrics,
+            class => &self._other_metrics[class - 1],
+        }
+    }
+
+    /// Metrics of this route for every drone class
+    pub fn class_metrics(&self) -> impl Iterator<Item = &DroneMetrics> {
+        iter::once(&self._metrics).chain(self._other_metrics.iter())
+    }
+
+    /// Cruise time of the leg from the `i`-th node of `customers` to the next one. A leg the drone cannot fly
+    /// against the wind takes its still-air cruise time and counts as a no-fly violation instead, so that routes
+    /// through it stay comparable.
+    fn _leg_cruise_time(customers: &[usize], i: usize, drone: &DroneConfig) -> f64 {
+        let config = CONFIG.load();
+        let distance = config.drone_distances[customers[i]][customers[i + 1]];
+        let cruise = drone.leg_cruise_time(distance, customers[i], customers[i + 1]);
+        if cruise.is_finite() {
+            cruise
+        } else {
+            drone.cruise_time(distance)
+        }
+    }
+
+    /// Landing time at each node of the route (including the depot at both ends) when performed by `drone`,
+    /// relative to its start. The drone takes off again immediately, unless it recharges at a charging station.
+    pub fn arrival_times(&self, drone: DroneId) -> Vec<f64> {
+        let config = CONFIG.load();
+        let class = &config.drone_classes[config.drone_class_of(drone)];
+        let customers = &self._data.customers;
+        let (charges, _) = Self::_charges(customers, class);
+        let mut arrivals = vec![0.0; customers.len()];
+        for i in 1..customers.len() {
+            arrivals[i] = arrivals[i - 1]
+                + charges.get(i - 1).map_or(0.0, |charge| charge / config.charging_power)
+                + class.takeoff_time()
+                + Self::_leg_cruise_time(customers, i - 1, class)
+                + class.landing_time();
+        }
+
+        arrivals
+    }
+
+    /// Energy consumed by `drone` on the leg from the `i`-th node of `customers` to the next: take off, cruise for
+    /// `cruise` seconds, climb to the elevation of the next node and land while carrying `weight`
+    fn _leg_energy(drone: &DroneConfig, customers: &[usize], i: usize, weight: f64, cruise: f64) -> f64 {
+        drone.leg_energy(weight, cruise) + drone.climb_energy(weight, customers[i], customers[i + 1])
+    }
+
+    /// Energy recharged by `drone` at each node of `customers` (see `--charging-stations`), empty when the route
+    /// visits no charging station, and the energy the route lacks. At each station, the drone recharges just enough
+    /// to reach the next station or the depot: as the charging time is proportional to the energy, this minimizes it.
+    fn _charges(customers: &[usize], drone: &DroneConfig) -> (Vec<f64>, f64) {
+        let config = CONFIG.load();
+        let stations = !config.charging_nodes.is_empty()
+            && customers[1..customers.len() - 1]
+                .iter()
+                .any(|&c| config.charging_nodes[c]);
+        if !stations {
+            return (vec![], 0.0);
+        }
+
+        let mut weight = 0.0;
+        let mut energies = vec![];
+        for i in 0..customers.len() - 1 {
+            energies.push(Self::_leg_energy(
+                drone,
+                customers,
+                i,
+                weight,
+                Self::_leg_cruise_time(customers, i, drone),
+            ));
+            weight += config.demands[customers[i]];
+        }
+
+        let mut charges = vec![0.0; customers.len()];
+        let mut level = drone.battery();
+        let mut missing = 0.0;
+        let mut i = 0;
+        while i < energies.len() {
+            let mut j = i + 1;
+            while j < customers.len() - 1 && !config.charging_nodes[customers[j]] {
+                j += 1;
+            }
+
+            let needed = energies[i..j].iter().sum::<f64>();
+            if i > 0 {
+                charges[i] = (needed.min(drone.battery()) - level).max(0.0);
+                level += charges[i];
+            }
+            level -= needed;
+            if level < 0.0 {
+                missing -= level;
+                level = 0.0;
+            }
+
+            i = j;
+        }
+
+        (charges, missing)
+    }
+
+    /// Legs of this route when performed by `drone`, evaluated with the same energy model as its metrics
+    pub fn legs(&self, drone: DroneId) -> Vec<DroneLeg> {
+        let config = CONFIG.load();
+        let class = &config.drone_classes[config.drone_class_of(drone)];
+        let customers = &self._data.customers;
+        let (charges, _) = Self::_charges(customers, class);
+
+        let mut legs = vec![];
+        let mut remaining_energy = class.battery();
+        let mut weight = 0.0;
+        for i in 0..customers.len() - 1 {
+            let cruise = Self::_leg_cruise_time(customers, i, class);
+            let energy = Self::_leg_energy(class, customers, i, weight, cruise);
+            let charge = charges.get(i + 1).copied().unwrap_or(0.0);
+            remaining_energy -= energy;
+            legs.push(DroneLeg {
+                from: customers[i],
+                to: customers[i + 1],
+                payload: weight,
+                time: class.takeoff_time() + cruise + class.landing_time(),
+                energy,
+                remaining_energy,
+                charge,
+            });
+            remaining_energy += charge;
+            weight += config.demands[customers[i]];
+        }
+
+        legs
+    }
+
+    fn _evaluate(data: &_RouteData, drone: &DroneConfig) -> DroneMetrics {
+        let config = CONFIG.load();
         let customers = &data.customers;
-        let distances = &CONFIG.drone_distances;
-        let drone = &CONFIG.drone;
 
-        let _working_time = (CONFIG.drone.takeoff_time() + CONFIG.drone.landing_time()).mul_add(
-            customers.len() as f64 - 1.0,
-            CONFIG.drone.cruise_time(data.value.distance),
-        );
-        let _capacity_violation = (data.value.weight - CONFIG.drone.capacity()).max(0.0);
+        let cruise_time = if drone.windy() {
+            (0..customers.len() - 1)
+                .map(|i| Self::_leg_cruise_time(customers, i, drone))
+                .sum::<f64>()
+        } else {
+            drone.cruise_time(data.value.distance)
+        };
+        let (charges, missing_energy) = Self::_charges(customers, drone);
+        let charging_time = charges.iter().sum::<f64>() / config.charging_power;
+        let working_time = (drone.takeoff_time() + drone.landing_time())
+            .mul_add(customers.len() as f64 - 1.0, cruise_time)
+            + charging_time;
+        let capacity_violation = (data.value.weight - drone.capacity()).max(0.0);
 
         let mut time = 0.0;
         let mut energy = 0.0;
         let mut weight = 0.0;
-        let mut _waiting_time_violation = 0.0;
+        let mut waiting_time_violation = 0.0;
 
         let takeoff = drone.takeoff_time();
         let landing = drone.landing_time();
         for i in 0..customers.len() - 1 {
-            let cruise = drone.cruise_time(distances[customers[i]][customers[i + 1]]);
-
-            time += takeoff + cruise + landing;
-            energy += drone.landing_power(weight).mul_add(
-                landing,
-                drone
-                    .takeoff_power(weight)
-                    .mul_add(takeoff, drone.cruise_power(weight) * cruise),
-            );
-            weight += CONFIG.demands[customers[i]];
-            _waiting_time_violation += (_working_time - time - CONFIG.waiting_time_limit).max(0.0);
+            let cruise = Self::_leg_cruise_time(customers, i, drone);
+
+            time += charges.get(i).map_or(0.0, |charge| charge / config.charging_power) + takeoff + cruise + landing;
+            energy += Self::_leg_energy(drone, customers, i, weight, cruise);
+            weight += config.demands[customers[i]];
+            waiting_time_violation += (working_time - time - config.waiting_time_limits[customers[i]]).max(0.0);
         }
 
-        let energy_violation = (energy - CONFIG.drone.battery()).max(0.0);
-        let fixed_time_violation = (_working_time - CONFIG.drone.fixed_time()).max(0.0);
+        let no_fly_violation = if config.no_fly.is_empty() {
+            0
+        } else {
+            customers.windows(2).filter(|leg| config.no_fly[leg[0]][leg[1]]).count()
+        };
+        let unflyable = if drone.windy() {
+            customers
+                .windows(2)
+                .filter(|leg| {
+                    drone
+                        .leg_cruise_time(config.drone_distances[leg[0]][leg[1]], leg[0], leg[1])
+                        .is_infinite()
+                })
+                .count()
+        } else {
+            0
+        };
+
+        DroneMetrics {
+            working_time,
+            capacity_violation,
+            waiting_time_violation,
+            energy_violation: if charges.is_empty() {
+                (energy - drone.battery()).max(0.0)
+            } else {
+                missing_energy
+            },
+            fixed_time_violation: (working_time - drone.fixed_time()).max(0.0),
+            no_fly_violation: (no_fly_violation + unflyable) as f64,
+            energy,
+        }
+    }
+
+    fn _construct(data: _RouteData) -> Self {
+        let config = CONFIG.load();
+        let _metrics = Self::_evaluate(&data, &config.drone_classes[0]);
+        let _other_metrics = config.drone_classes[1..]
+            .iter()
+            .map(|drone| Self::_evaluate(&data, drone))
+            .collect();
 
         Self {
             _data: data,
-            _working_time,
-            _capacity_violation,
-            _waiting_time_violation,
-            energy_violation,
-            fixed_time_violation,
+            _intra_neighbors: Default::default(),
+            _metrics,
+            _other_metrics,
         }
     }
 }
@@ -0,0 +1,81 @@
+use std::error::Error;
+use std::path::Path;
+
+use plotters::prelude::*;
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct Visit {
+    pub customer: usize,
+    pub arrival: f64,
+    /// Later than `arrival` only for trucks waiting for a drone sortie to return
+    pub departure: f64,
+}
+
+#[derive(Serialize)]
+pub struct SortieVisit {
+    pub customer: usize,
+    pub launch: f64,
+    pub arrival: f64,
+}
+
+#[derive(Serialize)]
+pub struct Trip {
+    pub route: usize,
+    pub start: f64,
+    pub end: f64,
+    pub visits: Vec<Visit>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sorties: Vec<SortieVisit>,
+}
+
+#[derive(Serialize)]
+pub struct VehicleSchedule {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub vehicle: usize,
+    pub trips: Vec<Trip>,
+}
+
+/// Render `schedule` as a Gantt chart to the SVG file `path`: one row per vehicle (trucks first, then drones), one bar
+/// per trip in the color of the vehicle, with a tick at each customer arrival and a dot at each sortie pickup.
+pub fn gantt(schedule: &[VehicleSchedule], path: &Path) -> Result<(), Box<dyn Error>> {
+    const ROW: u32 = 30;
+
+    let horizon = schedule
+        .iter()
+        .flat_map(|v| v.trips.iter().map(|t| t.end))
+        .fold(1.0, f64::max);
+    let rows = schedule.len().max(1);
+
+    let root = SVGBackend::new(path, (1200, ROW * rows as u32 + 20)).into_drawing_area();
+    root.fill(&WHITE)?;
+
+    let mut chart = ChartBuilder::on(&root)
+        .margin(10)
+        .build_cartesian_2d(0.0..horizon * 1.02, 0.0..rows as f64)?;
+
+    for (row, vehicle) in schedule.iter().enumerate() {
+        let color = Palette99::pick(row);
+        let (top, bottom) = ((rows - row) as f64 - 0.15, (rows - row - 1) as f64 + 0.15);
+        for trip in &vehicle.trips {
+            chart.draw_series([Rectangle::new(
+                [(trip.start, top), (trip.end, bottom)],
+                color.mix(0.5).filled(),
+            )])?;
+            chart.draw_series(
+                trip.visits
+                    .iter()
+                    .map(|v| PathElement::new([(v.arrival, top), (v.arrival, bottom)], BLACK.stroke_width(1))),
+            )?;
+            chart.draw_series(
+                trip.sorties
+                    .iter()
+                    .map(|s| Circle::new((s.arrival, (top + bottom) / 2.0), 3, BLUE.filled())),
+            )?;
+        }
+    }
+
+    root.present()?;
+    Ok(())
+}
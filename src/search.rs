@@ -0,0 +1,568 @@
+use std::cmp;
+use std::rc::Rc;
+
+use rand::distr::weighted::WeightedIndex;
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use tracing::{debug, debug_span, info, trace};
+
+use crate::cli::{Acceptance, Strategy};
+use crate::config::CONFIG;
+use crate::islands::Island;
+use crate::logger::{AdaptiveSegment, Logger, NeighborhoodStatistics};
+use crate::neighborhoods::{self, Neighborhood};
+use crate::pareto::ParetoArchive;
+use crate::pool::RoutePool;
+use crate::profile::{self, Phase};
+use crate::routes::Route;
+use crate::solutions::{self, FrequencyMemory, NEIGHBORHOODS, Solution, TOLERANCE};
+use crate::top::TopSolutions;
+
+/// Neighborhood selection state of `--strategy adaptive` (segments, scores and weights) and `--strategy ucb` (rewards
+/// and selections)
+pub struct AdaptiveState {
+    pub segment: usize,
+    segment_reset: usize,
+    last_improved_segment: usize,
+    scores: Vec<f64>,
+    pub weights: Vec<f64>,
+    occurences: Vec<u32>,
+    ucb_rewards: Vec<f64>,
+    ucb_selections: Vec<u32>,
+}
+
+impl AdaptiveState {
+    fn new() -> Self {
+        Self {
+            segment: 0,
+            segment_reset: 0,
+            last_improved_segment: 0,
+            scores: vec![0.0; NEIGHBORHOODS.len()],
+            weights: vec![1.0; NEIGHBORHOODS.len()],
+            occurences: vec![0; NEIGHBORHOODS.len()],
+            ucb_rewards: vec![0.0; NEIGHBORHOODS.len()],
+            ucb_selections: vec![0; NEIGHBORHOODS.len()],
+        }
+    }
+}
+
+/// Everything the tabu search carries from one iteration to the next: the current and best solutions, the tabu lists,
+/// the elite set and edge records used by the resets, the adaptive penalty coefficients, the long-term memories, the
+/// neighborhood selection state and the random number generator. The penalty coefficients are mirrored to the thread
+/// running the search since every evaluation reads them (see `solutions::set_penalty_coeffs`).
+pub struct SearchState {
+    pub current: Rc<Solution>,
+    pub result: Rc<Solution>,
+    pub last_improved_iteration: usize,
+    pub adaptive: AdaptiveState,
+    pub statistics: Vec<NeighborhoodStatistics>,
+    pub pool: RoutePool,
+    pub archive: ParetoArchive,
+    pub top: TopSolutions,
+    neighborhood_idx: usize,
+    /// Indices in `NEIGHBORHOODS` of the neighborhoods that may be selected (see `Neighborhood::assumes_symmetric`)
+    selectable: Vec<usize>,
+    tabu_lists: Vec<Vec<Vec<usize>>>,
+    tabu_size: usize,
+    edge_records: Vec<Vec<f64>>,
+    elite_set: Vec<Rc<Solution>>,
+    /// Resets since the last new best solution, and whether one of them rebuilt the solution (see `--recluster-after`)
+    stagnant_resets: usize,
+    reclustered: bool,
+    frequency: FrequencyMemory,
+    lahc_history: Vec<f64>,
+    /// Adaptive penalty coefficient of each violation (see `adapt_penalties`)
+    penalties: [f64; 7],
+    rng: StdRng,
+}
+
+impl SearchState {
+    /// Initial state of a search from `root` with tabu lists of `tabu_size` moves (unused by `--acceptance lahc`),
+    /// with penalty coefficients of 1 and a randomly seeded generator
+    pub fn new(root: Solution, tabu_size: usize) -> Self {
+        let config = CONFIG.load();
+        solutions::set_penalty_coeffs([1.0; 7]);
+        let root = Rc::new(root);
+        let selectable = solutions::selectable_neighborhoods();
+        Self {
+            current: root.clone(),
+            result: root.clone(),
+            last_improved_iteration: 0,
+            adaptive: AdaptiveState::new(),
+            statistics: NEIGHBORHOODS.iter().map(|&n| NeighborhoodStatistics::new(n)).collect(),
+            pool: RoutePool::new(),
+            archive: ParetoArchive::new(),
+            top: TopSolutions::new(),
+            neighborhood_idx: selectable[0],
+            selectable,
+            tabu_lists: vec![vec![]; NEIGHBORHOODS.len()],
+            tabu_size: match config.acceptance {
+                Acceptance::Tabu => tabu_size,
+                Acceptance::Lahc => 0,
+            },
+            edge_records: vec![vec![f64::MAX; config.customers_count + 1]; config.customers_count + 1],
+            elite_set: vec![root.clone()],
+            stagnant_resets: 0,
+            reclustered: false,
+            frequency: FrequencyMemory::new(),
+            lahc_history: vec![root.cost(); config.lahc_length.max(1)],
+            penalties: [1.0; 7],
+            rng: StdRng::from_rng(&mut rand::rng()),
+        }
+    }
+
+    /// Seed the generator behind the random choices of the search state (neighborhood selection and the elite
+    /// solution restarted from) with `seed`
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    /// Neighborhood searched at the current iteration
+    pub fn neighborhood(&self) -> Neighborhood {
+        NEIGHBORHOODS[self.neighborhood_idx]
+    }
+
+    /// Trace the progress of the search at the start of `iteration`
+    pub fn trace(&self, iteration: usize, reset_after: usize, logger: &Logger) {
+        let config = CONFIG.load();
+        let adaptive = &self.adaptive;
+        let extra = if let Strategy::Adaptive = config.strategy {
+            format!(
+                "(segments before reset {})",
+                if config.adaptive_fixed_segments {
+                    adaptive
+                        .segment
+                        .saturating_sub(adaptive.segment_reset + config.adaptive_segments)
+                } else {
+                    config.adaptive_segments.saturating_sub(
+                        adaptive.segment - cmp::max(adaptive.segment_reset, adaptive.last_improved_segment),
+                    )
+                }
+            )
+        } else {
+            format!(
+                "(reset in {})",
+                reset_after.saturating_sub((iteration - self.last_improved_iteration) % reset_after)
+            )
+        };
+
+        let gap = logger
+            .bks_gap(self.result.objective())
+            .map(|g| format!(", gap {g:.2}%"))
+            .unwrap_or_default();
+        trace!(
+            "Iteration #{} {}: {:.2}/{:.2}{}, elite set {}/{}",
+            iteration,
+            extra,
+            self.current.cost(),
+            self.result.cost(),
+            gap,
+            self.elite_set.len(),
+            config.max_elite_size
+        );
+    }
+
+    /// Best non-tabu neighbor of the current solution in the current neighborhood
+    pub fn search_neighborhood(&mut self) -> Option<Solution> {
+        let neighborhood = self.neighborhood();
+        neighborhoods::take_tabu_blocked(); // Discard the moves blocked during earlier ejection chains
+        let found = profile::time(Phase::Neighborhood(neighborhood), || {
+            neighborhood.search(
+                &self.current,
+                &mut self.tabu_lists[self.neighborhood_idx],
+                self.tabu_size,
+                self.result.cost(),
+                &mut self.rng,
+            )
+        });
+
+        let statistics = &mut self.statistics[self.neighborhood_idx];
+        statistics.selected += 1;
+        statistics.tabu_blocked += neighborhoods::take_tabu_blocked();
+        found
+    }
+
+    /// Reward the current neighborhood for `neighbor` found at `iteration`, record it if it improves the best solution
+    /// (after rebalancing its drone routes with `--drone-rebalancing`) and make it the current solution if the
+    /// acceptance criterion allows, returning whether it did
+    pub fn accept(&mut self, neighbor: Rc<Solution>, iteration: usize) -> bool {
+        let config = CONFIG.load();
+        let idx = self.neighborhood_idx;
+        let neighbor =
+            if config.drone_rebalancing && neighbor.feasible && neighbor.cost() + TOLERANCE < self.result.cost() {
+                match neighbor.rebalance_drones() {
+                    Some(rebalanced) => {
+                        debug!(
+                            iteration,
+                            from = neighbor.cost(),
+                            to = rebalanced.cost(),
+                            "Rebalanced drone routes"
+                        );
+                        Rc::new(rebalanced)
+                    }
+                    None => neighbor,
+                }
+            } else {
+                neighbor
+            };
+        if config.pareto_archive > 0 {
+            self.archive.insert(&neighbor);
+        }
+        if config.top_solutions > 0 {
+            self.top.insert(&neighbor);
+        }
+
+        // Update bandit rewards
+        if neighbor.feasible && neighbor.cost() + TOLERANCE < self.result.cost() {
+            self.adaptive.ucb_rewards[idx] += 1.0;
+        } else if neighbor.cost() < self.current.cost() {
+            self.adaptive.ucb_rewards[idx] += 0.5;
+        }
+
+        // Update adaptive state
+        if neighbor.feasible {
+            if neighbor.cost() + TOLERANCE < self.result.cost() {
+                self.adaptive.scores[idx] += 0.3;
+            } else if neighbor.cost() < self.current.cost() {
+                self.adaptive.scores[idx] += 0.2;
+            } else {
+                self.adaptive.scores[idx] += 0.1;
+            }
+        }
+
+        if self.record_improvement(&neighbor, iteration) {
+            self.statistics[idx].improved += 1;
+        }
+
+        let accepted = match config.acceptance {
+            Acceptance::Tabu => true,
+            Acceptance::Lahc => {
+                let cost = neighbor.cost();
+                cost <= self.lahc_history[iteration % self.lahc_history.len()] || cost <= self.current.cost()
+            }
+        };
+        if accepted {
+            self.current = neighbor;
+            self.statistics[idx].accepted += 1;
+        }
+
+        accepted
+    }
+
+    /// Make `neighbor` the best solution if it is feasible and improves it, updating the edge records and the elite
+    /// set, and return whether it did
+    pub fn record_improvement(&mut self, neighbor: &Rc<Solution>, iteration: usize) -> bool {
+        let config = CONFIG.load();
+        if !(neighbor.cost() + TOLERANCE < self.result.cost() && neighbor.feasible) {
+            return false;
+        }
+
+        debug!(iteration, cost = neighbor.cost(), "New best solution");
+        self.result = neighbor.clone();
+        self.last_improved_iteration = iteration;
+        self.adaptive.last_improved_segment = self.adaptive.segment;
+        self.stagnant_resets = 0;
+        self.reclustered = false;
+
+        for routes in &neighbor.truck_routes {
+            for route in routes {
+                let customers = &route.data().customers;
+                for i in 0..customers.len() - 1 {
+                    let r = &mut self.edge_records[customers[i]][customers[i + 1]];
+                    *r = r.min(neighbor.working_time);
+                }
+            }
+        }
+
+        // Reject near-duplicates of existing elite solutions to keep restart points diverse
+        let admissible = config.elite_min_distance == 0
+            || self
+                .elite_set
+                .iter()
+                .all(|s| s.hamming_distance(neighbor) >= config.elite_min_distance);
+
+        if config.max_elite_size > 0 && admissible {
+            if self.elite_set.len() == config.max_elite_size {
+                let (idx, _) = self
+                    .elite_set
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|s| s.1.hamming_distance(&self.result))
+                    .unwrap();
+                self.elite_set.remove(idx);
+            }
+
+            self.elite_set.push(neighbor.clone());
+        }
+
+        true
+    }
+
+    /// Remember the current solution at the end of `iteration` in the acceptance history, the selection counts and
+    /// the long-term memories
+    pub fn remember(&mut self, iteration: usize) {
+        let config = CONFIG.load();
+        if let Acceptance::Lahc = config.acceptance {
+            let v = iteration % self.lahc_history.len();
+            self.lahc_history[v] = self.current.cost();
+        }
+
+        self.adaptive.occurences[self.neighborhood_idx] += 1;
+        self.adaptive.ucb_selections[self.neighborhood_idx] += 1;
+        if config.frequency_penalty > 0.0 {
+            self.frequency.record(&self.current);
+        }
+        if config.route_pool > 0 {
+            self.pool.insert(&self.current);
+        }
+    }
+
+    /// Whether `iteration` ends an adaptive segment of `adaptive_iterations` iterations, starting the next one if so
+    pub fn end_segment(&mut self, iteration: usize, adaptive_iterations: usize) -> bool {
+        let end_of_segment = if CONFIG.load().adaptive_fixed_iterations {
+            iteration > 0 && iteration.is_multiple_of(adaptive_iterations)
+        } else {
+            iteration != self.last_improved_iteration
+                && (iteration - self.last_improved_iteration).is_multiple_of(adaptive_iterations)
+        };
+        if end_of_segment {
+            self.adaptive.segment += 1;
+        }
+
+        end_of_segment
+    }
+
+    /// Whether the search should restart from the elite set at `iteration`
+    pub fn should_reset(&self, iteration: usize, reset_after: usize) -> bool {
+        let config = CONFIG.load();
+        let adaptive = &self.adaptive;
+        if let Strategy::Adaptive = config.strategy {
+            if config.adaptive_fixed_segments {
+                adaptive.segment >= adaptive.segment_reset + config.adaptive_segments
+            } else {
+                adaptive.segment
+                    >= cmp::max(adaptive.segment_reset, adaptive.last_improved_segment) + config.adaptive_segments
+            }
+        } else {
+            iteration != self.last_improved_iteration
+                && (iteration - self.last_improved_iteration).is_multiple_of(reset_after)
+        }
+    }
+
+    /// Restart the search from a random elite solution after destroying and repairing it, clearing the tabu lists and
+    /// the adaptive weights. With `--recluster-after`, the solution is rebuilt from scratch instead after that many
+    /// resets without a new best solution, or when the elite set is exhausted. Returns `false` without restarting once
+    /// the elite set is exhausted (and a rebuild since the last new best solution did not help).
+    pub fn reset(&mut self, iteration: usize) -> bool {
+        let config = CONFIG.load();
+        let _span = debug_span!("reset", iteration).entered();
+        self.adaptive.segment_reset = self.adaptive.segment;
+        self.adaptive.weights = vec![1.0; NEIGHBORHOODS.len()];
+        self.stagnant_resets += 1;
+
+        let exhausted = self.elite_set.is_empty();
+        if exhausted && (config.recluster_after == 0 || self.reclustered) {
+            return false;
+        }
+
+        if exhausted || config.recluster_after > 0 && self.stagnant_resets.is_multiple_of(config.recluster_after) {
+            info!(
+                iteration,
+                resets = self.stagnant_resets,
+                exhausted,
+                "Rebuilding the solution from new clusters"
+            );
+            self.current = Rc::new(config.pins.impose(Solution::recluster()));
+            self.reclustered = true;
+        } else {
+            let i = self.rng.random_range(0..self.elite_set.len());
+            let elite = self.elite_set.swap_remove(i);
+            self.current = Rc::new(profile::time(Phase::DestroyAndRepair, || {
+                elite.destroy_and_repair(&self.edge_records, &self.frequency, &mut self.rng)
+            }));
+        }
+        self.penalties = solutions::restarted_penalty_coeffs(self.penalties, &self.current);
+        solutions::set_penalty_coeffs(self.penalties);
+        for tabu_list in &mut self.tabu_lists {
+            tabu_list.clear();
+        }
+
+        true
+    }
+
+    /// Adapt the penalty coefficients to the violations of the current solution
+    pub fn adapt_penalties(&mut self) {
+        self.penalties = solutions::updated_penalty_coeffs(self.penalties, &self.current);
+        solutions::set_penalty_coeffs(self.penalties);
+    }
+
+    /// Run `--ejection-chain-iterations` ejection chains from the current solution after a reset at `iteration`,
+    /// logging each of them
+    pub fn eject(&mut self, iteration: usize, logger: &mut Logger) {
+        let config = CONFIG.load();
+        let _span = debug_span!("ejection_chain", iteration).entered();
+        let mut ejection_chain_tabu_list = vec![]; // Still have to maintain a tabu list to avoid cycles
+        for _ in 0..config.ejection_chain_iterations {
+            if let Some(neighbor) = profile::time(Phase::Neighborhood(Neighborhood::EjectionChain), || {
+                Neighborhood::EjectionChain.search(
+                    &self.current,
+                    &mut ejection_chain_tabu_list,
+                    config.ejection_chain_iterations + 1,
+                    self.result.cost(),
+                    &mut self.rng,
+                )
+            }) {
+                self.current = Rc::new(neighbor);
+                self.record_improvement(&self.current.clone(), iteration);
+            }
+
+            self.adapt_penalties();
+            profile::time(Phase::Logging, || {
+                logger.log(
+                    &self.current,
+                    Neighborhood::EjectionChain,
+                    &ejection_chain_tabu_list,
+                    iteration == self.last_improved_iteration,
+                )
+            })
+            .unwrap();
+        }
+    }
+
+    /// Log the current solution at `iteration` along with the tabu list of its neighborhood
+    pub fn log(&mut self, iteration: usize, logger: &mut Logger) {
+        self.adapt_penalties();
+        profile::time(Phase::Logging, || {
+            logger.log(
+                &self.current,
+                self.neighborhood(),
+                &self.tabu_lists[self.neighborhood_idx],
+                iteration == self.last_improved_iteration,
+            )
+        })
+        .unwrap();
+    }
+
+    /// Report the progress of the search at `iteration`
+    pub fn progress(&self, iteration: usize, logger: &mut Logger) {
+        profile::time(Phase::Logging, || {
+            logger.progress(
+                iteration,
+                &self.result,
+                &self.current,
+                self.elite_set.len(),
+                &self.adaptive.weights,
+            )
+        })
+        .unwrap();
+    }
+
+    /// Exchange the best solution with the other islands, recording the migrants received
+    pub fn migrate(&mut self, iteration: usize, island: &Island) {
+        for migrant in island.migrate(&self.result) {
+            self.record_improvement(&Rc::new(migrant), iteration);
+        }
+    }
+
+    /// Next selectable neighborhood after the current one, in the order of `NEIGHBORHOODS`
+    fn _next_selectable(&self) -> usize {
+        self.selectable
+            .iter()
+            .copied()
+            .find(|&i| i > self.neighborhood_idx)
+            .unwrap_or(self.selectable[0])
+    }
+
+    /// Select the neighborhood of the next iteration according to `--strategy`. `previous` is the current solution at
+    /// the start of `iteration`, to which VNS returns when moving on to the next neighborhood.
+    pub fn select_neighborhood(
+        &mut self,
+        iteration: usize,
+        end_of_segment: bool,
+        reset: bool,
+        previous: Rc<Solution>,
+        logger: &mut Logger,
+    ) {
+        let config = CONFIG.load();
+        let adaptive = &mut self.adaptive;
+        match config.strategy {
+            Strategy::Random => {
+                self.neighborhood_idx = *self.selectable.choose(&mut self.rng).unwrap();
+            }
+            Strategy::Cyclic => {
+                self.neighborhood_idx = self._next_selectable();
+            }
+            Strategy::Vns => {
+                if iteration == self.last_improved_iteration {
+                    self.neighborhood_idx = self.selectable[0];
+                } else {
+                    self.neighborhood_idx = self._next_selectable();
+                    if self.neighborhood_idx != self.selectable[0] {
+                        self.current = previous;
+                    }
+                }
+            }
+            Strategy::Adaptive => {
+                if end_of_segment {
+                    let scores = adaptive.scores.clone();
+                    let occurrences = adaptive.occurences.clone();
+                    for neighborhood_idx in 0..NEIGHBORHOODS.len() {
+                        if adaptive.occurences[neighborhood_idx] > 0 {
+                            adaptive.weights[neighborhood_idx] = 0.7f64.mul_add(
+                                adaptive.weights[neighborhood_idx],
+                                0.3 * adaptive.scores[neighborhood_idx]
+                                    / f64::from(adaptive.occurences[neighborhood_idx]),
+                            );
+                        }
+
+                        adaptive.scores[neighborhood_idx] = 0.0;
+                        adaptive.occurences[neighborhood_idx] = 0;
+                    }
+
+                    logger
+                        .adaptive_segment(AdaptiveSegment {
+                            segment: adaptive.segment,
+                            iteration,
+                            reset,
+                            scores,
+                            occurrences,
+                            weights: adaptive.weights.clone(),
+                        })
+                        .unwrap();
+                }
+
+                let dist = WeightedIndex::new(
+                    adaptive
+                        .weights
+                        .iter()
+                        .enumerate()
+                        .map(|(i, &weight)| if self.selectable.contains(&i) { weight } else { 0.0 }),
+                )
+                .unwrap();
+                self.neighborhood_idx = dist.sample(&mut self.rng);
+            }
+            Strategy::Ucb => {
+                // UCB1: select each arm once, then maximize the upper confidence bound
+                self.neighborhood_idx = match self.selectable.iter().find(|&&i| adaptive.ucb_selections[i] == 0) {
+                    Some(&untried) => untried,
+                    None => {
+                        let total = f64::from(adaptive.ucb_selections.iter().sum::<u32>());
+                        let bound = |i: usize| {
+                            let n = f64::from(adaptive.ucb_selections[i]);
+                            config
+                                .ucb_exploration
+                                .mul_add((2.0 * total.ln() / n).sqrt(), adaptive.ucb_rewards[i] / n)
+                        };
+
+                        self.selectable
+                            .iter()
+                            .copied()
+                            .max_by(|&i, &j| bound(i).total_cmp(&bound(j)))
+                            .unwrap()
+                    }
+                };
+            }
+        }
+    }
+}
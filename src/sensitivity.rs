@@ -0,0 +1,55 @@
+use std::io::{self, Write};
+
+use rand::rng;
+
+use crate::cli::SensitivityParameter;
+use crate::simulation::{self, Scenario};
+use crate::solutions::Solution;
+
+/// Write a CSV row to `writer` for each of `steps` evenly spaced values of `parameter` from `from` to `to`, holding
+/// the deterministic evaluation of `solution` with that parameter value.
+pub fn sweep(
+    solution: &Solution,
+    parameter: SensitivityParameter,
+    (from, to): (f64, f64),
+    steps: usize,
+    writer: &mut impl Write,
+) -> io::Result<()> {
+    assert!(steps > 0, "At least one step is required");
+
+    writeln!(
+        writer,
+        "Value,Working time,Energy violation,Capacity violation,Waiting time violation,Fixed time violation,Feasible"
+    )?;
+
+    let mut rng = rng();
+    for step in 0..steps {
+        let value = if steps == 1 {
+            from
+        } else {
+            (to - from).mul_add(step as f64 / (steps - 1) as f64, from)
+        };
+
+        let mut scenario = Scenario::new();
+        match parameter {
+            SensitivityParameter::Battery => scenario.battery = Some(value),
+            SensitivityParameter::TruckSpeed => scenario.truck_speed = value,
+            SensitivityParameter::WaitingTimeLimit => scenario.waiting_time_limit = Some(value),
+        }
+
+        let sample = simulation::sample(solution, &scenario, &mut rng);
+        writeln!(
+            writer,
+            "{},{},{},{},{},{},{}",
+            value,
+            sample.makespan,
+            sample.energy_violation,
+            sample.capacity_violation,
+            sample.waiting_time_violation,
+            sample.fixed_time_violation,
+            i32::from(sample.feasible()),
+        )?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,354 @@
+use std::error::Error;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use serde_json::{Map, Value, json};
+use tracing::{info, warn};
+
+use crate::cli::{LogBackend, ProgressFormat};
+use crate::config::{CONFIG, Config, SerializedConfig};
+use crate::logger::Logger;
+use crate::solutions::{self, Solution};
+
+/// Keys of the config JSON naming server-side files or commands, which a request may not set
+const _FILE_KEYS: [&str; 8] = [
+    "truck_matrix",
+    "drone_matrix",
+    "no_fly_zones",
+    "pin",
+    "assignments",
+    "zones",
+    "bks_file",
+    "init_command",
+];
+
+/// Keys of the config JSON that a request may set: the instance data and the parameters of the search. The others are
+/// replaced by `_server_keys`.
+const _REQUEST_KEYS: [&str; 95] = [
+    "customers_count",
+    "trucks_count",
+    "drones_count",
+    "x",
+    "y",
+    "demands",
+    "dronable",
+    "truckable",
+    "waiting_time_limits",
+    "elevations",
+    "truck_distance",
+    "drone_distance",
+    "truck",
+    "drone",
+    "drone_classes",
+    "drone_class",
+    "config",
+    "tabu_size_factor",
+    "adaptive_iterations",
+    "adaptive_fixed_iterations",
+    "adaptive_segments",
+    "adaptive_fixed_segments",
+    "ejection_chain_iterations",
+    "destroy_rate",
+    "speed_type",
+    "range_type",
+    "waiting_time_limit",
+    "strategy",
+    "fix_iteration",
+    "reset_after_factor",
+    "max_elite_size",
+    "penalty_exponent",
+    "single_truck_route",
+    "single_drone_route",
+    "verbose",
+    "extra",
+    "log_budget",
+    "frequency_penalty",
+    "ucb_exploration",
+    "acceptance",
+    "lahc_length",
+    "elite_min_distance",
+    "tsp_polish",
+    "route_pool",
+    "target_cost",
+    "format",
+    "distance_rounding",
+    "vehicle_cost",
+    "objective",
+    "objective_weight",
+    "pareto_archive",
+    "depot_slots",
+    "depot_slot_time",
+    "mothership",
+    "allow_split",
+    "split_of",
+    "soft_constraints",
+    "release_times",
+    "original_ids",
+    "schedule",
+    "log_format",
+    "log_interval",
+    "log_columns",
+    "log_compress",
+    "quiet",
+    "progress_interval",
+    "distance_unit",
+    "time_unit",
+    "clustering",
+    "construction",
+    "grasp",
+    "grasp_cycles",
+    "grasp_iterations",
+    "decompose",
+    "decompose_iterations",
+    "islands",
+    "migration_interval",
+    "profile",
+    "search_scope",
+    "search_top_k",
+    "tie_break",
+    "tie_break_seed",
+    "search_seed",
+    "top_solutions",
+    "top_min_distance",
+    "detailed_routes",
+    "charging_stations",
+    "charging_power",
+    "ambient_temperature",
+    "derating_curve",
+    "tabu_acceptance",
+    "repair_noise",
+    "recluster_after",
+    "penalty_restart",
+    "drone_rebalancing",
+];
+
+/// Values of the config keys that a request may not set, which disable all file and terminal outputs
+fn _server_keys() -> Map<String, Value> {
+    let mut keys = Map::new();
+    for key in _FILE_KEYS {
+        keys.insert(key.to_string(), Value::Null);
+    }
+    keys.extend([
+        ("problem".to_string(), json!("request")),
+        ("outputs".to_string(), json!("outputs/")),
+        ("disable_logging".to_string(), json!(true)),
+        ("dry_run".to_string(), json!(false)),
+        ("log_backend".to_string(), json!(LogBackend::Csv)),
+        ("progress_format".to_string(), json!(ProgressFormat::Human)),
+        ("tui".to_string(), json!(false)),
+        ("status_port".to_string(), Value::Null),
+        ("status_bind".to_string(), json!("127.0.0.1")),
+        ("notify_url".to_string(), Value::Null),
+        ("run_id".to_string(), Value::Null),
+        ("overwrite".to_string(), json!(false)),
+        ("append".to_string(), json!(false)),
+        ("stdout_solution".to_string(), json!(false)),
+        ("params".to_string(), Value::Null),
+        ("truck_cfg".to_string(), json!("")),
+        ("drone_cfg".to_string(), json!([])),
+    ]);
+    keys
+}
+
+/// Build the configuration from a config JSON (as written by the `run` subcommand) whose keys are overridden by those
+/// of the optional problem JSON. Keys naming server-side files or commands are rejected, and the keys outside
+/// `_REQUEST_KEYS` are replaced by the server's, which disable all file and terminal outputs.
+fn _config(problem: Option<Value>, config: Value) -> Result<Config, Box<dyn Error>> {
+    let Value::Object(mut config) = config else {
+        return Err("The config JSON must be an object".into());
+    };
+    match problem {
+        Some(Value::Object(problem)) => config.extend(problem),
+        Some(Value::Null) | None => {}
+        Some(_) => return Err("The problem JSON must be an object".into()),
+    }
+
+    if let Some(key) = _FILE_KEYS
+        .iter()
+        .find(|&&key| config.get(key).is_some_and(|value| !value.is_null()))
+    {
+        return Err(format!("{key:?} names a server-side file or command and cannot be set by a request").into());
+    }
+    config.retain(|key, _| _REQUEST_KEYS.contains(&key.as_str()));
+    config.extend(_server_keys());
+
+    Ok(Config::from(serde_json::from_value::<SerializedConfig>(
+        Value::Object(config),
+    )?))
+}
+
+/// Run `body` with the configuration built from `problem` and `config` as that of the current thread, turning errors
+/// and panics into an error message. The penalty coefficients and the random number generator are per thread, so
+/// requests on different threads do not affect each other.
+fn _call(
+    problem: Option<Value>,
+    config: Value,
+    body: impl FnOnce() -> Result<String, Box<dyn Error>>,
+) -> Result<String, String> {
+    let result = panic::catch_unwind(AssertUnwindSafe(|| -> Result<String, Box<dyn Error>> {
+        let config = _config(problem, config)?;
+        CONFIG.scoped(Arc::new(config), || {
+            solutions::reset_penalty_coeff();
+            body()
+        })
+    }));
+
+    match result {
+        Ok(result) => result.map_err(|e| e.to_string()),
+        Err(panic) => Err(panic
+            .downcast_ref::<&str>()
+            .map(ToString::to_string)
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "The solver panicked".to_string())),
+    }
+}
+
+/// Solve the problem `problem` (keys overriding those of `config`) with the configuration `config`, returning the run
+/// JSON.
+pub fn solve(problem: Option<Value>, config: Value) -> Result<String, String> {
+    _call(problem, config, || {
+        let mut logger = Logger::detached()?;
+        Solution::tabu_search(Solution::initialize(), &mut logger);
+        Ok(logger.summary().unwrap_or("null").to_string())
+    })
+}
+
+/// Evaluate `solution` (as found in the run JSON) against the configuration `config`, returning the re-evaluated
+/// solution JSON.
+pub fn evaluate(solution: Value, config: Value) -> Result<String, String> {
+    _call(None, config, || {
+        let solution = serde_json::from_value::<Solution>(solution)?.reevaluated();
+
+        let mut json = serde_json::to_value(&solution)?;
+        if CONFIG.load().schedule {
+            json["schedule"] = serde_json::to_value(solution.schedule())?;
+        }
+        if CONFIG.load().detailed_routes {
+            json["drone_legs"] = serde_json::to_value(solution.drone_legs())?;
+        }
+        Ok(json.to_string())
+    })
+}
+
+/// Maximum size of the request line and headers of a request
+const _MAX_HEADERS: u64 = 64 << 10;
+
+/// Serve solve and evaluate requests over HTTP on `bind`:`port` until the process is stopped, each on its own thread
+/// (see the `serve` subcommand).
+pub fn serve(bind: &str, port: u16, max_body: usize, timeout: Duration, workers: usize) -> io::Result<()> {
+    let listener = TcpListener::bind((bind, port))?;
+    info!("Serving solve and evaluate requests on {bind}:{port}");
+
+    let active = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        let stream = match stream.and_then(|stream| {
+            stream.set_read_timeout(Some(timeout))?;
+            stream.set_write_timeout(Some(timeout))?;
+            Ok(stream)
+        }) {
+            Ok(stream) => stream,
+            Err(e) => {
+                warn!("Failed to accept a connection: {e}");
+                continue;
+            }
+        };
+
+        if active.fetch_add(1, Ordering::AcqRel) >= workers {
+            active.fetch_sub(1, Ordering::AcqRel);
+            let body = json!({ "error": format!("The server is busy with {workers} requests") }).to_string();
+            if let Err(e) = _write(stream, "503 Service Unavailable", &body) {
+                warn!("Failed to answer a request: {e}");
+            }
+            continue;
+        }
+
+        let active = active.clone();
+        thread::spawn(move || {
+            if let Err(e) = _respond(stream, max_body) {
+                warn!("Failed to answer a request: {e}");
+            }
+            active.fetch_sub(1, Ordering::AcqRel);
+        });
+    }
+
+    Ok(())
+}
+
+fn _respond(stream: TcpStream, max_body: usize) -> io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    let mut request = String::new();
+    let mut content_length = 0;
+    {
+        let mut headers = reader.by_ref().take(_MAX_HEADERS);
+        headers.read_line(&mut request)?;
+
+        let mut line = String::new();
+        while headers.read_line(&mut line)? > 2 {
+            if let Some((name, value)) = line.split_once(':')
+                && name.eq_ignore_ascii_case("content-length")
+            {
+                content_length = value.trim().parse::<usize>().unwrap_or(0);
+            }
+            line.clear();
+        }
+    }
+
+    let started = SystemTime::now();
+    let mut parts = request.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or_default(), parts.next().unwrap_or_default());
+    if content_length > max_body {
+        let body = json!({ "error": format!("The request body exceeds {max_body} bytes") }).to_string();
+        return _write(reader.into_inner(), "413 Payload Too Large", &body);
+    }
+
+    // The body is read up to its announced length only, a client sending less is dropped by the read timeout
+    let mut body = Vec::with_capacity(content_length);
+    reader.by_ref().take(content_length as u64).read_to_end(&mut body)?;
+    if body.len() < content_length {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+
+    let result = match (method, path) {
+        ("GET", "/health") => Ok(json!({ "status": "ok" }).to_string()),
+        ("POST", "/solve" | "/evaluate") => match serde_json::from_slice::<Value>(&body) {
+            Ok(mut request) => {
+                let config = request["config"].take();
+                if path == "/solve" {
+                    solve(Some(request["problem"].take()), config)
+                } else {
+                    evaluate(request["solution"].take(), config)
+                }
+            }
+            Err(e) => Err(e.to_string()),
+        },
+        _ => Err(format!("No route for {method} {path}")),
+    };
+
+    let (status, body) = match result {
+        Ok(body) => ("200 OK", body),
+        Err(e) if e.starts_with("No route") => ("404 Not Found", json!({ "error": e }).to_string()),
+        Err(e) => ("400 Bad Request", json!({ "error": e }).to_string()),
+    };
+    info!(
+        "{method} {path}: {status} in {:.2}s",
+        SystemTime::now()
+            .duration_since(started)
+            .map_or(0.0, |d| d.as_secs_f64())
+    );
+
+    _write(reader.into_inner(), status, &body)
+}
+
+fn _write(mut stream: TcpStream, status: &str, body: &str) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()
+}
@@ -0,0 +1,298 @@
+use std::rc::Rc;
+
+use rand::{Rng, rng};
+use serde::Serialize;
+
+use crate::config::CONFIG;
+use crate::ids::{DroneId, TruckId, VehicleId};
+use crate::routes::{DroneRoute, Route, TruckRoute};
+use crate::solutions::Solution;
+
+/// Realized makespan and violations of a solution under one sample of perturbed travel times and demands
+pub struct Sample {
+    pub makespan: f64,
+    pub energy_violation: f64,
+    pub capacity_violation: f64,
+    pub waiting_time_violation: f64,
+    pub fixed_time_violation: f64,
+}
+
+impl Sample {
+    /// Whether no hard constraint is violated (see `--soft-constraints`). Depot slots and charging stations are not
+    /// evaluated.
+    pub fn feasible(&self) -> bool {
+        [
+            self.energy_violation,
+            self.capacity_violation,
+            self.waiting_time_violation,
+            self.fixed_time_violation,
+        ]
+        .iter()
+        .zip(CONFIG.load().soft_weights)
+        .all(|(&violation, soft)| violation == 0.0 || soft.is_some())
+    }
+}
+
+/// Conditions under which a solution is evaluated, deviating from the config
+pub struct Scenario {
+    /// Relative noise of each leg travel time
+    pub time_noise: f64,
+    /// Relative noise of each customer demand
+    pub demand_noise: f64,
+    pub truck_speed: f64,
+    /// Battery capacity of every drone class, overriding the drone configs
+    pub battery: Option<f64>,
+    /// Waiting time limit of every customer, overriding the per-customer and global limits
+    pub waiting_time_limit: Option<f64>,
+}
+
+impl Default for Scenario {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scenario {
+    /// The conditions of the config, without noise
+    pub fn new() -> Self {
+        Self {
+            time_noise: 0.0,
+            demand_noise: 0.0,
+            truck_speed: CONFIG.load().truck.speed,
+            battery: None,
+            waiting_time_limit: None,
+        }
+    }
+
+    fn _waiting_time_limit(&self, customer: usize) -> f64 {
+        self.waiting_time_limit
+            .unwrap_or(CONFIG.load().waiting_time_limits[customer])
+    }
+}
+
+#[derive(Serialize)]
+pub struct Distribution {
+    pub mean: f64,
+    pub std: f64,
+    pub min: f64,
+    pub p5: f64,
+    pub p50: f64,
+    pub p95: f64,
+    pub max: f64,
+}
+
+impl Distribution {
+    fn new(mut values: Vec<f64>) -> Self {
+        values.sort_by(f64::total_cmp);
+
+        let n = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / n;
+        let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+        let percentile = |p: f64| values[((values.len() - 1) as f64 * p).round() as usize];
+
+        Self {
+            mean,
+            std: variance.sqrt(),
+            min: values[0],
+            p5: percentile(0.05),
+            p50: percentile(0.5),
+            p95: percentile(0.95),
+            max: values[values.len() - 1],
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ViolationSummary {
+    /// Fraction of the samples with a positive violation
+    pub probability: f64,
+    pub distribution: Distribution,
+}
+
+impl ViolationSummary {
+    fn new(values: Vec<f64>) -> Self {
+        Self {
+            probability: values.iter().filter(|&&v| v > 0.0).count() as f64 / values.len() as f64,
+            distribution: Distribution::new(values),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct SimulationReport {
+    pub samples: usize,
+    pub time_noise: f64,
+    pub demand_noise: f64,
+    /// Makespan of the deterministic evaluation
+    pub working_time: f64,
+    pub makespan: Distribution,
+    pub energy_violation: ViolationSummary,
+    pub capacity_violation: ViolationSummary,
+    pub waiting_time_violation: ViolationSummary,
+    pub fixed_time_violation: ViolationSummary,
+    /// Fraction of the samples without any violation of a hard constraint (see `--soft-constraints`)
+    pub feasible_probability: f64,
+}
+
+/// A relative perturbation factor drawn uniformly from `[1 - noise, 1 + noise]` (never negative)
+fn _factor(rng: &mut impl Rng, noise: f64) -> f64 {
+    if noise > 0.0 {
+        (1.0 + rng.random_range(-noise..=noise)).max(0.0)
+    } else {
+        1.0
+    }
+}
+
+fn _truck_sample(
+    truck: TruckId,
+    routes: &[Rc<TruckRoute>],
+    demands: &[f64],
+    rng: &mut impl Rng,
+    scenario: &Scenario,
+    sample: &mut Sample,
+) -> f64 {
+    let speed = scenario.truck_speed;
+    let mut working_time = CONFIG.load().truck.reload_time.mul_add(
+        routes.len().saturating_sub(1) as f64,
+        CONFIG.load().release_time(VehicleId::Truck(truck)),
+    );
+    for route in routes {
+        let customers = &route.data().customers;
+        let mut arrival = vec![0.0; customers.len()];
+        for i in 1..customers.len() {
+            arrival[i] = (CONFIG.load().truck_distances[customers[i - 1]][customers[i]] / speed)
+                .mul_add(_factor(rng, scenario.time_noise), arrival[i - 1]);
+        }
+
+        let route_time = arrival[customers.len() - 1];
+        let weight = customers.iter().map(|&c| demands[c]).sum::<f64>();
+        sample.capacity_violation += (weight - CONFIG.load().truck.capacity).max(0.0) / CONFIG.load().truck.capacity;
+        for i in 1..customers.len() - 1 {
+            sample.waiting_time_violation +=
+                (route_time - arrival[i] - scenario._waiting_time_limit(customers[i])).max(0.0);
+        }
+
+        working_time += route_time;
+    }
+
+    working_time
+}
+
+fn _drone_sample(
+    drone: DroneId,
+    routes: &[Rc<DroneRoute>],
+    demands: &[f64],
+    rng: &mut impl Rng,
+    scenario: &Scenario,
+    sample: &mut Sample,
+) -> f64 {
+    let class = &CONFIG.load().drone_classes[CONFIG.load().drone_class_of(drone)];
+    let (takeoff, landing) = (class.takeoff_time(), class.landing_time());
+
+    let mut working_time = class.recharge_time().mul_add(
+        routes.len().saturating_sub(1) as f64,
+        CONFIG.load().release_time(VehicleId::Drone(drone)),
+    );
+    for route in routes {
+        let customers = &route.data().customers;
+        let cruise = (0..customers.len() - 1)
+            .map(|i| {
+                class.leg_cruise_time(
+                    CONFIG.load().drone_distances[customers[i]][customers[i + 1]],
+                    customers[i],
+                    customers[i + 1],
+                ) * _factor(rng, scenario.time_noise)
+            })
+            .collect::<Vec<f64>>();
+        let route_time = (takeoff + landing).mul_add((customers.len() - 1) as f64, cruise.iter().sum::<f64>());
+
+        let mut time = 0.0;
+        let mut energy = 0.0;
+        let mut weight = 0.0;
+        for i in 0..customers.len() - 1 {
+            time += takeoff + cruise[i] + landing;
+            energy += class.landing_power(weight).mul_add(
+                landing,
+                class
+                    .takeoff_power(weight)
+                    .mul_add(takeoff, class.cruise_power(weight) * cruise[i]),
+            ) + class.climb_energy(weight, customers[i], customers[i + 1]);
+            weight += demands[customers[i]];
+            sample.waiting_time_violation += (route_time - time - scenario._waiting_time_limit(customers[i])).max(0.0);
+        }
+
+        let battery = scenario.battery.unwrap_or_else(|| class.battery());
+        sample.energy_violation += (energy - battery).max(0.0) / battery;
+        sample.capacity_violation += (weight - class.capacity()).max(0.0) / class.capacity();
+        sample.fixed_time_violation += (route_time - class.fixed_time()).max(0.0) / class.fixed_time();
+        working_time += route_time;
+    }
+
+    working_time
+}
+
+/// Evaluate `solution` once under `scenario`, drawing the perturbations of travel times and demands from `rng`.
+/// Violations are normalized as in the deterministic evaluation. Depot slots and charging stations are not evaluated.
+pub fn sample(solution: &Solution, scenario: &Scenario, rng: &mut impl Rng) -> Sample {
+    assert!(
+        solution.sorties.is_empty(),
+        "Solutions with drone sorties (see --mothership) cannot be simulated"
+    );
+
+    let demands = CONFIG
+        .load()
+        .demands
+        .iter()
+        .map(|&d| d * _factor(rng, scenario.demand_noise))
+        .collect::<Vec<f64>>();
+
+    let mut sample = Sample {
+        makespan: 0.0,
+        energy_violation: 0.0,
+        capacity_violation: 0.0,
+        waiting_time_violation: 0.0,
+        fixed_time_violation: 0.0,
+    };
+    for (truck, routes) in solution.truck_routes.iter().enumerate() {
+        let working_time = _truck_sample(TruckId(truck), routes, &demands, rng, scenario, &mut sample);
+        sample.makespan = sample.makespan.max(working_time);
+    }
+    for (drone, routes) in solution.drone_routes.iter().enumerate() {
+        let working_time = _drone_sample(DroneId(drone), routes, &demands, rng, scenario, &mut sample);
+        sample.makespan = sample.makespan.max(working_time);
+    }
+
+    sample.waiting_time_violation /= scenario.waiting_time_limit.unwrap_or(CONFIG.load().waiting_time_limit);
+    sample
+}
+
+/// Evaluate `solution` under `samples` independent perturbations: each leg travel time and each customer demand is
+/// scaled by a factor drawn uniformly from `[1 - time_noise, 1 + time_noise]` and `[1 - demand_noise, 1 + demand_noise]`
+/// respectively.
+pub fn simulate(solution: &Solution, samples: usize, time_noise: f64, demand_noise: f64) -> SimulationReport {
+    assert!(samples > 0, "At least one sample is required");
+
+    let scenario = Scenario {
+        time_noise,
+        demand_noise,
+        ..Scenario::new()
+    };
+    let mut rng = rng();
+    let results = (0..samples)
+        .map(|_| sample(solution, &scenario, &mut rng))
+        .collect::<Vec<Sample>>();
+    let feasible = results.iter().filter(|s| s.feasible()).count();
+
+    SimulationReport {
+        samples,
+        time_noise,
+        demand_noise,
+        working_time: solution.working_time,
+        makespan: Distribution::new(results.iter().map(|s| s.makespan).collect()),
+        energy_violation: ViolationSummary::new(results.iter().map(|s| s.energy_violation).collect()),
+        capacity_violation: ViolationSummary::new(results.iter().map(|s| s.capacity_violation).collect()),
+        waiting_time_violation: ViolationSummary::new(results.iter().map(|s| s.waiting_time_violation).collect()),
+        fixed_time_violation: ViolationSummary::new(results.iter().map(|s| s.fixed_time_violation).collect()),
+        feasible_probability: feasible as f64 / samples as f64,
+    }
+}
@@ -1,24 +1,30 @@
+use std::cell::Cell;
 use std::collections::{BTreeSet, BinaryHeap, HashSet};
 use std::marker::PhantomData;
 use std::rc::Rc;
 use std::sync::LazyLock;
-use std::sync::atomic::Ordering;
 use std::time::SystemTime;
-use std::{cmp, fmt};
+use std::{array, cmp, fmt};
 
-use rand::distr::weighted::WeightedIndex;
-use rand::prelude::*;
-use rand::seq::SliceRandom;
-use rand::{Rng, rng};
+use rand::rngs::StdRng;
+use rand::seq::{IndexedRandom, SliceRandom};
+use rand::{Rng, SeedableRng, rng};
 use serde::de::{SeqAccess, Visitor};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use tracing::{Level, debug, debug_span, enabled, info, info_span};
 
-use crate::cli::Strategy;
-use crate::clusterize;
+use crate::cli::{Clustering, Construction, Objective, PenaltyRestart};
 use crate::config::CONFIG;
+use crate::ids::{CustomerId, DroneId, TruckId, VehicleId};
+use crate::islands::Island;
 use crate::logger::Logger;
+use crate::mothership::{self, Sortie};
 use crate::neighborhoods::Neighborhood;
-use crate::routes::{DroneRoute, Route, TruckRoute};
+use crate::profile::{self, Phase};
+use crate::routes::{DroneLeg, DroneRoute, Route, TruckRoute};
+use crate::schedule::{SortieVisit, Trip, VehicleSchedule, Visit};
+use crate::search::SearchState;
+use crate::{clusterize, construction, tsp};
 
 fn _deserialize_routes<'de, R, D>(deserializer: D) -> Result<Vec<Vec<Rc<R>>>, D::Error>
 where
@@ -72,29 +78,48 @@ pub struct Solution {
     pub truck_routes: Vec<Vec<Rc<TruckRoute>>>,
     #[serde(deserialize_with = "_deserialize_routes", serialize_with = "_serialize_routes")]
     pub drone_routes: Vec<Vec<Rc<DroneRoute>>>,
+    /// Drone sorties launched from trucks (see `--mothership`)
+    #[serde(default)]
+    pub sorties: Vec<Sortie>,
 
     pub truck_working_time: Vec<f64>,
     pub drone_working_time: Vec<f64>,
 
     pub working_time: f64,
+    #[serde(default)]
+    pub total_time: f64,
+    #[serde(default)]
+    pub total_energy: f64,
     pub energy_violation: f64,
     pub capacity_violation: f64,
     pub waiting_time_violation: f64,
     pub fixed_time_violation: f64,
+    #[serde(default)]
+    pub depot_slot_violation: f64,
+    #[serde(default)]
+    pub assignment_violation: f64,
+    #[serde(default)]
+    pub no_fly_violation: f64,
 
     pub feasible: bool,
+
+    /// Working time and violations of each truck and each drone, to re-evaluate only the vehicles a move changes
+    #[serde(skip)]
+    _truck_metrics: Vec<VehicleMetrics>,
+    #[serde(skip)]
+    _drone_metrics: Vec<VehicleMetrics>,
+    /// Penalty coefficients of the last call to `cost` and the cost they gave
+    #[serde(skip)]
+    _cost: Cell<Option<([f64; 7], f64)>>,
 }
 
-static PENALTY_COEFF: LazyLock<[atomic_float::AtomicF64; 4]> = LazyLock::new(|| {
-    [
-        atomic_float::AtomicF64::new(1.0),
-        atomic_float::AtomicF64::new(1.0),
-        atomic_float::AtomicF64::new(1.0),
-        atomic_float::AtomicF64::new(1.0),
-    ]
-});
+thread_local! {
+    /// Adaptive penalty coefficient of each violation, per thread so that concurrent searches (see `--decompose`) do
+    /// not adapt each other's coefficients
+    static PENALTY_COEFF: [Cell<f64>; 7] = const { [const { Cell::new(1.0) }; 7] };
+}
 
-static NEIGHBORHOODS: LazyLock<[Neighborhood; 6]> = LazyLock::new(|| {
+pub static NEIGHBORHOODS: LazyLock<[Neighborhood; 6]> = LazyLock::new(|| {
     [
         Neighborhood::Move10,
         Neighborhood::Move11,
@@ -105,75 +130,656 @@ static NEIGHBORHOODS: LazyLock<[Neighborhood; 6]> = LazyLock::new(|| {
     ]
 });
 
-const TOLERANCE: f64 = 0.001;
+/// Indices in `NEIGHBORHOODS` of the neighborhoods that may be searched, excluding those that assume symmetric
+/// distances on asymmetric instances (see `Neighborhood::assumes_symmetric`)
+pub fn selectable_neighborhoods() -> Vec<usize> {
+    let (selectable, excluded) = (0..NEIGHBORHOODS.len())
+        .partition::<Vec<_>, _>(|&i| !(CONFIG.load().asymmetric && NEIGHBORHOODS[i].assumes_symmetric()));
+    if !excluded.is_empty() {
+        debug!(
+            "Excluded the neighborhoods assuming symmetric distances: {:?}",
+            excluded.iter().map(|&i| NEIGHBORHOODS[i]).collect::<Vec<_>>()
+        );
+    }
+
+    assert!(
+        !selectable.is_empty(),
+        "Every neighborhood assumes symmetric distances, which this instance does not have"
+    );
+    selectable
+}
+
+pub const TOLERANCE: f64 = 0.001;
+
+/// Working time and violations of the routes of a single vehicle, which a solution sums over its vehicles
+#[derive(Clone, Copy, Debug, Default)]
+pub struct VehicleMetrics {
+    pub working_time: f64,
+    pub energy: f64,
+    pub energy_violation: f64,
+    pub capacity_violation: f64,
+    /// Not yet relative to the waiting time limit
+    pub waiting_time_violation: f64,
+    pub fixed_time_violation: f64,
+    /// Assignment rules broken by this vehicle (see `--assignments`)
+    pub assignment_violation: f64,
+    /// Legs of this vehicle crossing a no-fly zone (see `--no-fly-zones`)
+    pub no_fly_violation: f64,
+}
+
+/// Where a customer is served: by `vehicle`, in its route `route` at position `position` (the depot being at 0)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Placement {
+    pub vehicle: VehicleId,
+    pub route: usize,
+    pub position: usize,
+}
+
+/// How the placement of a customer differs between two solutions, from the coarsest change to the finest
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Change {
+    /// Served by a different vehicle, or served in only one of the solutions (e.g. by a drone sortie)
+    Vehicle,
+    /// Served by the same vehicle in a different route
+    Route,
+    /// Served in the same route at a different position
+    Position,
+}
+
+/// A customer placed differently by two solutions
+pub struct CustomerChange {
+    pub customer: usize,
+    pub change: Change,
+    pub before: Option<Placement>,
+    pub after: Option<Placement>,
+}
+
+/// Differences between two solutions of the same instance (see `Solution::diff`)
+pub struct SolutionDiff {
+    /// Customers placed differently, in increasing order
+    pub changes: Vec<CustomerChange>,
+    pub hamming_distance: usize,
+}
+
+/// Hyperparameters of the tabu search, derived from the number of customers per vehicle used by the initial solution
+pub struct Hyperparameters {
+    /// Number of customers per used vehicle ([Base] in the help of the "run" subcommand)
+    pub base: f64,
+    pub tabu_size: usize,
+    /// Number of iterations per adaptive segment
+    pub adaptive_iterations: usize,
+    /// Number of non-improved iterations before resetting the current solution
+    pub reset_after: usize,
+}
+
+/// Long-term memory of how often each customer-customer edge and each customer-vehicle assignment appears in the
+/// solutions visited during the search. Vehicles are indexed with trucks first, then drones.
+pub struct FrequencyMemory {
+    edges: Vec<Vec<usize>>,
+    assignments: Vec<Vec<usize>>,
+    visited: usize,
+}
+
+impl Default for FrequencyMemory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrequencyMemory {
+    pub fn new() -> Self {
+        let config = CONFIG.load();
+        Self {
+            edges: vec![vec![0; config.customers_count + 1]; config.customers_count + 1],
+            assignments: vec![vec![0; config.trucks_count + config.drones_count]; config.customers_count + 1],
+            visited: 0,
+        }
+    }
+
+    pub fn record(&mut self, solution: &Solution) {
+        fn _record_routes<T>(
+            vehicle_routes: &[Vec<Rc<T>>],
+            offset: usize,
+            edges: &mut [Vec<usize>],
+            assignments: &mut [Vec<usize>],
+        ) where
+            T: Route,
+        {
+            for (vehicle, routes) in vehicle_routes.iter().enumerate() {
+                for route in routes {
+                    let customers = &route.data().customers;
+                    for i in 0..customers.len() - 1 {
+                        edges[customers[i]][customers[i + 1]] += 1;
+                        assignments[customers[i]][offset + vehicle] += 1;
+                    }
+                }
+            }
+        }
+
+        _record_routes(&solution.truck_routes, 0, &mut self.edges, &mut self.assignments);
+        _record_routes(
+            &solution.drone_routes,
+            CONFIG.load().trucks_count,
+            &mut self.edges,
+            &mut self.assignments,
+        );
+        self.visited += 1;
+    }
 
+    /// Multiplicative bias on the cost of inserting `customer` between `prev` and `next` on `vehicle`
+    fn bias(
+        &self,
+        CustomerId(customer): CustomerId,
+        vehicle: VehicleId,
+        CustomerId(prev): CustomerId,
+        CustomerId(next): CustomerId,
+    ) -> f64 {
+        let config = CONFIG.load();
+        if self.visited == 0 || config.frequency_penalty == 0.0 {
+            return 1.0;
+        }
+
+        let vehicle = vehicle.index(config.trucks_count);
+        let frequency = (self.assignments[customer][vehicle] + self.edges[prev][customer] + self.edges[customer][next])
+            as f64
+            / (3 * self.visited) as f64;
+        config.frequency_penalty.mul_add(frequency, 1.0)
+    }
+}
+
+/// Penalty coefficient of violation `N`, or its fixed weight if it is a soft constraint
 pub fn penalty_coeff<const N: usize>() -> f64 {
-    PENALTY_COEFF[N].load(Ordering::Relaxed)
+    CONFIG.load().soft_weights[N].unwrap_or_else(|| PENALTY_COEFF.with(|c| c[N].get()))
 }
 
-fn _update_violation<const N: usize>(violation: f64) {
-    let mut value = PENALTY_COEFF[N].load(Ordering::Relaxed);
-    if violation > 0.0 {
-        value *= 1.5;
-    } else {
-        value /= 1.5;
-    };
+/// Current penalty coefficient of every violation (see `penalty_coeff`)
+pub fn penalty_coeffs() -> [f64; 7] {
+    let config = CONFIG.load();
+    PENALTY_COEFF.with(|c| array::from_fn(|i| config.soft_weights[i].unwrap_or_else(|| c[i].get())))
+}
 
-    PENALTY_COEFF[N].store(value.clamp(1.0, 1e3), Ordering::Relaxed)
+/// Restore the initial penalty coefficients before another search in the same process
+pub fn reset_penalty_coeff() {
+    PENALTY_COEFF.with(|coefficients| {
+        for coefficient in coefficients {
+            coefficient.set(1.0);
+        }
+    });
+}
+
+/// Set the adaptive penalty coefficients read by the evaluations of this thread (see `penalty_coeff`)
+pub fn set_penalty_coeffs(values: [f64; 7]) {
+    PENALTY_COEFF.with(|coefficients| {
+        for (coefficient, value) in coefficients.iter().zip(values) {
+            coefficient.set(value);
+        }
+    });
+}
+
+fn _violations(s: &Solution) -> [f64; 7] {
+    [
+        s.energy_violation,
+        s.capacity_violation,
+        s.waiting_time_violation,
+        s.fixed_time_violation,
+        s.depot_slot_violation,
+        s.assignment_violation,
+        s.no_fly_violation,
+    ]
+}
+
+/// Adaptive penalty coefficients re-initialized from `coefficients` after a reset to the current solution `s` (see
+/// `--penalty-restart`)
+pub fn restarted_penalty_coeffs(coefficients: [f64; 7], s: &Solution) -> [f64; 7] {
+    match CONFIG.load().penalty_restart {
+        PenaltyRestart::Keep => coefficients,
+        PenaltyRestart::Reset => [1.0; 7],
+        PenaltyRestart::Profile => _violations(s).map(|v| if v > 0.0 { 1e3_f64.sqrt() } else { 1.0 }),
+    }
+}
+
+/// Run `f` with every adaptive penalty coefficient set to `value`, restoring them afterwards
+pub fn with_penalty_coeff<R>(value: f64, f: impl FnOnce() -> R) -> R {
+    let old_penalty = PENALTY_COEFF.with(|c| c.each_ref().map(|c| c.replace(value)));
+    let result = f();
+    PENALTY_COEFF.with(|coefficients| {
+        for (coefficient, old) in coefficients.iter().zip(old_penalty) {
+            coefficient.set(old);
+        }
+    });
+
+    result
+}
+
+/// Adaptive penalty coefficients adapted from `coefficients` to whether `s` violates each constraint
+pub fn updated_penalty_coeffs(coefficients: [f64; 7], s: &Solution) -> [f64; 7] {
+    let violations = _violations(s);
+    array::from_fn(|i| {
+        let value = if violations[i] > 0.0 {
+            coefficients[i] * 1.5
+        } else {
+            coefficients[i] / 1.5
+        };
+        value.clamp(1.0, 1e3)
+    })
 }
 
 impl Solution {
     pub fn new(truck_routes: Vec<Vec<Rc<TruckRoute>>>, drone_routes: Vec<Vec<Rc<DroneRoute>>>) -> Self {
-        let mut working_time: f64 = 0.0;
-        let mut energy_violation = 0.0;
-        let mut capacity_violation = 0.0;
-        let mut waiting_time_violation = 0.0;
-        let mut fixed_time_violation = 0.0;
-        for routes in &truck_routes {
-            working_time = working_time.max(routes.iter().map(|r| r.working_time()).sum());
-            capacity_violation += routes.iter().map(|r| r.capacity_violation()).sum::<f64>() / CONFIG.truck.capacity;
-            waiting_time_violation += routes.iter().map(|r| r.waiting_time_violation()).sum::<f64>();
-        }
-        for routes in &drone_routes {
-            working_time = working_time.max(routes.iter().map(|r| r.working_time()).sum::<f64>());
-            energy_violation += routes.iter().map(|r| r.energy_violation).sum::<f64>();
-            capacity_violation += routes.iter().map(|r| r.capacity_violation()).sum::<f64>() / CONFIG.drone.capacity();
-            waiting_time_violation += routes.iter().map(|r| r.waiting_time_violation()).sum::<f64>();
-            fixed_time_violation += routes.iter().map(|r| r.fixed_time_violation).sum::<f64>();
-        }
-
-        let truck_working_time = truck_routes
+        Self::with_sorties(truck_routes, drone_routes, vec![])
+    }
+
+    /// Rebuild the solution from its routes, re-evaluating it against the current config.
+    pub fn reevaluated(self) -> Self {
+        // Note: this solution contains attributes calculated using its old config.
+        // In order to evaluate it with the new config, we construct a new solution.
+        let mut truck_routes = vec![vec![]; self.truck_routes.len()];
+        for (truck, routes) in self.truck_routes.into_iter().enumerate() {
+            for route in routes {
+                truck_routes[truck].push(TruckRoute::new(route.data().customers.clone()));
+            }
+        }
+
+        let mut drone_routes = vec![vec![]; self.drone_routes.len()];
+        for (drone, routes) in self.drone_routes.into_iter().enumerate() {
+            for route in routes {
+                drone_routes[drone].push(DroneRoute::new(route.data().customers.clone()));
+            }
+        }
+
+        Self::with_sorties(truck_routes, drone_routes, self.sorties)
+    }
+
+    /// Construct a solution whose trucks additionally launch the drone sorties `sorties` (see `--mothership`).
+    pub fn with_sorties(
+        truck_routes: Vec<Vec<Rc<TruckRoute>>>,
+        drone_routes: Vec<Vec<Rc<DroneRoute>>>,
+        sorties: Vec<Sortie>,
+    ) -> Self {
+        profile::time(Phase::Evaluation, || {
+            Self::_evaluate(truck_routes, drone_routes, sorties)
+        })
+    }
+
+    /// Metrics of `routes`, the routes of truck `truck`, which waits for the drones it carries at the rendezvous nodes
+    /// of its `sorties`
+    pub fn truck_metrics(truck: TruckId, routes: &[Rc<TruckRoute>], sorties: &[Sortie]) -> VehicleMetrics {
+        let config = CONFIG.load();
+        let mut metrics = VehicleMetrics {
+            working_time: config.truck.reload_time.mul_add(
+                routes.len().saturating_sub(1) as f64,
+                routes.iter().map(|r| r.working_time()).sum::<f64>(),
+            ) + config.release_time(VehicleId::Truck(truck)),
+            capacity_violation: routes.iter().map(|r| r.capacity_violation()).sum::<f64>() / config.truck.capacity,
+            waiting_time_violation: routes.iter().map(|r| r.waiting_time_violation()).sum::<f64>(),
+            assignment_violation: config.assignment_rules.violation(
+                VehicleId::Truck(truck),
+                routes.iter().map(|r| r.data().customers.as_slice()),
+            ),
+            ..VehicleMetrics::default()
+        };
+
+        if !sorties.is_empty() {
+            for (route_idx, route) in routes.iter().enumerate() {
+                let route_sorties = mothership::route_sorties(sorties, truck.0, route_idx);
+                if route_sorties.is_empty() {
+                    continue;
+                }
+
+                let synchronized = mothership::evaluate(route, &route_sorties);
+                metrics.working_time += synchronized.working_time - route.working_time();
+                metrics.capacity_violation +=
+                    (synchronized.capacity_violation - route.capacity_violation()) / config.truck.capacity;
+                metrics.waiting_time_violation += synchronized.waiting_time_violation - route.waiting_time_violation();
+                metrics.energy_violation += synchronized.energy_violation;
+                metrics.fixed_time_violation += synchronized.fixed_time_violation;
+            }
+        }
+
+        metrics
+    }
+
+    /// Metrics of `routes`, performed back to back by drone `drone`
+    pub fn drone_metrics(drone: DroneId, routes: &[Rc<DroneRoute>]) -> VehicleMetrics {
+        let config = CONFIG.load();
+        let class = &config.drone_classes[config.drone_class_of(drone)];
+        let mut metrics = VehicleMetrics {
+            working_time: config.release_time(VehicleId::Drone(drone)),
+            assignment_violation: config.assignment_rules.violation(
+                VehicleId::Drone(drone),
+                routes.iter().map(|r| r.data().customers.as_slice()),
+            ),
+            ..VehicleMetrics::default()
+        };
+        let (mut energy, mut capacity, mut fixed_time) = (0.0, 0.0, 0.0);
+        let class_index = config.drone_class_of(drone);
+        for route in routes {
+            let route_metrics = route.class_metrics_of(class_index);
+            metrics.working_time += route_metrics.working_time;
+            energy += route_metrics.energy_violation;
+            capacity += route_metrics.capacity_violation;
+            metrics.waiting_time_violation += route_metrics.waiting_time_violation;
+            fixed_time += route_metrics.fixed_time_violation;
+            metrics.no_fly_violation += route_metrics.no_fly_violation;
+            metrics.energy += route_metrics.energy;
+        }
+
+        // Battery swap/recharge between consecutive routes
+        metrics.working_time += class.recharge_time() * routes.len().saturating_sub(1) as f64;
+        metrics.energy_violation = energy / class.battery();
+        metrics.capacity_violation = capacity / class.capacity();
+        metrics.fixed_time_violation = fixed_time / class.fixed_time();
+        metrics
+    }
+
+    fn _evaluate(
+        truck_routes: Vec<Vec<Rc<TruckRoute>>>,
+        drone_routes: Vec<Vec<Rc<DroneRoute>>>,
+        sorties: Vec<Sortie>,
+    ) -> Self {
+        let truck_metrics = truck_routes
             .iter()
-            .map(|r| r.iter().map(|r| r.working_time()).sum())
+            .enumerate()
+            .map(|(truck, routes)| Self::truck_metrics(TruckId(truck), routes, &sorties))
             .collect();
-        let drone_working_time = drone_routes
+        let drone_metrics = drone_routes
             .iter()
-            .map(|r| r.iter().map(|r| r.working_time()).sum())
+            .enumerate()
+            .map(|(drone, routes)| Self::drone_metrics(DroneId(drone), routes))
             .collect();
+        let depot_slot_violation = Self::_depot_slot_violation(&drone_routes);
+
+        Self::_aggregate(
+            truck_routes,
+            drone_routes,
+            sorties,
+            truck_metrics,
+            drone_metrics,
+            depot_slot_violation,
+        )
+    }
 
-        energy_violation /= CONFIG.drone.battery();
-        waiting_time_violation /= CONFIG.waiting_time_limit;
-        fixed_time_violation /= CONFIG.drone.fixed_time();
+    /// Construct a solution from the metrics of each of its vehicles
+    fn _aggregate(
+        truck_routes: Vec<Vec<Rc<TruckRoute>>>,
+        drone_routes: Vec<Vec<Rc<DroneRoute>>>,
+        sorties: Vec<Sortie>,
+        truck_metrics: Vec<VehicleMetrics>,
+        drone_metrics: Vec<VehicleMetrics>,
+        depot_slot_violation: f64,
+    ) -> Self {
+        let config = CONFIG.load();
+        let vehicles = || truck_metrics.iter().chain(&drone_metrics);
+        let working_time = vehicles().map(|m| m.working_time).fold(0.0, f64::max);
+        let total_time = vehicles().map(|m| m.working_time).sum::<f64>();
+        let total_energy = vehicles().map(|m| m.energy).sum::<f64>();
+        let energy_violation = vehicles().map(|m| m.energy_violation).sum::<f64>();
+        let capacity_violation = vehicles().map(|m| m.capacity_violation).sum::<f64>();
+        let waiting_time_violation =
+            vehicles().map(|m| m.waiting_time_violation).sum::<f64>() / config.waiting_time_limit;
+        let fixed_time_violation = vehicles().map(|m| m.fixed_time_violation).sum::<f64>();
+        let assignment_violation = vehicles().map(|m| m.assignment_violation).sum::<f64>();
+        let no_fly_violation = vehicles().map(|m| m.no_fly_violation).sum::<f64>();
 
         Self {
             truck_routes,
             drone_routes,
+            sorties,
             working_time,
+            total_time,
+            total_energy,
             energy_violation,
             capacity_violation,
             waiting_time_violation,
             fixed_time_violation,
-            feasible: energy_violation == 0.0
-                && capacity_violation == 0.0
-                && waiting_time_violation == 0.0
-                && fixed_time_violation == 0.0,
-            truck_working_time,
-            drone_working_time,
+            depot_slot_violation,
+            assignment_violation,
+            no_fly_violation,
+            feasible: [
+                energy_violation,
+                capacity_violation,
+                waiting_time_violation,
+                fixed_time_violation,
+                depot_slot_violation,
+                assignment_violation,
+                no_fly_violation,
+            ]
+            .iter()
+            .zip(config.soft_weights)
+            .all(|(&violation, soft)| violation == 0.0 || soft.is_some()),
+            truck_working_time: truck_metrics.iter().map(|m| m.working_time).collect(),
+            drone_working_time: drone_metrics.iter().map(|m| m.working_time).collect(),
+            _truck_metrics: truck_metrics,
+            _drone_metrics: drone_metrics,
+            _cost: Cell::new(None),
+        }
+    }
+
+    /// Construct the solution with the routes `truck_routes` and `drone_routes`, which differ from those of this solution
+    /// only for `vehicle`: the other vehicles keep their metrics instead of being re-evaluated. The sorties launched by
+    /// a changed truck follow their route to its new index, which must still be one of the truck's routes.
+    pub fn with_changed_vehicle(
+        &self,
+        truck_routes: Vec<Vec<Rc<TruckRoute>>>,
+        drone_routes: Vec<Vec<Rc<DroneRoute>>>,
+        vehicle: VehicleId,
+    ) -> Self {
+        let sorties = self
+            .sorties
+            .iter()
+            .map(|sortie| {
+                if vehicle != VehicleId::Truck(TruckId(sortie.truck)) {
+                    return sortie.clone();
+                }
+
+                let customers = &self.truck_routes[sortie.truck][sortie.route].data().customers;
+                let route = truck_routes[sortie.truck]
+                    .iter()
+                    .position(|r| &r.data().customers == customers)
+                    .unwrap_or_else(|| {
+                        panic!(
+                            "Route {customers:?} of truck {} launches a sortie to customer {} and cannot be changed",
+                            sortie.truck, sortie.customer
+                        )
+                    });
+                Sortie {
+                    route,
+                    ..sortie.clone()
+                }
+            })
+            .collect::<Vec<_>>();
+
+        // Deserialized solutions have no metrics to start from
+        if self._truck_metrics.len() != truck_routes.len() || self._drone_metrics.len() != drone_routes.len() {
+            return Self::with_sorties(truck_routes, drone_routes, sorties);
+        }
+
+        profile::time(Phase::Evaluation, || {
+            let mut truck_metrics = self._truck_metrics.clone();
+            let mut drone_metrics = self._drone_metrics.clone();
+            let depot_slot_violation = match vehicle {
+                VehicleId::Truck(truck) => {
+                    truck_metrics[truck.0] = Self::truck_metrics(truck, &truck_routes[truck.0], &sorties);
+                    self.depot_slot_violation
+                }
+                VehicleId::Drone(drone) => {
+                    drone_metrics[drone.0] = Self::drone_metrics(drone, &drone_routes[drone.0]);
+                    Self::_depot_slot_violation(&drone_routes)
+                }
+            };
+
+            Self::_aggregate(
+                truck_routes,
+                drone_routes,
+                sorties,
+                truck_metrics,
+                drone_metrics,
+                depot_slot_violation,
+            )
+        })
+    }
+
+    /// This solution with route `idx` of truck `truck` replaced by `route`, re-evaluating only that truck
+    pub fn with_replaced_truck_route(&self, truck: TruckId, idx: usize, route: Rc<TruckRoute>) -> Self {
+        let mut truck_routes = self.truck_routes.clone();
+        truck_routes[truck.0][idx] = route;
+        self.with_changed_vehicle(truck_routes, self.drone_routes.clone(), VehicleId::Truck(truck))
+    }
+
+    /// This solution with route `idx` of drone `drone` replaced by `route`, re-evaluating only that drone
+    pub fn with_replaced_drone_route(&self, drone: DroneId, idx: usize, route: Rc<DroneRoute>) -> Self {
+        let mut drone_routes = self.drone_routes.clone();
+        drone_routes[drone.0][idx] = route;
+        self.with_changed_vehicle(self.truck_routes.clone(), drone_routes, VehicleId::Drone(drone))
+    }
+
+    /// Reconstruct the launch and landing times of every drone (each drone performs its routes back to back from time
+    /// 0) and measure how long more than `--depot-slots` drones occupy the depot simultaneously. The result is
+    /// expressed in multiples of the slot occupancy of a launch by the class of the drone that last acquired a slot.
+    fn _depot_slot_violation(drone_routes: &[Vec<Rc<DroneRoute>>]) -> f64 {
+        let config = CONFIG.load();
+        if config.depot_slots == 0 || drone_routes.len() <= config.depot_slots {
+            return 0.0;
         }
+
+        let mut events = vec![];
+        for (drone, routes) in drone_routes.iter().enumerate() {
+            let drone = DroneId(drone);
+            let class_index = config.drone_class_of(drone);
+            let class = &config.drone_classes[class_index];
+            let takeoff = class.takeoff_time() + config.depot_slot_time;
+            let landing = class.landing_time() + config.depot_slot_time;
+
+            let mut time = config.release_time(VehicleId::Drone(drone));
+            for route in routes {
+                let end = time + route.class_metrics_of(class_index).working_time;
+                events.push((time, true, takeoff));
+                events.push((time + takeoff, false, takeoff));
+                events.push((end - landing, true, takeoff));
+                events.push((end, false, takeoff));
+                time = end + class.recharge_time();
+            }
+        }
+
+        // Releases sort before acquisitions at the same instant
+        events.sort_by(|f, s| f.0.total_cmp(&s.0).then(f.1.cmp(&s.1)));
+
+        let mut excess = 0.0;
+        let mut occupied = 0_usize;
+        let mut last = 0.0;
+        let mut occupancy = 0.0;
+        for (time, acquire, launch) in events {
+            if occupancy > 0.0 {
+                excess += occupied.saturating_sub(config.depot_slots) as f64 * (time - last) / occupancy;
+            }
+            if acquire {
+                occupied += 1;
+                occupancy = launch;
+            } else {
+                occupied -= 1;
+            }
+            last = time;
+        }
+
+        excess
+    }
+
+    /// Legs of each route of each drone (see `--detailed-routes`). Sorties launched from trucks are not included.
+    pub fn drone_legs(&self) -> Vec<Vec<Vec<DroneLeg>>> {
+        self.drone_routes
+            .iter()
+            .enumerate()
+            .map(|(drone, routes)| routes.iter().map(|r| r.legs(DroneId(drone))).collect())
+            .collect()
+    }
+
+    /// Reconstruct the timeline of the solution: each vehicle performs its routes back to back from its release time,
+    /// with the reload or recharge time between consecutive routes. Times are absolute, in seconds.
+    pub fn schedule(&self) -> Vec<VehicleSchedule> {
+        let config = CONFIG.load();
+        let mut result = vec![];
+        for (truck, routes) in self.truck_routes.iter().enumerate() {
+            let mut time = config.release_time(VehicleId::Truck(TruckId(truck)));
+            let mut trips = vec![];
+            for (route_idx, route) in routes.iter().enumerate() {
+                let customers = &route.data().customers;
+                let route_sorties = mothership::route_sorties(&self.sorties, truck, route_idx);
+                let (departures, pickups, working_time) = if route_sorties.is_empty() {
+                    (route.arrival_times(), vec![], route.working_time())
+                } else {
+                    let metrics = mothership::evaluate(route, &route_sorties);
+                    (metrics.arrival, metrics.pickups, metrics.working_time)
+                };
+
+                let speed = config.truck.speed;
+                trips.push(Trip {
+                    route: route_idx,
+                    start: time,
+                    end: time + working_time,
+                    visits: (1..customers.len() - 1)
+                        .map(|i| Visit {
+                            customer: customers[i],
+                            arrival: time
+                                + departures[i - 1]
+                                + config.truck_distances[customers[i - 1]][customers[i]] / speed,
+                            departure: time + departures[i],
+                        })
+                        .collect(),
+                    sorties: route_sorties
+                        .iter()
+                        .zip(pickups)
+                        .map(|(sortie, pickup)| SortieVisit {
+                            customer: sortie.customer,
+                            launch: time + departures[sortie.launch],
+                            arrival: time + pickup,
+                        })
+                        .collect(),
+                });
+                time += working_time + config.truck.reload_time;
+            }
+
+            result.push(VehicleSchedule {
+                kind: "truck",
+                vehicle: truck,
+                trips,
+            });
+        }
+
+        for (drone, routes) in self.drone_routes.iter().enumerate() {
+            let class = &config.drone_classes[config.drone_class_of(DroneId(drone))];
+            let mut time = config.release_time(VehicleId::Drone(DroneId(drone)));
+            let mut trips = vec![];
+            for (route_idx, route) in routes.iter().enumerate() {
+                let customers = &route.data().customers;
+                let arrivals = route.arrival_times(DroneId(drone));
+                trips.push(Trip {
+                    route: route_idx,
+                    start: time,
+                    end: time + arrivals[customers.len() - 1],
+                    visits: (1..customers.len() - 1)
+                        .map(|i| Visit {
+                            customer: customers[i],
+                            arrival: time + arrivals[i],
+                            departure: time + arrivals[i],
+                        })
+                        .collect(),
+                    sorties: vec![],
+                });
+                time += arrivals[customers.len() - 1] + class.recharge_time();
+            }
+
+            result.push(VehicleSchedule {
+                kind: "drone",
+                vehicle: drone,
+                trips,
+            });
+        }
+
+        result
     }
 
     pub fn verify(&self) {
-        let mut served = vec![false; CONFIG.customers_count + 1];
+        let mut served = vec![false; CONFIG.load().customers_count + 1];
         served[0] = true;
 
         fn _check_routes<R>(vehicle_routes: &[Vec<Rc<R>>], served: &mut [bool])
@@ -210,27 +816,74 @@ impl Solution {
         _check_routes(&self.truck_routes, &mut served);
         _check_routes(&self.drone_routes, &mut served);
 
-        for (c, s) in served.iter().enumerate() {
-            if !s {
+        for sortie in &self.sorties {
+            let customers = &self.truck_routes[sortie.truck][sortie.route].data().customers;
+            if sortie.launch >= sortie.rendezvous || sortie.rendezvous >= customers.len() {
+                panic!("Invalid sortie {sortie:?}");
+            }
+
+            if served[sortie.customer] {
+                panic!("Customer {} is served more than once", sortie.customer);
+            }
+
+            served[sortie.customer] = true;
+        }
+
+        // A split customer is served once its nodes together deliver its whole demand
+        let mut expected = vec![0.0; CONFIG.load().customers_count + 1];
+        let mut delivered = vec![0.0; CONFIG.load().customers_count + 1];
+        let mut visited = vec![false; CONFIG.load().customers_count + 1];
+        for (c, &s) in served.iter().enumerate() {
+            let customer = CONFIG.load().split_of[c];
+            expected[customer] += CONFIG.load().demands[c];
+            if s {
+                delivered[customer] += CONFIG.load().demands[c];
+                visited[customer] = true;
+            }
+        }
+
+        for c in (0..CONFIG.load().customers_count + 1).filter(|&c| CONFIG.load().split_of[c] == c) {
+            if !visited[c] {
                 panic!("Customer {c} is not served");
             }
+
+            if (delivered[c] - expected[c]).abs() > 1e-6 {
+                panic!("Customer {c} receives {} of its demand {}", delivered[c], expected[c]);
+            }
         }
     }
 
+    /// Penalized cost with the current penalty coefficients (see `Config::cost_function`), memoized until they change
     pub fn cost(&self) -> f64 {
-        self.working_time
-            * penalty_coeff::<3>()
-                .mul_add(
-                    self.fixed_time_violation,
-                    penalty_coeff::<2>().mul_add(
-                        self.waiting_time_violation,
-                        penalty_coeff::<1>().mul_add(
-                            self.capacity_violation,
-                            penalty_coeff::<0>().mul_add(self.energy_violation, 1.0),
-                        ),
-                    ),
-                )
-                .powf(CONFIG.penalty_exponent)
+        let coefficients = penalty_coeffs();
+        if let Some((cached, cost)) = self._cost.get()
+            && cached == coefficients
+        {
+            return cost;
+        }
+
+        let cost = CONFIG.load().cost_function.evaluate(self);
+        self._cost.set(Some((coefficients, cost)));
+        cost
+    }
+
+    /// Value of the objective selected by `--objective`, before penalties
+    pub fn objective(&self) -> f64 {
+        let config = CONFIG.load();
+        match config.objective {
+            Objective::Makespan => self.working_time,
+            Objective::TotalTime => self.total_time,
+            Objective::TotalEnergy => self.total_energy,
+            Objective::Weighted => config
+                .objective_weight
+                .mul_add(self.working_time, (1.0 - config.objective_weight) * self.total_time),
+        }
+    }
+
+    /// Number of trucks and drones serving at least one route
+    pub fn vehicles_used(&self) -> usize {
+        self.truck_routes.iter().filter(|r| !r.is_empty()).count()
+            + self.drone_routes.iter().filter(|r| !r.is_empty()).count()
     }
 
     pub fn hamming_distance(&self, other: &Self) -> usize {
@@ -248,17 +901,120 @@ impl Solution {
             }
         }
 
-        let mut self_repr = vec![0; CONFIG.customers_count + 1];
+        let mut self_repr = vec![0; CONFIG.load().customers_count + 1];
         fill_repr(&self.truck_routes, &mut self_repr);
         fill_repr(&self.drone_routes, &mut self_repr);
 
-        let mut other_repr = vec![0; CONFIG.customers_count + 1];
+        let mut other_repr = vec![0; CONFIG.load().customers_count + 1];
         fill_repr(&other.truck_routes, &mut other_repr);
         fill_repr(&other.drone_routes, &mut other_repr);
 
         self_repr.iter().zip(other_repr.iter()).filter(|(a, b)| a != b).count()
     }
 
+    /// Placement of each customer in the routes of this solution, `None` for the depot and the customers served by
+    /// drone sorties
+    pub fn placements(&self) -> Vec<Option<Placement>> {
+        fn _place<T>(
+            vehicle_routes: &[Vec<Rc<T>>],
+            vehicle: impl Fn(usize) -> VehicleId,
+            result: &mut [Option<Placement>],
+        ) where
+            T: Route,
+        {
+            for (v, routes) in vehicle_routes.iter().enumerate() {
+                for (route, r) in routes.iter().enumerate() {
+                    let customers = &r.data().customers;
+                    for position in 1..customers.len() - 1 {
+                        result[customers[position]] = Some(Placement {
+                            vehicle: vehicle(v),
+                            route,
+                            position,
+                        });
+                    }
+                }
+            }
+        }
+
+        let mut result = vec![None; CONFIG.load().customers_count + 1];
+        _place(&self.truck_routes, |t| VehicleId::Truck(TruckId(t)), &mut result);
+        _place(&self.drone_routes, |d| VehicleId::Drone(DroneId(d)), &mut result);
+        result
+    }
+
+    /// Customers whose vehicle, route or position differ in `other`, along with the Hamming distance between the two
+    /// solutions
+    pub fn diff(&self, other: &Self) -> SolutionDiff {
+        let changes = self
+            .placements()
+            .into_iter()
+            .zip(other.placements())
+            .enumerate()
+            .skip(1)
+            .filter_map(|(customer, (before, after))| {
+                let change = match (before, after) {
+                    (Some(b), Some(a)) if b == a => return None,
+                    (None, None) => return None,
+                    (Some(b), Some(a)) if b.vehicle == a.vehicle && b.route == a.route => Change::Position,
+                    (Some(b), Some(a)) if b.vehicle == a.vehicle => Change::Route,
+                    _ => Change::Vehicle,
+                };
+
+                Some(CustomerChange {
+                    customer,
+                    change,
+                    before,
+                    after,
+                })
+            })
+            .collect();
+
+        SolutionDiff {
+            changes,
+            hamming_distance: self.hamming_distance(other),
+        }
+    }
+
+    /// Re-sequence each route individually, keeping the new order only if it reduces the solution cost.
+    pub fn resequence_routes(&self) -> Self {
+        fn _resequence<T>(mut result: Solution, distances: &[Vec<f64>]) -> Solution
+        where
+            T: Route,
+        {
+            let mut truck_routes = result.truck_routes.clone();
+            let mut drone_routes = result.drone_routes.clone();
+
+            let vehicles = T::get_correct_route(&truck_routes, &drone_routes).len();
+            for vehicle in 0..vehicles {
+                for i in 0..T::get_correct_route(&truck_routes, &drone_routes)[vehicle].len() {
+                    let original = T::get_correct_route(&truck_routes, &drone_routes)[vehicle][i].clone();
+                    if CONFIG.load().pins.route(&original.data().customers) {
+                        continue;
+                    }
+
+                    let customers = tsp::resequence(&original.data().customers, distances);
+                    if customers == original.data().customers {
+                        continue;
+                    }
+
+                    T::get_correct_route_mut(&mut truck_routes, &mut drone_routes)[vehicle][i] = T::new(customers);
+
+                    let s = Solution::new(truck_routes.clone(), drone_routes.clone());
+                    if s.cost() + TOLERANCE < result.cost() && (s.feasible || !result.feasible) {
+                        result = s;
+                    } else {
+                        T::get_correct_route_mut(&mut truck_routes, &mut drone_routes)[vehicle][i] = original;
+                    }
+                }
+            }
+
+            result
+        }
+
+        let result = _resequence::<TruckRoute>(self.clone(), &CONFIG.load().truck_distances);
+        _resequence::<DroneRoute>(result, &CONFIG.load().drone_distances)
+    }
+
     // pub fn post_optimization(&self) -> Self {
     //     let mut result = Rc::new(self.clone());
 
@@ -290,7 +1046,126 @@ impl Solution {
     //     Self::clone(&result)
     // }
 
+    /// Whether this solution is better than `other`: feasible solutions come first, then the lowest cost
+    fn _better(&self, other: &Self) -> bool {
+        (self.feasible && !other.feasible)
+            || (self.feasible == other.feasible && self.cost() + TOLERANCE < other.cost())
+    }
+
+    /// Tabu search of `iterations` iterations from `root`, cycling through the neighborhoods, without the elite set,
+    /// resets or logging of the full search
+    pub fn short_search(root: Self, iterations: usize) -> Self {
+        let tabu_size = root.hyperparameters().tabu_size;
+        let mut tabu_lists = vec![vec![]; NEIGHBORHOODS.len()];
+        let selectable = selectable_neighborhoods();
+        let mut current = root.clone();
+        let mut result = root;
+        let mut coefficients = [1.0; 7];
+        set_penalty_coeffs(coefficients);
+        let mut rng = StdRng::from_rng(&mut rng());
+        for iteration in 0..iterations {
+            let index = selectable[iteration % selectable.len()];
+            if let Some(neighbor) =
+                NEIGHBORHOODS[index].search(&current, &mut tabu_lists[index], tabu_size, result.cost(), &mut rng)
+            {
+                if neighbor._better(&result) {
+                    result = neighbor.clone();
+                }
+                current = neighbor;
+            }
+
+            coefficients = updated_penalty_coeffs(coefficients, &current);
+            set_penalty_coeffs(coefficients);
+        }
+
+        result
+    }
+
+    /// Construct the initial solution with `--grasp-cycles` cycles of GRASP randomized construction followed by a
+    /// short tabu search, keeping the best solution
+    fn _grasp(alpha: f64) -> Self {
+        let config = CONFIG.load();
+        let _span = info_span!("grasp", alpha).entered();
+        let mut result: Option<Self> = None;
+        for cycle in 0..config.grasp_cycles {
+            let constructed = construction::grasp(alpha);
+            let constructed_cost = constructed.cost();
+            let solution = Self::short_search(constructed, config.grasp_iterations);
+            reset_penalty_coeff();
+            debug!(
+                "GRASP cycle #{cycle}: constructed {constructed_cost:.2}, searched {:.2}{}",
+                solution.cost(),
+                if solution.feasible { "" } else { " (infeasible)" }
+            );
+
+            if result.as_ref().is_none_or(|r| solution._better(r)) {
+                result = Some(solution);
+            }
+        }
+
+        let result = result.expect("--grasp-cycles must be positive");
+        info!("GRASP initial solution: {:.2}", result.cost());
+        result
+    }
+
+    /// Construct the initial solution with the heuristic of `--construction`, or with GRASP (see `--grasp`). With
+    /// `--verbose`, the initial cost of every other heuristic is logged as well.
     pub fn initialize() -> Self {
+        let config = CONFIG.load();
+        if let Some(alpha) = config.grasp {
+            return Self::_grasp(alpha);
+        }
+
+        let _span = info_span!("initialize", construction = %config.construction).entered();
+        let construct = |construction| match construction {
+            Construction::Cluster => Self::_cluster_construction(config.clustering),
+            Construction::Savings => construction::savings(),
+            Construction::Regret => construction::regret(),
+        };
+
+        if enabled!(Level::DEBUG) {
+            for construction in [Construction::Cluster, Construction::Savings, Construction::Regret] {
+                if construction != config.construction {
+                    let solution = construct(construction);
+                    debug!(
+                        "Initial cost of the {construction} construction: {} (working time = {}, feasible = {})",
+                        solution.cost(),
+                        solution.working_time,
+                        solution.feasible
+                    );
+                }
+            }
+        }
+
+        let solution = construct(config.construction);
+        debug!(
+            "Initial cost of the {} construction: {} (working time = {}, feasible = {})",
+            config.construction,
+            solution.cost(),
+            solution.working_time,
+            solution.feasible
+        );
+        solution
+    }
+
+    /// Construct a new solution from scratch with the cluster construction, partitioning the customers with k-means or
+    /// k-medoids drawn at random so that each call starts from different clusters (see `--recluster-after`)
+    pub fn recluster() -> Self {
+        let clustering = *[Clustering::Kmeans, Clustering::Kmedoids].choose(&mut rng()).unwrap();
+        let _span = info_span!("recluster", %clustering).entered();
+        let solution = Self::_cluster_construction(clustering);
+        debug!(
+            "Re-clustered solution: {} (working time = {}, feasible = {})",
+            solution.cost(),
+            solution.working_time,
+            solution.feasible
+        );
+        solution
+    }
+
+    /// Assign the customers of each cluster (partitioned with `clustering`) to a truck and a drone, and grow their
+    /// routes with the nearest customer of the vehicle that currently has the smallest working time
+    fn _cluster_construction(clustering: Clustering) -> Self {
         fn _sort_cluster_with_starting_point(cluster: &mut [usize], mut start: usize, distance: &[Vec<f64>]) {
             if cluster.is_empty() {
                 return;
@@ -312,47 +1187,76 @@ impl Solution {
             }
         }
 
+        /// Whether the solution is feasible, ignoring the assignment rules: customers are probed on the first vehicle
+        /// of each type, while `CONFIG.truckable` and `CONFIG.dronable` already account for the rules
         fn _feasible(truck_routes: Vec<Vec<Rc<TruckRoute>>>, drone_routes: Vec<Vec<Rc<DroneRoute>>>) -> bool {
             let solution = Solution::new(truck_routes, drone_routes);
-            solution.feasible
+            [
+                solution.energy_violation,
+                solution.capacity_violation,
+                solution.waiting_time_violation,
+                solution.fixed_time_violation,
+                solution.depot_slot_violation,
+                solution.no_fly_violation,
+            ]
+            .iter()
+            .zip(CONFIG.load().soft_weights)
+            .all(|(&violation, soft)| violation == 0.0 || soft.is_some())
         }
 
-        let mut index = Vec::from_iter(1..CONFIG.customers_count + 1);
-        let mut clusters = clusterize::clusterize(&mut index, CONFIG.trucks_count);
+        let mut index = (1..CONFIG.load().customers_count + 1)
+            .map(CustomerId)
+            .collect::<Vec<_>>();
+        let mut clusters = clusterize::clusterize(&mut index, CONFIG.load().trucks_count, clustering)
+            .into_iter()
+            .map(|cluster| cluster.into_iter().map(|CustomerId(c)| c).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
 
-        let mut truck_routes = vec![vec![]; CONFIG.trucks_count];
-        let mut drone_routes = vec![vec![]; CONFIG.trucks_count];
+        let mut truck_routes = vec![vec![]; CONFIG.load().trucks_count];
+        let mut drone_routes = vec![vec![]; CONFIG.load().trucks_count];
 
-        let mut clusters_mapping = vec![0; CONFIG.customers_count + 1];
+        let mut clusters_mapping = vec![0; CONFIG.load().customers_count + 1];
         for (i, cluster) in clusters.iter().enumerate() {
             for &customer in cluster {
                 clusters_mapping[customer] = i;
             }
         }
 
-        let mut truckable = vec![false; CONFIG.customers_count + 1];
-        if CONFIG.trucks_count > 0 {
+        let mut truckable = vec![false; CONFIG.load().customers_count + 1];
+        if CONFIG.load().trucks_count > 0 {
             truckable[0] = true;
-            for (customer, truckable) in truckable.iter_mut().enumerate().skip(1).take(CONFIG.customers_count) {
-                truck_routes[0].push(TruckRoute::single(customer));
-                *truckable = _feasible(truck_routes.clone(), drone_routes.clone());
-                truck_routes[0].pop();
+            for (customer, truckable) in truckable
+                .iter_mut()
+                .enumerate()
+                .skip(1)
+                .take(CONFIG.load().customers_count)
+            {
+                if CONFIG.load().truckable[customer] {
+                    truck_routes[0].push(TruckRoute::single(CustomerId(customer)));
+                    *truckable = _feasible(truck_routes.clone(), drone_routes.clone());
+                    truck_routes[0].pop();
+                }
             }
         }
 
-        let mut dronable = vec![false; CONFIG.customers_count + 1];
-        if CONFIG.drones_count > 0 {
+        let mut dronable = vec![false; CONFIG.load().customers_count + 1];
+        if CONFIG.load().drones_count > 0 {
             dronable[0] = true;
-            for (customer, dronable) in dronable.iter_mut().enumerate().skip(1).take(CONFIG.customers_count) {
-                if CONFIG.dronable[customer] {
-                    drone_routes[0].push(DroneRoute::single(customer));
+            for (customer, dronable) in dronable
+                .iter_mut()
+                .enumerate()
+                .skip(1)
+                .take(CONFIG.load().customers_count)
+            {
+                if CONFIG.load().dronable[customer] {
+                    drone_routes[0].push(DroneRoute::single(CustomerId(customer)));
                     *dronable = _feasible(truck_routes.clone(), drone_routes.clone());
                     drone_routes[0].pop();
                 }
             }
         }
 
-        for customer in 1..CONFIG.customers_count + 1 {
+        for customer in 1..CONFIG.load().customers_count + 1 {
             if !truckable[customer] && !dronable[customer] {
                 panic!("Customer {customer} cannot be served by neither trucks nor drones")
             }
@@ -409,7 +1313,8 @@ impl Solution {
                 }
             }
 
-            cluster.sort_by(|&i, &j| CONFIG.drone_distances[0][i].total_cmp(&CONFIG.drone_distances[0][j]));
+            cluster
+                .sort_by(|&i, &j| CONFIG.load().drone_distances[0][i].total_cmp(&CONFIG.load().drone_distances[0][j]));
             for &customer in cluster.iter() {
                 if dronable[customer] {
                     queue.push(_State {
@@ -425,7 +1330,7 @@ impl Solution {
             }
         }
 
-        let mut global = BTreeSet::from_iter(1..CONFIG.customers_count + 1);
+        let mut global = BTreeSet::from_iter(1..CONFIG.load().customers_count + 1);
 
         fn truck_next(
             truckable: &[bool],
@@ -433,33 +1338,31 @@ impl Solution {
             clusters_mapping: &[usize],
             queue: &mut BinaryHeap<_State>,
             global: &BTreeSet<usize>,
-            truck_routes: &mut [Vec<Rc<TruckRoute>>],
-            drone_routes: &[Vec<Rc<DroneRoute>>],
+            truck_routes: &[Vec<Rc<TruckRoute>>],
             parent: usize,
             vehicle: usize,
         ) {
             let mut min_distance = f64::INFINITY;
             let mut min_idx = 0;
             for &customer in &clusters[clusters_mapping[parent]] {
-                if truckable[customer] && CONFIG.truck_distances[parent][customer] < min_distance {
-                    min_distance = CONFIG.truck_distances[parent][customer];
+                if truckable[customer] && CONFIG.load().truck_distances[parent][customer] < min_distance {
+                    min_distance = CONFIG.load().truck_distances[parent][customer];
                     min_idx = customer;
                 }
             }
 
             if min_idx == 0 {
                 for &customer in global.iter() {
-                    if truckable[customer] && CONFIG.truck_distances[parent][customer] < min_distance {
-                        min_distance = CONFIG.truck_distances[parent][customer];
+                    if truckable[customer] && CONFIG.load().truck_distances[parent][customer] < min_distance {
+                        min_distance = CONFIG.load().truck_distances[parent][customer];
                         min_idx = customer;
                     }
                 }
             }
 
             if min_idx != 0 {
-                let temp = Solution::new(truck_routes.to_vec(), drone_routes.to_vec());
                 queue.push(_State {
-                    working_time: temp.truck_working_time[vehicle],
+                    working_time: Solution::truck_metrics(TruckId(vehicle), &truck_routes[vehicle], &[]).working_time,
                     vehicle,
                     parent,
                     index: min_idx,
@@ -474,33 +1377,31 @@ impl Solution {
             clusters_mapping: &[usize],
             queue: &mut BinaryHeap<_State>,
             global: &BTreeSet<usize>,
-            truck_routes: &[Vec<Rc<TruckRoute>>],
-            drone_routes: &mut [Vec<Rc<DroneRoute>>],
+            drone_routes: &[Vec<Rc<DroneRoute>>],
             parent: usize,
             vehicle: usize,
         ) {
             let mut min_distance = f64::INFINITY;
             let mut min_idx = 0;
             for &customer in &clusters[clusters_mapping[parent]] {
-                if dronable[customer] && CONFIG.drone_distances[parent][customer] < min_distance {
-                    min_distance = CONFIG.drone_distances[parent][customer];
+                if dronable[customer] && CONFIG.load().drone_distances[parent][customer] < min_distance {
+                    min_distance = CONFIG.load().drone_distances[parent][customer];
                     min_idx = customer;
                 }
             }
 
             if min_idx == 0 {
                 for &customer in global.iter() {
-                    if dronable[customer] && CONFIG.drone_distances[parent][customer] < min_distance {
-                        min_distance = CONFIG.drone_distances[parent][customer];
+                    if dronable[customer] && CONFIG.load().drone_distances[parent][customer] < min_distance {
+                        min_distance = CONFIG.load().drone_distances[parent][customer];
                         min_idx = customer;
                     }
                 }
             }
 
             if min_idx != 0 {
-                let temp = Solution::new(truck_routes.to_vec(), drone_routes.to_vec());
                 queue.push(_State {
-                    working_time: temp.drone_working_time[vehicle],
+                    working_time: Solution::drone_metrics(DroneId(vehicle), &drone_routes[vehicle]).working_time,
                     vehicle,
                     parent,
                     index: min_idx,
@@ -517,13 +1418,13 @@ impl Solution {
                 Some(index) => {
                     if packed.is_truck {
                         if packed.parent == 0 {
-                            truck_routes[packed.vehicle].push(TruckRoute::single(packed.index));
+                            truck_routes[packed.vehicle].push(TruckRoute::single(CustomerId(packed.index)));
                         } else {
                             let route = truck_routes[packed.vehicle].last_mut().unwrap();
                             *route = route.push(packed.index);
                         }
                     } else if packed.parent == 0 {
-                        drone_routes[packed.vehicle].push(DroneRoute::single(packed.index));
+                        drone_routes[packed.vehicle].push(DroneRoute::single(CustomerId(packed.index)));
                     } else {
                         let route = drone_routes[packed.vehicle].last_mut().unwrap();
                         *route = route.push(packed.index);
@@ -540,8 +1441,7 @@ impl Solution {
                                 &clusters_mapping,
                                 &mut queue,
                                 &global,
-                                &mut truck_routes,
-                                &drone_routes,
+                                &truck_routes,
                                 packed.index,
                                 packed.vehicle,
                             );
@@ -552,9 +1452,12 @@ impl Solution {
                                 &clusters_mapping,
                                 &mut queue,
                                 &global,
-                                &truck_routes,
-                                &mut drone_routes,
-                                if CONFIG.single_drone_route { 0 } else { packed.index },
+                                &drone_routes,
+                                if CONFIG.load().single_drone_route {
+                                    0
+                                } else {
+                                    packed.index
+                                },
                                 packed.vehicle,
                             );
                         }
@@ -566,15 +1469,14 @@ impl Solution {
                             *route = route.pop();
                         }
 
-                        if !CONFIG.single_truck_route {
+                        if !CONFIG.load().single_truck_route {
                             truck_next(
                                 &truckable,
                                 &clusters,
                                 &clusters_mapping,
                                 &mut queue,
                                 &global,
-                                &mut truck_routes,
-                                &drone_routes,
+                                &truck_routes,
                                 0,
                                 packed.vehicle,
                             );
@@ -593,8 +1495,7 @@ impl Solution {
                             &clusters_mapping,
                             &mut queue,
                             &global,
-                            &truck_routes,
-                            &mut drone_routes,
+                            &drone_routes,
                             0,
                             packed.vehicle,
                         );
@@ -608,8 +1509,7 @@ impl Solution {
                             &clusters_mapping,
                             &mut queue,
                             &global,
-                            &mut truck_routes,
-                            &drone_routes,
+                            &truck_routes,
                             packed.parent,
                             packed.vehicle,
                         );
@@ -620,9 +1520,12 @@ impl Solution {
                             &clusters_mapping,
                             &mut queue,
                             &global,
-                            &truck_routes,
-                            &mut drone_routes,
-                            if CONFIG.single_drone_route { 0 } else { packed.parent },
+                            &drone_routes,
+                            if CONFIG.load().single_drone_route {
+                                0
+                            } else {
+                                packed.parent
+                            },
                             packed.vehicle,
                         );
                     }
@@ -630,8 +1533,9 @@ impl Solution {
             }
         }
 
-        if CONFIG.drones_count > 0 {
-            // Resize drone routes to `CONFIG.drones_count`
+        if CONFIG.load().drones_count > 0 {
+            // Resize drone routes to `CONFIG.drones_count` with the longest-processing-time rule, each route to the
+            // drone that would finish it the earliest given its class
             let mut all_routes = vec![];
             for routes in &drone_routes {
                 all_routes.extend(routes.iter().cloned());
@@ -639,21 +1543,22 @@ impl Solution {
             all_routes.sort_by(|f, s| f.working_time().total_cmp(&s.working_time()).reverse());
 
             drone_routes.clear();
-            drone_routes.resize(CONFIG.drones_count, vec![]);
+            drone_routes.resize(CONFIG.load().drones_count, vec![]);
 
-            let mut working_time = vec![0.0; CONFIG.drones_count];
+            let mut working_time = vec![0.0; CONFIG.load().drones_count];
             for route in all_routes {
                 let mut min_idx = 0;
                 let mut min_time = f64::INFINITY;
                 for (i, &time) in working_time.iter().enumerate() {
-                    if time < min_time {
-                        min_time = time;
+                    let finish = time + route.metrics(DroneId(i)).working_time;
+                    if finish < min_time {
+                        min_time = finish;
                         min_idx = i;
                     }
                 }
 
                 drone_routes[min_idx].push(route.clone());
-                working_time[min_idx] += route.working_time();
+                working_time[min_idx] += route.metrics(DroneId(min_idx)).working_time;
             }
         } else {
             drone_routes.clear();
@@ -662,9 +1567,69 @@ impl Solution {
         Self::new(truck_routes, drone_routes)
     }
 
-    pub fn destroy_and_repair(&self, edge_records: &[Vec<f64>]) -> Self {
+    /// Redistribute the unpinned drone routes among the drones with the longest-processing-time rule: longest route
+    /// first, each to the drone allowed to serve its customers that would finish it the earliest. Returns the
+    /// redistributed solution if it is feasible and cheaper than this one (see `--drone-rebalancing`).
+    pub fn rebalance_drones(&self) -> Option<Self> {
+        let config = CONFIG.load();
+        if config.drones_count < 2 || !self.sorties.is_empty() {
+            return None;
+        }
+
+        let mut drone_routes = vec![vec![]; config.drones_count];
+        let mut free = vec![];
+        for (drone, routes) in self.drone_routes.iter().enumerate() {
+            for route in routes {
+                if route.data().customers.iter().any(|&c| config.pins.customer(c)) {
+                    drone_routes[drone].push(route.clone());
+                } else {
+                    free.push(route.clone());
+                }
+            }
+        }
+        free.sort_by(|f, s| f.working_time().total_cmp(&s.working_time()).reverse());
+
+        let mut working_time = drone_routes
+            .iter()
+            .enumerate()
+            .map(|(drone, routes)| Self::drone_metrics(DroneId(drone), routes).working_time)
+            .collect::<Vec<_>>();
+        for route in free {
+            let customers = &route.data().customers;
+            let finish = |drone: usize| {
+                let recharge = if drone_routes[drone].is_empty() {
+                    0.0
+                } else {
+                    config.drone_classes[config.drone_class_of(DroneId(drone))].recharge_time()
+                };
+                working_time[drone] + recharge + route.metrics(DroneId(drone)).working_time
+            };
+            let drone = (0..config.drones_count)
+                .filter(|&drone| {
+                    customers[1..customers.len() - 1]
+                        .iter()
+                        .all(|&c| config.assignment_rules.assignable(c, VehicleId::Drone(DroneId(drone))))
+                })
+                .min_by(|&i, &j| finish(i).total_cmp(&finish(j)))?;
+
+            working_time[drone] = finish(drone);
+            drone_routes[drone].push(route);
+        }
+
+        let rebalanced = Self::new(self.truck_routes.clone(), drone_routes);
+        (rebalanced.feasible && rebalanced.cost() + TOLERANCE < self.cost()).then_some(rebalanced)
+    }
+
+    pub fn destroy_and_repair(
+        &self,
+        edge_records: &[Vec<f64>],
+        frequency: &FrequencyMemory,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let config = CONFIG.load();
+        let _span = debug_span!("destroy_and_repair").entered();
         // TODO: Implement
-        let mut scores = vec![0.0; CONFIG.customers_count + 1];
+        let mut scores = vec![0.0; config.customers_count + 1];
         for routes in &self.truck_routes {
             for route in routes {
                 let customers = &route.data().customers;
@@ -684,11 +1649,13 @@ impl Solution {
             }
         }
 
-        let mut ordered = (1..CONFIG.customers_count + 1).collect::<Vec<usize>>();
+        // Pinned customers are never destroyed, since the repair could move them to another vehicle
+        let mut ordered = (1..config.customers_count + 1)
+            .filter(|&c| !config.pins.customer(c))
+            .collect::<Vec<usize>>();
         ordered.sort_unstable_by(|&a, &b| scores[a].total_cmp(&scores[b]));
 
-        let mut rng = rng();
-        let destroy_count = (CONFIG.customers_count as f64 * CONFIG.destroy_rate) as usize;
+        let destroy_count = ((config.customers_count as f64 * config.destroy_rate) as usize).min(ordered.len());
         let mut to_destroy = HashSet::new();
         while to_destroy.len() < destroy_count {
             let index = rng.random_range(0..ordered.len()).pow(2) / ordered.len();
@@ -739,92 +1706,165 @@ impl Solution {
 
         // Repair phase
         let mut to_destroy = to_destroy.into_iter().collect::<Vec<usize>>();
-        to_destroy.shuffle(&mut rng);
-
-        let old_penalty = [
-            penalty_coeff::<0>(),
-            penalty_coeff::<1>(),
-            penalty_coeff::<2>(),
-            penalty_coeff::<3>(),
-        ];
-        for i in 0..4 {
-            PENALTY_COEFF[i].store(1e3, Ordering::Relaxed);
-        }
-
-        for customer in to_destroy {
-            let mut min_cost = f64::MAX;
-            let mut insert = (true, true, 0, 0, 0);
-
-            for truck in 0..truck_routes.len() {
-                // Try appending
-                if !CONFIG.single_truck_route || truck_routes[truck].is_empty() {
-                    truck_routes[truck].push(TruckRoute::single(customer));
-                    let temp = Self::new(truck_routes, drone_routes);
-                    if temp.cost() < min_cost {
-                        min_cost = temp.cost();
-                        insert = (true, true, truck, 0, 0);
-                    }
+        to_destroy.shuffle(rng);
 
-                    truck_routes = temp.truck_routes;
-                    drone_routes = temp.drone_routes;
-                    truck_routes[truck].pop();
-                }
+        Self::repair(
+            truck_routes,
+            drone_routes,
+            to_destroy,
+            frequency,
+            config.repair_noise,
+            None,
+            rng,
+        )
+    }
 
-                // Try inserting
-                for route in 0..truck_routes[truck].len() {
-                    let recover = truck_routes[truck][route].clone();
-                    let customers = &recover.data().customers;
-                    let mut buffer = customers.clone();
+    /// Greedily insert each of `customers` (in order) at the position of `truck_routes` and `drone_routes` yielding
+    /// the lowest cost, with all penalty coefficients temporarily raised to their maximum. The cost of each candidate
+    /// position is multiplied by a random factor between 1 - `noise` and 1 + `noise` (see `--repair-noise`), drawn
+    /// from `rng`. The vehicle `excluded` receives no customer.
+    pub fn repair(
+        mut truck_routes: Vec<Vec<Rc<TruckRoute>>>,
+        mut drone_routes: Vec<Vec<Rc<DroneRoute>>>,
+        customers: Vec<usize>,
+        frequency: &FrequencyMemory,
+        noise: f64,
+        excluded: Option<VehicleId>,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let mut noisy = |cost: f64| {
+            if noise == 0.0 {
+                cost
+            } else {
+                cost * rng.random_range(1.0 - noise..=1.0 + noise)
+            }
+        };
 
-                    buffer.insert(1, customer);
-                    for i in 1..customers.len() - 1 {
-                        truck_routes[truck][route] = TruckRoute::new(buffer.clone());
+        let old_penalty = penalty_coeffs();
+        for i in 0..old_penalty.len() {
+            PENALTY_COEFF.with(|c| c[i].set(1e3));
+        }
+
+        for customer in customers {
+            // Every placement changes a single vehicle of this solution
+            let base = Self::new(truck_routes.clone(), drone_routes.clone());
+            let mut min_cost = f64::INFINITY;
+            let mut insert = None;
+
+            if CONFIG.load().truckable[customer] {
+                for truck in 0..truck_routes.len() {
+                    let vehicle = VehicleId::Truck(TruckId(truck));
+                    if excluded == Some(vehicle) || !CONFIG.load().assignment_rules.assignable(customer, vehicle) {
+                        continue;
+                    }
 
-                        let temp = Self::new(truck_routes, drone_routes);
-                        if temp.cost() < min_cost {
-                            min_cost = temp.cost();
-                            insert = (true, false, truck, route, i);
+                    // Try appending
+                    if !CONFIG.load().single_truck_route || truck_routes[truck].is_empty() {
+                        truck_routes[truck].push(TruckRoute::single(CustomerId(customer)));
+                        let temp = base.with_changed_vehicle(truck_routes, drone_routes, vehicle);
+                        let cost = noisy(temp.cost())
+                            * frequency.bias(CustomerId(customer), vehicle, CustomerId(0), CustomerId(0));
+                        if insert.is_none() || cost < min_cost {
+                            min_cost = cost;
+                            insert = Some((vehicle, true, 0, 0));
                         }
 
                         truck_routes = temp.truck_routes;
                         drone_routes = temp.drone_routes;
-
-                        buffer.swap(i, i + 1);
+                        truck_routes[truck].pop();
                     }
 
-                    // buffer.pop();  // No need to recover, we're throwing `buffer` away anyway.
-                    truck_routes[truck][route] = recover;
+                    // Try inserting
+                    for route in 0..truck_routes[truck].len() {
+                        let recover = truck_routes[truck][route].clone();
+                        let customers = &recover.data().customers;
+                        if CONFIG.load().pins.route(customers) {
+                            continue;
+                        }
+
+                        let mut buffer = customers.clone();
+
+                        buffer.insert(1, customer);
+                        for i in 1..customers.len() - 1 {
+                            truck_routes[truck][route] = TruckRoute::new(buffer.clone());
+
+                            let temp = base.with_changed_vehicle(truck_routes, drone_routes, vehicle);
+                            let cost = noisy(temp.cost())
+                                * frequency.bias(
+                                    CustomerId(customer),
+                                    vehicle,
+                                    CustomerId(buffer[i - 1]),
+                                    CustomerId(buffer[i + 1]),
+                                );
+                            if insert.is_none() || cost < min_cost {
+                                min_cost = cost;
+                                insert = Some((vehicle, false, route, i));
+                            }
+
+                            truck_routes = temp.truck_routes;
+                            drone_routes = temp.drone_routes;
+
+                            buffer.swap(i, i + 1);
+                        }
+
+                        // buffer.pop();  // No need to recover, we're throwing `buffer` away anyway.
+                        truck_routes[truck][route] = recover;
+                    }
                 }
             }
 
-            if CONFIG.dronable[customer] {
+            if CONFIG.load().dronable[customer] {
                 for drone in 0..drone_routes.len() {
+                    let vehicle = VehicleId::Drone(DroneId(drone));
+                    if excluded == Some(vehicle) || !CONFIG.load().assignment_rules.assignable(customer, vehicle) {
+                        continue;
+                    }
+
                     // Try appending
-                    drone_routes[drone].push(DroneRoute::single(customer));
-                    let temp = Self::new(truck_routes.clone(), drone_routes.clone());
-                    if temp.cost() < min_cost {
-                        min_cost = temp.cost();
-                        insert = (false, true, drone, 0, 0);
+                    drone_routes[drone].push(DroneRoute::single(CustomerId(customer)));
+                    let temp = base.with_changed_vehicle(truck_routes, drone_routes, vehicle);
+                    let cost = noisy(temp.cost())
+                        * frequency.bias(CustomerId(customer), vehicle, CustomerId(0), CustomerId(0));
+                    if insert.is_none() || cost < min_cost {
+                        min_cost = cost;
+                        insert = Some((vehicle, true, 0, 0));
                     }
                     truck_routes = temp.truck_routes;
                     drone_routes = temp.drone_routes;
                     drone_routes[drone].pop();
 
                     // Try inserting
-                    if !CONFIG.single_drone_route {
+                    if !CONFIG.load().single_drone_route {
                         for route in 0..drone_routes[drone].len() {
                             let recover = drone_routes[drone][route].clone();
                             let customers = &recover.data().customers;
+                            if CONFIG.load().pins.route(customers) {
+                                continue;
+                            }
+
                             let mut buffer = customers.clone();
 
                             buffer.insert(1, customer);
                             for i in 1..customers.len() - 1 {
+                                // No drone can fly the legs to and from `customer` in a feasible route
+                                if DroneRoute::_hopeless(&buffer[i - 1..i + 2]) {
+                                    buffer.swap(i, i + 1);
+                                    continue;
+                                }
+
                                 drone_routes[drone][route] = DroneRoute::new(buffer.clone());
 
-                                let temp = Self::new(truck_routes.clone(), drone_routes.clone());
-                                if temp.cost() < min_cost {
-                                    min_cost = temp.cost();
-                                    insert = (false, false, drone, route, i);
+                                let temp = base.with_changed_vehicle(truck_routes, drone_routes, vehicle);
+                                let cost = noisy(temp.cost())
+                                    * frequency.bias(
+                                        CustomerId(customer),
+                                        vehicle,
+                                        CustomerId(buffer[i - 1]),
+                                        CustomerId(buffer[i + 1]),
+                                    );
+                                if insert.is_none() || cost < min_cost {
+                                    min_cost = cost;
+                                    insert = Some((vehicle, false, route, i));
                                 }
 
                                 truck_routes = temp.truck_routes;
@@ -850,7 +1890,7 @@ impl Solution {
                 T: Route,
             {
                 if append {
-                    routes[vehicle].push(T::single(customer));
+                    routes[vehicle].push(T::single(CustomerId(customer)));
                 } else {
                     let mut buffer = routes[vehicle][route].data().customers.clone();
                     buffer.insert(index, customer);
@@ -858,314 +1898,164 @@ impl Solution {
                 }
             }
 
-            let (is_truck, append, vehicle, route, index) = insert;
-            if is_truck {
-                _insert(&mut truck_routes, customer, append, vehicle, route, index);
-            } else {
-                _insert(&mut drone_routes, customer, append, vehicle, route, index);
+            // Unreachable: the config rejects customers that neither trucks nor drones can serve
+            let (vehicle, append, route, index) =
+                insert.unwrap_or_else(|| panic!("No vehicle can serve customer {customer}"));
+            match vehicle {
+                VehicleId::Truck(TruckId(truck)) => _insert(&mut truck_routes, customer, append, truck, route, index),
+                VehicleId::Drone(DroneId(drone)) => _insert(&mut drone_routes, customer, append, drone, route, index),
             }
         }
 
-        for i in 0..4 {
-            PENALTY_COEFF[i].store(old_penalty[i], Ordering::Relaxed);
+        for i in 0..old_penalty.len() {
+            PENALTY_COEFF.with(|c| c[i].set(old_penalty[i]));
         }
 
         Self::new(truck_routes, drone_routes)
         // s.verify();
     }
 
-    pub fn tabu_search(root: Self, logger: &mut Logger) -> Self {
+    /// Hyperparameters of a tabu search starting from this solution
+    pub fn hyperparameters(&self) -> Hyperparameters {
+        let config = CONFIG.load();
         let mut total_vehicle = 0;
-        for truck in &root.truck_routes {
+        for truck in &self.truck_routes {
             total_vehicle += usize::from(!truck.is_empty());
         }
-        for drone in &root.drone_routes {
+        for drone in &self.drone_routes {
             total_vehicle += usize::from(!drone.is_empty());
         }
-        let base_hyperparameter = CONFIG.customers_count as f64 / total_vehicle as f64;
-        let tabu_size = (CONFIG.tabu_size_factor * base_hyperparameter) as usize;
+        let base = config.customers_count as f64 / total_vehicle as f64;
+        let tabu_size = (config.tabu_size_factor * base) as usize;
 
-        let adaptive_iterations = (CONFIG.adaptive_iterations as f64 * base_hyperparameter) as usize;
+        let adaptive_iterations = (config.adaptive_iterations as f64 * base) as usize;
 
-        let reset_after = if CONFIG.fix_iteration.is_some() {
+        let reset_after = if config.fix_iteration.is_some() {
             i64::MAX as usize // usize::MAX cannot be stored in SQLite
         } else {
-            (CONFIG.reset_after_factor * base_hyperparameter) as usize
+            (config.reset_after_factor * base) as usize
         };
 
-        let mut result = Rc::new(root);
+        Hyperparameters {
+            base,
+            tabu_size,
+            adaptive_iterations,
+            reset_after,
+        }
+    }
 
-        let mut last_improved_iteration = 0;
+    pub fn tabu_search(root: Self, logger: &mut Logger) -> Self {
+        Self::_search(root, logger, None)
+    }
 
-        struct _AdaptiveState {
-            segment: usize,
-            segment_reset: usize,
-            last_improved_segment: usize,
-            scores: Vec<f64>,
-            weights: Vec<f64>,
-            occurences: Vec<u32>,
-        }
+    /// Tabu search of `island` among those of `--islands`, exchanging its best solution with the other islands every
+    /// `--migration-interval` iterations (see `islands::search`)
+    pub fn island_search(root: Self, logger: &mut Logger, island: Island) -> Self {
+        Self::_search(root, logger, Some(island))
+    }
 
-        let mut adaptive = _AdaptiveState {
-            segment: 0,
-            segment_reset: 0,
-            last_improved_segment: 0,
-            scores: vec![0.0; NEIGHBORHOODS.len()],
-            weights: vec![1.0; NEIGHBORHOODS.len()],
-            occurences: vec![0; NEIGHBORHOODS.len()],
-        };
+    fn _search(root: Self, logger: &mut Logger, island: Option<Island>) -> Self {
+        let config = CONFIG.load();
+        let root = config.pins.impose(root);
+        let Hyperparameters {
+            tabu_size,
+            adaptive_iterations,
+            reset_after,
+            ..
+        } = root.hyperparameters();
+
+        let mut state = SearchState::new(root, tabu_size);
+        if let Some(seed) = config.search_seed {
+            state = state.with_seed(seed);
+        }
 
         let mut post_optimization = 0.0;
         let mut post_optimization_elapsed = 0.0;
-        if !CONFIG.dry_run {
-            let mut current = result.clone();
-            let mut edge_records = vec![vec![f64::MAX; CONFIG.customers_count + 1]; CONFIG.customers_count + 1];
-            let mut elite_set = vec![];
-            elite_set.push(result.clone());
-
-            let mut neighborhood_idx = 0;
-
-            let iteration_range = match CONFIG.fix_iteration {
+        if !config.dry_run {
+            let iteration_range = match config.fix_iteration {
                 Some(iteration) => 1..iteration + 1,
                 None => 1..usize::MAX,
             };
-            let mut rng = rand::rng();
-
-            let mut tabu_lists = vec![vec![]; NEIGHBORHOODS.len()];
-
-            fn _record_new_solution(
-                neighbor: &Rc<Solution>,
-                result: &mut Rc<Solution>,
-                last_improved_iteration: &mut usize,
-                last_improved_segment: &mut usize,
-                iteration: usize,
-                segment: usize,
-                edge_records: &mut [Vec<f64>],
-                elite_set: &mut Vec<Rc<Solution>>,
-            ) {
-                if neighbor.cost() + TOLERANCE < result.cost() && neighbor.feasible {
-                    *result = neighbor.clone();
-                    *last_improved_iteration = iteration;
-                    *last_improved_segment = segment;
-
-                    for routes in &neighbor.truck_routes {
-                        for route in routes {
-                            let customers = &route.data().customers;
-                            for i in 0..customers.len() - 1 {
-                                let r = &mut edge_records[customers[i]][customers[i + 1]];
-                                *r = r.min(neighbor.working_time);
-                            }
-                        }
-                    }
-
-                    if CONFIG.max_elite_size > 0 {
-                        if elite_set.len() == CONFIG.max_elite_size {
-                            let (idx, _) = elite_set
-                                .iter()
-                                .enumerate()
-                                .min_by_key(|s| s.1.hamming_distance(result))
-                                .unwrap();
-                            elite_set.remove(idx);
-                        }
-
-                        elite_set.push(neighbor.clone());
-                    }
-                }
-            }
-
-            fn _update_violation_solution(s: &Solution) {
-                _update_violation::<0>(s.energy_violation);
-                _update_violation::<1>(s.capacity_violation);
-                _update_violation::<2>(s.waiting_time_violation);
-                _update_violation::<3>(s.fixed_time_violation);
-            }
 
             for iteration in iteration_range {
-                if CONFIG.verbose {
-                    let extra = if let Strategy::Adaptive = CONFIG.strategy {
-                        format!(
-                            "(segments before reset {})",
-                            if CONFIG.adaptive_fixed_segments {
-                                adaptive
-                                    .segment
-                                    .saturating_sub(adaptive.segment_reset + CONFIG.adaptive_segments)
-                            } else {
-                                CONFIG.adaptive_segments.saturating_sub(
-                                    adaptive.segment - cmp::max(adaptive.segment_reset, adaptive.last_improved_segment),
-                                )
-                            }
-                        )
-                    } else {
-                        format!(
-                            "(reset in {})",
-                            reset_after.saturating_sub((iteration - last_improved_iteration) % reset_after)
-                        )
-                    };
-
-                    eprint!(
-                        "Iteration #{} {}: {:.2}/{:.2}, elite set {}/{}     \r",
-                        iteration,
-                        extra,
-                        current.cost(),
-                        result.cost(),
-                        elite_set.len(),
-                        CONFIG.max_elite_size
-                    );
+                if enabled!(Level::TRACE) {
+                    state.trace(iteration, reset_after, logger);
                 }
 
-                let neighborhood = NEIGHBORHOODS[neighborhood_idx];
-
-                let old_current = current.clone();
-                if let Some(neighbor) =
-                    neighborhood.search(&current, &mut tabu_lists[neighborhood_idx], tabu_size, result.cost())
-                {
-                    let neighbor = Rc::new(neighbor);
-
-                    // Update adaptive state
-                    if neighbor.feasible {
-                        if neighbor.cost() + TOLERANCE < result.cost() {
-                            adaptive.scores[neighborhood_idx] += 0.3;
-                        } else if neighbor.cost() < current.cost() {
-                            adaptive.scores[neighborhood_idx] += 0.2;
-                        } else {
-                            adaptive.scores[neighborhood_idx] += 0.1;
-                        }
-                    }
-
-                    _record_new_solution(
-                        &neighbor,
-                        &mut result,
-                        &mut last_improved_iteration,
-                        &mut adaptive.last_improved_segment,
-                        iteration,
-                        adaptive.segment,
-                        &mut edge_records,
-                        &mut elite_set,
-                    );
-
-                    current = neighbor;
+                let old_current = state.current.clone();
+                if let Some(neighbor) = state.search_neighborhood() {
+                    state.accept(Rc::new(neighbor), iteration);
                 }
+                state.remember(iteration);
 
-                adaptive.occurences[neighborhood_idx] += 1;
-
-                let end_of_segment = if CONFIG.adaptive_fixed_iterations {
-                    iteration > 0 && iteration % adaptive_iterations == 0
-                } else {
-                    iteration != last_improved_iteration
-                        && (iteration - last_improved_iteration) % adaptive_iterations == 0
-                };
-                if end_of_segment {
-                    adaptive.segment += 1;
+                let end_of_segment = state.end_segment(iteration, adaptive_iterations);
+                let reset = state.should_reset(iteration, reset_after);
+                if reset && !state.reset(iteration) {
+                    break;
                 }
 
-                let reset = if let Strategy::Adaptive = CONFIG.strategy {
-                    if CONFIG.adaptive_fixed_segments {
-                        adaptive.segment >= adaptive.segment_reset + CONFIG.adaptive_segments
-                    } else {
-                        adaptive.segment
-                            >= cmp::max(adaptive.segment_reset, adaptive.last_improved_segment)
-                                + CONFIG.adaptive_segments
-                    }
+                if reset && config.ejection_chain_iterations > 0 {
+                    state.eject(iteration, logger);
                 } else {
-                    iteration != last_improved_iteration && (iteration - last_improved_iteration) % reset_after == 0
-                };
-
-                if reset {
-                    adaptive.segment_reset = adaptive.segment;
-                    adaptive.weights = vec![1.0; NEIGHBORHOODS.len()];
-
-                    if elite_set.is_empty() {
-                        break;
-                    }
-
-                    let i = rng.random_range(0..elite_set.len());
-                    current = Rc::new(elite_set.swap_remove(i).destroy_and_repair(&edge_records));
-                    for tabu_list in &mut tabu_lists {
-                        tabu_list.clear();
-                    }
+                    state.log(iteration, logger);
                 }
+                state.progress(iteration, logger);
 
-                if reset && CONFIG.ejection_chain_iterations > 0 {
-                    let mut ejection_chain_tabu_list = vec![]; // Still have to maintain a tabu list to avoid cycles
-                    for _ in 0..CONFIG.ejection_chain_iterations {
-                        if let Some(neighbor) = Neighborhood::EjectionChain.search(
-                            &current,
-                            &mut ejection_chain_tabu_list,
-                            CONFIG.ejection_chain_iterations + 1,
-                            result.cost(),
-                        ) {
-                            current = Rc::new(neighbor);
-                            _record_new_solution(
-                                &current,
-                                &mut result,
-                                &mut last_improved_iteration,
-                                &mut adaptive.last_improved_segment,
-                                iteration,
-                                adaptive.segment,
-                                &mut edge_records,
-                                &mut elite_set,
-                            );
-                        }
-
-                        _update_violation_solution(&current);
-                        logger
-                            .log(&current, Neighborhood::EjectionChain, &ejection_chain_tabu_list)
-                            .unwrap();
-                    }
-                } else {
-                    _update_violation_solution(&current);
-                    logger
-                        .log(&current, neighborhood, &tabu_lists[neighborhood_idx])
-                        .unwrap();
+                if let Some(ref island) = island
+                    && iteration % config.migration_interval == 0
+                {
+                    state.migrate(iteration, island);
                 }
 
-                match CONFIG.strategy {
-                    Strategy::Random => {
-                        neighborhood_idx = rng.random_range(0..NEIGHBORHOODS.len());
-                    }
-                    Strategy::Cyclic => {
-                        neighborhood_idx = (neighborhood_idx + 1) % NEIGHBORHOODS.len();
-                    }
-                    Strategy::Vns => {
-                        if iteration == last_improved_iteration {
-                            neighborhood_idx = 0;
-                        } else {
-                            neighborhood_idx = (neighborhood_idx + 1) % NEIGHBORHOODS.len();
-                            if neighborhood_idx != 0 {
-                                current = old_current;
-                            }
-                        }
-                    }
-                    Strategy::Adaptive => {
-                        if end_of_segment {
-                            for neighborhood_idx in 0..NEIGHBORHOODS.len() {
-                                if adaptive.occurences[neighborhood_idx] > 0 {
-                                    adaptive.weights[neighborhood_idx] = 0.7f64.mul_add(
-                                        adaptive.weights[neighborhood_idx],
-                                        0.3 * adaptive.scores[neighborhood_idx]
-                                            / f64::from(adaptive.occurences[neighborhood_idx]),
-                                    );
-                                }
+                if let Some(target) = config.target_cost
+                    && state.result.feasible
+                    && state.result.objective() <= target
+                {
+                    logger.reach_target();
+                    break;
+                }
 
-                                adaptive.scores[neighborhood_idx] = 0.0;
-                                adaptive.occurences[neighborhood_idx] = 0;
-                            }
-                        }
+                state.select_neighborhood(iteration, end_of_segment, reset, old_current, logger);
+            }
 
-                        let dist = WeightedIndex::new(&adaptive.weights).unwrap();
-                        neighborhood_idx = dist.sample(&mut rng);
+            if let Some(island) = island {
+                for solution in island.finish(&state.result) {
+                    if solution._better(&state.result) {
+                        state.result = Rc::new(solution);
                     }
                 }
             }
 
-            if CONFIG.verbose {
-                eprintln!();
-            }
-
-            let preresult_cost = result.cost();
+            let preresult_cost = state.result.cost();
             let preresult_time_offset = SystemTime::now();
             // result = Rc::new(result.post_optimization());
-            post_optimization = preresult_cost - result.cost();
+            if config.route_pool > 0 {
+                state.pool.insert(&state.result);
+                info!("Recombining {} pooled routes", state.pool.len());
+
+                let recombined = state.pool.recombine(&state.result);
+                if config.pins.respected(&recombined) {
+                    state.result = Rc::new(recombined);
+                }
+            }
+            if config.tsp_polish {
+                state.result = Rc::new(state.result.resequence_routes());
+            }
+            if config.mothership {
+                let improved = mothership::improve(&state.result);
+                if config.pins.respected(&improved) {
+                    state.result = Rc::new(improved);
+                }
+                info!("Launching {} sortie(s) from trucks", state.result.sorties.len());
+            }
+            if config.pareto_archive > 0 {
+                state.archive.insert(&state.result);
+                logger.pareto_front(state.archive.front()).unwrap();
+            }
+            if config.top_solutions > 0 {
+                state.top.insert(&state.result);
+            }
+            post_optimization = preresult_cost - state.result.cost();
             post_optimization_elapsed = SystemTime::now()
                 .duration_since(preresult_time_offset)
                 .unwrap()
@@ -1174,17 +2064,138 @@ impl Solution {
 
         logger
             .finalize(
-                &result,
+                &state.result,
                 tabu_size,
                 reset_after,
                 adaptive_iterations,
-                adaptive.segment,
-                last_improved_iteration,
+                state.adaptive.segment,
+                state.last_improved_iteration,
                 post_optimization,
                 post_optimization_elapsed,
+                &state.statistics,
+                state.top.solutions(),
             )
             .unwrap();
 
-        Self::clone(&result)
+        Self::clone(&state.result)
+    }
+}
+
+impl fmt::Display for Solution {
+    /// One row per trip of each vehicle with its working time, the constraints it violates (relative to their limits,
+    /// as in the totals) and its customers, followed by the objective and the total violations
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn _violations<const N: usize>(violations: [(&str, f64); N]) -> String {
+            let violated = violations
+                .iter()
+                .filter(|(_, violation)| *violation > 0.0)
+                .map(|(name, violation)| format!("{name} {violation:.2}"))
+                .collect::<Vec<_>>();
+            if violated.is_empty() {
+                "-".to_string()
+            } else {
+                violated.join(", ")
+            }
+        }
+
+        fn _customers(customers: &[usize]) -> String {
+            customers.iter().map(usize::to_string).collect::<Vec<_>>().join(" ")
+        }
+
+        writeln!(
+            f,
+            "{:<10}{:>6}{:>14}  {:<32}Customers",
+            "Vehicle", "Trip", "Working time", "Violations"
+        )?;
+        for (truck, routes) in self.truck_routes.iter().enumerate() {
+            for (trip, route) in routes.iter().enumerate() {
+                writeln!(
+                    f,
+                    "{:<10}{:>6}{:>14.2}  {:<32}{}",
+                    format!("truck {truck}"),
+                    trip + 1,
+                    route.working_time(),
+                    _violations([
+                        ("energy", 0.0),
+                        ("capacity", route.capacity_violation() / CONFIG.load().truck.capacity),
+                        (
+                            "waiting-time",
+                            route.waiting_time_violation() / CONFIG.load().waiting_time_limit
+                        ),
+                        ("fixed-time", 0.0),
+                    ]),
+                    _customers(&route.data().customers)
+                )?;
+            }
+            writeln!(
+                f,
+                "{:<10}{:>6}{:>14.2}",
+                format!("truck {truck}"),
+                "total",
+                self.truck_working_time[truck]
+            )?;
+        }
+        for (drone, routes) in self.drone_routes.iter().enumerate() {
+            for (trip, route) in routes.iter().enumerate() {
+                let class = &CONFIG.load().drone_classes[CONFIG.load().drone_class_of(DroneId(drone))];
+                let metrics = route.metrics(DroneId(drone));
+                writeln!(
+                    f,
+                    "{:<10}{:>6}{:>14.2}  {:<32}{}",
+                    format!("drone {drone}"),
+                    trip + 1,
+                    metrics.working_time,
+                    _violations([
+                        ("energy", metrics.energy_violation / class.battery()),
+                        ("capacity", metrics.capacity_violation / class.capacity()),
+                        (
+                            "waiting-time",
+                            metrics.waiting_time_violation / CONFIG.load().waiting_time_limit
+                        ),
+                        ("fixed-time", metrics.fixed_time_violation / class.fixed_time()),
+                        ("no-fly", metrics.no_fly_violation),
+                    ]),
+                    _customers(&route.data().customers)
+                )?;
+            }
+            writeln!(
+                f,
+                "{:<10}{:>6}{:>14.2}",
+                format!("drone {drone}"),
+                "total",
+                self.drone_working_time[drone]
+            )?;
+        }
+        for sortie in &self.sorties {
+            writeln!(
+                f,
+                "Sortie from truck {}, trip {}: launched at position {}, serves customer {}, rejoins at position {}",
+                sortie.truck,
+                sortie.route + 1,
+                sortie.launch,
+                sortie.customer,
+                sortie.rendezvous
+            )?;
+        }
+
+        writeln!(
+            f,
+            "Working time = {:.2}, cost = {:.2}, feasible = {}",
+            self.working_time,
+            self.cost(),
+            self.feasible
+        )?;
+        write!(
+            f,
+            "Violations: energy = {:.4}, capacity = {:.4}, waiting-time = {:.4}, fixed-time = {:.4}, depot-slot = {:.4}, \
+             assignment = {:.4}, no-fly = {:.4}",
+            self.energy_violation,
+            self.capacity_violation,
+            self.waiting_time_violation,
+            self.fixed_time_violation,
+            self.depot_slot_violation,
+            self.assignment_violation,
+            self.no_fly_violation
+        )
     }
 }
@@ -0,0 +1,113 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::SystemTime;
+
+use crate::solutions::Solution;
+
+#[derive(serde::Serialize)]
+struct Status {
+    iteration: usize,
+    elapsed: f64,
+    cost: f64,
+    working_time: f64,
+    feasible: bool,
+}
+
+/// Snapshot of the search shared with the server thread
+struct _Shared {
+    iteration: usize,
+    cost: f64,
+    working_time: f64,
+    feasible: bool,
+    best_solution: String,
+}
+
+/// HTTP server reporting the progress of the search (see `--status-port`). `GET /status` returns the iteration,
+/// elapsed time and the cost, working time and feasibility of the incumbent; `GET /best-solution` returns the
+/// incumbent solution JSON.
+pub struct StatusServer {
+    _shared: Arc<Mutex<_Shared>>,
+}
+
+impl StatusServer {
+    pub fn start(bind: &str, port: u16) -> io::Result<Self> {
+        let listener = TcpListener::bind((bind, port))?;
+        let shared = Arc::new(Mutex::new(_Shared {
+            iteration: 0,
+            cost: f64::NAN,
+            working_time: f64::NAN,
+            feasible: false,
+            best_solution: "null".to_string(),
+        }));
+
+        let server = shared.clone();
+        let started = SystemTime::now();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let _ = _respond(stream, &server, started);
+            }
+        });
+
+        Ok(Self { _shared: shared })
+    }
+
+    /// Publish the current iteration and the incumbent `result`. The incumbent is only serialized when its cost
+    /// changes.
+    pub fn update(&self, iteration: usize, result: &Solution) -> serde_json::Result<()> {
+        let mut shared = self._shared.lock().unwrap();
+        shared.iteration = iteration;
+        if shared.cost.to_bits() != result.cost().to_bits() {
+            shared.cost = result.cost();
+            shared.working_time = result.working_time;
+            shared.feasible = result.feasible;
+            shared.best_solution = serde_json::to_string(result)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn _respond(stream: TcpStream, shared: &Mutex<_Shared>, started: SystemTime) -> io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+
+    // Skip the headers
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 2 {
+        line.clear();
+    }
+
+    let mut parts = request.split_whitespace();
+    let (status, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/status")) => {
+            let shared = shared.lock().unwrap();
+            let status = Status {
+                iteration: shared.iteration,
+                elapsed: SystemTime::now()
+                    .duration_since(started)
+                    .map_or(0.0, |d| d.as_secs_f64()),
+                cost: shared.cost,
+                working_time: shared.working_time,
+                feasible: shared.feasible,
+            };
+            ("200 OK", serde_json::to_string(&status)?)
+        }
+        (Some("GET"), Some("/best-solution")) => ("200 OK", shared.lock().unwrap().best_solution.clone()),
+        (Some("GET"), _) => ("404 Not Found", "{\"error\":\"not found\"}".to_string()),
+        _ => (
+            "405 Method Not Allowed",
+            "{\"error\":\"method not allowed\"}".to_string(),
+        ),
+    };
+
+    let mut stream = reader.into_inner();
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()
+}
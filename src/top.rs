@@ -0,0 +1,48 @@
+use std::rc::Rc;
+
+use crate::config::CONFIG;
+use crate::solutions::Solution;
+
+/// Bounded pool of the cheapest feasible solutions found during the search, pairwise at least `--top-min-distance`
+/// apart in Hamming distance, offered as alternatives to the best solution
+pub struct TopSolutions {
+    solutions: Vec<Rc<Solution>>,
+}
+
+impl TopSolutions {
+    pub fn new() -> Self {
+        Self { solutions: vec![] }
+    }
+
+    /// Add `solution` to the pool if it is feasible and cheaper than every pooled solution closer to it than
+    /// `--top-min-distance`, evicting those. When the pool exceeds `--top-solutions` solutions, the most expensive one
+    /// is removed.
+    pub fn insert(&mut self, solution: &Rc<Solution>) -> bool {
+        if !solution.feasible {
+            return false;
+        }
+
+        let cost = solution.cost();
+        if self.solutions.len() == CONFIG.load().top_solutions
+            && self.solutions.last().is_some_and(|s| s.cost() <= cost)
+        {
+            return false;
+        }
+
+        let close = |s: &Rc<Solution>| s.hamming_distance(solution) < CONFIG.load().top_min_distance;
+        if self.solutions.iter().any(|s| close(s) && s.cost() <= cost) {
+            return false;
+        }
+
+        self.solutions.retain(|s| !close(s));
+        self.solutions.push(solution.clone());
+        self.solutions.sort_by(|f, s| f.cost().total_cmp(&s.cost()));
+        self.solutions.truncate(CONFIG.load().top_solutions);
+        true
+    }
+
+    /// The pooled solutions in increasing order of cost
+    pub fn solutions(&self) -> &[Rc<Solution>] {
+        &self.solutions
+    }
+}
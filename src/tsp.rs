@@ -0,0 +1,137 @@
+use crate::config::CONFIG;
+
+/// Maximum number of customers in a route to be re-sequenced exactly
+const HELD_KARP_LIMIT: usize = 15;
+
+fn _length(route: &[usize], distances: &[Vec<f64>]) -> f64 {
+    route.windows(2).map(|w| distances[w[0]][w[1]]).sum()
+}
+
+/// Find the shortest visiting order of `route` (depot at both ends) via Held-Karp dynamic programming.
+fn _held_karp(route: &[usize], distances: &[Vec<f64>]) -> Vec<usize> {
+    let customers = &route[1..route.len() - 1];
+    let n = customers.len();
+    let full = 1 << n;
+
+    let mut dp = vec![f64::INFINITY; full * n];
+    let mut parent = vec![usize::MAX; full * n];
+    for (j, &customer) in customers.iter().enumerate() {
+        dp[(1 << j) * n + j] = distances[0][customer];
+    }
+
+    for mask in 1..full {
+        for j in 0..n {
+            let current = dp[mask * n + j];
+            if mask & (1 << j) == 0 || current == f64::INFINITY {
+                continue;
+            }
+
+            for k in 0..n {
+                if mask & (1 << k) != 0 {
+                    continue;
+                }
+
+                let next = (mask | (1 << k)) * n + k;
+                let cost = current + distances[customers[j]][customers[k]];
+                if cost < dp[next] {
+                    dp[next] = cost;
+                    parent[next] = j;
+                }
+            }
+        }
+    }
+
+    let close = |j: usize| dp[(full - 1) * n + j] + distances[customers[j]][0];
+    let mut j = (0..n).min_by(|&a, &b| close(a).total_cmp(&close(b))).unwrap();
+    let mut mask = full - 1;
+
+    let mut order = vec![];
+    loop {
+        order.push(customers[j]);
+        let p = parent[mask * n + j];
+        mask ^= 1 << j;
+        if p == usize::MAX {
+            break;
+        }
+
+        j = p;
+    }
+
+    let mut result = vec![0];
+    result.extend(order.into_iter().rev());
+    result.push(0);
+    result
+}
+
+/// Improve the visiting order of `route` with 2-opt and Or-opt moves until a local optimum is reached.
+///
+/// 2-opt moves are priced in constant time for symmetric distances, other route lengths are recomputed from scratch
+/// so that asymmetric distances are priced correctly.
+fn _local_search(route: &[usize], distances: &[Vec<f64>]) -> Vec<usize> {
+    let mut best = route.to_vec();
+    let mut best_length = _length(&best, distances);
+    let length = best.len();
+
+    let mut improved = true;
+    while improved {
+        improved = false;
+
+        // 2-opt: reverse the segment best[i..j + 1]
+        for i in 1..length - 2 {
+            for j in i + 1..length - 1 {
+                let candidate_length = if CONFIG.load().asymmetric {
+                    // Reversing the segment changes the direction of every inner edge
+                    let mut candidate = best.clone();
+                    candidate[i..j + 1].reverse();
+                    _length(&candidate, distances)
+                } else {
+                    best_length + distances[best[i - 1]][best[j]] + distances[best[i]][best[j + 1]]
+                        - distances[best[i - 1]][best[i]]
+                        - distances[best[j]][best[j + 1]]
+                };
+
+                if candidate_length + 1e-9 < best_length {
+                    best[i..j + 1].reverse();
+                    best_length = _length(&best, distances);
+                    improved = true;
+                }
+            }
+        }
+
+        // Or-opt: relocate a segment of 1 to 3 consecutive customers
+        for size in 1..4 {
+            for i in 1..length.saturating_sub(size) {
+                for j in 1..length - size {
+                    if j == i {
+                        continue;
+                    }
+
+                    let mut candidate = best.clone();
+                    let segment = candidate.drain(i..i + size).collect::<Vec<usize>>();
+                    candidate.splice(j..j, segment);
+
+                    let candidate_length = _length(&candidate, distances);
+                    if candidate_length + 1e-9 < best_length {
+                        best = candidate;
+                        best_length = candidate_length;
+                        improved = true;
+                    }
+                }
+            }
+        }
+    }
+
+    best
+}
+
+/// Re-sequence the customers of `route` (depot at both ends) to minimize its total distance: exactly for routes
+/// with at most 15 customers, heuristically otherwise. Routes with at most one customer are returned unchanged.
+pub fn resequence(route: &[usize], distances: &[Vec<f64>]) -> Vec<usize> {
+    if route.len() <= 3 {
+        route.to_vec()
+    } else if route.len() - 2 <= HELD_KARP_LIMIT {
+        _held_karp(route, distances)
+    } else {
+        _local_search(route, distances)
+    }
+}
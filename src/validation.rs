@@ -0,0 +1,311 @@
+use std::fs;
+use std::path::Path;
+
+use serde_json::{Map, Value};
+use tracing::warn;
+
+use crate::cli;
+
+/// Allowed range of a numeric field
+#[derive(Clone, Copy)]
+enum Bound {
+    Positive,
+    NonNegative,
+    Any,
+}
+
+/// A numeric field of a config: its name, whether it is required and its allowed range
+type Field = (&'static str, bool, Bound);
+
+const TRUCK_FIELDS: &[Field] = &[
+    ("V_max (m/s)", true, Bound::Positive),
+    ("M_t (kg)", true, Bound::NonNegative),
+    ("reloadTime (s)", false, Bound::NonNegative),
+];
+
+/// Fields shared by the linear, non-linear and table drone configs
+const FLIGHT_FIELDS: &[Field] = &[
+    ("takeoffSpeed [m/s]", true, Bound::Positive),
+    ("cruiseSpeed [m/s]", true, Bound::Positive),
+    ("landingSpeed [m/s]", true, Bound::Positive),
+    ("cruiseAlt [m]", true, Bound::NonNegative),
+    ("capacity [kg]", true, Bound::NonNegative),
+    ("batteryPower [Joule]", true, Bound::Positive),
+];
+
+/// Optional fields of every drone config
+const DRONE_OPTIONAL_FIELDS: &[Field] = &[
+    ("rechargeTime [s]", false, Bound::NonNegative),
+    ("windSpeed [m/s]", false, Bound::NonNegative),
+    ("windBearing [deg]", false, Bound::Any),
+];
+
+const LINEAR_FIELDS: &[Field] = &[
+    ("beta(w/kg)", true, Bound::NonNegative),
+    ("gamma(w)", true, Bound::NonNegative),
+];
+
+const ENDURANCE_FIELDS: &[Field] = &[
+    ("capacity [kg]", true, Bound::NonNegative),
+    ("FixedTime (s)", true, Bound::Positive),
+    ("V_max (m/s)", true, Bound::Positive),
+];
+
+/// Coefficients at the top level of a non-linear config file
+const NON_LINEAR_COEFFICIENTS: &[Field] = &[
+    ("k1", true, Bound::NonNegative),
+    ("k2 (sqrt(kg/m))", true, Bound::Positive),
+    ("c1 (sqrt(m/kg))", true, Bound::NonNegative),
+    ("c2 (sqrt(m/kg))", true, Bound::NonNegative),
+    ("c4 (kg/m)", true, Bound::NonNegative),
+    ("c5 (Ns/m)", true, Bound::NonNegative),
+];
+
+/// Non-numeric fields of every drone config entry
+const TYPE_FIELDS: [&str; 2] = ["speed_type", "range_type"];
+
+/// Levenshtein distance between `a` and `b`, ignoring case
+fn _edit_distance(a: &str, b: &str) -> usize {
+    let a = a.to_lowercase().chars().collect::<Vec<_>>();
+    let b = b.to_lowercase().chars().collect::<Vec<_>>();
+    let mut previous = (0..b.len() + 1).collect::<Vec<_>>();
+    for (i, &x) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, &y) in b.iter().enumerate() {
+            current[j + 1] = (previous[j] + usize::from(x != y))
+                .min(previous[j + 1] + 1)
+                .min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+/// The candidate closest to `name`, if it is close enough to be a likely misspelling
+fn _closest<'a>(name: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    candidates
+        .map(|c| (_edit_distance(name, c), c))
+        .min()
+        .filter(|&(distance, _)| distance <= name.len().max(3) / 3)
+        .map(|(_, c)| c)
+}
+
+/// Check the `fields` of `object`, appending a diagnostic prefixed with `context` to `errors` for each missing,
+/// non-numeric or out-of-range field. Unknown keys (which would be silently ignored) are reported as warnings.
+fn _check(object: &Map<String, Value>, fields: &[Field], known: &[&str], context: &str, errors: &mut Vec<String>) {
+    let known = fields.iter().map(|&(name, _, _)| name).chain(known.iter().copied());
+    let unknown = object
+        .keys()
+        .filter(|key| !known.clone().any(|name| name == key.as_str()))
+        .collect::<Vec<_>>();
+
+    for &(name, required, bound) in fields {
+        match object.get(name) {
+            None if required => {
+                let found = _closest(name, unknown.iter().map(|key| key.as_str()));
+                errors.push(match found {
+                    Some(key) => format!("{context}: missing field {name:?} (found {key:?} instead)"),
+                    None => format!("{context}: missing field {name:?}"),
+                });
+            }
+            None => {}
+            Some(value) => match value.as_f64() {
+                None => errors.push(format!("{context}: field {name:?} must be a number, got {value}")),
+                Some(number) => {
+                    let message = match bound {
+                        Bound::Positive if number <= 0.0 => Some("positive"),
+                        Bound::NonNegative if number < 0.0 => Some("non-negative"),
+                        _ => None,
+                    };
+                    if let Some(message) = message {
+                        errors.push(format!("{context}: field {name:?} must be {message}, got {number}"));
+                    }
+                }
+            },
+        }
+    }
+
+    for key in unknown {
+        match _closest(key, known.clone()) {
+            Some(name) => warn!("{context}: unknown field {key:?} is ignored, did you mean {name:?}?"),
+            None => warn!("{context}: unknown field {key:?} is ignored"),
+        }
+    }
+}
+
+/// Numeric fields of a config entry of the energy model `model`
+fn _fields(model: cli::EnergyModel) -> Vec<Field> {
+    match model {
+        cli::EnergyModel::Linear => [FLIGHT_FIELDS, LINEAR_FIELDS, DRONE_OPTIONAL_FIELDS].concat(),
+        cli::EnergyModel::NonLinear | cli::EnergyModel::Table => [FLIGHT_FIELDS, DRONE_OPTIONAL_FIELDS].concat(),
+        cli::EnergyModel::Endurance => [ENDURANCE_FIELDS, DRONE_OPTIONAL_FIELDS].concat(),
+        cli::EnergyModel::Unlimited => vec![],
+    }
+}
+
+/// Whether `object` has every required field of a config entry of the energy model `model`
+fn _is_model(model: cli::EnergyModel, object: &Map<String, Value>) -> bool {
+    _fields(model)
+        .iter()
+        .all(|&(name, required, _)| !required || object.contains_key(name))
+        && (model != cli::EnergyModel::Table || object.contains_key("powerCurve"))
+}
+
+/// Panic with every diagnostic of `errors` about the config file at `path`
+fn _report(path: &str, errors: &[String]) {
+    assert!(errors.is_empty(), "Invalid config {path:?}:\n  {}", errors.join("\n  "));
+}
+
+fn _load(path: &str) -> Value {
+    let data = fs::read_to_string(path).unwrap_or_else(|e| panic!("Cannot read config {path:?}: {e}"));
+    serde_json::from_str::<Value>(&data).unwrap_or_else(|e| panic!("Config {path:?} is not valid JSON: {e}"))
+}
+
+/// Validate the truck config at `path`, panicking with a description of every missing or out-of-range field.
+pub fn truck_config(path: &str) {
+    let errors = match _load(path) {
+        Value::Object(object) => {
+            let mut errors = vec![];
+            _check(&object, TRUCK_FIELDS, &[], "truck config", &mut errors);
+            errors
+        }
+        value => vec![format!("expected an object of truck parameters, got {value}")],
+    };
+    _report(path, &errors);
+}
+
+/// Validate the drone config at `path` for the energy model `model`, panicking with a description of every missing
+/// or out-of-range field. If no entry has the requested speed and range types, the available combinations are listed
+/// along with the closest one.
+pub fn drone_config(path: &str, model: cli::EnergyModel, speed_type: cli::ConfigType, range_type: cli::ConfigType) {
+    if model == cli::EnergyModel::Unlimited {
+        return;
+    }
+
+    let mut errors = vec![];
+    let entries = match (model, _load(path)) {
+        (cli::EnergyModel::NonLinear, Value::Object(object)) => {
+            _check(&object, NON_LINEAR_COEFFICIENTS, &["config"], "file", &mut errors);
+            match object.get("config") {
+                Some(Value::Array(entries)) => entries.clone(),
+                Some(value) => {
+                    errors.push(format!("field \"config\" must be an array of configs, got {value}"));
+                    vec![]
+                }
+                None => {
+                    errors.push("missing field \"config\" (the array of configs)".to_string());
+                    vec![]
+                }
+            }
+        }
+        (cli::EnergyModel::NonLinear, _) => {
+            errors.push(
+                "expected an object with the coefficients k1, k2, c1, c2, c4, c5 and a \"config\" array of configs"
+                    .to_string(),
+            );
+            vec![]
+        }
+        (_, Value::Array(entries)) => entries,
+        (_, Value::Object(object)) if object.contains_key("config") => {
+            errors.push(format!(
+                "expected an array of configs for the {model} model, this looks like a non-linear config (see --config)"
+            ));
+            vec![]
+        }
+        (_, value) => {
+            errors.push(format!("expected an array of configs, got {value}"));
+            vec![]
+        }
+    };
+
+    // A whole file of another energy model is reported once rather than field by field
+    if let Some(Value::Object(first)) = entries.first()
+        && !_is_model(model, first)
+        && let Some(other) = [
+            cli::EnergyModel::Linear,
+            cli::EnergyModel::Endurance,
+            cli::EnergyModel::Table,
+        ]
+        .into_iter()
+        .find(|&other| _is_model(other, first))
+    {
+        errors.push(format!(
+            "the configs are those of the {other} energy model, pass --config {other} or a {model} config file"
+        ));
+    }
+    if !errors.is_empty() {
+        _report(path, &errors);
+    }
+
+    let fields = _fields(model);
+    let mut known = TYPE_FIELDS.to_vec();
+    if model == cli::EnergyModel::Table {
+        known.push("powerCurve");
+    }
+
+    let mut available = vec![];
+    for (index, entry) in entries.iter().enumerate() {
+        let context = format!("config {index}");
+        let Value::Object(object) = entry else {
+            errors.push(format!("{context}: expected an object, got {entry}"));
+            continue;
+        };
+        _check(object, &fields, &known, &context, &mut errors);
+
+        let mut types = vec![];
+        for name in TYPE_FIELDS {
+            match object.get(name) {
+                None => errors.push(format!("{context}: missing field {name:?}")),
+                Some(value) => match serde_json::from_value::<cli::ConfigType>(value.clone()) {
+                    Ok(t) => types.push(t),
+                    Err(_) => errors.push(format!(
+                        "{context}: field {name:?} must be \"low\" or \"high\", got {value}"
+                    )),
+                },
+            }
+        }
+        if let [speed, range] = types[..] {
+            available.push((speed, range));
+        }
+
+        if model == cli::EnergyModel::Table {
+            match object.get("powerCurve") {
+                Some(Value::String(curve)) => {
+                    let curve_path = Path::new(path).parent().unwrap_or(Path::new("")).join(curve);
+                    if !curve_path.is_file() {
+                        errors.push(format!(
+                            "{context}: power curve {} does not exist",
+                            curve_path.display()
+                        ));
+                    }
+                }
+                Some(value) => errors.push(format!("{context}: field \"powerCurve\" must be a path, got {value}")),
+                None => errors.push(format!("{context}: missing field \"powerCurve\"")),
+            }
+        }
+    }
+
+    if errors.is_empty() && !available.contains(&(speed_type, range_type)) {
+        let combinations = available
+            .iter()
+            .map(|(speed, range)| format!("{speed}/{range}"))
+            .collect::<Vec<_>>();
+        // Prefer the first of the combinations sharing the most types with the requested one
+        let message = match available
+            .iter()
+            .rev()
+            .max_by_key(|&&(speed, range)| usize::from(speed == speed_type) + usize::from(range == range_type))
+        {
+            Some((speed, range)) => format!(
+                "no {model} config with --speed-type {speed_type} and --range-type {range_type}, the available \
+                 speed/range combinations are {}. Closest match: --speed-type {speed} --range-type {range}",
+                combinations.join(", ")
+            ),
+            None => format!("no {model} config in the file"),
+        };
+        errors.push(message);
+    }
+
+    _report(path, &errors);
+}